@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use serde_sarif::sarif::{Artifact, ReportingDescriptor, Result as SarifResult};
+
+use crate::callgraph::{
+    CallGraphAlgorithm, CallGraphOptions, DEFAULT_POINTS_TO_BUDGET, MethodPattern,
+};
+use crate::class_filter::ClassFilter;
+use crate::classpath::resolve_classpath;
+use crate::engine::{
+    AnalysisContext, ContextTimings, Engine, EngineOptions, RuleFailure, RuleStats,
+    build_context_with_timings,
+};
+use crate::metrics::{ClassMetrics, class_metrics};
+use crate::rules::Rule;
+use crate::scan::scan_inputs;
+use crate::trace;
+
+/// Scans an input and its classpath and runs the built-in and registered rules.
+pub struct Analyzer {
+    input: PathBuf,
+    classpath: Vec<PathBuf>,
+    engine: Engine,
+    call_graph: CallGraphAlgorithm,
+    points_to_budget: Option<Duration>,
+    entry_points: Vec<MethodPattern>,
+    class_filter: ClassFilter,
+    collect_metrics: bool,
+}
+
+/// Settings for an [`Analyzer`]; `build` validates them before anything is scanned.
+#[derive(Default)]
+pub struct AnalyzerBuilder {
+    input: Option<PathBuf>,
+    classpath: Vec<PathBuf>,
+    options: EngineOptions,
+    rules: Vec<Box<dyn Rule>>,
+    collect_metrics: bool,
+}
+
+impl AnalyzerBuilder {
+    /// Class file, JAR or directory to analyze.
+    pub fn input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Add class files, JARs or directories used to resolve the input.
+    pub fn classpath<I, P>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.classpath.extend(entries.into_iter().map(Into::into));
+        self
+    }
+
+    /// Rule selection, levels and rule settings.
+    pub fn options(mut self, options: EngineOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Run `rule` alongside the built-in rules; its ID must not clash with theirs.
+    pub fn rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Compute class and method metrics of the analysis target.
+    pub fn metrics(mut self, collect: bool) -> Self {
+        self.collect_metrics = collect;
+        self
+    }
+
+    pub fn build(self) -> Result<Analyzer> {
+        let input = self.input.context("input is required")?;
+        ensure_inputs_exist(&input, &self.classpath)?;
+        let call_graph = self.options.call_graph;
+        let points_to_budget = self.options.points_to_budget;
+        let entry_points = self.options.dead_code_entry_points.clone();
+        let class_filter = self.options.class_filter.clone();
+        let engine = Engine::with_rules(self.options, self.rules)?;
+        Ok(Analyzer {
+            input,
+            classpath: self.classpath,
+            engine,
+            call_graph,
+            points_to_budget,
+            entry_points,
+            class_filter,
+            collect_metrics: self.collect_metrics,
+        })
+    }
+}
+
+/// Findings of one analysis, before baseline filtering and SARIF serialization.
+pub struct Analysis {
+    pub artifacts: Vec<Artifact>,
+    pub rules: Vec<ReportingDescriptor>,
+    pub results: Vec<SarifResult>,
+    /// Rules that failed on part of the program; `results` holds everything else.
+    pub failures: Vec<RuleFailure>,
+    pub stats: AnalysisStats,
+    /// Present when metrics were requested.
+    pub metrics: Option<Vec<ClassMetrics>>,
+}
+
+/// Phase timings and sizes of an analysis, emitted as SARIF invocation properties.
+pub struct AnalysisStats {
+    pub scan_duration_ms: u128,
+    pub classpath_duration_ms: u128,
+    pub analysis_call_graph_duration_ms: u128,
+    pub analysis_artifact_duration_ms: u128,
+    pub analysis_call_graph_hierarchy_duration_ms: u128,
+    pub analysis_call_graph_index_duration_ms: u128,
+    pub analysis_call_graph_edges_duration_ms: u128,
+    pub analysis_rules_duration_ms: u128,
+    pub rule_stats: Vec<RuleStats>,
+    pub call_graph_algorithm: CallGraphAlgorithm,
+    pub call_graph_edge_count: usize,
+    pub class_count: usize,
+    pub artifact_count: usize,
+    pub classpath_class_count: usize,
+}
+
+/// Scanned inputs with their call graph, ready for the rules.
+struct Loaded {
+    artifacts: Vec<Artifact>,
+    context: AnalysisContext,
+    context_timings: ContextTimings,
+    scan_duration_ms: u128,
+    classpath_duration_ms: u128,
+    class_count: usize,
+    classpath_class_count: usize,
+}
+
+impl Analyzer {
+    pub fn builder() -> AnalyzerBuilder {
+        AnalyzerBuilder::default()
+    }
+
+    /// Scan the inputs and build the call graph without running any rule.
+    pub fn context(&self) -> Result<AnalysisContext> {
+        Ok(self.load()?.context)
+    }
+
+    pub fn run(&self) -> Result<Analysis> {
+        let loaded = self.load()?;
+        let context = loaded.context;
+        let call_graph_algorithm = context.call_graph.algorithm;
+        let call_graph_edge_count = context.call_graph.edges.len();
+        let metrics = self.collect_metrics.then(|| {
+            let targets = context
+                .classes
+                .iter()
+                .filter(|class| context.is_analysis_target_class(class));
+            class_metrics(targets, &context.call_graph)
+        });
+        let analysis_rules_started_at = Instant::now();
+        let output = self.engine.analyze(context)?;
+        let analysis_rules_duration_ms = analysis_rules_started_at.elapsed().as_millis();
+        let timings = loaded.context_timings;
+        let stats = AnalysisStats {
+            scan_duration_ms: loaded.scan_duration_ms,
+            classpath_duration_ms: loaded.classpath_duration_ms,
+            analysis_call_graph_duration_ms: timings.call_graph_duration_ms,
+            analysis_artifact_duration_ms: timings.artifact_duration_ms,
+            analysis_call_graph_hierarchy_duration_ms: timings.call_graph_hierarchy_duration_ms,
+            analysis_call_graph_index_duration_ms: timings.call_graph_index_duration_ms,
+            analysis_call_graph_edges_duration_ms: timings.call_graph_edges_duration_ms,
+            analysis_rules_duration_ms,
+            rule_stats: output.rule_stats,
+            call_graph_algorithm,
+            call_graph_edge_count,
+            class_count: loaded.class_count,
+            artifact_count: loaded.artifacts.len(),
+            classpath_class_count: loaded.classpath_class_count,
+        };
+        Ok(Analysis {
+            artifacts: loaded.artifacts,
+            rules: output.rules,
+            results: output.results,
+            failures: output.failures,
+            stats,
+            metrics,
+        })
+    }
+
+    fn load(&self) -> Result<Loaded> {
+        let scan_started_at = Instant::now();
+        let scan = scan_inputs(&self.input, &self.classpath)?;
+        let scan_duration_ms = scan_started_at.elapsed().as_millis();
+        let classpath_started_at = Instant::now();
+        let classpath_span = trace::span("classpath", "resolve classpath");
+        let classpath_index = resolve_classpath(&scan.classes)?;
+        drop(classpath_span);
+        let classpath_duration_ms = classpath_started_at.elapsed().as_millis();
+        let classpath_class_count = classpath_index.classes.len();
+        let call_graph_options = CallGraphOptions {
+            algorithm: self.call_graph,
+            service_providers: scan.service_providers,
+            entry_points: self.entry_points.clone(),
+            points_to_budget: self.points_to_budget.unwrap_or(DEFAULT_POINTS_TO_BUDGET),
+        };
+        let (context, context_timings) = build_context_with_timings(
+            scan.classes,
+            classpath_index,
+            &scan.artifacts,
+            &call_graph_options,
+            self.class_filter.clone(),
+        );
+        Ok(Loaded {
+            artifacts: scan.artifacts,
+            context,
+            context_timings,
+            scan_duration_ms,
+            classpath_duration_ms,
+            class_count: scan.class_count,
+            classpath_class_count,
+        })
+    }
+}
+
+impl AnalysisStats {
+    /// `inspequte.*` invocation properties describing the run.
+    pub fn properties(&self) -> BTreeMap<String, Value> {
+        let rule_stats: serde_json::Map<String, Value> = self
+            .rule_stats
+            .iter()
+            .map(|rule| {
+                (
+                    rule.id.to_string(),
+                    json!({ "ms": rule.duration_ms, "results": rule.result_count }),
+                )
+            })
+            .collect();
+        [
+            ("scan_ms", json!(self.scan_duration_ms)),
+            ("classpath_ms", json!(self.classpath_duration_ms)),
+            (
+                "analysis_callgraph_ms",
+                json!(self.analysis_call_graph_duration_ms),
+            ),
+            (
+                "analysis_callgraph_hierarchy_ms",
+                json!(self.analysis_call_graph_hierarchy_duration_ms),
+            ),
+            (
+                "analysis_callgraph_index_ms",
+                json!(self.analysis_call_graph_index_duration_ms),
+            ),
+            (
+                "analysis_callgraph_edges_ms",
+                json!(self.analysis_call_graph_edges_duration_ms),
+            ),
+            (
+                "analysis_artifact_ms",
+                json!(self.analysis_artifact_duration_ms),
+            ),
+            ("analysis_rules_ms", json!(self.analysis_rules_duration_ms)),
+            ("rules", json!(rule_stats)),
+            (
+                "callgraph_algorithm",
+                json!(self.call_graph_algorithm.as_str()),
+            ),
+            ("callgraph_edges", json!(self.call_graph_edge_count)),
+            ("class_count", json!(self.class_count)),
+            ("artifact_count", json!(self.artifact_count)),
+            ("classpath_class_count", json!(self.classpath_class_count)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (format!("inspequte.{name}"), value))
+        .collect()
+    }
+}
+
+/// Fail early when the input or a classpath entry is missing.
+fn ensure_inputs_exist(input: &Path, classpath: &[PathBuf]) -> Result<()> {
+    if !input.exists() {
+        anyhow::bail!("input not found: {}", input.display());
+    }
+    for entry in classpath {
+        if !entry.exists() {
+            anyhow::bail!("classpath entry not found: {}", entry.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use serde_sarif::sarif::ResultLevel;
+
+    use super::*;
+    use crate::ir::Class;
+    use crate::rules::{Precision, RuleMetadata, RuleVisitor, class_location, result_message};
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    /// Reports every analysis-target class whose name ends with `Impl`.
+    struct ImplSuffixRule;
+
+    impl Rule for ImplSuffixRule {
+        fn metadata(&self) -> RuleMetadata {
+            RuleMetadata {
+                id: "IMPL_SUFFIX".into(),
+                name: "Impl suffix".into(),
+                description: "Classes named after their interface".into(),
+                tags: Cow::Borrowed(&["style"]),
+                default_level: ResultLevel::Note,
+                help: "# IMPL_SUFFIX: Impl suffix\n".into(),
+                cwe: &[],
+                precision: Precision::VeryHigh,
+                security_severity: None,
+            }
+        }
+
+        fn visitor(&self) -> Option<&dyn RuleVisitor> {
+            Some(self)
+        }
+    }
+
+    impl RuleVisitor for ImplSuffixRule {
+        fn visit_class(
+            &self,
+            _context: &AnalysisContext,
+            class: &Class,
+            results: &mut Vec<SarifResult>,
+        ) -> Result<()> {
+            if class.name.ends_with("Impl") {
+                results.push(
+                    SarifResult::builder()
+                        .message(result_message(format!("{} ends with Impl", class.name)))
+                        .locations(vec![class_location(&class.name)])
+                        .build(),
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn analyzer_runs_custom_rules_with_built_in_rules() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/ServiceImpl.java".to_string(),
+            contents: r#"
+package com.example;
+public class ServiceImpl {
+    public void run() {}
+}
+"#
+            .to_string(),
+        }];
+        let compiled = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+
+        let analysis = Analyzer::builder()
+            .input(compiled.classes_dir())
+            .rule(ImplSuffixRule)
+            .build()
+            .expect("build analyzer")
+            .run()
+            .expect("run analysis");
+
+        let custom: Vec<&SarifResult> = analysis
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("IMPL_SUFFIX"))
+            .collect();
+        assert_eq!(1, custom.len());
+        assert_eq!(Some(ResultLevel::Note), custom[0].level);
+        assert!(analysis.rules.iter().any(|rule| rule.id == "IMPL_SUFFIX"));
+        assert!(analysis.rules.iter().any(|rule| rule.id == "NULLNESS"));
+    }
+
+    #[test]
+    fn analyzer_rejects_missing_input_and_duplicate_rule_ids() {
+        assert!(Analyzer::builder().build().is_err());
+
+        let dir = tempfile::tempdir().expect("temp dir");
+        let duplicate = Analyzer::builder()
+            .input(dir.path())
+            .rule(ImplSuffixRule)
+            .rule(ImplSuffixRule)
+            .build();
+        assert!(duplicate.is_err());
+    }
+}
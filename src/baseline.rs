@@ -9,7 +9,7 @@ use serde_sarif::sarif::{Location, Result as SarifResult};
 
 /// Baseline data used to suppress known issues in subsequent scans.
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub(crate) struct Baseline {
+pub struct Baseline {
     version: u32,
     findings: Vec<BaselineEntry>,
 }
@@ -31,7 +31,7 @@ struct BaselineLocation {
 }
 
 impl Baseline {
-    pub(crate) fn capture(results: &[SarifResult]) -> Self {
+    pub fn capture(results: &[SarifResult]) -> Self {
         let mut findings = BTreeSet::new();
         for result in results {
             findings.insert(BaselineEntry::from(result));
@@ -42,7 +42,7 @@ impl Baseline {
         }
     }
 
-    pub(crate) fn filter(&self, results: Vec<SarifResult>) -> Vec<SarifResult> {
+    pub fn filter(&self, results: Vec<SarifResult>) -> Vec<SarifResult> {
         results
             .into_iter()
             .filter(|result| {
@@ -53,7 +53,7 @@ impl Baseline {
     }
 }
 
-pub(crate) fn write_baseline(path: &Path, results: &[SarifResult]) -> Result<()> {
+pub fn write_baseline(path: &Path, results: &[SarifResult]) -> Result<()> {
     let baseline = Baseline::capture(results);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
@@ -83,7 +83,7 @@ pub(crate) fn write_baseline(path: &Path, results: &[SarifResult]) -> Result<()>
     Ok(())
 }
 
-pub(crate) fn load_baseline(path: &Path) -> Result<Option<Baseline>> {
+pub fn load_baseline(path: &Path) -> Result<Option<Baseline>> {
     let content = match fs::read_to_string(path) {
         Ok(content) => content,
         Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
@@ -1,30 +1,133 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::ir::{CallKind, Class};
+use anyhow::{Context, Result};
+
+use crate::class_filter::ClassPattern;
+use crate::ir::{CallKind, CallSite, Class, ConstantValue, InstructionKind, Method};
+use crate::opcodes;
+use crate::pointsto::{PointsTo, solve_points_to};
+use crate::trace;
+
+/// Time the points-to analysis may take before falling back to CHA.
+pub const DEFAULT_POINTS_TO_BUDGET: Duration = Duration::from_secs(60);
 
 /// Unique identifier for a method in the classpath.
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) struct MethodId {
-    pub(crate) class_name: String,
-    pub(crate) name: String,
-    pub(crate) descriptor: String,
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct MethodId {
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// Method named by a user as `Class.method` or `Class.method(descriptor)`; without a
+/// descriptor every overload matches. The class and method names are `ClassPattern`
+/// globs, and as method names have no separators, `*` in them matches any run of
+/// characters.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MethodPattern {
+    pub class_name: ClassPattern,
+    pub name: ClassPattern,
+    pub descriptor: Option<String>,
+}
+
+impl MethodPattern {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (path, descriptor) = match spec.find('(') {
+            Some(index) => (&spec[..index], Some(&spec[index..])),
+            None => (spec, None),
+        };
+        let (class_name, name) = path
+            .rsplit_once('.')
+            .filter(|(class_name, name)| !class_name.is_empty() && !name.is_empty())
+            .with_context(|| format!("expected Class.method, got {}", spec))?;
+        Ok(Self {
+            class_name: ClassPattern::parse(class_name)?,
+            name: ClassPattern::parse(name)?,
+            descriptor: descriptor.map(str::to_string),
+        })
+    }
+
+    pub fn matches(&self, class_name: &str, name: &str, descriptor: &str) -> bool {
+        self.class_name.is_match(class_name)
+            && self.name.is_match(name)
+            && self
+                .descriptor
+                .as_deref()
+                .is_none_or(|expected| expected == descriptor)
+    }
+
+    pub fn matches_id(&self, id: &MethodId) -> bool {
+        self.matches(&id.class_name, &id.name, &id.descriptor)
+    }
 }
 
 /// Directed call edge between caller and callee.
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) struct CallEdge {
-    pub(crate) caller: Arc<MethodId>,
-    pub(crate) callee: Arc<MethodId>,
-    pub(crate) kind: CallKind,
-    pub(crate) offset: u32,
+pub struct CallEdge {
+    pub caller: Arc<MethodId>,
+    pub callee: Arc<MethodId>,
+    pub kind: CallKind,
+    pub offset: u32,
+}
+
+/// Algorithm used to resolve virtual and interface calls.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum CallGraphAlgorithm {
+    /// Class hierarchy analysis: every subtype of the receiver's declared type.
+    #[default]
+    Cha,
+    /// Rapid type analysis: only subtypes instantiated in reachable code.
+    Rta,
+    /// Andersen points-to analysis: only the classes of objects the receiver may
+    /// point to. Falls back to CHA when it exceeds its time budget.
+    Pta,
+}
+
+impl CallGraphAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cha => "cha",
+            Self::Rta => "rta",
+            Self::Pta => "pta",
+        }
+    }
 }
 
-/// Call graph built from CHA on the parsed classpath.
+/// Settings for call graph construction.
+#[derive(Clone, Debug)]
+pub(crate) struct CallGraphOptions {
+    pub(crate) algorithm: CallGraphAlgorithm,
+    /// Classes listed in `META-INF/services` files, instantiated by `ServiceLoader`.
+    pub(crate) service_providers: Vec<String>,
+    /// Configured entry points, roots of RTA and points-to analysis in addition to the
+    /// public methods of entry classes.
+    pub(crate) entry_points: Vec<MethodPattern>,
+    /// Time the points-to analysis may take.
+    pub(crate) points_to_budget: Duration,
+}
+
+impl Default for CallGraphOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: CallGraphAlgorithm::default(),
+            service_providers: Vec::new(),
+            entry_points: Vec::new(),
+            points_to_budget: DEFAULT_POINTS_TO_BUDGET,
+        }
+    }
+}
+
+/// Call graph built from CHA, RTA or points-to analysis on the parsed classpath.
 #[derive(Clone, Debug, Default)]
-pub(crate) struct CallGraph {
-    pub(crate) edges: Vec<CallEdge>,
+pub struct CallGraph {
+    /// Algorithm the edges come from, which is CHA when points-to ran out of time.
+    pub algorithm: CallGraphAlgorithm,
+    pub edges: Vec<CallEdge>,
+    /// Methods the runtime calls without a call site in the parsed code, such as
+    /// constructors of service providers.
+    pub entry_points: Vec<Arc<MethodId>>,
 }
 
 /// Timing breakdown for call graph construction.
@@ -34,27 +137,113 @@ pub(crate) struct CallGraphTimings {
     pub(crate) edges_duration_ms: u128,
 }
 
-pub(crate) fn build_call_graph_with_timings(classes: &[Class]) -> (CallGraph, CallGraphTimings) {
+/// Build the call graph with the configured algorithm. Public methods, static
+/// initializers and configured entry points of classes accepted by `is_entry_class`
+/// are the roots of RTA and points-to analysis; CHA ignores them. The points-to result is returned when it
+/// was computed.
+pub(crate) fn build_call_graph_with_timings(
+    classes: &[Class],
+    options: &CallGraphOptions,
+    is_entry_class: impl Fn(&Class) -> bool,
+) -> (CallGraph, Option<PointsTo>, CallGraphTimings) {
     let hierarchy_started_at = Instant::now();
-    let hierarchy = build_hierarchy(classes);
+    let hierarchy = {
+        let _span = trace::span("callgraph", "hierarchy");
+        build_hierarchy(classes)
+    };
     let hierarchy_duration_ms = hierarchy_started_at.elapsed().as_millis();
     let index_started_at = Instant::now();
-    let methods = index_methods(classes);
+    let methods = {
+        let _span = trace::span("callgraph", "index methods");
+        index_methods(classes)
+    };
     let index_duration_ms = index_started_at.elapsed().as_millis();
     let edges_started_at = Instant::now();
-    let edges = build_edges(classes, &hierarchy, &methods);
+    let mut edges_span = trace::span("callgraph", "edges");
+    let entry_points: Vec<Arc<MethodId>> = options
+        .service_providers
+        .iter()
+        .flat_map(|provider| {
+            [
+                lookup_method(&methods, provider, "<init>", "()V"),
+                lookup_method(&methods, provider, "<clinit>", "()V"),
+            ]
+        })
+        .flatten()
+        .collect();
+    let mut points_to = None;
+    let (algorithm, edges) = match options.algorithm {
+        CallGraphAlgorithm::Cha => (
+            CallGraphAlgorithm::Cha,
+            build_edges(classes, &hierarchy, &methods),
+        ),
+        CallGraphAlgorithm::Rta => (
+            CallGraphAlgorithm::Rta,
+            build_rta_edges(
+                classes,
+                &hierarchy,
+                &methods,
+                &options.service_providers,
+                &options.entry_points,
+                is_entry_class,
+            ),
+        ),
+        CallGraphAlgorithm::Pta => match solve_points_to(
+            classes,
+            &hierarchy,
+            &methods,
+            &options.service_providers,
+            &options.entry_points,
+            is_entry_class,
+            options.points_to_budget,
+        ) {
+            Some(result) => {
+                let edges = result.edges.clone();
+                points_to = Some(result);
+                (CallGraphAlgorithm::Pta, edges)
+            }
+            None => (
+                CallGraphAlgorithm::Cha,
+                build_edges(classes, &hierarchy, &methods),
+            ),
+        },
+    };
+    edges_span.arg("algorithm", algorithm.as_str());
+    edges_span.arg("edges", edges.len());
+    drop(edges_span);
     let edges_duration_ms = edges_started_at.elapsed().as_millis();
     let timings = CallGraphTimings {
         hierarchy_duration_ms,
         index_duration_ms,
         edges_duration_ms,
     };
-    (CallGraph { edges }, timings)
+    (
+        CallGraph {
+            algorithm,
+            edges,
+            entry_points,
+        },
+        points_to,
+        timings,
+    )
+}
+
+/// Whether `method` of an entry class is a root of RTA and points-to analysis.
+pub(crate) fn is_root_method(
+    class: &Class,
+    method: &Method,
+    entry_points: &[MethodPattern],
+) -> bool {
+    method.access.is_public
+        || method.name == "<clinit>"
+        || entry_points
+            .iter()
+            .any(|pattern| pattern.matches(&class.name, &method.name, &method.descriptor))
 }
 
 fn build_edges(
     classes: &[Class],
-    hierarchy: &BTreeMap<String, Vec<String>>,
+    hierarchy: &ClassHierarchy<'_>,
     methods: &MethodIndex,
 ) -> Vec<CallEdge> {
     let estimated_edges = classes
@@ -68,6 +257,7 @@ fn build_edges(
         })
         .sum();
     let mut edges = Vec::with_capacity(estimated_edges);
+    let mut resolver = Resolver::new(hierarchy, methods);
     for class in classes {
         for method in &class.methods {
             let Some(caller) =
@@ -75,75 +265,695 @@ fn build_edges(
             else {
                 continue;
             };
-            for call in &method.calls {
+            let implicit = implicit_calls(class, method, hierarchy);
+            for call in method.calls.iter().chain(&implicit.calls) {
+                let callees = match call.kind {
+                    CallKind::Static | CallKind::Special => resolver
+                        .resolve(&call.owner, &call.name, &call.descriptor)
+                        .into_iter()
+                        .collect(),
+                    CallKind::Virtual | CallKind::Interface => {
+                        resolver.dispatch(&call.owner, &call.name, &call.descriptor)
+                    }
+                };
+                for callee in callees {
+                    edges.push(CallEdge {
+                        caller: caller.clone(),
+                        callee,
+                        kind: call.kind,
+                        offset: call.offset,
+                    });
+                }
+            }
+        }
+    }
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+/// Rapid type analysis: starting from the entry methods, follow calls from reachable
+/// methods only, and dispatch virtual calls to the classes whose `new` is reachable.
+/// Entry classes with a public constructor and service providers count as
+/// instantiated, since code outside the analyzed classes may create them.
+fn build_rta_edges(
+    classes: &[Class],
+    hierarchy: &ClassHierarchy<'_>,
+    methods: &MethodIndex,
+    service_providers: &[String],
+    entry_points: &[MethodPattern],
+    is_entry_class: impl Fn(&Class) -> bool,
+) -> Vec<CallEdge> {
+    let bodies: HashMap<Arc<MethodId>, (&Class, &Method)> = classes
+        .iter()
+        .flat_map(|class| {
+            class.methods.iter().filter_map(move |method| {
+                lookup_method(methods, &class.name, &method.name, &method.descriptor)
+                    .map(|id| (id, (class, method)))
+            })
+        })
+        .collect();
+    let mut state = RtaState {
+        resolver: Resolver::new(hierarchy, methods),
+        reachable: HashSet::new(),
+        worklist: Vec::new(),
+        instantiated: HashSet::new(),
+        new_types: Vec::new(),
+        virtual_sites: HashMap::new(),
+        instantiated_subtypes: HashMap::new(),
+        linked: HashSet::new(),
+        edges: Vec::new(),
+    };
+    for class in classes.iter().filter(|class| is_entry_class(class)) {
+        for method in &class.methods {
+            if !is_root_method(class, method, entry_points) {
+                continue;
+            }
+            if method.name == "<init>" {
+                state.instantiate(&class.name);
+            }
+            if let Some(id) = lookup_method(methods, &class.name, &method.name, &method.descriptor)
+            {
+                state.reach(id);
+            }
+        }
+    }
+
+    for provider in service_providers {
+        state.instantiate(provider);
+        for name in ["<init>", "<clinit>"] {
+            if let Some(id) = lookup_method(methods, provider, name, "()V") {
+                state.reach(id);
+            }
+        }
+    }
+
+    loop {
+        if let Some(caller) = state.worklist.pop() {
+            let Some((class, method)) = bodies.get(&caller) else {
+                continue;
+            };
+            let implicit = implicit_calls(class, method, hierarchy);
+            for class_name in &implicit.instantiated {
+                state.instantiate(class_name);
+            }
+            for inst in method
+                .cfg
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+            {
+                if inst.opcode == opcodes::NEW
+                    && let InstructionKind::Type(class_name) = &inst.kind
+                {
+                    state.instantiate(class_name);
+                }
+            }
+            for call in method.calls.iter().chain(&implicit.calls) {
                 match call.kind {
                     CallKind::Static | CallKind::Special => {
                         if let Some(callee) =
-                            lookup_method(methods, &call.owner, &call.name, &call.descriptor)
+                            state
+                                .resolver
+                                .resolve(&call.owner, &call.name, &call.descriptor)
                         {
-                            edges.push(CallEdge {
-                                caller: caller.clone(),
-                                callee,
-                                kind: call.kind,
-                                offset: call.offset,
-                            });
+                            state.link(&caller, callee, call.kind, call.offset);
                         }
                     }
                     CallKind::Virtual | CallKind::Interface => {
-                        if let Some(owner_candidate) =
-                            lookup_method(methods, &call.owner, &call.name, &call.descriptor)
-                        {
-                            edges.push(CallEdge {
-                                caller: caller.clone(),
-                                callee: owner_candidate,
-                                kind: call.kind,
-                                offset: call.offset,
-                            });
-                        }
-                        if let Some(descendants) = hierarchy.get(&call.owner) {
-                            for class_name in descendants {
-                                if let Some(candidate) =
-                                    lookup_method(methods, class_name, &call.name, &call.descriptor)
-                                {
-                                    edges.push(CallEdge {
-                                        caller: caller.clone(),
-                                        callee: candidate,
-                                        kind: call.kind,
-                                        offset: call.offset,
-                                    });
-                                }
-                            }
+                        let site = VirtualSite {
+                            caller: caller.clone(),
+                            name: call.name.clone(),
+                            descriptor: call.descriptor.clone(),
+                            kind: call.kind,
+                            offset: call.offset,
+                        };
+                        let receivers = state
+                            .instantiated_subtypes
+                            .get(&call.owner)
+                            .cloned()
+                            .unwrap_or_default();
+                        for receiver in receivers {
+                            state.dispatch(&receiver, &site);
                         }
+                        state
+                            .virtual_sites
+                            .entry(call.owner.clone())
+                            .or_default()
+                            .push(site);
                     }
                 }
             }
+        } else if let Some(class_name) = state.new_types.pop() {
+            // Revisit the recorded call sites whose declared owner is a supertype.
+            let supertypes = hierarchy
+                .superclass_chain(&class_name)
+                .into_iter()
+                .chain(hierarchy.superinterfaces(&class_name))
+                .map(str::to_string)
+                .collect::<BTreeSet<_>>();
+            for supertype in supertypes {
+                state
+                    .instantiated_subtypes
+                    .entry(supertype.clone())
+                    .or_default()
+                    .push(class_name.clone());
+                // Dispatching only adds edges and reachable methods, so the sites
+                // can be taken out while they are visited.
+                let Some(sites) = state.virtual_sites.remove(&supertype) else {
+                    continue;
+                };
+                for site in &sites {
+                    state.dispatch(&class_name, site);
+                }
+                state.virtual_sites.insert(supertype, sites);
+            }
+        } else {
+            break;
         }
     }
+
+    let mut edges = state.edges;
     edges.sort();
-    edges.dedup();
     edges
 }
 
-fn build_hierarchy(classes: &[Class]) -> BTreeMap<String, Vec<String>> {
-    let mut hierarchy = BTreeMap::new();
+/// Virtual or interface call recorded while its owner may gain instantiated subtypes.
+struct VirtualSite {
+    caller: Arc<MethodId>,
+    name: String,
+    descriptor: String,
+    kind: CallKind,
+    offset: u32,
+}
+
+/// Worklists of the RTA fixpoint.
+struct RtaState<'h, 'a> {
+    resolver: Resolver<'h, 'a>,
+    reachable: HashSet<Arc<MethodId>>,
+    worklist: Vec<Arc<MethodId>>,
+    instantiated: HashSet<String>,
+    /// Instantiated classes whose effect on recorded call sites is pending.
+    new_types: Vec<String>,
+    /// Recorded virtual call sites, keyed by the owner in the method reference.
+    virtual_sites: HashMap<String, Vec<VirtualSite>>,
+    /// Instantiated classes whose pending work is done, keyed by each of their
+    /// supertypes and themselves.
+    instantiated_subtypes: HashMap<String, Vec<String>>,
+    /// Added edges as (caller, offset, callee); the index hands out one `Arc` per
+    /// method, so pointers identify methods.
+    linked: HashSet<(*const MethodId, u32, *const MethodId)>,
+    edges: Vec<CallEdge>,
+}
+
+impl RtaState<'_, '_> {
+    fn reach(&mut self, method: Arc<MethodId>) {
+        if self.reachable.insert(method.clone()) {
+            self.worklist.push(method);
+        }
+    }
+
+    fn instantiate(&mut self, class_name: &str) {
+        if self.instantiated.insert(class_name.to_string()) {
+            self.new_types.push(class_name.to_string());
+        }
+    }
+
+    fn link(&mut self, caller: &Arc<MethodId>, callee: Arc<MethodId>, kind: CallKind, offset: u32) {
+        if !self
+            .linked
+            .insert((Arc::as_ptr(caller), offset, Arc::as_ptr(&callee)))
+        {
+            return;
+        }
+        self.edges.push(CallEdge {
+            caller: caller.clone(),
+            callee: callee.clone(),
+            kind,
+            offset,
+        });
+        self.reach(callee);
+    }
+
+    /// Add the edge for `site` when the receiver is an instance of `receiver`.
+    fn dispatch(&mut self, receiver: &str, site: &VirtualSite) {
+        if let Some(callee) = self.resolver.select(receiver, &site.name, &site.descriptor) {
+            self.link(&site.caller, callee, site.kind, site.offset);
+        }
+    }
+}
+
+/// JDK method that calls back into its receiver or an argument.
+struct Callback {
+    /// Declaring types of the JDK method; parsed subtypes match too.
+    owners: &'static [&'static str],
+    name: &'static str,
+    descriptor: &'static str,
+    /// Methods called back, as (owner, name, descriptor).
+    targets: &'static [(&'static str, &'static str, &'static str)],
+    /// Whether the receiver's own override of the first target is called.
+    on_receiver: bool,
+}
+
+const EXECUTORS: &[&str] = &[
+    "java/util/concurrent/Executor",
+    "java/util/concurrent/ExecutorService",
+    "java/util/concurrent/ScheduledExecutorService",
+    "java/util/concurrent/AbstractExecutorService",
+    "java/util/concurrent/ThreadPoolExecutor",
+    "java/util/concurrent/ScheduledThreadPoolExecutor",
+    "java/util/concurrent/ForkJoinPool",
+];
+
+const RUNNABLE_RUN: (&str, &str, &str) = ("java/lang/Runnable", "run", "()V");
+const CALLABLE_CALL: (&str, &str, &str) = (
+    "java/util/concurrent/Callable",
+    "call",
+    "()Ljava/lang/Object;",
+);
+
+const CALLBACKS: &[Callback] = &[
+    Callback {
+        owners: &["java/lang/Thread"],
+        name: "start",
+        descriptor: "()V",
+        targets: &[RUNNABLE_RUN],
+        on_receiver: true,
+    },
+    Callback {
+        owners: EXECUTORS,
+        name: "execute",
+        descriptor: "(Ljava/lang/Runnable;)V",
+        targets: &[RUNNABLE_RUN],
+        on_receiver: false,
+    },
+    Callback {
+        owners: EXECUTORS,
+        name: "submit",
+        descriptor: "(Ljava/lang/Runnable;)Ljava/util/concurrent/Future;",
+        targets: &[RUNNABLE_RUN],
+        on_receiver: false,
+    },
+    Callback {
+        owners: EXECUTORS,
+        name: "submit",
+        descriptor: "(Ljava/lang/Runnable;Ljava/lang/Object;)Ljava/util/concurrent/Future;",
+        targets: &[RUNNABLE_RUN],
+        on_receiver: false,
+    },
+    Callback {
+        owners: EXECUTORS,
+        name: "submit",
+        descriptor: "(Ljava/util/concurrent/Callable;)Ljava/util/concurrent/Future;",
+        targets: &[CALLABLE_CALL],
+        on_receiver: false,
+    },
+];
+
+/// Calls made on behalf of a method by reflection or the JDK, with the classes
+/// they may instantiate.
+#[derive(Default)]
+pub(crate) struct ImplicitCalls {
+    pub(crate) calls: Vec<CallSite>,
+    pub(crate) instantiated: Vec<String>,
+}
+
+/// Calls that `method` causes without invoking the target directly:
+/// - `Class.forName` initializes and may instantiate the parsed classes named by the
+///   method's string literals.
+/// - `getMethod` and `getDeclaredMethod` reach the methods named by its string
+///   literals in classes the method refers to by literal or class constant, or in
+///   its own class.
+/// - JDK methods in `CALLBACKS` call back into `Runnable.run` or `Callable.call`.
+pub(crate) fn implicit_calls<'a>(
+    class: &'a Class,
+    method: &'a Method,
+    hierarchy: &ClassHierarchy<'a>,
+) -> ImplicitCalls {
+    let mut implicit = ImplicitCalls::default();
+    let mut reflective_classes: BTreeSet<&str> = BTreeSet::new();
+    let mut reflective_offsets = Vec::new();
+    for call in &method.calls {
+        if call.owner == "java/lang/Class" {
+            match call.name.as_str() {
+                "forName" => {
+                    for literal in &method.string_literals {
+                        let class_name = literal.replace('.', "/");
+                        if let Some(parsed) = hierarchy.class(&class_name) {
+                            reflective_classes.insert(parsed.name.as_str());
+                            for (name, kind) in [
+                                ("<clinit>", CallKind::Static),
+                                ("<init>", CallKind::Special),
+                            ] {
+                                implicit.calls.push(synthetic_call(
+                                    &parsed.name,
+                                    name,
+                                    "()V",
+                                    kind,
+                                    call.offset,
+                                ));
+                            }
+                            implicit.instantiated.push(parsed.name.clone());
+                        }
+                    }
+                }
+                "getMethod" | "getDeclaredMethod" => reflective_offsets.push(call.offset),
+                _ => {}
+            }
+            continue;
+        }
+        for callback in CALLBACKS {
+            if call.name != callback.name
+                || call.descriptor != callback.descriptor
+                || !hierarchy.is_subtype_of(&call.owner, callback.owners)
+            {
+                continue;
+            }
+            if callback.on_receiver
+                && let Some((_, name, descriptor)) = callback.targets.first()
+            {
+                implicit.calls.push(synthetic_call(
+                    &call.owner,
+                    name,
+                    descriptor,
+                    CallKind::Virtual,
+                    call.offset,
+                ));
+            }
+            for (owner, name, descriptor) in callback.targets {
+                implicit.calls.push(synthetic_call(
+                    owner,
+                    name,
+                    descriptor,
+                    CallKind::Interface,
+                    call.offset,
+                ));
+            }
+        }
+    }
+
+    if reflective_offsets.is_empty() {
+        return implicit;
+    }
+    reflective_classes.insert(class.name.as_str());
+    for inst in method
+        .cfg
+        .blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+    {
+        if let InstructionKind::Constant(ConstantValue::Class(name)) = &inst.kind
+            && let Some(parsed) = hierarchy.class(name)
+        {
+            reflective_classes.insert(parsed.name.as_str());
+        }
+    }
+    for offset in reflective_offsets {
+        for class_name in &reflective_classes {
+            let Some(target) = hierarchy.class(class_name) else {
+                continue;
+            };
+            for candidate in &target.methods {
+                if candidate.name.starts_with('<')
+                    || !method.string_literals.contains(&candidate.name)
+                {
+                    continue;
+                }
+                implicit.calls.push(synthetic_call(
+                    &target.name,
+                    &candidate.name,
+                    &candidate.descriptor,
+                    CallKind::Special,
+                    offset,
+                ));
+            }
+        }
+    }
+    implicit
+}
+
+fn synthetic_call(
+    owner: &str,
+    name: &str,
+    descriptor: &str,
+    kind: CallKind,
+    offset: u32,
+) -> CallSite {
+    CallSite {
+        owner: owner.to_string(),
+        name: name.to_string(),
+        descriptor: descriptor.to_string(),
+        kind,
+        offset,
+    }
+}
+
+/// Supertypes and direct subtypes of the parsed classes and interfaces.
+pub(crate) struct ClassHierarchy<'a> {
+    classes: HashMap<&'a str, &'a Class>,
+    super_classes: HashMap<&'a str, &'a str>,
+    interfaces: HashMap<&'a str, &'a [String]>,
+    /// Classes extending and types implementing or extending each type.
+    subtypes: BTreeMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> ClassHierarchy<'a> {
+    pub(crate) fn class(&self, class_name: &str) -> Option<&'a Class> {
+        self.classes.get(class_name).copied()
+    }
+
+    /// Whether `class_name` is one of `supertypes` or a parsed subtype of one.
+    pub(crate) fn is_subtype_of(&self, class_name: &'a str, supertypes: &[&str]) -> bool {
+        supertypes.contains(&class_name)
+            || self
+                .superclass_chain(class_name)
+                .into_iter()
+                .chain(self.superinterfaces(class_name))
+                .any(|supertype| supertypes.contains(&supertype))
+    }
+
+    /// `class_name` followed by its superclasses, as far as they are parsed.
+    pub(crate) fn superclass_chain(&self, class_name: &'a str) -> Vec<&'a str> {
+        let mut chain = vec![class_name];
+        let mut current = class_name;
+        while let Some(super_name) = self.super_classes.get(current) {
+            if chain.contains(super_name) {
+                break;
+            }
+            chain.push(super_name);
+            current = super_name;
+        }
+        chain
+    }
+
+    /// Every interface implemented by `class_name`, directly or through its
+    /// superclasses and superinterfaces.
+    pub(crate) fn superinterfaces(&self, class_name: &'a str) -> BTreeSet<&'a str> {
+        let mut seen = BTreeSet::new();
+        let mut stack: Vec<&str> = self
+            .superclass_chain(class_name)
+            .into_iter()
+            .flat_map(|name| self.direct_interfaces(name))
+            .collect();
+        while let Some(interface) = stack.pop() {
+            if seen.insert(interface) {
+                stack.extend(self.direct_interfaces(interface));
+            }
+        }
+        seen
+    }
+
+    fn direct_interfaces(&self, class_name: &str) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.interfaces
+            .get(class_name)
+            .copied()
+            .unwrap_or_default()
+            .iter()
+            .map(String::as_str)
+    }
+
+    /// `type_name` and every type that extends or implements it, transitively.
+    fn subtypes_of<'b>(&self, type_name: &'b str) -> BTreeSet<&'b str>
+    where
+        'a: 'b,
+    {
+        let mut seen = BTreeSet::from([type_name]);
+        let mut stack = vec![type_name];
+        while let Some(current) = stack.pop() {
+            for subtype in self.subtypes.get(current).into_iter().flatten() {
+                if seen.insert(*subtype) {
+                    stack.push(*subtype);
+                }
+            }
+        }
+        seen
+    }
+}
+
+pub(crate) fn build_hierarchy(classes: &[Class]) -> ClassHierarchy<'_> {
+    let mut by_name = HashMap::new();
+    let mut super_classes = HashMap::new();
+    let mut interfaces = HashMap::new();
+    let mut subtypes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
     for class in classes {
+        by_name.insert(class.name.as_str(), class);
         if let Some(super_name) = &class.super_name {
-            hierarchy
-                .entry(super_name.clone())
-                .or_insert_with(Vec::new)
-                .push(class.name.clone());
+            super_classes.insert(class.name.as_str(), super_name.as_str());
+            subtypes
+                .entry(super_name.as_str())
+                .or_default()
+                .push(class.name.as_str());
+        }
+        interfaces.insert(class.name.as_str(), class.interfaces.as_slice());
+        for interface in &class.interfaces {
+            subtypes
+                .entry(interface.as_str())
+                .or_default()
+                .push(class.name.as_str());
         }
     }
-    for descendants in hierarchy.values_mut() {
+    for descendants in subtypes.values_mut() {
         descendants.sort();
         descendants.dedup();
     }
-    hierarchy
+    ClassHierarchy {
+        classes: by_name,
+        super_classes,
+        interfaces,
+        subtypes,
+    }
+}
+
+/// Method resolution and selection over the parsed classes, following JVMS §5.4.3.3,
+/// §5.4.3.4 and §5.4.6. Every declared method is indexed, abstract ones included,
+/// so a reference to an abstract method resolves to its declaration as in the JVM.
+pub(crate) struct Resolver<'h, 'a> {
+    hierarchy: &'h ClassHierarchy<'a>,
+    methods: &'h MethodIndex,
+    dispatch_cache: HashMap<(String, String, String), Vec<Arc<MethodId>>>,
+    /// Selected methods by receiver, name and descriptor.
+    selection_cache: MethodTable<Option<Arc<MethodId>>>,
+}
+
+impl<'h, 'a> Resolver<'h, 'a> {
+    pub(crate) fn new(hierarchy: &'h ClassHierarchy<'a>, methods: &'h MethodIndex) -> Self {
+        Self {
+            hierarchy,
+            methods,
+            dispatch_cache: HashMap::new(),
+            selection_cache: HashMap::new(),
+        }
+    }
+
+    /// Method a reference to `owner.name descriptor` resolves to: the nearest
+    /// declaration in the superclass chain, otherwise the maximally-specific
+    /// superinterface method. Constructors and initializers are never inherited.
+    pub(crate) fn resolve(
+        &self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Arc<MethodId>> {
+        if name == "<init>" || name == "<clinit>" {
+            return lookup_method(self.methods, owner, name, descriptor);
+        }
+        let Some((owner, _)) = self.hierarchy.interfaces.get_key_value(owner) else {
+            return lookup_method(self.methods, owner, name, descriptor);
+        };
+        self.hierarchy
+            .superclass_chain(owner)
+            .into_iter()
+            .find_map(|class_name| lookup_method(self.methods, class_name, name, descriptor))
+            .or_else(|| self.maximally_specific(owner, name, descriptor))
+    }
+
+    /// The unique superinterface method of `class_name` that no other candidate
+    /// overrides; `None` when there is none or the choice is ambiguous.
+    fn maximally_specific(
+        &self,
+        class_name: &'a str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Arc<MethodId>> {
+        let candidates: Vec<(&str, Arc<MethodId>)> = self
+            .hierarchy
+            .superinterfaces(class_name)
+            .into_iter()
+            .filter_map(|interface| {
+                lookup_method(self.methods, interface, name, descriptor)
+                    .map(|method| (interface, method))
+            })
+            .collect();
+        let overridden: HashSet<&str> = candidates
+            .iter()
+            .flat_map(|(interface, _)| self.hierarchy.superinterfaces(interface))
+            .collect();
+        let mut specific = candidates
+            .into_iter()
+            .filter(|(interface, _)| !overridden.contains(interface));
+        match (specific.next(), specific.next()) {
+            (Some((_, method)), None) => Some(method),
+            _ => None,
+        }
+    }
+
+    /// Method selected for a call on an instance of exactly `receiver`, cached because
+    /// RTA revisits the same call sites for every newly instantiated class.
+    pub(crate) fn select(
+        &mut self,
+        receiver: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Arc<MethodId>> {
+        if let Some(selected) = self
+            .selection_cache
+            .get(receiver)
+            .and_then(|by_name| by_name.get(name))
+            .and_then(|by_descriptor| by_descriptor.get(descriptor))
+        {
+            return selected.clone();
+        }
+        let selected = self.resolve(receiver, name, descriptor);
+        self.selection_cache
+            .entry(receiver.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_default()
+            .insert(descriptor.to_string(), selected.clone());
+        selected
+    }
+
+    /// Methods a virtual or interface call may select at runtime: the selected
+    /// method for the owner and for each of its parsed subtypes.
+    pub(crate) fn dispatch(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Vec<Arc<MethodId>> {
+        let key = (owner.to_string(), name.to_string(), descriptor.to_string());
+        if let Some(targets) = self.dispatch_cache.get(&key) {
+            return targets.clone();
+        }
+        let targets: Vec<Arc<MethodId>> = self
+            .hierarchy
+            .subtypes_of(owner)
+            .into_iter()
+            .filter_map(|receiver| self.resolve(receiver, name, descriptor))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        self.dispatch_cache.insert(key, targets.clone());
+        targets
+    }
 }
 
-type MethodIndex = HashMap<String, HashMap<String, HashMap<String, Arc<MethodId>>>>;
+/// Values keyed by class name, method name and descriptor.
+type MethodTable<T> = HashMap<String, HashMap<String, HashMap<String, T>>>;
 
-fn index_methods(classes: &[Class]) -> MethodIndex {
+pub(crate) type MethodIndex = MethodTable<Arc<MethodId>>;
+
+pub(crate) fn index_methods(classes: &[Class]) -> MethodIndex {
     let mut map = HashMap::new();
     for class in classes {
         let class_entry = map.entry(class.name.clone()).or_insert_with(HashMap::new);
@@ -164,7 +974,7 @@ fn index_methods(classes: &[Class]) -> MethodIndex {
     map
 }
 
-fn lookup_method(
+pub(crate) fn lookup_method(
     methods: &MethodIndex,
     class_name: &str,
     method_name: &str,
@@ -181,24 +991,12 @@ fn lookup_method(
 mod tests {
     use super::*;
     use crate::ir::{CallSite, Method, MethodAccess, MethodNullness};
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
 
-    fn class_with_method(name: &str, super_name: Option<&str>, method: &Method) -> Class {
-        Class {
+    fn method_with_calls(name: &str, calls: Vec<CallSite>) -> Method {
+        Method {
             name: name.to_string(),
-            super_name: super_name.map(str::to_string),
-            interfaces: Vec::new(),
-            referenced_classes: Vec::new(),
-            fields: Vec::new(),
-            methods: vec![method.clone()],
-            artifact_index: 0,
-            is_record: false,
-        }
-    }
-
-    #[test]
-    fn call_graph_includes_virtual_targets() {
-        let caller = Method {
-            name: "caller".to_string(),
             descriptor: "()V".to_string(),
             access: MethodAccess {
                 is_public: true,
@@ -207,40 +1005,72 @@ mod tests {
             },
             nullness: MethodNullness::unknown(0),
             bytecode: Vec::new(),
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: crate::ir::ControlFlowGraph {
                 blocks: Vec::new(),
                 edges: Vec::new(),
             },
-            calls: vec![CallSite {
-                owner: "com/example/Base".to_string(),
-                name: "target".to_string(),
-                descriptor: "()V".to_string(),
-                kind: CallKind::Virtual,
-                offset: 0,
-            }],
+            calls,
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
-        };
-        let base_method = Method {
-            name: "target".to_string(),
+            annotations: Vec::new(),
+        }
+    }
+
+    fn call(owner: &str, name: &str, kind: CallKind) -> CallSite {
+        CallSite {
+            owner: owner.to_string(),
+            name: name.to_string(),
             descriptor: "()V".to_string(),
-            access: MethodAccess {
-                is_public: true,
-                is_static: false,
-                is_abstract: false,
-            },
-            nullness: MethodNullness::unknown(0),
-            bytecode: Vec::new(),
-            line_numbers: Vec::new(),
-            cfg: crate::ir::ControlFlowGraph {
-                blocks: Vec::new(),
-                edges: Vec::new(),
-            },
-            calls: Vec::new(),
-            string_literals: Vec::new(),
-            exception_handlers: Vec::new(),
-        };
+            kind,
+            offset: 0,
+        }
+    }
+
+    fn class_with(
+        name: &str,
+        super_name: Option<&str>,
+        interfaces: &[&str],
+        methods: Vec<Method>,
+    ) -> Class {
+        Class {
+            name: name.to_string(),
+            super_name: super_name.map(str::to_string),
+            interfaces: interfaces.iter().map(|name| name.to_string()).collect(),
+            referenced_classes: Vec::new(),
+            fields: Vec::new(),
+            methods,
+            artifact_index: 0,
+            is_record: false,
+            annotations: Vec::new(),
+        }
+    }
+
+    fn class_with_method(name: &str, super_name: Option<&str>, method: &Method) -> Class {
+        class_with(name, super_name, &[], vec![method.clone()])
+    }
+
+    /// Callee classes of the edges leaving `Caller.caller`.
+    fn callees(classes: &[Class]) -> Vec<String> {
+        let (graph, _, _) =
+            build_call_graph_with_timings(classes, &CallGraphOptions::default(), |_| true);
+        graph
+            .edges
+            .iter()
+            .filter(|edge| edge.caller.class_name == "com/example/Caller")
+            .map(|edge| format!("{}.{}", edge.callee.class_name, edge.callee.name))
+            .collect()
+    }
+
+    #[test]
+    fn call_graph_includes_virtual_targets() {
+        let caller = method_with_calls(
+            "caller",
+            vec![call("com/example/Base", "target", CallKind::Virtual)],
+        );
+        let base_method = method_with_calls("target", Vec::new());
         let subclass_method = base_method.clone();
         let classes = vec![
             class_with_method("com/example/Caller", None, &caller),
@@ -252,8 +1082,329 @@ mod tests {
             ),
         ];
 
-        let (graph, _) = build_call_graph_with_timings(&classes);
+        let (graph, _, _) =
+            build_call_graph_with_timings(&classes, &CallGraphOptions::default(), |_| true);
 
         assert!(!graph.edges.is_empty());
     }
+
+    #[test]
+    fn call_graph_dispatches_interface_calls_to_transitive_implementations() {
+        let caller = method_with_calls(
+            "caller",
+            vec![call("com/example/Handler", "handle", CallKind::Interface)],
+        );
+        let handle = method_with_calls("handle", Vec::new());
+        let classes = vec![
+            class_with_method("com/example/Caller", None, &caller),
+            class_with("com/example/Handler", None, &[], Vec::new()),
+            class_with(
+                "com/example/AsyncHandler",
+                None,
+                &["com/example/Handler"],
+                Vec::new(),
+            ),
+            class_with(
+                "com/example/Direct",
+                None,
+                &["com/example/Handler"],
+                vec![handle.clone()],
+            ),
+            class_with(
+                "com/example/Queued",
+                None,
+                &["com/example/AsyncHandler"],
+                vec![handle.clone()],
+            ),
+            class_with(
+                "com/example/LoggingDirect",
+                Some("com/example/Direct"),
+                &[],
+                vec![handle],
+            ),
+            class_with(
+                "com/example/Unrelated",
+                None,
+                &[],
+                vec![method_with_calls("handle", Vec::new())],
+            ),
+        ];
+
+        assert_eq!(
+            vec![
+                "com/example/Direct.handle",
+                "com/example/LoggingDirect.handle",
+                "com/example/Queued.handle",
+            ],
+            callees(&classes)
+        );
+    }
+
+    #[test]
+    fn call_graph_resolves_abstract_declarations() {
+        let caller = method_with_calls(
+            "caller",
+            vec![call("com/example/Shape", "area", CallKind::Virtual)],
+        );
+        let mut area = method_with_calls("area", Vec::new());
+        area.access.is_abstract = true;
+        let classes = vec![
+            class_with_method("com/example/Caller", None, &caller),
+            class_with("com/example/Shape", None, &[], vec![area]),
+            class_with(
+                "com/example/Circle",
+                Some("com/example/Shape"),
+                &[],
+                vec![method_with_calls("area", Vec::new())],
+            ),
+        ];
+
+        assert_eq!(
+            vec!["com/example/Circle.area", "com/example/Shape.area"],
+            callees(&classes)
+        );
+    }
+
+    #[test]
+    fn call_graph_resolves_inherited_methods_through_superclasses() {
+        let caller = method_with_calls(
+            "caller",
+            vec![
+                call("com/example/Sub", "inherited", CallKind::Virtual),
+                call("com/example/Sub", "helper", CallKind::Static),
+            ],
+        );
+        let classes = vec![
+            class_with_method("com/example/Caller", None, &caller),
+            class_with(
+                "com/example/Base",
+                None,
+                &[],
+                vec![
+                    method_with_calls("inherited", Vec::new()),
+                    method_with_calls("helper", Vec::new()),
+                ],
+            ),
+            class_with(
+                "com/example/Middle",
+                Some("com/example/Base"),
+                &[],
+                Vec::new(),
+            ),
+            class_with(
+                "com/example/Sub",
+                Some("com/example/Middle"),
+                &[],
+                Vec::new(),
+            ),
+        ];
+
+        assert_eq!(
+            vec!["com/example/Base.helper", "com/example/Base.inherited"],
+            callees(&classes)
+        );
+    }
+
+    #[test]
+    fn call_graph_selects_maximally_specific_default_methods() {
+        let caller = method_with_calls(
+            "caller",
+            vec![
+                call("com/example/Impl", "greet", CallKind::Virtual),
+                call("com/example/Impl", "shared", CallKind::Virtual),
+            ],
+        );
+        let classes = vec![
+            class_with_method("com/example/Caller", None, &caller),
+            class_with(
+                "com/example/Greeter",
+                Some("java/lang/Object"),
+                &[],
+                vec![
+                    method_with_calls("greet", Vec::new()),
+                    method_with_calls("shared", Vec::new()),
+                ],
+            ),
+            class_with(
+                "com/example/LoudGreeter",
+                Some("java/lang/Object"),
+                &["com/example/Greeter"],
+                vec![method_with_calls("greet", Vec::new())],
+            ),
+            class_with(
+                "com/example/Impl",
+                Some("java/lang/Object"),
+                &["com/example/Greeter", "com/example/LoudGreeter"],
+                Vec::new(),
+            ),
+        ];
+
+        assert_eq!(
+            vec![
+                "com/example/Greeter.shared",
+                "com/example/LoudGreeter.greet",
+            ],
+            callees(&classes)
+        );
+    }
+
+    const TASKS: &str = r#"
+package com.example;
+interface Task {
+    void run();
+}
+class Used implements Task {
+    public void run() {}
+}
+class Unused implements Task {
+    public void run() {}
+}
+class Lazy implements Task {
+    public void run() {}
+}
+public class App {
+    public static void main(String[] args) {
+        Task task = make();
+        task.run();
+    }
+
+    private static Task make() {
+        return new Used();
+    }
+
+    private static Task never() {
+        return new Lazy();
+    }
+}
+"#;
+
+    /// Classes whose `run` the `task.run()` call in `App.main` dispatches to.
+    fn run_targets(algorithm: CallGraphAlgorithm) -> Vec<String> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/App.java".to_string(),
+            contents: TASKS.to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let (graph, _, _) = build_call_graph_with_timings(
+            &classes,
+            &CallGraphOptions {
+                algorithm,
+                ..CallGraphOptions::default()
+            },
+            |_| true,
+        );
+        graph
+            .edges
+            .iter()
+            .filter(|edge| edge.caller.name == "main" && edge.callee.name == "run")
+            .map(|edge| edge.callee.class_name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn call_graph_cha_dispatches_to_every_implementation() {
+        assert_eq!(
+            vec!["com/example/Lazy", "com/example/Unused", "com/example/Used"],
+            run_targets(CallGraphAlgorithm::Cha)
+        );
+    }
+
+    #[test]
+    fn call_graph_rta_dispatches_to_classes_instantiated_in_reachable_code() {
+        assert_eq!(
+            vec!["com/example/Used"],
+            run_targets(CallGraphAlgorithm::Rta)
+        );
+    }
+
+    #[test]
+    fn call_graph_pta_dispatches_to_objects_the_receiver_points_to() {
+        assert_eq!(
+            vec!["com/example/Used"],
+            run_targets(CallGraphAlgorithm::Pta)
+        );
+    }
+
+    #[test]
+    fn call_graph_models_reflection_and_jdk_callbacks() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/App.java".to_string(),
+            contents: r#"
+package com.example;
+import java.util.concurrent.Executor;
+class Plugin {
+    static {
+        init();
+    }
+
+    private static void init() {}
+
+    private void helper() {}
+}
+class Job implements Runnable {
+    public void run() {}
+}
+class Worker extends Thread {
+    public void run() {}
+}
+public class App {
+    public static void main(String[] args) throws Exception {
+        Class<?> type = Class.forName("com.example.Plugin");
+        type.getDeclaredMethod("helper");
+        new Worker().start();
+    }
+
+    public static void schedule(Executor executor) {
+        executor.execute(new Job());
+    }
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+
+        for algorithm in [
+            CallGraphAlgorithm::Cha,
+            CallGraphAlgorithm::Rta,
+            CallGraphAlgorithm::Pta,
+        ] {
+            let options = CallGraphOptions {
+                algorithm,
+                ..CallGraphOptions::default()
+            };
+            let (graph, _, _) = build_call_graph_with_timings(&classes, &options, |_| true);
+            let edges: BTreeSet<String> = graph
+                .edges
+                .iter()
+                .filter(|edge| edge.caller.class_name == "com/example/App")
+                .map(|edge| {
+                    format!(
+                        "{} -> {}.{}",
+                        edge.caller.name, edge.callee.class_name, edge.callee.name
+                    )
+                })
+                .collect();
+            for expected in [
+                "main -> com/example/Plugin.<clinit>",
+                "main -> com/example/Plugin.<init>",
+                "main -> com/example/Plugin.helper",
+                "main -> com/example/Worker.run",
+                "schedule -> com/example/Job.run",
+            ] {
+                assert!(edges.contains(expected), "{algorithm:?}: {edges:?}");
+            }
+        }
+    }
 }
@@ -0,0 +1,500 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::callgraph::{CallEdge, CallGraph, MethodId, MethodPattern};
+use crate::ir::{Class, Method};
+
+/// File format of an exported call graph.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Dot,
+    Json,
+    Graphml,
+}
+
+/// Level at which call graph nodes are reported.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Granularity {
+    #[default]
+    Method,
+    Class,
+    Package,
+}
+
+/// Which part of the call graph to export and how to collapse it.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    pub granularity: Granularity,
+    /// Keep only calls between analysis-target classes.
+    pub targets_only: bool,
+    /// Keep only calls reachable from these methods, given as `Class.method` or
+    /// `Class.method(descriptor)`.
+    pub roots: Vec<String>,
+    /// Maximum number of calls from a root; unlimited when `None`.
+    pub depth: Option<usize>,
+}
+
+/// Call graph reduced to the requested nodes and edges.
+#[derive(Debug, Default, Serialize)]
+pub struct ExportGraph {
+    pub nodes: Vec<String>,
+    pub edges: Vec<ExportEdge>,
+}
+
+/// Edge of an exported graph. Method-level edges describe one call site;
+/// collapsed edges aggregate every call between two classes or packages.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct ExportEdge {
+    pub source: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// Number of call sites represented by the edge.
+    pub calls: usize,
+}
+
+/// Select and collapse the edges of `graph` according to `options`.
+pub fn export_graph(
+    classes: &[Class],
+    graph: &CallGraph,
+    is_target: impl Fn(&Class) -> bool,
+    options: &ExportOptions,
+) -> Result<ExportGraph> {
+    let classes_by_name: BTreeMap<&str, &Class> = classes
+        .iter()
+        .map(|class| (class.name.as_str(), class))
+        .collect();
+    let is_target_class = |name: &str| {
+        classes_by_name
+            .get(name)
+            .is_some_and(|class| is_target(class))
+    };
+    let mut edges: Vec<&CallEdge> = graph
+        .edges
+        .iter()
+        .filter(|edge| {
+            !options.targets_only
+                || (is_target_class(&edge.caller.class_name)
+                    && is_target_class(&edge.callee.class_name))
+        })
+        .collect();
+    if !options.roots.is_empty() {
+        let roots = resolve_roots(&edges, classes, &options.roots)?;
+        edges = reachable_edges(&edges, &roots, options.depth);
+    }
+
+    let method_of = |id: &MethodId| -> Option<&Method> {
+        classes_by_name
+            .get(id.class_name.as_str())
+            .and_then(|class| {
+                class
+                    .methods
+                    .iter()
+                    .find(|method| method.name == id.name && method.descriptor == id.descriptor)
+            })
+    };
+    let mut export = ExportGraph::default();
+    let mut nodes = BTreeSet::new();
+    match options.granularity {
+        Granularity::Method => {
+            for edge in edges {
+                let source = method_label(&edge.caller);
+                let target = method_label(&edge.callee);
+                nodes.insert(source.clone());
+                nodes.insert(target.clone());
+                export.edges.push(ExportEdge {
+                    source,
+                    target,
+                    kind: Some(edge.kind.as_str()),
+                    offset: Some(edge.offset),
+                    line: method_of(&edge.caller)
+                        .and_then(|method| method.line_for_offset(edge.offset)),
+                    calls: 1,
+                });
+            }
+        }
+        Granularity::Class | Granularity::Package => {
+            let collapse = |id: &MethodId| match options.granularity {
+                Granularity::Package => package_name(&id.class_name).to_string(),
+                _ => id.class_name.clone(),
+            };
+            let mut counts: BTreeMap<(String, String), usize> = BTreeMap::new();
+            for edge in edges {
+                let source = collapse(&edge.caller);
+                let target = collapse(&edge.callee);
+                nodes.insert(source.clone());
+                nodes.insert(target.clone());
+                if source != target {
+                    *counts.entry((source, target)).or_default() += 1;
+                }
+            }
+            export.edges = counts
+                .into_iter()
+                .map(|((source, target), calls)| ExportEdge {
+                    source,
+                    target,
+                    kind: None,
+                    offset: None,
+                    line: None,
+                    calls,
+                })
+                .collect();
+        }
+    }
+    export.nodes = nodes.into_iter().collect();
+    Ok(export)
+}
+
+/// Methods matching the root specifications; unknown roots are an error.
+fn resolve_roots(
+    edges: &[&CallEdge],
+    classes: &[Class],
+    specs: &[String],
+) -> Result<BTreeSet<MethodId>> {
+    let mut candidates: BTreeSet<MethodId> = class_methods(classes);
+    candidates.extend(edges.iter().map(|edge| edge.caller.as_ref().clone()));
+    let mut roots = BTreeSet::new();
+    for spec in specs {
+        roots.extend(match_methods(&candidates, spec)?);
+    }
+    Ok(roots)
+}
+
+/// Methods of `classes` matching `spec`, given as `Class.method` or
+/// `Class.method(descriptor)` with `/` or `.` separated class names.
+pub fn find_methods(classes: &[Class], spec: &str) -> Result<Vec<MethodId>> {
+    match_methods(&class_methods(classes), spec)
+}
+
+fn class_methods(classes: &[Class]) -> BTreeSet<MethodId> {
+    classes
+        .iter()
+        .flat_map(|class| {
+            class.methods.iter().map(|method| MethodId {
+                class_name: class.name.clone(),
+                name: method.name.clone(),
+                descriptor: method.descriptor.clone(),
+            })
+        })
+        .collect()
+}
+
+fn match_methods(candidates: &BTreeSet<MethodId>, spec: &str) -> Result<Vec<MethodId>> {
+    let pattern = MethodPattern::parse(spec)?;
+    let matched: Vec<MethodId> = candidates
+        .iter()
+        .filter(|id| pattern.matches_id(id))
+        .cloned()
+        .collect();
+    if matched.is_empty() {
+        anyhow::bail!("method not found: {}", spec);
+    }
+    Ok(matched)
+}
+
+/// Edges on paths of at most `depth` calls from `roots`.
+fn reachable_edges<'a>(
+    edges: &[&'a CallEdge],
+    roots: &BTreeSet<MethodId>,
+    depth: Option<usize>,
+) -> Vec<&'a CallEdge> {
+    let mut outgoing: BTreeMap<&MethodId, Vec<&'a CallEdge>> = BTreeMap::new();
+    for edge in edges {
+        outgoing.entry(edge.caller.as_ref()).or_default().push(edge);
+    }
+    let mut distance: BTreeMap<&MethodId, usize> = roots.iter().map(|root| (root, 0)).collect();
+    let mut queue: VecDeque<&MethodId> = roots.iter().collect();
+    let mut kept = Vec::new();
+    while let Some(method) = queue.pop_front() {
+        let current = distance[method];
+        if depth.is_some_and(|depth| current >= depth) {
+            continue;
+        }
+        for edge in outgoing.get(method).into_iter().flatten() {
+            kept.push(*edge);
+            let callee = edge.callee.as_ref();
+            if !distance.contains_key(callee) {
+                distance.insert(callee, current + 1);
+                queue.push_back(callee);
+            }
+        }
+    }
+    kept.sort();
+    kept
+}
+
+fn method_label(id: &MethodId) -> String {
+    format!("{}.{}{}", id.class_name, id.name, id.descriptor)
+}
+
+fn package_name(class_name: &str) -> &str {
+    class_name
+        .rsplit_once('/')
+        .map(|(package, _)| package)
+        .unwrap_or("")
+}
+
+pub fn write_graph(
+    graph: &ExportGraph,
+    format: ExportFormat,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    match format {
+        ExportFormat::Dot => write_dot(graph, writer),
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, graph)
+                .context("failed to serialize call graph")?;
+            writer.write_all(b"\n")?;
+            Ok(())
+        }
+        ExportFormat::Graphml => write_graphml(graph, writer),
+    }
+    .context("failed to write call graph")
+}
+
+fn write_dot(graph: &ExportGraph, writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, "digraph callgraph {{")?;
+    for node in &graph.nodes {
+        writeln!(writer, "  {};", dot_quote(node))?;
+    }
+    for edge in &graph.edges {
+        let label = match (edge.kind, edge.line) {
+            (Some(kind), Some(line)) => format!("{} L{}", kind, line),
+            (Some(kind), None) => kind.to_string(),
+            _ => edge.calls.to_string(),
+        };
+        writeln!(
+            writer,
+            "  {} -> {} [label={}];",
+            dot_quote(&edge.source),
+            dot_quote(&edge.target),
+            dot_quote(&label)
+        )?;
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn write_graphml(graph: &ExportGraph, writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (id, ty) in [
+        ("kind", "string"),
+        ("offset", "int"),
+        ("line", "int"),
+        ("calls", "int"),
+    ] {
+        writeln!(
+            writer,
+            r#"  <key id="{id}" for="edge" attr.name="{id}" attr.type="{ty}"/>"#
+        )?;
+    }
+    writeln!(writer, r#"  <graph id="callgraph" edgedefault="directed">"#)?;
+    for node in &graph.nodes {
+        writeln!(writer, r#"    <node id="{}"/>"#, xml_escape(node))?;
+    }
+    for edge in &graph.edges {
+        writeln!(
+            writer,
+            r#"    <edge source="{}" target="{}">"#,
+            xml_escape(&edge.source),
+            xml_escape(&edge.target)
+        )?;
+        if let Some(kind) = edge.kind {
+            writeln!(writer, r#"      <data key="kind">{}</data>"#, kind)?;
+        }
+        if let Some(offset) = edge.offset {
+            writeln!(writer, r#"      <data key="offset">{}</data>"#, offset)?;
+        }
+        if let Some(line) = edge.line {
+            writeln!(writer, r#"      <data key="line">{}</data>"#, line)?;
+        }
+        writeln!(writer, r#"      <data key="calls">{}</data>"#, edge.calls)?;
+        writeln!(writer, "    </edge>")?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    Ok(())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::{CallGraphOptions, build_call_graph_with_timings};
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "com/example/app/App.java",
+            r#"
+package com.example.app;
+import com.example.util.Text;
+public class App {
+    public static void main(String[] args) {
+        run(args);
+    }
+
+    static void run(String[] args) {
+        Text.trim(args[0]);
+    }
+}
+"#,
+        ),
+        (
+            "com/example/util/Text.java",
+            r#"
+package com.example.util;
+public class Text {
+    public static String trim(String value) {
+        return strip(value);
+    }
+
+    static String strip(String value) {
+        return value.strip();
+    }
+}
+"#,
+        ),
+    ];
+
+    fn export(options: &ExportOptions) -> ExportGraph {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources: Vec<SourceFile> = SOURCES
+            .iter()
+            .map(|(path, contents)| SourceFile {
+                path: path.to_string(),
+                contents: contents.to_string(),
+            })
+            .collect();
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let (graph, _, _) =
+            build_call_graph_with_timings(&classes, &CallGraphOptions::default(), |_| true);
+        export_graph(&classes, &graph, |_| true, options).expect("export graph")
+    }
+
+    fn render(graph: &ExportGraph, format: ExportFormat) -> String {
+        let mut buffer = Vec::new();
+        write_graph(graph, format, &mut buffer).expect("write graph");
+        String::from_utf8(buffer).expect("utf-8 output")
+    }
+
+    #[test]
+    fn export_limits_depth_from_root_and_reports_lines() {
+        let graph = export(&ExportOptions {
+            roots: vec!["com.example.app.App.main".to_string()],
+            depth: Some(2),
+            ..ExportOptions::default()
+        });
+
+        let calls: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "com/example/app/App.main([Ljava/lang/String;)V",
+                    "com/example/app/App.run([Ljava/lang/String;)V"
+                ),
+                (
+                    "com/example/app/App.run([Ljava/lang/String;)V",
+                    "com/example/util/Text.trim(Ljava/lang/String;)Ljava/lang/String;"
+                ),
+            ],
+            calls
+        );
+        assert_eq!(Some("static"), graph.edges[0].kind);
+        assert_eq!(Some(6), graph.edges[0].line);
+        assert_eq!(Some(10), graph.edges[1].line);
+    }
+
+    #[test]
+    fn export_collapses_to_packages() {
+        let graph = export(&ExportOptions {
+            granularity: Granularity::Package,
+            ..ExportOptions::default()
+        });
+
+        assert_eq!(vec!["com/example/app", "com/example/util"], graph.nodes);
+        assert_eq!(
+            vec![ExportEdge {
+                source: "com/example/app".to_string(),
+                target: "com/example/util".to_string(),
+                kind: None,
+                offset: None,
+                line: None,
+                calls: 1,
+            }],
+            graph.edges
+        );
+    }
+
+    #[test]
+    fn export_writes_dot_json_and_graphml() {
+        let graph = export(&ExportOptions {
+            granularity: Granularity::Class,
+            ..ExportOptions::default()
+        });
+
+        let dot = render(&graph, ExportFormat::Dot);
+        assert!(dot.starts_with("digraph callgraph {"), "{dot}");
+        assert!(dot.contains(r#""com/example/app/App" -> "com/example/util/Text" [label="1"];"#));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&graph, ExportFormat::Json)).expect("parse json");
+        assert_eq!("com/example/util/Text", json["edges"][0]["target"]);
+        assert_eq!(1, json["edges"][0]["calls"]);
+
+        let graphml = render(&graph, ExportFormat::Graphml);
+        assert!(graphml.contains(r#"<node id="com/example/app/App"/>"#));
+        assert!(
+            graphml
+                .contains(r#"<edge source="com/example/app/App" target="com/example/util/Text">"#)
+        );
+    }
+
+    #[test]
+    fn export_rejects_unknown_roots() {
+        let result = export_graph(
+            &[],
+            &CallGraph::default(),
+            |_| true,
+            &ExportOptions {
+                roots: vec!["com/example/Missing.main".to_string()],
+                ..ExportOptions::default()
+            },
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -2,19 +2,19 @@ use std::collections::BTreeSet;
 
 use anyhow::{Context, Result};
 
-use crate::ir::{BasicBlock, ControlFlowGraph, EdgeKind, FlowEdge, Instruction};
+use crate::ir::{BasicBlock, ControlFlowGraph, EdgeKind, ExceptionHandler, FlowEdge, Instruction};
 use crate::opcodes;
 
 /// Build a control flow graph from bytecode instructions.
 pub(crate) fn build_cfg(
     code: &[u8],
     instructions: &[Instruction],
-    handlers: &[u32],
+    handlers: &[ExceptionHandler],
 ) -> Result<ControlFlowGraph> {
     let mut leaders = BTreeSet::new();
     leaders.insert(0u32);
     for handler in handlers {
-        leaders.insert(*handler);
+        leaders.insert(handler.handler_pc);
     }
     for inst in instructions {
         if let Some(targets) = branch_targets(code, inst.offset as usize)? {
@@ -24,7 +24,7 @@ pub(crate) fn build_cfg(
             let next = inst.offset + opcode_length(code, inst.offset as usize)? as u32;
             leaders.insert(next);
         }
-        if is_exit_opcode(inst.opcode) {
+        if is_exit(code, inst) {
             let next = inst.offset + opcode_length(code, inst.offset as usize)? as u32;
             leaders.insert(next);
         }
@@ -76,25 +76,40 @@ pub(crate) fn build_cfg(
                     kind: EdgeKind::Branch,
                 });
             }
-            if !is_unconditional_branch(last_inst.opcode) {
-                if let Some(next) = next_block_start(&blocks, block.end_offset) {
-                    edges.push(FlowEdge {
-                        from: block.start_offset,
-                        to: next,
-                        kind: EdgeKind::FallThrough,
-                    });
-                }
-            }
-        } else if !is_exit_opcode(last_inst.opcode) {
-            if let Some(next) = next_block_start(&blocks, block.end_offset) {
+            if !is_unconditional_branch(last_inst.opcode)
+                && let Some(next) = next_block_start(&blocks, block.end_offset)
+            {
                 edges.push(FlowEdge {
                     from: block.start_offset,
                     to: next,
                     kind: EdgeKind::FallThrough,
                 });
             }
+        } else if !is_exit(code, last_inst)
+            && let Some(next) = next_block_start(&blocks, block.end_offset)
+        {
+            edges.push(FlowEdge {
+                from: block.start_offset,
+                to: next,
+                kind: EdgeKind::FallThrough,
+            });
+        }
+    }
+
+    // Any block overlapping a protected range may transfer control to its handler.
+    for handler in handlers {
+        for block in &blocks {
+            if block.start_offset < handler.end_pc && block.end_offset > handler.start_pc {
+                edges.push(FlowEdge {
+                    from: block.start_offset,
+                    to: handler.handler_pc,
+                    kind: EdgeKind::Exception,
+                });
+            }
         }
     }
+    let mut seen = BTreeSet::new();
+    edges.retain(|edge| seen.insert((edge.from, edge.to, edge.kind)));
 
     Ok(ControlFlowGraph { blocks, edges })
 }
@@ -106,24 +121,27 @@ fn next_block_start(blocks: &[BasicBlock], offset: u32) -> Option<u32> {
         .map(|block| block.start_offset)
 }
 
-fn is_exit_opcode(opcode: u8) -> bool {
-    matches!(
-        opcode,
-        opcodes::IRETURN
-            | opcodes::LRETURN
-            | opcodes::FRETURN
-            | opcodes::DRETURN
-            | opcodes::ARETURN
-            | opcodes::RETURN
-            | opcodes::ATHROW
-    )
+fn is_exit(code: &[u8], inst: &Instruction) -> bool {
+    // `ret` leaves the subroutine; control resumes after the matching `jsr`.
+    let is_wide_ret = inst.opcode == opcodes::WIDE
+        && code.get(inst.offset as usize + 1).copied() == Some(opcodes::RET);
+    is_wide_ret
+        || matches!(
+            inst.opcode,
+            opcodes::IRETURN
+                | opcodes::LRETURN
+                | opcodes::FRETURN
+                | opcodes::DRETURN
+                | opcodes::ARETURN
+                | opcodes::RETURN
+                | opcodes::ATHROW
+                | opcodes::RET
+        )
 }
 
+/// Subroutine calls (`jsr`) are treated as returning to the next instruction.
 fn is_unconditional_branch(opcode: u8) -> bool {
-    matches!(
-        opcode,
-        opcodes::GOTO | opcodes::JSR | opcodes::GOTO_W | opcodes::JSR_W
-    )
+    matches!(opcode, opcodes::GOTO | opcodes::GOTO_W)
 }
 
 fn branch_targets(code: &[u8], offset: usize) -> Result<Option<Vec<u16>>> {
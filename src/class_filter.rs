@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Class name pattern: a glob where `.` and `/` both separate packages, `*` matches
+/// within a package segment and `**` across segments, or a regular expression
+/// prefixed with `regex:` that must match the whole `/` separated name. A glob
+/// without a separator, such as `*OuterClass`, matches the simple class name.
+#[derive(Clone, Debug)]
+pub struct ClassPattern {
+    spec: String,
+    regex: Regex,
+}
+
+impl ClassPattern {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let source = match spec.strip_prefix("regex:") {
+            Some(pattern) => format!("^(?:{pattern})$"),
+            None => glob_to_regex(spec),
+        };
+        let regex = Regex::new(&source).with_context(|| format!("invalid class pattern {spec}"))?;
+        Ok(Self {
+            spec: spec.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether the pattern matches `class_name` itself.
+    pub fn is_match(&self, class_name: &str) -> bool {
+        self.regex.is_match(class_name)
+    }
+
+    /// Whether the pattern matches `class_name`, or the class it is nested in.
+    pub fn matches(&self, class_name: &str) -> bool {
+        self.is_match(class_name)
+            || class_name
+                .match_indices('$')
+                .any(|(index, _)| self.regex.is_match(&class_name[..index]))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+}
+
+impl PartialEq for ClassPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.spec == other.spec
+    }
+}
+
+impl Eq for ClassPattern {}
+
+impl std::fmt::Display for ClassPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+/// Classes selected by `--include` and `--exclude`: those matching an include pattern,
+/// or every class when there is none, except those matching an exclude pattern.
+#[derive(Clone, Debug, Default)]
+pub struct ClassFilter {
+    pub include: Vec<ClassPattern>,
+    pub exclude: Vec<ClassPattern>,
+}
+
+impl ClassFilter {
+    pub fn parse(include: &[String], exclude: &[String]) -> Result<Self> {
+        let patterns = |specs: &[String]| {
+            specs
+                .iter()
+                .map(|spec| ClassPattern::parse(spec))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            include: patterns(include)?,
+            exclude: patterns(exclude)?,
+        })
+    }
+
+    pub fn selects(&self, class_name: &str) -> bool {
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(class_name)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches(class_name))
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut source = String::from("^");
+    if !glob.contains(['.', '/']) && !glob.starts_with("**") {
+        source.push_str("(?:.*/)?");
+    }
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if matches!(chars.peek(), Some('.' | '/')) {
+                    chars.next();
+                    source.push_str("(?:.*/)?");
+                } else {
+                    source.push_str(".*");
+                }
+            }
+            '*' => source.push_str("[^/]*"),
+            '?' => source.push_str("[^/]"),
+            '.' | '/' => source.push('/'),
+            _ => source.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    source.push('$');
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> ClassFilter {
+        let specs = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        ClassFilter::parse(&specs(include), &specs(exclude)).expect("class filter")
+    }
+
+    #[test]
+    fn class_filter_matches_globs_and_regexes() {
+        let filter = filter(
+            &["com.acme.**"],
+            &["**/generated/**", "*OuterClass", "regex:.*/jooq/.*Record"],
+        );
+
+        assert!(filter.selects("com/acme/App"));
+        assert!(filter.selects("com/acme/billing/Invoice$Line"));
+        assert!(!filter.selects("org/library/Util"));
+        assert!(!filter.selects("com/acme/generated/Api"));
+        assert!(!filter.selects("com/acme/proto/UserOuterClass"));
+        assert!(!filter.selects("com/acme/proto/UserOuterClass$User"));
+        assert!(!filter.selects("com/acme/jooq/tables/UserRecord"));
+        assert!(filter.selects("com/acme/jooq/Setup"));
+    }
+
+    #[test]
+    fn class_pattern_star_stays_within_a_package() {
+        let pattern = ClassPattern::parse("com.acme.*").expect("pattern");
+
+        assert!(pattern.matches("com/acme/App"));
+        assert!(!pattern.matches("com/acme/legacy/App"));
+        let matches = |spec: &str, class_name: &str| {
+            ClassPattern::parse(spec)
+                .expect("pattern")
+                .matches(class_name)
+        };
+        assert!(matches("**OuterClass", "com/acme/UserOuterClass"));
+        assert!(matches("App", "com/acme/App"));
+        assert!(!matches("App", "com/acme/WebApp"));
+        assert!(ClassPattern::parse("regex:(").is_err());
+    }
+}
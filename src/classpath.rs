@@ -76,6 +76,7 @@ mod tests {
                 methods: Vec::new(),
                 artifact_index: 0,
                 is_record: false,
+                annotations: Vec::new(),
             },
             Class {
                 name: "com/example/Bar".to_string(),
@@ -86,6 +87,7 @@ mod tests {
                 methods: Vec::new(),
                 artifact_index: 1,
                 is_record: false,
+                annotations: Vec::new(),
             },
         ];
 
@@ -105,6 +107,7 @@ mod tests {
             methods: Vec::new(),
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }];
 
         let result = resolve_classpath(&classes);
@@ -124,6 +127,7 @@ mod tests {
                 methods: Vec::new(),
                 artifact_index: 0,
                 is_record: false,
+                annotations: Vec::new(),
             },
             Class {
                 name: "com/example/Foo".to_string(),
@@ -134,6 +138,7 @@ mod tests {
                 methods: Vec::new(),
                 artifact_index: 1,
                 is_record: false,
+                annotations: Vec::new(),
             },
         ];
 
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_sarif::sarif::ResultLevel;
+
+use crate::callgraph::MethodPattern;
+use crate::class_filter::{ClassFilter, ClassPattern};
+use crate::engine::EngineOptions;
+use crate::rules::custom::CustomRuleConfig;
+
+/// File read from the working directory when `--config` is not given.
+pub const CONFIG_FILE_NAME: &str = "inspequte.toml";
+
+/// Project settings from `inspequte.toml`; command-line options take precedence.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// File the settings were read from; relative paths in it are resolved
+    /// against its directory.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub input: Option<PathBuf>,
+    #[serde(default)]
+    pub classpath: Vec<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    /// Class patterns selecting the classes rules report on; every input class when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Class patterns of input classes rules do not report on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    /// `[[custom-rules]]` tables declaring project-specific rules.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_rules: Vec<CustomRuleConfig>,
+}
+
+/// `[rules]` table: which rules run, and a `[rules.<RULE_ID>]` table per rule.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RulesConfig {
+    /// Rule IDs or categories to run; every rule when absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<Vec<String>>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(flatten)]
+    pub settings: BTreeMap<String, RuleConfig>,
+}
+
+/// Settings of a single rule: its severity and rule-specific options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RuleConfig {
+    /// SARIF level given to the rule's results: `error`, `warning`, `note` or `none`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<ResultLevel>,
+    #[serde(flatten)]
+    pub options: BTreeMap<String, toml::Value>,
+}
+
+/// Read `explicit`, or `inspequte.toml` in the working directory when it exists.
+pub fn load_config(explicit: Option<&Path>) -> Result<Config> {
+    let path = match explicit {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let discovered = PathBuf::from(CONFIG_FILE_NAME);
+            if !discovered.is_file() {
+                return Ok(Config::default());
+            }
+            discovered
+        }
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut config =
+        parse_config(&content, base).with_context(|| format!("invalid {}", path.display()))?;
+    config.path = Some(path);
+    Ok(config)
+}
+
+/// Parse configuration, resolving relative paths against `base`.
+pub fn parse_config(content: &str, base: &Path) -> Result<Config> {
+    let mut config: Config = toml::from_str(content)?;
+    let resolve = |path: &mut PathBuf| {
+        if path.is_relative() {
+            *path = base.join(&*path);
+        }
+    };
+    config.input.iter_mut().for_each(resolve);
+    config.classpath.iter_mut().for_each(resolve);
+    config.baseline.iter_mut().for_each(resolve);
+    Ok(config)
+}
+
+impl Config {
+    /// Apply class filters, custom rules, rule selection, severities and rule options
+    /// to `options`.
+    pub fn apply(&self, options: &mut EngineOptions) -> Result<()> {
+        let class_filter = ClassFilter::parse(&self.include, &self.exclude)?;
+        options.class_filter.include.extend(class_filter.include);
+        options.class_filter.exclude.extend(class_filter.exclude);
+        options
+            .custom_rules
+            .extend(self.custom_rules.iter().cloned());
+        if let Some(enabled) = &self.rules.enabled {
+            options.enabled_rules = Some(enabled.iter().cloned().collect());
+        }
+        options
+            .disabled_rules
+            .extend(self.rules.disabled.iter().cloned());
+        for (rule_id, settings) in &self.rules.settings {
+            if let Some(level) = settings.severity {
+                options.rule_levels.insert(rule_id.clone(), level);
+            }
+            for (name, value) in &settings.options {
+                apply_rule_option(options, rule_id, name, value)
+                    .with_context(|| format!("rules.{rule_id}.{name}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply_rule_option(
+    options: &mut EngineOptions,
+    rule_id: &str,
+    name: &str,
+    value: &toml::Value,
+) -> Result<()> {
+    match (rule_id, name) {
+        (_, "include") => {
+            let patterns = class_patterns(value)?;
+            let filter = options
+                .rule_class_filters
+                .entry(rule_id.to_string())
+                .or_default();
+            filter.include.extend(patterns);
+        }
+        (_, "exclude") => {
+            let patterns = class_patterns(value)?;
+            let filter = options
+                .rule_class_filters
+                .entry(rule_id.to_string())
+                .or_default();
+            filter.exclude.extend(patterns);
+        }
+        ("DEAD_CODE", "entry-points") => {
+            options.dead_code_entry_points = method_patterns(value)?;
+        }
+        ("INSECURE_API", "methods") => {
+            options.insecure_api_methods = Some(method_patterns(value)?);
+        }
+        ("METHOD_METRICS", "thresholds") => {
+            let thresholds = value
+                .as_table()
+                .context("expected a table of metric thresholds")?;
+            for (metric, threshold) in thresholds {
+                let threshold = match threshold {
+                    toml::Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                options.metric_thresholds.set(metric, &threshold)?;
+            }
+        }
+        _ => anyhow::bail!("unknown option for rule {rule_id}"),
+    }
+    Ok(())
+}
+
+fn method_patterns(value: &toml::Value) -> Result<Vec<MethodPattern>> {
+    value
+        .as_array()
+        .context("expected an array of methods")?
+        .iter()
+        .map(|spec| {
+            spec.as_str()
+                .context("expected a method name")
+                .and_then(MethodPattern::parse)
+        })
+        .collect()
+}
+
+fn class_patterns(value: &toml::Value) -> Result<Vec<ClassPattern>> {
+    value
+        .as_array()
+        .context("expected an array of class patterns")?
+        .iter()
+        .map(|spec| {
+            spec.as_str()
+                .context("expected a class pattern")
+                .and_then(ClassPattern::parse)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_resolves_paths_and_applies_rule_settings() {
+        let config = parse_config(
+            r#"
+input = "build/app.jar"
+classpath = ["lib/", "/opt/shared.jar"]
+exclude = ["**/generated/**"]
+
+[rules]
+disabled = ["METHOD_METRICS"]
+
+[rules.DEAD_CODE]
+severity = "note"
+entry-points = ["com.example.*Controller.handle*"]
+
+[rules.INSECURE_API]
+methods = ["java.lang.Runtime.exec"]
+
+[rules.NULLNESS]
+exclude = ["com.acme.legacy.**"]
+
+[[custom-rules]]
+id = "NO_SYSTEM_EXIT"
+message = "Do not exit the JVM"
+calls = ["java.lang.System.exit"]
+"#,
+            Path::new("project"),
+        )
+        .expect("parse config");
+
+        assert_eq!(Some(PathBuf::from("project/build/app.jar")), config.input);
+        assert_eq!(
+            vec![
+                PathBuf::from("project/lib/"),
+                PathBuf::from("/opt/shared.jar")
+            ],
+            config.classpath
+        );
+        let mut options = EngineOptions::default();
+        config.apply(&mut options).expect("apply config");
+        assert!(options.disabled_rules.contains("METHOD_METRICS"));
+        assert_eq!(
+            Some(&ResultLevel::Note),
+            options.rule_levels.get("DEAD_CODE")
+        );
+        assert!(options.dead_code_entry_points[0].matches(
+            "com/example/UserController",
+            "handleGet",
+            "()V"
+        ));
+        assert!(!options.dead_code_entry_points[0].matches(
+            "com/example/legacy/UserController",
+            "handleGet",
+            "()V"
+        ));
+        assert_eq!(
+            Some(vec![
+                MethodPattern::parse("java.lang.Runtime.exec").expect("pattern")
+            ]),
+            options.insecure_api_methods
+        );
+        assert_eq!("NO_SYSTEM_EXIT", options.custom_rules[0].id);
+        assert!(!options.class_filter.selects("com/acme/generated/Api"));
+        assert!(options.class_filter.selects("com/acme/legacy/Dao"));
+        assert!(!options.rule_class_filters["NULLNESS"].selects("com/acme/legacy/Dao"));
+    }
+
+    #[test]
+    fn config_rejects_unknown_keys() {
+        let unknown_key = parse_config("inputs = \"app.jar\"\n", Path::new(""));
+        assert!(unknown_key.is_err());
+
+        let config = parse_config("[rules.DEAD_CODE]\nentry-point = []\n", Path::new(""))
+            .expect("parse config");
+        let error = config
+            .apply(&mut EngineOptions::default())
+            .expect_err("unknown rule option");
+        assert!(format!("{error:#}").contains("rules.DEAD_CODE.entry-point"));
+
+        let bad_severity = parse_config("[rules.NULLNESS]\nseverity = \"fatal\"\n", Path::new(""));
+        assert!(bad_severity.is_err());
+    }
+}
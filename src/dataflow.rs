@@ -0,0 +1,733 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+
+use crate::ir::{BasicBlock, EdgeKind, FlowEdge, Instruction, Method};
+
+/// Direction in which facts propagate through the control flow graph.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Bounds that keep the solver from iterating forever on large or tall lattices.
+#[derive(Clone, Copy, Debug)]
+pub struct IterationLimits {
+    /// Visits to a single block before joins at its entry switch to widening.
+    pub widening_threshold: usize,
+    /// Total block visits allowed for one method before the solver gives up.
+    pub max_block_visits: usize,
+}
+
+impl Default for IterationLimits {
+    fn default() -> Self {
+        Self {
+            widening_threshold: 3,
+            max_block_visits: 50_000,
+        }
+    }
+}
+
+/// Edge being crossed when facts move from one block to another.
+pub struct EdgeContext<'a, D> {
+    pub edge: &'a FlowEdge,
+    /// Last instruction of the source block; `None` for exception edges.
+    pub terminator: Option<&'a Instruction>,
+    /// Forward only: state right before `terminator` executed, e.g. to see the operands of a
+    /// conditional branch.
+    pub before_terminator: Option<&'a D>,
+}
+
+/// Monotone dataflow problem solved over a method's control flow graph.
+pub trait Analysis {
+    /// Lattice element tracked at every program point.
+    type Domain: Clone + PartialEq;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    /// State at the method boundary: the entry block for forward analyses, blocks without
+    /// successors for backward analyses.
+    fn boundary_state(&self) -> Result<Self::Domain>;
+
+    fn join(&self, left: &Self::Domain, right: &Self::Domain) -> Self::Domain;
+
+    /// Accelerate convergence once a block was visited often; `next` already includes `previous`.
+    fn widen(&self, previous: &Self::Domain, next: &Self::Domain) -> Self::Domain {
+        let _ = previous;
+        next.clone()
+    }
+
+    /// Apply the effect of a single instruction.
+    fn transfer(&self, state: &mut Self::Domain, instruction: &Instruction) -> Result<()>;
+
+    /// Map a state observed at a potentially throwing instruction to a handler entry state.
+    fn exception_state(&self, state: &Self::Domain) -> Self::Domain {
+        state.clone()
+    }
+
+    /// Refine the state flowing along an edge; returning `None` marks the edge infeasible.
+    fn refine_edge(
+        &self,
+        edge: &EdgeContext<'_, Self::Domain>,
+        state: Self::Domain,
+    ) -> Option<Self::Domain> {
+        let _ = edge;
+        Some(state)
+    }
+}
+
+/// Fixpoint states keyed by basic block start offset.
+///
+/// States are positional regardless of direction: `entry_states` hold the state at the first
+/// instruction of a block and `exit_states` the state after its last instruction.
+#[derive(Clone, Debug)]
+pub struct DataflowResult<D> {
+    pub entry_states: BTreeMap<u32, D>,
+    pub exit_states: BTreeMap<u32, D>,
+    /// False when the iteration cap was hit before reaching a fixpoint.
+    pub converged: bool,
+}
+
+impl<D: Clone + PartialEq> DataflowResult<D> {
+    /// Visit every instruction of every reached block with the state flowing into it.
+    ///
+    /// Forward analyses see the state before each instruction in program order; backward
+    /// analyses see the state after each instruction in reverse order.
+    pub fn replay<A, F>(&self, method: &Method, analysis: &A, mut visit: F) -> Result<()>
+    where
+        A: Analysis<Domain = D>,
+        F: FnMut(&BasicBlock, &Instruction, &D) -> Result<()>,
+    {
+        for block in &method.cfg.blocks {
+            match analysis.direction() {
+                Direction::Forward => {
+                    let Some(state) = self.entry_states.get(&block.start_offset) else {
+                        continue;
+                    };
+                    let mut state = state.clone();
+                    for inst in &block.instructions {
+                        visit(block, inst, &state)?;
+                        analysis.transfer(&mut state, inst)?;
+                    }
+                }
+                Direction::Backward => {
+                    let Some(state) = self.exit_states.get(&block.start_offset) else {
+                        continue;
+                    };
+                    let mut state = state.clone();
+                    for inst in block.instructions.iter().rev() {
+                        visit(block, inst, &state)?;
+                        analysis.transfer(&mut state, inst)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Solve a dataflow problem with the default iteration limits.
+pub fn solve<A: Analysis>(method: &Method, analysis: &A) -> Result<DataflowResult<A::Domain>> {
+    solve_with_limits(method, analysis, IterationLimits::default())
+}
+
+pub fn solve_with_limits<A: Analysis>(
+    method: &Method,
+    analysis: &A,
+    limits: IterationLimits,
+) -> Result<DataflowResult<A::Domain>> {
+    let graph = BlockGraph::new(method);
+    match analysis.direction() {
+        Direction::Forward => solve_forward(&graph, analysis, limits),
+        Direction::Backward => solve_backward(&graph, analysis, limits),
+    }
+}
+
+/// Block lookup tables shared by both solver directions.
+struct BlockGraph<'a> {
+    blocks: Vec<&'a BasicBlock>,
+    index_by_offset: BTreeMap<u32, usize>,
+    successors: Vec<Vec<(usize, &'a FlowEdge)>>,
+    predecessors: Vec<Vec<(usize, &'a FlowEdge)>>,
+    /// Reverse postorder position of each block, rooted at the entry block.
+    order: Vec<usize>,
+}
+
+impl<'a> BlockGraph<'a> {
+    fn new(method: &'a Method) -> Self {
+        let blocks: Vec<&BasicBlock> = method.cfg.blocks.iter().collect();
+        let index_by_offset: BTreeMap<u32, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.start_offset, index))
+            .collect();
+        let mut successors = vec![Vec::new(); blocks.len()];
+        let mut predecessors = vec![Vec::new(); blocks.len()];
+        for edge in &method.cfg.edges {
+            let (Some(from), Some(to)) = (
+                index_by_offset.get(&edge.from).copied(),
+                index_by_offset.get(&edge.to).copied(),
+            ) else {
+                continue;
+            };
+            successors[from].push((to, edge));
+            predecessors[to].push((from, edge));
+        }
+        let order = reverse_postorder(&successors, index_by_offset.get(&0).copied());
+        Self {
+            blocks,
+            index_by_offset,
+            successors,
+            predecessors,
+            order,
+        }
+    }
+}
+
+fn reverse_postorder(successors: &[Vec<(usize, &FlowEdge)>], entry: Option<usize>) -> Vec<usize> {
+    let mut postorder = Vec::with_capacity(successors.len());
+    let mut visited = vec![false; successors.len()];
+    let roots = entry.into_iter().chain(0..successors.len());
+    for root in roots {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0usize)];
+        while let Some((node, next_child)) = stack.pop() {
+            if let Some((child, _)) = successors[node].get(next_child) {
+                stack.push((node, next_child + 1));
+                if !visited[*child] {
+                    visited[*child] = true;
+                    stack.push((*child, 0));
+                }
+            } else {
+                postorder.push(node);
+            }
+        }
+    }
+    let mut order = vec![0; successors.len()];
+    for (position, node) in postorder.iter().rev().enumerate() {
+        order[*node] = position;
+    }
+    order
+}
+
+fn solve_forward<A: Analysis>(
+    graph: &BlockGraph<'_>,
+    analysis: &A,
+    limits: IterationLimits,
+) -> Result<DataflowResult<A::Domain>> {
+    let mut entry_states: BTreeMap<u32, A::Domain> = BTreeMap::new();
+    let mut exit_states: BTreeMap<u32, A::Domain> = BTreeMap::new();
+    let mut visits = vec![0usize; graph.blocks.len()];
+    let mut worklist = BTreeSet::new();
+    if let Some(entry) = graph.index_by_offset.get(&0).copied() {
+        entry_states.insert(0, analysis.boundary_state()?);
+        worklist.insert((graph.order[entry], entry));
+    }
+
+    let mut total_visits = 0usize;
+    while let Some((_, index)) = worklist.pop_first() {
+        total_visits += 1;
+        if total_visits > limits.max_block_visits {
+            return Ok(DataflowResult {
+                entry_states,
+                exit_states,
+                converged: false,
+            });
+        }
+        let block = graph.blocks[index];
+        let mut state = entry_states[&block.start_offset].clone();
+        let has_exception_edges = graph.successors[index]
+            .iter()
+            .any(|(_, edge)| edge.kind == EdgeKind::Exception);
+        let mut exceptional: Option<A::Domain> = None;
+        let mut before_terminator = None;
+        for (position, inst) in block.instructions.iter().enumerate() {
+            if has_exception_edges {
+                let thrown = analysis.exception_state(&state);
+                exceptional = Some(match exceptional {
+                    Some(existing) => analysis.join(&existing, &thrown),
+                    None => thrown,
+                });
+            }
+            if position + 1 == block.instructions.len() {
+                before_terminator = Some(state.clone());
+            }
+            analysis.transfer(&mut state, inst)?;
+        }
+        exit_states.insert(block.start_offset, state.clone());
+
+        for (successor, edge) in &graph.successors[index] {
+            let (incoming, context) = if edge.kind == EdgeKind::Exception {
+                let Some(thrown) = exceptional.clone() else {
+                    continue;
+                };
+                let context = EdgeContext {
+                    edge,
+                    terminator: None,
+                    before_terminator: None,
+                };
+                (thrown, context)
+            } else {
+                let context = EdgeContext {
+                    edge,
+                    terminator: block.instructions.last(),
+                    before_terminator: before_terminator.as_ref(),
+                };
+                (state.clone(), context)
+            };
+            let Some(incoming) = analysis.refine_edge(&context, incoming) else {
+                continue;
+            };
+            let target = graph.blocks[*successor].start_offset;
+            let updated = match entry_states.get(&target) {
+                Some(existing) => {
+                    let joined = analysis.join(existing, &incoming);
+                    let joined = if visits[*successor] >= limits.widening_threshold {
+                        analysis.widen(existing, &joined)
+                    } else {
+                        joined
+                    };
+                    if &joined == existing {
+                        continue;
+                    }
+                    joined
+                }
+                None => incoming,
+            };
+            entry_states.insert(target, updated);
+            worklist.insert((graph.order[*successor], *successor));
+        }
+        visits[index] += 1;
+    }
+
+    Ok(DataflowResult {
+        entry_states,
+        exit_states,
+        converged: true,
+    })
+}
+
+fn solve_backward<A: Analysis>(
+    graph: &BlockGraph<'_>,
+    analysis: &A,
+    limits: IterationLimits,
+) -> Result<DataflowResult<A::Domain>> {
+    let mut entry_states: BTreeMap<u32, A::Domain> = BTreeMap::new();
+    let mut exit_states: BTreeMap<u32, A::Domain> = BTreeMap::new();
+    let mut visits = vec![0usize; graph.blocks.len()];
+    // Postorder puts successors first, which is the natural order for backward problems.
+    let priority = |index: usize| graph.blocks.len() - graph.order[index];
+    let mut worklist = BTreeSet::new();
+    for (index, successors) in graph.successors.iter().enumerate() {
+        if successors.is_empty() {
+            exit_states.insert(graph.blocks[index].start_offset, analysis.boundary_state()?);
+            worklist.insert((priority(index), index));
+        }
+    }
+
+    let mut total_visits = 0usize;
+    loop {
+        let Some((_, index)) = worklist.pop_first() else {
+            // Blocks that never reach an exit (e.g. infinite loops) start from the boundary.
+            let unvisited = (0..graph.blocks.len())
+                .find(|index| !exit_states.contains_key(&graph.blocks[*index].start_offset));
+            let Some(index) = unvisited else {
+                break;
+            };
+            exit_states.insert(graph.blocks[index].start_offset, analysis.boundary_state()?);
+            worklist.insert((priority(index), index));
+            continue;
+        };
+        total_visits += 1;
+        if total_visits > limits.max_block_visits {
+            return Ok(DataflowResult {
+                entry_states,
+                exit_states,
+                converged: false,
+            });
+        }
+        let block = graph.blocks[index];
+        let mut state = exit_states[&block.start_offset].clone();
+        for inst in block.instructions.iter().rev() {
+            analysis.transfer(&mut state, inst)?;
+        }
+        entry_states.insert(block.start_offset, state.clone());
+
+        for (predecessor, edge) in &graph.predecessors[index] {
+            let pred_block = graph.blocks[*predecessor];
+            let context = EdgeContext {
+                edge,
+                terminator: if edge.kind == EdgeKind::Exception {
+                    None
+                } else {
+                    pred_block.instructions.last()
+                },
+                before_terminator: None,
+            };
+            let incoming = if edge.kind == EdgeKind::Exception {
+                analysis.exception_state(&state)
+            } else {
+                state.clone()
+            };
+            let Some(incoming) = analysis.refine_edge(&context, incoming) else {
+                continue;
+            };
+            let target = pred_block.start_offset;
+            let updated = match exit_states.get(&target) {
+                Some(existing) => {
+                    let joined = analysis.join(existing, &incoming);
+                    let joined = if visits[*predecessor] >= limits.widening_threshold {
+                        analysis.widen(existing, &joined)
+                    } else {
+                        joined
+                    };
+                    if &joined == existing {
+                        continue;
+                    }
+                    joined
+                }
+                None => incoming,
+            };
+            exit_states.insert(target, updated);
+            worklist.insert((priority(*predecessor), *predecessor));
+        }
+        visits[index] += 1;
+    }
+
+    Ok(DataflowResult {
+        entry_states,
+        exit_states,
+        converged: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{ControlFlowGraph, InstructionKind, MethodAccess, MethodNullness};
+    use crate::opcodes;
+
+    fn inst(offset: u32, opcode: u8) -> Instruction {
+        Instruction {
+            offset,
+            opcode,
+            kind: InstructionKind::Other(opcode),
+        }
+    }
+
+    fn block(start: u32, end: u32, instructions: Vec<Instruction>) -> BasicBlock {
+        BasicBlock {
+            start_offset: start,
+            end_offset: end,
+            instructions,
+        }
+    }
+
+    fn edge(from: u32, to: u32, kind: EdgeKind) -> FlowEdge {
+        FlowEdge { from, to, kind }
+    }
+
+    fn method_with_cfg(cfg: ControlFlowGraph) -> Method {
+        Method {
+            name: "run".to_string(),
+            descriptor: "()V".to_string(),
+            access: MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            nullness: MethodNullness::unknown(0),
+            bytecode: vec![0],
+            max_stack: 0,
+            max_locals: 0,
+            line_numbers: Vec::new(),
+            cfg,
+            calls: Vec::new(),
+            string_literals: Vec::new(),
+            exception_handlers: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Diamond: 0 -> {3, 6} -> 9, plus a loop 9 -> 0 for widening.
+    fn diamond_with_loop() -> Method {
+        method_with_cfg(ControlFlowGraph {
+            blocks: vec![
+                block(0, 3, vec![inst(0, opcodes::IFNULL)]),
+                block(3, 6, vec![inst(3, opcodes::NOP), inst(4, opcodes::GOTO)]),
+                block(6, 9, vec![inst(6, opcodes::NOP)]),
+                block(9, 12, vec![inst(9, opcodes::GOTO)]),
+            ],
+            edges: vec![
+                edge(0, 6, EdgeKind::Branch),
+                edge(0, 3, EdgeKind::FallThrough),
+                edge(3, 9, EdgeKind::Branch),
+                edge(6, 9, EdgeKind::FallThrough),
+                edge(9, 0, EdgeKind::Branch),
+            ],
+        })
+    }
+
+    /// Counts NOP instructions on the longest path, saturating through widening.
+    struct NopCounter;
+
+    impl Analysis for NopCounter {
+        type Domain = u32;
+
+        fn boundary_state(&self) -> Result<u32> {
+            Ok(0)
+        }
+
+        fn join(&self, left: &u32, right: &u32) -> u32 {
+            *left.max(right)
+        }
+
+        fn widen(&self, previous: &u32, next: &u32) -> u32 {
+            if next > previous { u32::MAX } else { *previous }
+        }
+
+        fn transfer(&self, state: &mut u32, instruction: &Instruction) -> Result<()> {
+            if instruction.opcode == opcodes::NOP {
+                *state = state.saturating_add(1);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn forward_solver_widens_loops_to_fixpoint() {
+        let method = diamond_with_loop();
+
+        let result = solve(&method, &NopCounter).expect("solve");
+
+        assert!(result.converged);
+        assert_eq!(result.entry_states[&0], u32::MAX);
+        assert_eq!(result.entry_states[&9], u32::MAX);
+    }
+
+    #[test]
+    fn forward_solver_stops_at_iteration_cap() {
+        let method = diamond_with_loop();
+        let limits = IterationLimits {
+            widening_threshold: usize::MAX,
+            max_block_visits: 10,
+        };
+
+        let result = solve_with_limits(&method, &NopCounter, limits).expect("solve");
+
+        assert!(!result.converged);
+    }
+
+    /// Tracks which branch edges were taken, pruning the branch edge of IFNULL.
+    struct PruneBranch;
+
+    impl Analysis for PruneBranch {
+        type Domain = BTreeSet<u32>;
+
+        fn boundary_state(&self) -> Result<Self::Domain> {
+            Ok(BTreeSet::new())
+        }
+
+        fn join(&self, left: &Self::Domain, right: &Self::Domain) -> Self::Domain {
+            left.union(right).copied().collect()
+        }
+
+        fn transfer(&self, state: &mut Self::Domain, instruction: &Instruction) -> Result<()> {
+            state.insert(instruction.offset);
+            Ok(())
+        }
+
+        fn refine_edge(
+            &self,
+            edge: &EdgeContext<'_, Self::Domain>,
+            state: Self::Domain,
+        ) -> Option<Self::Domain> {
+            let is_ifnull = edge
+                .terminator
+                .is_some_and(|inst| inst.opcode == opcodes::IFNULL);
+            if is_ifnull && edge.edge.kind == EdgeKind::Branch {
+                assert!(edge.before_terminator.is_some());
+                return None;
+            }
+            Some(state)
+        }
+    }
+
+    #[test]
+    fn forward_solver_skips_infeasible_edges() {
+        let method = diamond_with_loop();
+
+        let result = solve(&method, &PruneBranch).expect("solve");
+
+        assert!(!result.entry_states.contains_key(&6));
+        assert!(result.entry_states[&9].contains(&3));
+        assert!(!result.entry_states[&9].contains(&6));
+    }
+
+    #[test]
+    fn forward_solver_propagates_exception_edges() {
+        let method = method_with_cfg(ControlFlowGraph {
+            blocks: vec![
+                block(0, 2, vec![inst(0, opcodes::NOP), inst(1, opcodes::RETURN)]),
+                block(2, 3, vec![inst(2, opcodes::ATHROW)]),
+            ],
+            edges: vec![edge(0, 2, EdgeKind::Exception)],
+        });
+
+        let result = solve(&method, &NopCounter).expect("solve");
+
+        // The handler sees the join of the states before each protected instruction.
+        assert_eq!(result.entry_states[&2], 1);
+    }
+
+    /// Backward analysis collecting offsets that may execute later.
+    struct Upcoming;
+
+    impl Analysis for Upcoming {
+        type Domain = BTreeSet<u32>;
+
+        fn direction(&self) -> Direction {
+            Direction::Backward
+        }
+
+        fn boundary_state(&self) -> Result<Self::Domain> {
+            Ok(BTreeSet::new())
+        }
+
+        fn join(&self, left: &Self::Domain, right: &Self::Domain) -> Self::Domain {
+            left.union(right).copied().collect()
+        }
+
+        fn transfer(&self, state: &mut Self::Domain, instruction: &Instruction) -> Result<()> {
+            state.insert(instruction.offset);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn backward_solver_reaches_predecessors() {
+        let method = method_with_cfg(ControlFlowGraph {
+            blocks: vec![
+                block(0, 3, vec![inst(0, opcodes::IFNULL)]),
+                block(3, 4, vec![inst(3, opcodes::RETURN)]),
+                block(6, 7, vec![inst(6, opcodes::RETURN)]),
+            ],
+            edges: vec![
+                edge(0, 6, EdgeKind::Branch),
+                edge(0, 3, EdgeKind::FallThrough),
+            ],
+        });
+
+        let result = solve(&method, &Upcoming).expect("solve");
+
+        assert!(result.converged);
+        let at_entry = &result.entry_states[&0];
+        assert!(at_entry.contains(&0) && at_entry.contains(&3) && at_entry.contains(&6));
+        assert_eq!(result.entry_states[&3].len(), 1);
+    }
+
+    /// Runs the wrapped analysis from the exits towards the entry.
+    struct Backward<A>(A);
+
+    impl<A: Analysis> Analysis for Backward<A> {
+        type Domain = A::Domain;
+
+        fn direction(&self) -> Direction {
+            Direction::Backward
+        }
+
+        fn boundary_state(&self) -> Result<Self::Domain> {
+            self.0.boundary_state()
+        }
+
+        fn join(&self, left: &Self::Domain, right: &Self::Domain) -> Self::Domain {
+            self.0.join(left, right)
+        }
+
+        fn widen(&self, previous: &Self::Domain, next: &Self::Domain) -> Self::Domain {
+            self.0.widen(previous, next)
+        }
+
+        fn transfer(&self, state: &mut Self::Domain, instruction: &Instruction) -> Result<()> {
+            self.0.transfer(state, instruction)
+        }
+    }
+
+    #[test]
+    fn backward_solver_covers_loops_without_exits() {
+        let method = diamond_with_loop();
+
+        let result = solve(&method, &Upcoming).expect("solve");
+
+        assert!(result.converged);
+        let everything = BTreeSet::from([0, 3, 4, 6, 9]);
+        for offset in [0, 3, 6, 9] {
+            assert_eq!(result.entry_states[&offset], everything);
+        }
+    }
+
+    #[test]
+    fn backward_solver_widens_loops_and_stops_at_iteration_cap() {
+        let method = diamond_with_loop();
+
+        let widened = solve(&method, &Backward(NopCounter)).expect("solve");
+        let limits = IterationLimits {
+            widening_threshold: usize::MAX,
+            max_block_visits: 10,
+        };
+        let capped = solve_with_limits(&method, &Backward(NopCounter), limits).expect("solve");
+
+        assert!(widened.converged);
+        assert_eq!(widened.entry_states[&9], u32::MAX);
+        assert!(!capped.converged);
+    }
+
+    #[test]
+    fn backward_solver_propagates_handler_states_to_protected_blocks() {
+        let method = method_with_cfg(ControlFlowGraph {
+            blocks: vec![
+                block(0, 2, vec![inst(0, opcodes::NOP), inst(1, opcodes::RETURN)]),
+                block(2, 4, vec![inst(2, opcodes::NOP), inst(3, opcodes::ATHROW)]),
+            ],
+            edges: vec![edge(0, 2, EdgeKind::Exception)],
+        });
+
+        let result = solve(&method, &Backward(NopCounter)).expect("solve");
+
+        assert_eq!(result.exit_states[&0], 1);
+        assert_eq!(result.entry_states[&0], 2);
+    }
+
+    #[test]
+    fn backward_replay_visits_instructions_in_reverse() {
+        let method = method_with_cfg(ControlFlowGraph {
+            blocks: vec![block(
+                0,
+                3,
+                vec![
+                    inst(0, opcodes::NOP),
+                    inst(1, opcodes::NOP),
+                    inst(2, opcodes::RETURN),
+                ],
+            )],
+            edges: Vec::new(),
+        });
+        let result = solve(&method, &Upcoming).expect("solve");
+
+        let mut visited = Vec::new();
+        result
+            .replay(&method, &Upcoming, |_, inst, later| {
+                visited.push((inst.offset, later.len()));
+                Ok(())
+            })
+            .expect("replay");
+
+        assert_eq!(vec![(2, 0), (1, 1), (0, 2)], visited);
+    }
+}
@@ -1,31 +1,73 @@
-use std::collections::{BTreeMap, BTreeSet};
-use std::time::Instant;
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde_sarif::sarif::Artifact;
-use serde_sarif::sarif::{MultiformatMessageString, ReportingDescriptor, Result as SarifResult};
+use serde_sarif::sarif::{
+    MultiformatMessageString, PropertyBag, ReportingConfiguration, ReportingDescriptor,
+    Result as SarifResult, ResultLevel,
+};
 
-use crate::callgraph::{CallGraph, build_call_graph_with_timings};
+use crate::callgraph::{
+    CallGraph, CallGraphAlgorithm, CallGraphOptions, MethodId, MethodPattern,
+    build_call_graph_with_timings,
+};
+use crate::class_filter::ClassFilter;
 use crate::classpath::ClasspathIndex;
-use crate::ir::Class;
+use crate::ir::{Class, Method};
+use crate::numeric::{NumericMethod, analyze_ranges};
+use crate::pointsto::{PointsTo, analyze_points_to};
 use crate::rules::{
-    Rule, RuleMetadata, array_equals::ArrayEqualsRule, dead_code::DeadCodeRule,
-    empty_catch::EmptyCatchRule, ineffective_equals::IneffectiveEqualsRule,
-    insecure_api::InsecureApiRule, nullness::NullnessRule,
+    RULE_CATEGORIES, Rule, RuleMetadata, RuleVisitor,
+    array_equals::ArrayEqualsRule,
+    custom::{CustomRule, CustomRuleConfig},
+    dead_code::DeadCodeRule,
+    empty_catch::EmptyCatchRule,
+    ineffective_equals::IneffectiveEqualsRule,
+    insecure_api::InsecureApiRule,
+    method_metrics::{MethodMetricsRule, MetricThresholds},
+    nullness::NullnessRule,
+    numeric_range::NumericRangeRule,
     record_array_field::RecordArrayFieldRule,
+    unreachable_code::UnreachableCodeRule,
+    vulnerable_method::{VulnerableMethod, VulnerableMethodRule},
 };
+use crate::ssa::{SsaMethod, lift};
+use crate::summary::{Summaries, SummaryAnalysis, compute_summaries};
+use crate::trace;
 
 /// Inputs shared by analysis rules.
-pub(crate) struct AnalysisContext {
-    pub(crate) classes: Vec<Class>,
+pub struct AnalysisContext {
+    pub classes: Vec<Class>,
     #[allow(dead_code)]
     pub(crate) classpath: ClasspathIndex,
-    pub(crate) call_graph: CallGraph,
+    pub call_graph: CallGraph,
     artifact_uris: BTreeMap<i64, String>,
     analysis_target_artifacts: BTreeSet<i64>,
     artifact_parents: BTreeMap<i64, i64>,
+    /// SSA forms lifted on first request.
+    ssa_cache: Mutex<BTreeMap<MethodId, Arc<SsaMethod>>>,
+    /// Numeric ranges computed on first request.
+    numeric_cache: Mutex<BTreeMap<MethodId, Arc<NumericMethod>>>,
+    /// Method summaries by summary type, computed on first request.
+    summary_cache: Mutex<HashMap<TypeId, Arc<OnceLock<SummaryCell>>>>,
+    /// Points-to result, from call graph construction or computed on first request.
+    points_to: OnceLock<Option<Arc<PointsTo>>>,
+    service_providers: Vec<String>,
+    entry_points: Vec<MethodPattern>,
+    points_to_budget: Duration,
+    /// Position of each class in `classes` by name, built on first lookup.
+    class_index: OnceLock<BTreeMap<String, usize>>,
+    class_filter: ClassFilter,
 }
 
+/// Summaries of one summary type, as cached by `AnalysisContext::summaries`.
+type SummaryCell = Arc<dyn Any + Send + Sync>;
+
 /// Timing breakdown for context construction.
 pub(crate) struct ContextTimings {
     pub(crate) call_graph_duration_ms: u128,
@@ -35,38 +77,215 @@ pub(crate) struct ContextTimings {
     pub(crate) call_graph_edges_duration_ms: u128,
 }
 
+/// Settings that change how rules behave.
+#[derive(Clone, Debug, Default)]
+pub struct EngineOptions {
+    pub metric_thresholds: MetricThresholds,
+    pub call_graph: CallGraphAlgorithm,
+    /// Time the points-to analysis may take; the call graph default when unset.
+    pub points_to_budget: Option<Duration>,
+    pub vulnerable_methods: Vec<VulnerableMethod>,
+    /// Rule IDs or categories to run; every rule when unset.
+    pub enabled_rules: Option<BTreeSet<String>>,
+    /// Rule IDs or categories to skip, even when enabled.
+    pub disabled_rules: BTreeSet<String>,
+    /// SARIF level given to every result of a rule, replacing the rule's own levels.
+    pub rule_levels: BTreeMap<String, ResultLevel>,
+    /// Methods treated as reachable in addition to public API and known frameworks.
+    pub dead_code_entry_points: Vec<MethodPattern>,
+    /// Methods reported as insecure in place of the built-in list.
+    pub insecure_api_methods: Option<Vec<MethodPattern>>,
+    /// Rules declared in the configuration file, run alongside the built-in rules.
+    pub custom_rules: Vec<CustomRuleConfig>,
+    /// Input classes rules report on, applied when the analysis context is built.
+    pub class_filter: ClassFilter,
+    /// Classes each rule reports on, in addition to `class_filter`, by rule ID.
+    pub rule_class_filters: BTreeMap<String, ClassFilter>,
+}
+
 /// Analysis engine that executes configured rules.
-pub(crate) struct Engine {
+pub struct Engine {
     rules: Vec<Box<dyn Rule>>,
+    levels: BTreeMap<String, ResultLevel>,
+    /// IDs of rules that are not built in, and so have no published documentation.
+    custom_rule_ids: BTreeSet<String>,
+    class_filters: BTreeMap<String, ClassFilter>,
 }
 
 impl Engine {
+    #[cfg(test)]
     pub(crate) fn new() -> Self {
+        Self::with_options(EngineOptions::default()).expect("default engine options")
+    }
+
+    pub fn with_options(options: EngineOptions) -> Result<Self> {
+        Self::with_rules(options, Vec::new())
+    }
+
+    /// Built-in rules configured by `options`, plus `custom_rules` and the rules declared
+    /// in `options.custom_rules`. Custom rules are selected and leveled like built-in
+    /// ones; their IDs must be unique.
+    pub fn with_rules(
+        options: EngineOptions,
+        mut custom_rules: Vec<Box<dyn Rule>>,
+    ) -> Result<Self> {
+        for config in &options.custom_rules {
+            custom_rules.push(Box::new(CustomRule::new(config)?));
+        }
+        let custom_rule_ids = custom_rules
+            .iter()
+            .map(|rule| rule.metadata().id.into_owned())
+            .collect();
+        let insecure_api = match options.insecure_api_methods {
+            Some(methods) => InsecureApiRule { methods },
+            None => InsecureApiRule::default(),
+        };
         let mut rules: Vec<Box<dyn Rule>> = vec![
             Box::new(ArrayEqualsRule),
-            Box::new(DeadCodeRule),
+            Box::new(DeadCodeRule {
+                entry_points: options.dead_code_entry_points,
+            }),
             Box::new(NullnessRule),
             Box::new(EmptyCatchRule),
-            Box::new(InsecureApiRule),
+            Box::new(insecure_api),
             Box::new(IneffectiveEqualsRule),
             Box::new(RecordArrayFieldRule),
+            Box::new(UnreachableCodeRule),
+            Box::new(NumericRangeRule),
+            Box::new(MethodMetricsRule {
+                thresholds: options.metric_thresholds,
+            }),
+            Box::new(VulnerableMethodRule {
+                methods: options.vulnerable_methods,
+            }),
         ];
-        rules.sort_by(|a, b| a.metadata().id.cmp(b.metadata().id));
-        Self { rules }
+        rules.extend(custom_rules);
+        rules.sort_by_key(|rule| rule.metadata().id);
+        if let Some(pair) = rules
+            .windows(2)
+            .find(|pair| pair[0].metadata().id == pair[1].metadata().id)
+        {
+            anyhow::bail!("duplicate rule ID {}", pair[0].metadata().id);
+        }
+
+        let metadata: Vec<RuleMetadata> = rules.iter().map(|rule| rule.metadata()).collect();
+        let selectors = options
+            .enabled_rules
+            .iter()
+            .flatten()
+            .chain(&options.disabled_rules);
+        for selector in selectors {
+            let known = RULE_CATEGORIES.contains(&selector.as_str())
+                || metadata.iter().any(|rule| rule.is_selected_by(selector));
+            if !known {
+                anyhow::bail!(
+                    "unknown rule or category {selector}; known rules: {}; categories: {}",
+                    rule_ids(&metadata),
+                    RULE_CATEGORIES.join(", ")
+                );
+            }
+        }
+        for rule_id in options
+            .rule_levels
+            .keys()
+            .chain(options.rule_class_filters.keys())
+        {
+            if !metadata.iter().any(|rule| rule.id == *rule_id) {
+                anyhow::bail!(
+                    "unknown rule {rule_id}; known rules: {}",
+                    rule_ids(&metadata)
+                );
+            }
+        }
+        let is_enabled = |rule: &RuleMetadata| {
+            let selected = |selectors: &BTreeSet<String>| {
+                selectors
+                    .iter()
+                    .any(|selector| rule.is_selected_by(selector))
+            };
+            options.enabled_rules.as_ref().is_none_or(selected)
+                && !selected(&options.disabled_rules)
+        };
+        rules.retain(|rule| is_enabled(&rule.metadata()));
+        Ok(Self {
+            rules,
+            levels: options.rule_levels,
+            custom_rule_ids,
+            class_filters: options.rule_class_filters,
+        })
     }
 
-    pub(crate) fn analyze(&self, context: AnalysisContext) -> Result<EngineOutput> {
+    /// Metadata of the rules this engine runs, sorted by ID.
+    pub fn rule_metadata(&self) -> Vec<RuleMetadata> {
+        self.rules.iter().map(|rule| rule.metadata()).collect()
+    }
+
+    pub fn analyze(&self, context: AnalysisContext) -> Result<EngineOutput> {
         let mut rules = Vec::new();
         let mut results = Vec::new();
 
-        for rule in &self.rules {
-            let metadata = rule.metadata();
-            rules.push(rule_descriptor(&metadata));
-            let mut rule_results = rule.run(&context)?;
+        let mut rule_stats = Vec::new();
+
+        let mut failures = Vec::new();
+
+        let metadata = self.rule_metadata();
+        let visitors: Vec<(&str, &dyn RuleVisitor)> = self
+            .rules
+            .iter()
+            .zip(&metadata)
+            .filter_map(|(rule, metadata)| Some((metadata.id.as_ref(), rule.visitor()?)))
+            .collect();
+        let mut visited = {
+            let _span = trace::span("rule", "rule visitors");
+            visit_target_classes(&context, &visitors).into_iter()
+        };
+        for (rule, metadata) in self.rules.iter().zip(&metadata) {
+            let level = self.levels.get(metadata.id.as_ref()).copied();
+            rules.push(rule_descriptor(
+                metadata,
+                level.unwrap_or(metadata.default_level),
+                !self.custom_rule_ids.contains(metadata.id.as_ref()),
+            ));
+            let (mut rule_results, duration) = match rule.visitor() {
+                Some(_) => {
+                    let output = visited.next().unwrap_or_default();
+                    failures.extend(output.failures);
+                    (output.results, output.duration)
+                }
+                None => {
+                    let mut span = trace::span("rule", metadata.id.as_ref());
+                    let started_at = Instant::now();
+                    let results = match isolate(&metadata.id, None, None, || rule.run(&context)) {
+                        Ok(output) => {
+                            failures.extend(output.failures);
+                            output.results
+                        }
+                        Err(failure) => {
+                            failures.push(failure);
+                            Vec::new()
+                        }
+                    };
+                    span.arg("results", results.len());
+                    (results, started_at.elapsed())
+                }
+            };
+            if let Some(filter) = self.class_filters.get(metadata.id.as_ref()) {
+                rule_results.retain(|result| {
+                    result_class_name(result).is_none_or(|class_name| filter.selects(class_name))
+                });
+            }
+            rule_stats.push(RuleStats {
+                id: metadata.id.to_string(),
+                duration_ms: duration.as_millis(),
+                result_count: rule_results.len(),
+            });
             for result in &mut rule_results {
                 if result.rule_id.is_none() {
                     result.rule_id = Some(metadata.id.to_string());
                 }
+                if level.is_some() || result.level.is_none() {
+                    result.level = Some(level.unwrap_or(metadata.default_level));
+                }
             }
             results.extend(rule_results);
         }
@@ -79,14 +298,52 @@ impl Engine {
             left_id.cmp(right_id).then(left_msg.cmp(&right_msg))
         });
 
-        Ok(EngineOutput { rules, results })
+        Ok(EngineOutput {
+            rules,
+            results,
+            rule_stats,
+            failures,
+        })
     }
 }
 
 /// Aggregated SARIF payload from rule execution.
-pub(crate) struct EngineOutput {
-    pub(crate) rules: Vec<ReportingDescriptor>,
+pub struct EngineOutput {
+    pub rules: Vec<ReportingDescriptor>,
+    pub results: Vec<SarifResult>,
+    /// Cost and yield of each rule, in rule order.
+    pub rule_stats: Vec<RuleStats>,
+    /// Errors and panics of rules, in rule order; the other results are complete.
+    pub failures: Vec<RuleFailure>,
+}
+
+/// Error or panic that stopped a rule on a method, a class or, for rules that
+/// analyze the whole program, everything. Results the rule reported for the failed
+/// part are dropped.
+#[derive(Clone, Debug)]
+pub struct RuleFailure {
+    pub rule_id: String,
+    pub class_name: Option<String>,
+    /// Method name and descriptor, e.g. `run()V`.
+    pub method: Option<String>,
+    pub message: String,
+    pub panicked: bool,
+}
+
+/// Time a rule took and how many results it reported before baseline filtering.
+/// Visitor time is summed over the threads of the shared traversal.
+pub struct RuleStats {
+    pub id: String,
+    pub duration_ms: u128,
+    pub result_count: usize,
+}
+
+/// Results of one visitor and the time spent in its callbacks.
+#[derive(Clone, Default)]
+pub(crate) struct VisitorOutput {
     pub(crate) results: Vec<SarifResult>,
+    pub(crate) duration: Duration,
+    pub(crate) failures: Vec<RuleFailure>,
 }
 
 #[cfg(test)]
@@ -95,7 +352,13 @@ pub(crate) fn build_context(
     classpath: ClasspathIndex,
     artifacts: &[Artifact],
 ) -> AnalysisContext {
-    let (context, _) = build_context_with_timings(classes, classpath, artifacts);
+    let (context, _) = build_context_with_timings(
+        classes,
+        classpath,
+        artifacts,
+        &CallGraphOptions::default(),
+        ClassFilter::default(),
+    );
     context
 }
 
@@ -103,13 +366,24 @@ pub(crate) fn build_context_with_timings(
     classes: Vec<Class>,
     classpath: ClasspathIndex,
     artifacts: &[Artifact],
+    call_graph_options: &CallGraphOptions,
+    class_filter: ClassFilter,
 ) -> (AnalysisContext, ContextTimings) {
-    let call_graph_started_at = Instant::now();
-    let (call_graph, call_graph_timings) = build_call_graph_with_timings(&classes);
-    let call_graph_duration_ms = call_graph_started_at.elapsed().as_millis();
     let artifact_started_at = Instant::now();
     let (analysis_target_artifacts, artifact_parents, artifact_uris) = analyze_artifacts(artifacts);
     let artifact_duration_ms = artifact_started_at.elapsed().as_millis();
+    let call_graph_started_at = Instant::now();
+    let call_graph_span = trace::span("callgraph", "call graph");
+    let (call_graph, points_to, call_graph_timings) =
+        build_call_graph_with_timings(&classes, call_graph_options, |class| {
+            is_target_artifact(
+                class.artifact_index,
+                &analysis_target_artifacts,
+                &artifact_parents,
+            )
+        });
+    drop(call_graph_span);
+    let call_graph_duration_ms = call_graph_started_at.elapsed().as_millis();
     let timings = ContextTimings {
         call_graph_duration_ms,
         artifact_duration_ms,
@@ -124,42 +398,194 @@ pub(crate) fn build_context_with_timings(
         artifact_uris,
         analysis_target_artifacts,
         artifact_parents,
+        ssa_cache: Mutex::new(BTreeMap::new()),
+        numeric_cache: Mutex::new(BTreeMap::new()),
+        summary_cache: Mutex::new(HashMap::new()),
+        points_to: OnceLock::new(),
+        service_providers: call_graph_options.service_providers.clone(),
+        entry_points: call_graph_options.entry_points.clone(),
+        points_to_budget: call_graph_options.points_to_budget,
+        class_index: OnceLock::new(),
+        class_filter,
     };
+    if let Some(points_to) = points_to {
+        let _ = context.points_to.set(Some(Arc::new(points_to)));
+    }
     (context, timings)
 }
 
-fn rule_descriptor(metadata: &RuleMetadata) -> ReportingDescriptor {
-    ReportingDescriptor::builder()
-        .id(metadata.id)
-        .name(metadata.name)
+/// Class of the primary location of `result`, from its logical location.
+fn result_class_name(result: &SarifResult) -> Option<&str> {
+    let logical = result
+        .locations
+        .as_ref()?
+        .first()?
+        .logical_locations
+        .as_ref()?
+        .first()?;
+    let name = logical.name.as_deref()?;
+    match logical.kind.as_deref() {
+        Some("function") => {
+            let signature = name
+                .split_once('(')
+                .map_or(name, |(signature, _)| signature);
+            signature.rsplit_once('.').map(|(class_name, _)| class_name)
+        }
+        _ => Some(name),
+    }
+}
+
+fn rule_ids(metadata: &[RuleMetadata]) -> String {
+    metadata
+        .iter()
+        .map(|rule| rule.id.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn rule_descriptor(
+    metadata: &RuleMetadata,
+    level: ResultLevel,
+    built_in: bool,
+) -> ReportingDescriptor {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "precision".to_string(),
+        serde_json::json!(metadata.precision.as_str()),
+    );
+    if let Some(severity) = metadata.security_severity {
+        properties.insert(
+            "security-severity".to_string(),
+            serde_json::json!(format!("{severity:.1}")),
+        );
+    }
+    let descriptor = ReportingDescriptor::builder()
+        .id(metadata.id.as_ref())
+        .name(metadata.name.as_ref())
+        .default_configuration(
+            ReportingConfiguration::builder()
+                .level(serde_json::json!(level))
+                .build(),
+        )
         .short_description(
             MultiformatMessageString::builder()
-                .text(metadata.description)
+                .text(metadata.description.as_ref())
+                .build(),
+        )
+        .full_description(
+            MultiformatMessageString::builder()
+                .text(metadata.full_description())
                 .build(),
         )
-        .build()
+        .help(
+            MultiformatMessageString::builder()
+                .text(metadata.full_description())
+                .markdown(metadata.help.as_ref())
+                .build(),
+        )
+        .properties(
+            PropertyBag::builder()
+                .tags(metadata.sarif_tags())
+                .additional_properties(properties)
+                .build(),
+        );
+    if built_in {
+        descriptor.help_uri(metadata.help_uri()).build()
+    } else {
+        descriptor.build()
+    }
 }
 
 impl AnalysisContext {
-    pub(crate) fn is_analysis_target_class(&self, class: &Class) -> bool {
-        if self.analysis_target_artifacts.is_empty() {
-            return true;
-        }
-        let mut current = Some(class.artifact_index);
-        while let Some(index) = current {
-            if self.analysis_target_artifacts.contains(&index) {
-                return true;
-            }
-            current = self.artifact_parents.get(&index).copied();
-        }
-        false
+    /// Scanned class named `name`, from the input or the classpath.
+    pub fn class(&self, name: &str) -> Option<&Class> {
+        let index = self.class_index.get_or_init(|| {
+            self.classes
+                .iter()
+                .enumerate()
+                .map(|(index, class)| (class.name.clone(), index))
+                .collect()
+        });
+        index.get(name).map(|&index| &self.classes[index])
+    }
+
+    /// Whether rules report on `class`: it comes from the input rather than the
+    /// classpath, and `--include` and `--exclude` select it.
+    pub fn is_analysis_target_class(&self, class: &Class) -> bool {
+        self.is_input_class(class) && self.class_filter.selects(&class.name)
+    }
+
+    /// Whether `class` comes from the input, even if filtered out. Such classes call
+    /// into the rest of the program like any other input class.
+    pub(crate) fn is_input_class(&self, class: &Class) -> bool {
+        is_target_artifact(
+            class.artifact_index,
+            &self.analysis_target_artifacts,
+            &self.artifact_parents,
+        )
+    }
+
+    /// SSA form of `method`, lifted lazily and shared between rules.
+    pub fn ssa(&self, class: &Class, method: &Method) -> Result<Arc<SsaMethod>> {
+        cached(&self.ssa_cache, class, method, || {
+            lift(&class.name, method).with_context(|| {
+                format!("lift {}.{}{}", class.name, method.name, method.descriptor)
+            })
+        })
+    }
+
+    /// Constant and interval facts for `method`, computed lazily and shared between rules.
+    pub(crate) fn numeric(&self, class: &Class, method: &Method) -> Result<Arc<NumericMethod>> {
+        cached(&self.numeric_cache, class, method, || {
+            analyze_ranges(&class.name, method).with_context(|| {
+                format!(
+                    "analyze ranges of {}.{}{}",
+                    class.name, method.name, method.descriptor
+                )
+            })
+        })
+    }
+
+    /// Bottom-up summaries of `analysis` for every parsed method, computed on first
+    /// request and shared between rules using the same summary type.
+    pub(crate) fn summaries<A: SummaryAnalysis>(&self, analysis: &A) -> Arc<Summaries<A::Summary>> {
+        // Rules visiting classes in parallel wait for the first computation of a summary
+        // type instead of repeating it.
+        let cell = self
+            .summary_cache
+            .lock()
+            .expect("summary cache lock")
+            .entry(TypeId::of::<A::Summary>())
+            .or_default()
+            .clone();
+        cell.get_or_init(|| Arc::new(compute_summaries(&self.classes, &self.call_graph, analysis)))
+            .clone()
+            .downcast()
+            .expect("summaries cached by their type")
+    }
+
+    /// Points-to result over the parsed classes, reused from `--callgraph pta` or
+    /// computed on first request; `None` when it exceeds its time budget.
+    pub fn points_to(&self) -> Option<Arc<PointsTo>> {
+        self.points_to
+            .get_or_init(|| {
+                analyze_points_to(
+                    &self.classes,
+                    &self.service_providers,
+                    &self.entry_points,
+                    |class| self.is_input_class(class),
+                    self.points_to_budget,
+                )
+                .map(Arc::new)
+            })
+            .clone()
     }
 
     pub(crate) fn artifact_uri(&self, index: i64) -> Option<&str> {
         self.artifact_uris.get(&index).map(|value| value.as_str())
     }
 
-    pub(crate) fn class_artifact_uri(&self, class: &Class) -> Option<String> {
+    pub fn class_artifact_uri(&self, class: &Class) -> Option<String> {
         let uri = self.artifact_uri(class.artifact_index)?;
         if uri.ends_with(".class") {
             return Some(uri.to_string());
@@ -171,6 +597,168 @@ impl AnalysisContext {
     }
 }
 
+/// Look up a per-method analysis result, computing and storing it on a miss.
+fn cached<T>(
+    cache: &Mutex<BTreeMap<MethodId, Arc<T>>>,
+    class: &Class,
+    method: &Method,
+    compute: impl FnOnce() -> Result<T>,
+) -> Result<Arc<T>> {
+    let key = MethodId {
+        class_name: class.name.clone(),
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
+    };
+    if let Some(value) = cache.lock().expect("analysis cache lock").get(&key) {
+        return Ok(Arc::clone(value));
+    }
+    let value = Arc::new(compute()?);
+    cache
+        .lock()
+        .expect("analysis cache lock")
+        .insert(key, Arc::clone(&value));
+    Ok(value)
+}
+
+/// Visit every analysis-target class with each visitor, calling it for the class, its
+/// methods and their instructions. Classes are split into contiguous chunks visited
+/// in parallel, each visitor in turn under a trace span named by its rule ID; results
+/// are returned per visitor in class order, as a sequential traversal would produce
+/// them.
+pub(crate) fn visit_target_classes(
+    context: &AnalysisContext,
+    visitors: &[(&str, &dyn RuleVisitor)],
+) -> Vec<VisitorOutput> {
+    let targets: Vec<&Class> = context
+        .classes
+        .iter()
+        .filter(|class| context.is_analysis_target_class(class))
+        .collect();
+    let threads = thread::available_parallelism().map_or(1, |count| count.get());
+    let chunk_size = targets.len().div_ceil(threads).max(1);
+    let visit_chunk = |classes: &[&Class]| -> Vec<VisitorOutput> {
+        let mut span = trace::span("rule", "visit classes");
+        span.arg("classes", classes.len());
+        let mut outputs = vec![VisitorOutput::default(); visitors.len()];
+        for ((rule_id, visitor), output) in visitors.iter().zip(outputs.iter_mut()) {
+            let mut span = trace::span("rule", *rule_id);
+            let started_at = Instant::now();
+            for class in classes {
+                visit_class(context, rule_id, *visitor, class, output);
+            }
+            output.duration = started_at.elapsed();
+            span.arg("results", output.results.len());
+        }
+        outputs
+    };
+    let chunks: Vec<Vec<VisitorOutput>> = thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || visit_chunk(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("rule visitor thread panicked"))
+            .collect()
+    });
+    let mut outputs = vec![VisitorOutput::default(); visitors.len()];
+    for chunk in chunks {
+        for (output, chunk_output) in outputs.iter_mut().zip(chunk) {
+            output.results.extend(chunk_output.results);
+            output.duration += chunk_output.duration;
+            output.failures.extend(chunk_output.failures);
+        }
+    }
+    outputs
+}
+
+/// Call `visitor` for `class` and then for each method and its instructions. A failure
+/// drops the results of the class, or of the method, and skips to the next.
+fn visit_class(
+    context: &AnalysisContext,
+    rule_id: &str,
+    visitor: &dyn RuleVisitor,
+    class: &Class,
+    output: &mut VisitorOutput,
+) {
+    let results = &mut output.results;
+    let kept = results.len();
+    if let Err(failure) = isolate(rule_id, Some(class), None, || {
+        visitor.visit_class(context, class, results)
+    }) {
+        results.truncate(kept);
+        output.failures.push(failure);
+        return;
+    }
+    for method in &class.methods {
+        let kept = results.len();
+        let visited = isolate(rule_id, Some(class), Some(method), || {
+            visitor.visit_method(context, class, method, results)?;
+            for instruction in method
+                .cfg
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+            {
+                visitor.visit_instruction(context, class, method, instruction, results)?;
+            }
+            Ok(())
+        });
+        if let Err(failure) = visited {
+            results.truncate(kept);
+            output.failures.push(failure);
+        }
+    }
+}
+
+/// Run `analyze`, turning an error or a panic into a failure of the rule.
+pub(crate) fn isolate<T>(
+    rule_id: &str,
+    class: Option<&Class>,
+    method: Option<&Method>,
+    analyze: impl FnOnce() -> Result<T>,
+) -> std::result::Result<T, RuleFailure> {
+    let (message, panicked) = match panic::catch_unwind(AssertUnwindSafe(analyze)) {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(error)) => (format!("{error:#}"), false),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            (message, true)
+        }
+    };
+    Err(RuleFailure {
+        rule_id: rule_id.to_string(),
+        class_name: class.map(|class| class.name.clone()),
+        method: method.map(|method| format!("{}{}", method.name, method.descriptor)),
+        message,
+        panicked,
+    })
+}
+
+/// Whether the artifact or one of its ancestors is an analysis target; everything
+/// is a target when no artifact has that role.
+fn is_target_artifact(
+    artifact_index: i64,
+    analysis_targets: &BTreeSet<i64>,
+    parents: &BTreeMap<i64, i64>,
+) -> bool {
+    if analysis_targets.is_empty() {
+        return true;
+    }
+    let mut current = Some(artifact_index);
+    while let Some(index) = current {
+        if analysis_targets.contains(&index) {
+            return true;
+        }
+        current = parents.get(&index).copied();
+    }
+    false
+}
+
 fn analyze_artifacts(
     artifacts: &[Artifact],
 ) -> (BTreeSet<i64>, BTreeMap<i64, i64>, BTreeMap<i64, String>) {
@@ -179,22 +767,479 @@ fn analyze_artifacts(
     let mut uris = BTreeMap::new();
     for (index, artifact) in artifacts.iter().enumerate() {
         let index = index as i64;
-        if let Some(location) = artifact.location.as_ref() {
-            if let Some(uri) = location.uri.as_ref() {
-                uris.insert(index, uri.clone());
-            }
+        if let Some(location) = artifact.location.as_ref()
+            && let Some(uri) = location.uri.as_ref()
+        {
+            uris.insert(index, uri.clone());
         }
         if let Some(parent) = artifact.parent_index {
             parents.insert(index, parent);
         }
-        if let Some(roles) = &artifact.roles {
-            if roles
+        if let Some(roles) = &artifact.roles
+            && roles
                 .iter()
                 .any(|role| role.as_str() == Some("analysisTarget"))
-            {
-                analysis_targets.insert(index);
-            }
+        {
+            analysis_targets.insert(index);
         }
     }
     (analysis_targets, parents, uris)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::ir::{ControlFlowGraph, MethodAccess, MethodNullness};
+    use crate::rules::{
+        Precision, RuleOutput, class_location, method_location_with_line, result_message,
+    };
+
+    fn rule_ids(engine: &Engine) -> Vec<String> {
+        engine
+            .rules
+            .iter()
+            .map(|rule| rule.metadata().id.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn engine_selects_rules_by_id_and_category() {
+        let engine = Engine::with_options(EngineOptions {
+            enabled_rules: Some(["security".to_string(), "NULLNESS".to_string()].into()),
+            disabled_rules: ["INSECURE_API".to_string()].into(),
+            ..EngineOptions::default()
+        })
+        .expect("engine");
+
+        assert_eq!(
+            vec!["NULLNESS", "VULNERABLE_METHOD_REACHABLE"],
+            rule_ids(&engine)
+        );
+    }
+
+    #[test]
+    fn engine_emits_default_and_overridden_levels() {
+        let engine = Engine::with_options(EngineOptions {
+            rule_levels: [("DEAD_CODE".to_string(), ResultLevel::None)].into(),
+            ..EngineOptions::default()
+        })
+        .expect("engine");
+        let classpath = resolve_classpath(&[]).expect("classpath build");
+        let output = engine
+            .analyze(build_context(Vec::new(), classpath, &[]))
+            .expect("analysis");
+
+        let level = |id: &str| {
+            output
+                .rules
+                .iter()
+                .find(|rule| rule.id == id)
+                .and_then(|rule| rule.default_configuration.as_ref())
+                .and_then(|configuration| configuration.level.clone())
+        };
+        assert_eq!(Some(serde_json::json!("none")), level("DEAD_CODE"));
+        assert_eq!(Some(serde_json::json!("error")), level("NUMERIC_RANGE"));
+        assert_eq!(Some(serde_json::json!("note")), level("RECORD_ARRAY_FIELD"));
+    }
+
+    #[test]
+    fn every_rule_is_documented() {
+        for rule in Engine::new().rule_metadata() {
+            assert!(
+                rule.help
+                    .starts_with(&format!("# {}: {}\n", rule.id, rule.name)),
+                "{} help must start with its ID and name",
+                rule.id
+            );
+            assert!(rule.help.contains("## Noncompliant code"), "{}", rule.id);
+            assert!(rule.help.contains("## Compliant code"), "{}", rule.id);
+            assert!(!rule.full_description().starts_with('#'), "{}", rule.id);
+            assert_eq!(
+                rule.tags.contains(&"security"),
+                rule.security_severity.is_some(),
+                "{} needs a security severity exactly when it is a security rule",
+                rule.id
+            );
+        }
+    }
+
+    /// Records every class and method it is called for.
+    struct RecordingVisitor;
+
+    impl RuleVisitor for RecordingVisitor {
+        fn visit_class(
+            &self,
+            _context: &AnalysisContext,
+            class: &Class,
+            results: &mut Vec<SarifResult>,
+        ) -> Result<()> {
+            results.push(
+                SarifResult::builder()
+                    .message(result_message(class.name.clone()))
+                    .locations(vec![class_location(&class.name)])
+                    .build(),
+            );
+            Ok(())
+        }
+
+        fn visit_method(
+            &self,
+            _context: &AnalysisContext,
+            class: &Class,
+            method: &Method,
+            results: &mut Vec<SarifResult>,
+        ) -> Result<()> {
+            results.push(
+                SarifResult::builder()
+                    .message(result_message(format!("{}.{}", class.name, method.name)))
+                    .locations(vec![method_location_with_line(
+                        &class.name,
+                        &method.name,
+                        &method.descriptor,
+                        None,
+                        None,
+                    )])
+                    .build(),
+            );
+            Ok(())
+        }
+    }
+
+    /// Class with a single empty `run()V` method.
+    fn class(name: &str, artifact_index: i64) -> Class {
+        Class {
+            name: name.to_string(),
+            super_name: None,
+            interfaces: Vec::new(),
+            referenced_classes: Vec::new(),
+            fields: Vec::new(),
+            methods: vec![Method {
+                name: "run".to_string(),
+                descriptor: "()V".to_string(),
+                access: MethodAccess {
+                    is_public: true,
+                    is_static: true,
+                    is_abstract: false,
+                },
+                nullness: MethodNullness::unknown(0),
+                bytecode: Vec::new(),
+                max_stack: 0,
+                max_locals: 0,
+                line_numbers: Vec::new(),
+                cfg: ControlFlowGraph {
+                    blocks: Vec::new(),
+                    edges: Vec::new(),
+                },
+                calls: Vec::new(),
+                string_literals: Vec::new(),
+                exception_handlers: Vec::new(),
+                annotations: Vec::new(),
+            }],
+            artifact_index,
+            is_record: false,
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn visit_target_classes_skips_classpath_classes() {
+        let classes = vec![
+            class("com/example/App", 0),
+            class("org/library/Util", 1),
+            class("com/example/Other", 0),
+        ];
+        let artifacts = vec![
+            Artifact::builder()
+                .roles(vec![serde_json::json!("analysisTarget")])
+                .build(),
+            Artifact::builder().build(),
+        ];
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &artifacts);
+
+        let outputs = visit_target_classes(&context, &[("RECORDING", &RecordingVisitor)]);
+
+        let visited: Vec<String> = outputs[0]
+            .results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+        assert_eq!(
+            vec![
+                "com/example/App",
+                "com/example/App.run",
+                "com/example/Other",
+                "com/example/Other.run"
+            ],
+            visited
+        );
+    }
+
+    fn test_metadata(id: &'static str) -> RuleMetadata {
+        RuleMetadata {
+            id: id.into(),
+            name: id.into(),
+            description: id.into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: "".into(),
+            cwe: &[],
+            precision: Precision::High,
+            security_severity: None,
+        }
+    }
+
+    /// Fails on `com/example/Broken` and panics on `com/example/Panicky.run`.
+    struct FragileRule;
+
+    impl Rule for FragileRule {
+        fn metadata(&self) -> RuleMetadata {
+            test_metadata("FRAGILE")
+        }
+
+        fn visitor(&self) -> Option<&dyn RuleVisitor> {
+            Some(self)
+        }
+    }
+
+    impl RuleVisitor for FragileRule {
+        fn visit_class(
+            &self,
+            context: &AnalysisContext,
+            class: &Class,
+            results: &mut Vec<SarifResult>,
+        ) -> Result<()> {
+            RecordingVisitor.visit_class(context, class, results)?;
+            if class.name == "com/example/Broken" {
+                anyhow::bail!("malformed descriptor");
+            }
+            Ok(())
+        }
+
+        fn visit_method(
+            &self,
+            context: &AnalysisContext,
+            class: &Class,
+            method: &Method,
+            results: &mut Vec<SarifResult>,
+        ) -> Result<()> {
+            RecordingVisitor.visit_method(context, class, method, results)?;
+            if class.name == "com/example/Panicky" {
+                panic!("stack underflow");
+            }
+            Ok(())
+        }
+    }
+
+    /// Whole-program rule that always fails.
+    struct FailingRule;
+
+    impl Rule for FailingRule {
+        fn metadata(&self) -> RuleMetadata {
+            test_metadata("FAILING")
+        }
+
+        fn run(&self, _context: &AnalysisContext) -> Result<RuleOutput> {
+            anyhow::bail!("no entry points")
+        }
+    }
+
+    #[test]
+    fn engine_isolates_rule_failures() {
+        let engine = Engine::with_rules(
+            EngineOptions {
+                enabled_rules: Some(["FRAGILE".to_string(), "FAILING".to_string()].into()),
+                ..EngineOptions::default()
+            },
+            vec![Box::new(FragileRule), Box::new(FailingRule)],
+        )
+        .expect("engine");
+        let classes = vec![
+            class("com/example/App", 0),
+            class("com/example/Broken", 0),
+            class("com/example/Panicky", 0),
+        ];
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+
+        let run = FragileRule.run(&context).expect("fragile rule run");
+        assert_eq!(3, run.results.len());
+        assert_eq!(2, run.failures.len());
+
+        let output = engine.analyze(context).expect("analysis");
+
+        let results: Vec<String> = output
+            .results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+        assert_eq!(
+            vec![
+                "com/example/App",
+                "com/example/App.run",
+                "com/example/Panicky"
+            ],
+            results
+        );
+        let failures: Vec<_> = output
+            .failures
+            .iter()
+            .map(|failure| {
+                (
+                    failure.rule_id.as_str(),
+                    failure.class_name.as_deref(),
+                    failure.method.as_deref(),
+                    failure.message.as_str(),
+                    failure.panicked,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("FAILING", None, None, "no entry points", false),
+                (
+                    "FRAGILE",
+                    Some("com/example/Broken"),
+                    None,
+                    "malformed descriptor",
+                    false
+                ),
+                (
+                    "FRAGILE",
+                    Some("com/example/Panicky"),
+                    Some("run()V"),
+                    "stack underflow",
+                    true
+                ),
+            ],
+            failures
+        );
+    }
+
+    /// Reports every class and method through [`RecordingVisitor`].
+    struct RecordingRule;
+
+    impl Rule for RecordingRule {
+        fn metadata(&self) -> RuleMetadata {
+            test_metadata("RECORDING")
+        }
+
+        fn visitor(&self) -> Option<&dyn RuleVisitor> {
+            Some(&RecordingVisitor)
+        }
+    }
+
+    /// Counts the methods it summarizes.
+    #[derive(Default)]
+    struct CountingSummaries {
+        summarized: AtomicUsize,
+    }
+
+    #[derive(Clone, PartialEq)]
+    struct Counted;
+
+    impl SummaryAnalysis for CountingSummaries {
+        type Summary = Counted;
+
+        fn unknown(&self) -> Counted {
+            Counted
+        }
+
+        fn initial(&self) -> Counted {
+            Counted
+        }
+
+        fn summarize(
+            &self,
+            _class: &Class,
+            _method: &Method,
+            _summaries: &Summaries<Counted>,
+        ) -> Result<Counted> {
+            self.summarized.fetch_add(1, Ordering::Relaxed);
+            Ok(Counted)
+        }
+    }
+
+    #[test]
+    fn summaries_are_computed_once_for_concurrent_requests() {
+        let classes: Vec<Class> = (0..4)
+            .map(|index| {
+                let mut class = class(&format!("com/example/C{index}"), 0);
+                class.methods[0].bytecode = vec![crate::opcodes::RETURN];
+                class
+            })
+            .collect();
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+        let analysis = CountingSummaries::default();
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| context.summaries(&analysis));
+            }
+        });
+
+        assert_eq!(4, analysis.summarized.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn engine_reports_only_on_filtered_classes() {
+        let filter =
+            |exclude: &str| ClassFilter::parse(&[], &[exclude.to_string()]).expect("filter");
+        let engine = Engine::with_rules(
+            EngineOptions {
+                enabled_rules: Some(["RECORDING".to_string()].into()),
+                rule_class_filters: [("RECORDING".to_string(), filter("com.example.legacy.**"))]
+                    .into(),
+                ..EngineOptions::default()
+            },
+            vec![Box::new(RecordingRule)],
+        )
+        .expect("engine");
+        let classes = vec![
+            class("com/example/App", 0),
+            class("com/example/generated/Api", 0),
+            class("com/example/legacy/Dao$Row", 0),
+        ];
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let (context, _) = build_context_with_timings(
+            classes,
+            classpath,
+            &[],
+            &CallGraphOptions::default(),
+            filter("**/generated/**"),
+        );
+
+        let generated = &context.classes[1];
+        assert!(context.is_input_class(generated));
+        assert!(!context.is_analysis_target_class(generated));
+        let output = engine.analyze(context).expect("analysis");
+
+        let results: Vec<String> = output
+            .results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+        assert_eq!(vec!["com/example/App", "com/example/App.run"], results);
+    }
+
+    #[test]
+    fn engine_rejects_unknown_rules() {
+        let unknown = Engine::with_options(EngineOptions {
+            disabled_rules: ["NO_SUCH_RULE".to_string()].into(),
+            ..EngineOptions::default()
+        });
+        assert!(unknown.is_err());
+
+        let empty_category = Engine::with_options(EngineOptions {
+            enabled_rules: Some(["performance".to_string()].into()),
+            ..EngineOptions::default()
+        })
+        .expect("engine");
+        assert!(rule_ids(&empty_category).is_empty());
+    }
+}
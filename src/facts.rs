@@ -0,0 +1,410 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::callgraph::MethodId;
+use crate::engine::AnalysisContext;
+use crate::ir::{EdgeKind, InstructionKind, Method};
+
+use ColumnType::{Number, Symbol};
+
+/// Type of a relation column, as declared to Soufflé.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColumnType {
+    Symbol,
+    Number,
+}
+
+impl ColumnType {
+    fn souffle(self) -> &'static str {
+        match self {
+            Self::Symbol => "symbol",
+            Self::Number => "number",
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Symbol => "TEXT",
+            Self::Number => "INTEGER",
+        }
+    }
+}
+
+/// Value of one column in a fact.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum FactValue {
+    Symbol(String),
+    Number(i64),
+}
+
+/// Named set of facts with the same columns, sorted for stable output.
+#[derive(Debug)]
+pub struct Relation {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, ColumnType)],
+    pub rows: Vec<Vec<FactValue>>,
+}
+
+const CLASS: &[(&str, ColumnType)] = &[("class", Symbol), ("target", Number), ("record", Number)];
+const DIRECT_SUPERCLASS: &[(&str, ColumnType)] = &[("class", Symbol), ("superclass", Symbol)];
+const DIRECT_SUPERINTERFACE: &[(&str, ColumnType)] = &[("class", Symbol), ("interface", Symbol)];
+const METHOD: &[(&str, ColumnType)] = &[
+    ("method", Symbol),
+    ("class", Symbol),
+    ("name", Symbol),
+    ("descriptor", Symbol),
+    ("public", Number),
+    ("static", Number),
+    ("abstract", Number),
+];
+const FIELD: &[(&str, ColumnType)] = &[
+    ("class", Symbol),
+    ("name", Symbol),
+    ("descriptor", Symbol),
+    ("static", Number),
+];
+const CALL_SITE: &[(&str, ColumnType)] = &[
+    ("caller", Symbol),
+    ("offset", Number),
+    ("owner", Symbol),
+    ("name", Symbol),
+    ("descriptor", Symbol),
+    ("kind", Symbol),
+];
+const CALL_GRAPH_EDGE: &[(&str, ColumnType)] = &[
+    ("caller", Symbol),
+    ("offset", Number),
+    ("callee", Symbol),
+    ("kind", Symbol),
+];
+const STRING_LITERAL: &[(&str, ColumnType)] =
+    &[("method", Symbol), ("offset", Number), ("value", Symbol)];
+const CLASS_ANNOTATION: &[(&str, ColumnType)] = &[("class", Symbol), ("annotation", Symbol)];
+const METHOD_ANNOTATION: &[(&str, ColumnType)] = &[("method", Symbol), ("annotation", Symbol)];
+const CFG_EDGE: &[(&str, ColumnType)] = &[
+    ("method", Symbol),
+    ("from", Number),
+    ("to", Number),
+    ("kind", Symbol),
+];
+
+/// Relations describing every scanned class, its methods and their code, and the call
+/// graph. Methods are named `Class.name(descriptor)`, as in SARIF logical locations.
+pub fn extract_facts(context: &AnalysisContext) -> Vec<Relation> {
+    let mut classes = Vec::new();
+    let mut superclasses = Vec::new();
+    let mut superinterfaces = Vec::new();
+    let mut methods = Vec::new();
+    let mut fields = Vec::new();
+    let mut call_sites = Vec::new();
+    let mut string_literals = Vec::new();
+    let mut class_annotations = Vec::new();
+    let mut method_annotations = Vec::new();
+    let mut cfg_edges = Vec::new();
+    for class in &context.classes {
+        let name = || symbol(&class.name);
+        classes.push(vec![
+            name(),
+            flag(context.is_analysis_target_class(class)),
+            flag(class.is_record),
+        ]);
+        if let Some(super_name) = &class.super_name {
+            superclasses.push(vec![name(), symbol(super_name)]);
+        }
+        for interface in &class.interfaces {
+            superinterfaces.push(vec![name(), symbol(interface)]);
+        }
+        for annotation in &class.annotations {
+            class_annotations.push(vec![name(), symbol(annotation)]);
+        }
+        for field in &class.fields {
+            fields.push(vec![
+                name(),
+                symbol(&field.name),
+                symbol(&field.descriptor),
+                flag(field.access.is_static),
+            ]);
+        }
+        for method in &class.methods {
+            let id = || FactValue::Symbol(method_key(&class.name, method));
+            methods.push(vec![
+                id(),
+                name(),
+                symbol(&method.name),
+                symbol(&method.descriptor),
+                flag(method.access.is_public),
+                flag(method.access.is_static),
+                flag(method.access.is_abstract),
+            ]);
+            for annotation in &method.annotations {
+                method_annotations.push(vec![id(), symbol(annotation)]);
+            }
+            for edge in &method.cfg.edges {
+                cfg_edges.push(vec![
+                    id(),
+                    number(edge.from),
+                    number(edge.to),
+                    symbol(edge_kind(edge.kind)),
+                ]);
+            }
+            for instruction in method
+                .cfg
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+            {
+                match &instruction.kind {
+                    InstructionKind::Invoke(call) => call_sites.push(vec![
+                        id(),
+                        number(call.offset),
+                        symbol(&call.owner),
+                        symbol(&call.name),
+                        symbol(&call.descriptor),
+                        symbol(call.kind.as_str()),
+                    ]),
+                    InstructionKind::ConstString(value) => {
+                        string_literals.push(vec![id(), number(instruction.offset), symbol(value)])
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    let call_graph_edges = context
+        .call_graph
+        .edges
+        .iter()
+        .map(|edge| {
+            vec![
+                FactValue::Symbol(method_id_key(&edge.caller)),
+                number(edge.offset),
+                FactValue::Symbol(method_id_key(&edge.callee)),
+                symbol(edge.kind.as_str()),
+            ]
+        })
+        .collect();
+
+    let relation = |name, columns, mut rows: Vec<Vec<FactValue>>| {
+        rows.sort();
+        rows.dedup();
+        Relation {
+            name,
+            columns,
+            rows,
+        }
+    };
+    vec![
+        relation("Class", CLASS, classes),
+        relation("DirectSuperclass", DIRECT_SUPERCLASS, superclasses),
+        relation(
+            "DirectSuperinterface",
+            DIRECT_SUPERINTERFACE,
+            superinterfaces,
+        ),
+        relation("Method", METHOD, methods),
+        relation("Field", FIELD, fields),
+        relation("CallSite", CALL_SITE, call_sites),
+        relation("CallGraphEdge", CALL_GRAPH_EDGE, call_graph_edges),
+        relation("StringLiteral", STRING_LITERAL, string_literals),
+        relation("ClassAnnotation", CLASS_ANNOTATION, class_annotations),
+        relation("MethodAnnotation", METHOD_ANNOTATION, method_annotations),
+        relation("CfgEdge", CFG_EDGE, cfg_edges),
+    ]
+}
+
+/// Write each relation to `<dir>/<Name>.facts` as tab-separated rows, the layout
+/// Soufflé's `.input` reads, plus `schema.dl` declaring the relations. Backslashes,
+/// tabs and line breaks in values are escaped as `\\`, `\t`, `\n` and `\r`.
+pub fn write_facts(relations: &[Relation], dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut schema = String::new();
+    for relation in relations {
+        let columns: Vec<String> = relation
+            .columns
+            .iter()
+            .map(|(name, column_type)| format!("{name}: {}", column_type.souffle()))
+            .collect();
+        schema.push_str(&format!(
+            ".decl {}({})\n.input {}\n",
+            relation.name,
+            columns.join(", "),
+            relation.name
+        ));
+
+        let path = dir.join(format!("{}.facts", relation.name));
+        let file =
+            File::create(&path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for row in &relation.rows {
+            let values: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    FactValue::Symbol(text) => escape_fact(text),
+                    FactValue::Number(number) => number.to_string(),
+                })
+                .collect();
+            writeln!(writer, "{}", values.join("\t"))
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    let path = dir.join("schema.dl");
+    fs::write(&path, schema).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Write the relations as SQL statements that create and fill one table per relation,
+/// readable by `sqlite3`.
+pub fn write_sql(relations: &[Relation], writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, "BEGIN TRANSACTION;")?;
+    for relation in relations {
+        let columns: Vec<String> = relation
+            .columns
+            .iter()
+            .map(|(name, column_type)| format!("\"{name}\" {}", column_type.sql()))
+            .collect();
+        writeln!(
+            writer,
+            "CREATE TABLE \"{}\" ({});",
+            relation.name,
+            columns.join(", ")
+        )?;
+        for row in &relation.rows {
+            let values: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    FactValue::Symbol(text) => format!("'{}'", text.replace('\'', "''")),
+                    FactValue::Number(number) => number.to_string(),
+                })
+                .collect();
+            writeln!(
+                writer,
+                "INSERT INTO \"{}\" VALUES ({});",
+                relation.name,
+                values.join(", ")
+            )?;
+        }
+    }
+    writeln!(writer, "COMMIT;")?;
+    Ok(())
+}
+
+fn method_key(class_name: &str, method: &Method) -> String {
+    format!("{class_name}.{}{}", method.name, method.descriptor)
+}
+
+fn method_id_key(id: &MethodId) -> String {
+    format!("{}.{}{}", id.class_name, id.name, id.descriptor)
+}
+
+fn symbol(text: &str) -> FactValue {
+    FactValue::Symbol(text.to_string())
+}
+
+fn number(value: u32) -> FactValue {
+    FactValue::Number(value.into())
+}
+
+fn flag(value: bool) -> FactValue {
+    FactValue::Number(value.into())
+}
+
+fn edge_kind(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::FallThrough => "fallthrough",
+        EdgeKind::Branch => "branch",
+        EdgeKind::Exception => "exception",
+    }
+}
+
+fn escape_fact(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn relations() -> Vec<Relation> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "example/Greeter.java".to_string(),
+            contents: r#"
+package example;
+
+public class Greeter implements Runnable {
+    @Deprecated
+    public void run() {
+        System.out.println("it's\tdone");
+    }
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let scan = scan_inputs(output.classes_dir(), &[]).expect("scan classes");
+        let classpath = resolve_classpath(&scan.classes).expect("classpath build");
+        extract_facts(&build_context(scan.classes, classpath, &scan.artifacts))
+    }
+
+    #[test]
+    fn facts_are_written_as_tab_separated_relations() {
+        let relations = relations();
+        let dir = tempfile::tempdir().expect("temp dir");
+
+        write_facts(&relations, dir.path()).expect("write facts");
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).expect("read facts");
+        assert_eq!("example/Greeter\t1\t0\n", read("Class.facts"));
+        assert_eq!(
+            "example/Greeter\tjava/lang/Runnable\n",
+            read("DirectSuperinterface.facts")
+        );
+        assert_eq!(
+            "example/Greeter.run()V\tjava/lang/Deprecated\n",
+            read("MethodAnnotation.facts")
+        );
+        assert_eq!(
+            "example/Greeter.run()V\t3\tit's\\tdone\n",
+            read("StringLiteral.facts")
+        );
+        assert!(read("CallSite.facts").contains(
+            "example/Greeter.run()V\t5\tjava/io/PrintStream\tprintln\t(Ljava/lang/String;)V\tvirtual\n"
+        ));
+        assert!(read("schema.dl").contains(".decl StringLiteral(method: symbol, offset: number, value: symbol)\n.input StringLiteral\n"));
+    }
+
+    #[test]
+    fn facts_are_written_as_sql() {
+        let mut sql = Vec::new();
+
+        write_sql(&relations(), &mut sql).expect("write sql");
+
+        let sql = String::from_utf8(sql).expect("utf-8 sql");
+        assert!(sql.starts_with("BEGIN TRANSACTION;\nCREATE TABLE \"Class\" (\"class\" TEXT, \"target\" INTEGER, \"record\" INTEGER);\n"));
+        assert!(sql.contains(
+            "INSERT INTO \"StringLiteral\" VALUES ('example/Greeter.run()V', 3, 'it''s\tdone');\n"
+        ));
+        assert!(sql.ends_with("COMMIT;\n"));
+    }
+}
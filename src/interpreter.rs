@@ -0,0 +1,1316 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use jdescriptor::{MethodDescriptor, TypeDescriptor};
+
+use crate::ir::{CallSite, ConstantValue, Instruction, InstructionKind, Method};
+use crate::opcodes;
+
+const OBJECT_CLASS: &str = "java/lang/Object";
+const THROWABLE_CLASS: &str = "java/lang/Throwable";
+
+/// Element type of a reference: a class or a primitive array component.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum BaseType {
+    /// Internal class name such as `java/lang/String`.
+    Class(String),
+    /// Primitive descriptor character such as `I` in `int[]`.
+    Primitive(char),
+}
+
+/// Declared reference type made of a base type and array dimensions.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ReferenceType {
+    pub base: BaseType,
+    pub dimensions: usize,
+}
+
+impl ReferenceType {
+    pub(crate) fn class(name: &str) -> Self {
+        Self {
+            base: BaseType::Class(name.to_string()),
+            dimensions: 0,
+        }
+    }
+
+    /// Parse a class constant name, which uses descriptor syntax for arrays.
+    pub(crate) fn from_internal_name(name: &str) -> Result<Self> {
+        if !name.starts_with('[') {
+            return Ok(Self::class(name));
+        }
+        let descriptor = TypeDescriptor::from_str(name).context("parse array type")?;
+        match ValueType::from_descriptor(&descriptor) {
+            ValueType::Reference(reference) => Ok(reference),
+            _ => anyhow::bail!("unexpected array type {}", name),
+        }
+    }
+
+    fn array_of(mut self, dimensions: usize) -> Self {
+        self.dimensions += dimensions;
+        self
+    }
+
+    fn component(&self) -> ValueType {
+        match (&self.base, self.dimensions) {
+            (_, 0) => ValueType::Reference(Self::class(OBJECT_CLASS)),
+            (BaseType::Primitive(descriptor), 1) => primitive_type(*descriptor),
+            (base, dimensions) => ValueType::Reference(Self {
+                base: base.clone(),
+                dimensions: dimensions - 1,
+            }),
+        }
+    }
+}
+
+/// Verification type of a local variable or operand stack slot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValueType {
+    /// Unusable value: unset local, second half of a long/double, or conflicting merge.
+    Top,
+    /// boolean, byte, char, short, or int.
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    Reference(ReferenceType),
+    /// Object allocated by `new` at the given offset whose constructor has not run yet.
+    Uninitialized(u32, String),
+    /// `this` in a constructor before the super constructor runs.
+    UninitializedThis(String),
+    /// Return address pushed by `jsr`.
+    ReturnAddress(u32),
+}
+
+impl ValueType {
+    /// Map a field or parameter descriptor; `void` maps to `Top`.
+    pub(crate) fn from_descriptor(descriptor: &TypeDescriptor) -> Self {
+        match descriptor {
+            TypeDescriptor::Boolean
+            | TypeDescriptor::Byte
+            | TypeDescriptor::Char
+            | TypeDescriptor::Short
+            | TypeDescriptor::Integer => ValueType::Int,
+            TypeDescriptor::Float => ValueType::Float,
+            TypeDescriptor::Long => ValueType::Long,
+            TypeDescriptor::Double => ValueType::Double,
+            TypeDescriptor::Void => ValueType::Top,
+            TypeDescriptor::Object(name) => ValueType::Reference(ReferenceType::class(name)),
+            TypeDescriptor::Array(base, dimensions) => {
+                let base = match base.as_ref() {
+                    TypeDescriptor::Object(name) => BaseType::Class(name.clone()),
+                    primitive => {
+                        BaseType::Primitive(primitive.to_string().chars().next().unwrap_or('I'))
+                    }
+                };
+                ValueType::Reference(ReferenceType {
+                    base,
+                    dimensions: *dimensions as usize,
+                })
+            }
+        }
+    }
+
+    pub(crate) fn from_field_descriptor(descriptor: &str) -> Result<Self> {
+        let descriptor = TypeDescriptor::from_str(descriptor).context("parse field descriptor")?;
+        Ok(Self::from_descriptor(&descriptor))
+    }
+
+    /// Long and double values occupy two local variable slots.
+    pub(crate) fn is_category2(&self) -> bool {
+        matches!(self, ValueType::Long | ValueType::Double)
+    }
+
+    pub(crate) fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            ValueType::Null
+                | ValueType::Reference(_)
+                | ValueType::Uninitialized(_, _)
+                | ValueType::UninitializedThis(_)
+        )
+    }
+
+    /// Number of array dimensions, or zero for non-array values.
+    pub(crate) fn array_dimensions(&self) -> usize {
+        match self {
+            ValueType::Reference(reference) => reference.dimensions,
+            _ => 0,
+        }
+    }
+
+    fn words(&self) -> usize {
+        if self.is_category2() { 2 } else { 1 }
+    }
+
+    /// Least upper bound without consulting the class hierarchy, so distinct
+    /// classes merge to `java/lang/Object`.
+    pub(crate) fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (left, right) if left == right => left.clone(),
+            (ValueType::Null, ValueType::Reference(reference))
+            | (ValueType::Reference(reference), ValueType::Null) => {
+                ValueType::Reference(reference.clone())
+            }
+            (ValueType::Reference(left), ValueType::Reference(right)) => {
+                let both_class_arrays = left.dimensions == right.dimensions
+                    && matches!(left.base, BaseType::Class(_))
+                    && matches!(right.base, BaseType::Class(_));
+                let dimensions = if both_class_arrays {
+                    left.dimensions
+                } else {
+                    0
+                };
+                ValueType::Reference(ReferenceType::class(OBJECT_CLASS).array_of(dimensions))
+            }
+            _ => ValueType::Top,
+        }
+    }
+}
+
+/// Rule-specific information attached to every slot.
+pub(crate) trait Fact: Clone + PartialEq + Default {
+    fn join(&self, other: &Self) -> Self;
+}
+
+impl Fact for () {
+    fn join(&self, _other: &Self) -> Self {}
+}
+
+/// A local variable or operand stack entry. Long and double values take a
+/// single stack entry; in locals they are followed by a `Top` slot.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Slot<F> {
+    pub(crate) ty: ValueType,
+    pub(crate) fact: F,
+}
+
+impl<F: Fact> Slot<F> {
+    pub(crate) fn top() -> Self {
+        Self {
+            ty: ValueType::Top,
+            fact: F::default(),
+        }
+    }
+}
+
+/// Locals and operand stack at a program point.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Frame<F> {
+    pub(crate) locals: Vec<Slot<F>>,
+    pub(crate) stack: Vec<Slot<F>>,
+}
+
+impl<F: Fact> Frame<F> {
+    /// Slot `depth` entries below the top of the stack.
+    pub(crate) fn peek(&self, depth: usize) -> Option<&Slot<F>> {
+        self.stack
+            .len()
+            .checked_sub(depth + 1)
+            .and_then(|index| self.stack.get(index))
+    }
+
+    pub(crate) fn local(&self, index: usize) -> Slot<F> {
+        self.locals.get(index).cloned().unwrap_or_else(Slot::top)
+    }
+
+    pub(crate) fn join(&self, other: &Self) -> Self {
+        let max_locals = self.locals.len().max(other.locals.len());
+        let locals = (0..max_locals)
+            .map(|index| join_slots(&self.local(index), &other.local(index)))
+            .collect();
+        // Verified bytecode always merges equal stack heights.
+        let stack = if self.stack.len() == other.stack.len() {
+            self.stack
+                .iter()
+                .zip(other.stack.iter())
+                .map(|(left, right)| join_slots(left, right))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self { locals, stack }
+    }
+
+    /// Frame on entry to an exception handler: locals are kept and the stack
+    /// holds only the thrown exception.
+    pub(crate) fn exception_frame(&self, fact: F) -> Self {
+        Self {
+            locals: self.locals.clone(),
+            stack: vec![Slot {
+                ty: ValueType::Reference(ReferenceType::class(THROWABLE_CLASS)),
+                fact,
+            }],
+        }
+    }
+
+    fn pop(&mut self) -> Slot<F> {
+        // Unmodelled paths (such as `jsr` subroutines) degrade to Top instead of failing.
+        self.stack.pop().unwrap_or_else(Slot::top)
+    }
+
+    /// Pop `count` entries, returned bottom-most first.
+    fn pop_n(&mut self, count: usize) -> Vec<Slot<F>> {
+        let mut values: Vec<Slot<F>> = (0..count).map(|_| self.pop()).collect();
+        values.reverse();
+        values
+    }
+
+    /// Number of top entries that together hold `words` stack words.
+    fn entries_for_words(&self, words: usize) -> usize {
+        let mut covered = 0;
+        let mut entries = 0;
+        for slot in self.stack.iter().rev() {
+            if covered >= words {
+                break;
+            }
+            covered += slot.ty.words();
+            entries += 1;
+        }
+        entries
+    }
+
+    /// Shared implementation of the dup family: copy the top `words` words and
+    /// insert the copy below the next `skip` words.
+    fn dup_words(&mut self, words: usize, skip: usize) {
+        let copy = self.entries_for_words(words);
+        let below = self.entries_for_words(words + skip);
+        let len = self.stack.len();
+        let copied: Vec<Slot<F>> = self.stack[len - copy..].to_vec();
+        let at = len - below;
+        self.stack.splice(at..at, copied);
+    }
+
+    fn store(&mut self, index: usize, slot: Slot<F>) {
+        let width = slot.ty.words();
+        if self.locals.len() < index + width {
+            self.locals.resize(index + width, Slot::top());
+        }
+        if index > 0 && self.locals[index - 1].ty.is_category2() {
+            self.locals[index - 1] = Slot::top();
+        }
+        self.locals[index] = slot;
+        if width == 2 {
+            self.locals[index + 1] = Slot::top();
+        }
+    }
+
+    /// Replace every occurrence of an uninitialized value once its constructor ran.
+    fn initialize(&mut self, uninitialized: &ValueType, class_name: &str) {
+        let initialized = ValueType::Reference(ReferenceType::class(class_name));
+        for slot in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if slot.ty == *uninitialized {
+                slot.ty = initialized.clone();
+            }
+        }
+    }
+}
+
+fn join_slots<F: Fact>(left: &Slot<F>, right: &Slot<F>) -> Slot<F> {
+    Slot {
+        ty: left.ty.join(&right.ty),
+        fact: left.fact.join(&right.fact),
+    }
+}
+
+/// Callbacks through which rules attach facts to values as they are created.
+pub(crate) trait FactHooks<F: Fact> {
+    /// Fact for `this` (`index` is `None`) or the parameter at `index`.
+    fn parameter(&self, _index: Option<usize>, _ty: &ValueType) -> F {
+        F::default()
+    }
+
+    /// Fact for a value pushed by `inst` (or written to a local by `iinc`).
+    /// `inputs` are the consumed operands, bottom-most first.
+    fn produce(&self, _inst: &Instruction, _ty: &ValueType, _inputs: &[Slot<F>]) -> F {
+        F::default()
+    }
+
+    /// Fact for a value loaded from local `index`.
+    fn load(&self, _index: usize, slot: &Slot<F>) -> F {
+        slot.fact.clone()
+    }
+
+    /// Fact for a value stored into local `index`.
+    fn store(&self, _index: usize, slot: &Slot<F>) -> F {
+        slot.fact.clone()
+    }
+}
+
+/// Hooks for analyses that only need declared types.
+pub(crate) struct NoFacts;
+
+impl<F: Fact> FactHooks<F> for NoFacts {}
+
+/// Abstract interpreter modelling the stack effect of every JVM opcode.
+pub(crate) struct Interpreter<'a> {
+    class_name: &'a str,
+    method: &'a Method,
+    calls: BTreeMap<u32, &'a CallSite>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub(crate) fn new(class_name: &'a str, method: &'a Method) -> Self {
+        let calls = method
+            .calls
+            .iter()
+            .map(|call| (call.offset, call))
+            .collect();
+        Self {
+            class_name,
+            method,
+            calls,
+        }
+    }
+
+    /// Call site invoked by `inst`, if it is an invoke instruction.
+    pub(crate) fn call_site(&self, inst: &Instruction) -> Option<&'a CallSite> {
+        self.calls.get(&inst.offset).copied()
+    }
+
+    /// Frame on method entry with `this` and the parameters in their locals.
+    pub(crate) fn entry_frame<F: Fact>(&self, hooks: &impl FactHooks<F>) -> Result<Frame<F>> {
+        let descriptor = MethodDescriptor::from_str(&self.method.descriptor)
+            .context("parse method descriptor")?;
+        let mut frame = Frame {
+            locals: Vec::new(),
+            stack: Vec::new(),
+        };
+        let mut index = 0;
+        if !self.method.access.is_static {
+            let ty = if self.method.name == "<init>" {
+                ValueType::UninitializedThis(self.class_name.to_string())
+            } else {
+                ValueType::Reference(ReferenceType::class(self.class_name))
+            };
+            let fact = hooks.parameter(None, &ty);
+            frame.store(index, Slot { ty, fact });
+            index += 1;
+        }
+        for (param, descriptor) in descriptor.parameter_types().iter().enumerate() {
+            let ty = ValueType::from_descriptor(descriptor);
+            let width = ty.words();
+            let fact = hooks.parameter(Some(param), &ty);
+            frame.store(index, Slot { ty, fact });
+            index += width;
+        }
+        Ok(frame)
+    }
+
+    /// Apply the effect of `inst` to `frame`.
+    pub(crate) fn execute<F: Fact>(
+        &self,
+        frame: &mut Frame<F>,
+        inst: &Instruction,
+        hooks: &impl FactHooks<F>,
+    ) -> Result<()> {
+        let code = &self.method.bytecode;
+        let at = inst.offset as usize;
+        match inst.opcode {
+            opcodes::NOP => {}
+            opcodes::ACONST_NULL => push(frame, inst, hooks, ValueType::Null, &[]),
+            opcodes::ICONST_M1
+            | opcodes::ICONST_0
+            | opcodes::ICONST_1
+            | opcodes::ICONST_2
+            | opcodes::ICONST_3
+            | opcodes::ICONST_4
+            | opcodes::ICONST_5
+            | opcodes::BIPUSH
+            | opcodes::SIPUSH => push(frame, inst, hooks, ValueType::Int, &[]),
+            opcodes::LCONST_0 | opcodes::LCONST_1 => {
+                push(frame, inst, hooks, ValueType::Long, &[]);
+            }
+            opcodes::FCONST_0 | opcodes::FCONST_1 | opcodes::FCONST_2 => {
+                push(frame, inst, hooks, ValueType::Float, &[]);
+            }
+            opcodes::DCONST_0 | opcodes::DCONST_1 => {
+                push(frame, inst, hooks, ValueType::Double, &[]);
+            }
+            opcodes::LDC | opcodes::LDC_W | opcodes::LDC2_W => {
+                let ty = constant_type(inst)?;
+                push(frame, inst, hooks, ty, &[]);
+            }
+            opcodes::ILOAD | opcodes::LLOAD | opcodes::FLOAD | opcodes::DLOAD | opcodes::ALOAD => {
+                let index = local_operand(code, at, false)?;
+                load(frame, hooks, inst.opcode - opcodes::ILOAD, index);
+            }
+            opcodes::ILOAD_0..=opcodes::ALOAD_3 => {
+                let relative = inst.opcode - opcodes::ILOAD_0;
+                load(frame, hooks, relative / 4, (relative % 4) as usize);
+            }
+            opcodes::IALOAD
+            | opcodes::LALOAD
+            | opcodes::FALOAD
+            | opcodes::DALOAD
+            | opcodes::AALOAD
+            | opcodes::BALOAD
+            | opcodes::CALOAD
+            | opcodes::SALOAD => {
+                let inputs = frame.pop_n(2);
+                let ty = match inst.opcode {
+                    opcodes::LALOAD => ValueType::Long,
+                    opcodes::FALOAD => ValueType::Float,
+                    opcodes::DALOAD => ValueType::Double,
+                    opcodes::AALOAD => match &inputs[0].ty {
+                        ValueType::Reference(array) => array.component(),
+                        _ => ValueType::Reference(ReferenceType::class(OBJECT_CLASS)),
+                    },
+                    _ => ValueType::Int,
+                };
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::ISTORE
+            | opcodes::LSTORE
+            | opcodes::FSTORE
+            | opcodes::DSTORE
+            | opcodes::ASTORE => {
+                let index = local_operand(code, at, false)?;
+                store(frame, hooks, index);
+            }
+            opcodes::ISTORE_0..=opcodes::ASTORE_3 => {
+                store(
+                    frame,
+                    hooks,
+                    ((inst.opcode - opcodes::ISTORE_0) % 4) as usize,
+                );
+            }
+            opcodes::IASTORE
+            | opcodes::LASTORE
+            | opcodes::FASTORE
+            | opcodes::DASTORE
+            | opcodes::AASTORE
+            | opcodes::BASTORE
+            | opcodes::CASTORE
+            | opcodes::SASTORE => {
+                frame.pop_n(3);
+            }
+            opcodes::POP => {
+                frame.pop();
+            }
+            opcodes::POP2 => {
+                let entries = frame.entries_for_words(2);
+                frame.pop_n(entries);
+            }
+            opcodes::DUP => frame.dup_words(1, 0),
+            opcodes::DUP_X1 => frame.dup_words(1, 1),
+            opcodes::DUP_X2 => frame.dup_words(1, 2),
+            opcodes::DUP2 => frame.dup_words(2, 0),
+            opcodes::DUP2_X1 => frame.dup_words(2, 1),
+            opcodes::DUP2_X2 => frame.dup_words(2, 2),
+            opcodes::SWAP => {
+                let inputs = frame.pop_n(2);
+                frame.stack.extend(inputs.into_iter().rev());
+            }
+            opcodes::IADD..=opcodes::DREM => {
+                let inputs = frame.pop_n(2);
+                let ty = arithmetic_type(inst.opcode - opcodes::IADD);
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::INEG | opcodes::LNEG | opcodes::FNEG | opcodes::DNEG => {
+                let inputs = frame.pop_n(1);
+                let ty = arithmetic_type(inst.opcode - opcodes::INEG);
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::ISHL
+            | opcodes::LSHL
+            | opcodes::ISHR
+            | opcodes::LSHR
+            | opcodes::IUSHR
+            | opcodes::LUSHR => {
+                let inputs = frame.pop_n(2);
+                let ty = arithmetic_type((inst.opcode - opcodes::ISHL) % 2);
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::IAND
+            | opcodes::LAND
+            | opcodes::IOR
+            | opcodes::LOR
+            | opcodes::IXOR
+            | opcodes::LXOR => {
+                let inputs = frame.pop_n(2);
+                let ty = arithmetic_type((inst.opcode - opcodes::IAND) % 2);
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::IINC => {
+                let index = local_operand(code, at, false)?;
+                increment(frame, inst, hooks, index);
+            }
+            opcodes::I2L
+            | opcodes::I2F
+            | opcodes::I2D
+            | opcodes::L2I
+            | opcodes::L2F
+            | opcodes::L2D
+            | opcodes::F2I
+            | opcodes::F2L
+            | opcodes::F2D
+            | opcodes::D2I
+            | opcodes::D2L
+            | opcodes::D2F
+            | opcodes::I2B
+            | opcodes::I2C
+            | opcodes::I2S => {
+                let inputs = frame.pop_n(1);
+                let ty = match inst.opcode {
+                    opcodes::I2L | opcodes::F2L | opcodes::D2L => ValueType::Long,
+                    opcodes::I2F | opcodes::L2F | opcodes::D2F => ValueType::Float,
+                    opcodes::I2D | opcodes::L2D | opcodes::F2D => ValueType::Double,
+                    _ => ValueType::Int,
+                };
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::LCMP | opcodes::FCMPL | opcodes::FCMPG | opcodes::DCMPL | opcodes::DCMPG => {
+                let inputs = frame.pop_n(2);
+                push(frame, inst, hooks, ValueType::Int, &inputs);
+            }
+            opcodes::IFEQ
+            | opcodes::IFNE
+            | opcodes::IFLT
+            | opcodes::IFGE
+            | opcodes::IFGT
+            | opcodes::IFLE
+            | opcodes::IFNULL
+            | opcodes::IFNONNULL
+            | opcodes::TABLESWITCH
+            | opcodes::LOOKUPSWITCH => {
+                frame.pop();
+            }
+            opcodes::IF_ICMPEQ
+            | opcodes::IF_ICMPNE
+            | opcodes::IF_ICMPLT
+            | opcodes::IF_ICMPGE
+            | opcodes::IF_ICMPGT
+            | opcodes::IF_ICMPLE
+            | opcodes::IF_ACMPEQ
+            | opcodes::IF_ACMPNE => {
+                frame.pop_n(2);
+            }
+            opcodes::GOTO | opcodes::GOTO_W | opcodes::RET | opcodes::RETURN => {}
+            opcodes::JSR | opcodes::JSR_W => {
+                let next = inst.offset + crate::scan::opcode_length(code, at)? as u32;
+                push(frame, inst, hooks, ValueType::ReturnAddress(next), &[]);
+            }
+            opcodes::IRETURN
+            | opcodes::LRETURN
+            | opcodes::FRETURN
+            | opcodes::DRETURN
+            | opcodes::ARETURN
+            | opcodes::ATHROW
+            | opcodes::MONITORENTER
+            | opcodes::MONITOREXIT => {
+                frame.pop();
+            }
+            opcodes::GETSTATIC | opcodes::PUTSTATIC | opcodes::GETFIELD | opcodes::PUTFIELD => {
+                let InstructionKind::Field(field) = &inst.kind else {
+                    anyhow::bail!("missing field operand at offset {}", inst.offset);
+                };
+                let ty = ValueType::from_field_descriptor(&field.descriptor)?;
+                match inst.opcode {
+                    opcodes::GETSTATIC => push(frame, inst, hooks, ty, &[]),
+                    opcodes::PUTSTATIC => {
+                        frame.pop();
+                    }
+                    opcodes::GETFIELD => {
+                        let inputs = frame.pop_n(1);
+                        push(frame, inst, hooks, ty, &inputs);
+                    }
+                    _ => {
+                        frame.pop_n(2);
+                    }
+                }
+            }
+            opcodes::INVOKEVIRTUAL
+            | opcodes::INVOKESPECIAL
+            | opcodes::INVOKESTATIC
+            | opcodes::INVOKEINTERFACE => {
+                let call = match &inst.kind {
+                    InstructionKind::Invoke(call) => call,
+                    _ => self
+                        .call_site(inst)
+                        .with_context(|| format!("missing call site at offset {}", inst.offset))?,
+                };
+                let has_receiver = inst.opcode != opcodes::INVOKESTATIC;
+                let inputs = invoke(frame, inst, hooks, &call.descriptor, has_receiver)?;
+                if inst.opcode == opcodes::INVOKESPECIAL
+                    && call.name == "<init>"
+                    && let Some(receiver) = inputs.first()
+                {
+                    match &receiver.ty {
+                        ValueType::Uninitialized(_, class_name)
+                        | ValueType::UninitializedThis(class_name) => {
+                            frame.initialize(&receiver.ty, class_name);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            opcodes::INVOKEDYNAMIC => {
+                let InstructionKind::InvokeDynamic(call) = &inst.kind else {
+                    anyhow::bail!("missing invokedynamic operand at offset {}", inst.offset);
+                };
+                invoke(frame, inst, hooks, &call.descriptor, false)?;
+            }
+            opcodes::NEW => {
+                let class_name = type_operand(inst)?;
+                let ty = ValueType::Uninitialized(inst.offset, class_name.to_string());
+                push(frame, inst, hooks, ty, &[]);
+            }
+            opcodes::NEWARRAY => {
+                let inputs = frame.pop_n(1);
+                let atype = code.get(at + 1).copied().context("newarray type")?;
+                let descriptor = match atype {
+                    4 => 'Z',
+                    5 => 'C',
+                    6 => 'F',
+                    7 => 'D',
+                    8 => 'B',
+                    9 => 'S',
+                    10 => 'I',
+                    11 => 'J',
+                    _ => anyhow::bail!("invalid newarray type {} at offset {}", atype, at),
+                };
+                let ty = ValueType::Reference(ReferenceType {
+                    base: BaseType::Primitive(descriptor),
+                    dimensions: 1,
+                });
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::ANEWARRAY => {
+                let inputs = frame.pop_n(1);
+                let component = ReferenceType::from_internal_name(type_operand(inst)?)?;
+                let ty = ValueType::Reference(component.array_of(1));
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::MULTIANEWARRAY => {
+                let dimensions = code.get(at + 3).copied().context("multianewarray dims")?;
+                let inputs = frame.pop_n(dimensions as usize);
+                let ty =
+                    ValueType::Reference(ReferenceType::from_internal_name(type_operand(inst)?)?);
+                push(frame, inst, hooks, ty, &inputs);
+            }
+            opcodes::ARRAYLENGTH | opcodes::INSTANCEOF => {
+                let inputs = frame.pop_n(1);
+                push(frame, inst, hooks, ValueType::Int, &inputs);
+            }
+            opcodes::CHECKCAST => {
+                // A cast narrows the type but keeps the value, and so its fact.
+                let mut value = frame.pop();
+                if value.ty != ValueType::Null {
+                    value.ty = ValueType::Reference(ReferenceType::from_internal_name(
+                        type_operand(inst)?,
+                    )?);
+                }
+                frame.stack.push(value);
+            }
+            opcodes::WIDE => {
+                let opcode = code.get(at + 1).copied().context("wide opcode")?;
+                let index = local_operand(code, at, true)?;
+                match opcode {
+                    opcodes::ILOAD
+                    | opcodes::LLOAD
+                    | opcodes::FLOAD
+                    | opcodes::DLOAD
+                    | opcodes::ALOAD => load(frame, hooks, opcode - opcodes::ILOAD, index),
+                    opcodes::ISTORE
+                    | opcodes::LSTORE
+                    | opcodes::FSTORE
+                    | opcodes::DSTORE
+                    | opcodes::ASTORE => store(frame, hooks, index),
+                    opcodes::IINC => increment(frame, inst, hooks, index),
+                    opcodes::RET => {}
+                    _ => anyhow::bail!("invalid wide opcode 0x{:02x} at offset {}", opcode, at),
+                }
+            }
+            opcode => anyhow::bail!("unsupported opcode 0x{:02x} at offset {}", opcode, at),
+        }
+        Ok(())
+    }
+}
+
+fn push<F: Fact>(
+    frame: &mut Frame<F>,
+    inst: &Instruction,
+    hooks: &impl FactHooks<F>,
+    ty: ValueType,
+    inputs: &[Slot<F>],
+) {
+    let fact = hooks.produce(inst, &ty, inputs);
+    frame.stack.push(Slot { ty, fact });
+}
+
+/// Load a local; `kind` is the offset from `iload` (0 = int ... 4 = reference).
+fn load<F: Fact>(frame: &mut Frame<F>, hooks: &impl FactHooks<F>, kind: u8, index: usize) {
+    let slot = frame.local(index);
+    let fact = hooks.load(index, &slot);
+    let ty = match kind {
+        0 => ValueType::Int,
+        1 => ValueType::Long,
+        2 => ValueType::Float,
+        3 => ValueType::Double,
+        _ => slot.ty,
+    };
+    frame.stack.push(Slot { ty, fact });
+}
+
+fn store<F: Fact>(frame: &mut Frame<F>, hooks: &impl FactHooks<F>, index: usize) {
+    let value = frame.pop();
+    let fact = hooks.store(index, &value);
+    frame.store(index, Slot { ty: value.ty, fact });
+}
+
+fn increment<F: Fact>(
+    frame: &mut Frame<F>,
+    inst: &Instruction,
+    hooks: &impl FactHooks<F>,
+    index: usize,
+) {
+    let current = frame.local(index);
+    let fact = hooks.produce(inst, &ValueType::Int, std::slice::from_ref(&current));
+    frame.store(
+        index,
+        Slot {
+            ty: ValueType::Int,
+            fact,
+        },
+    );
+}
+
+/// Pop the arguments (and receiver) of a call and push its return value.
+/// Returns the consumed operands, receiver first.
+fn invoke<F: Fact>(
+    frame: &mut Frame<F>,
+    inst: &Instruction,
+    hooks: &impl FactHooks<F>,
+    descriptor: &str,
+    has_receiver: bool,
+) -> Result<Vec<Slot<F>>> {
+    let descriptor = MethodDescriptor::from_str(descriptor).context("parse call descriptor")?;
+    let count = descriptor.parameter_types().len() + usize::from(has_receiver);
+    let inputs = frame.pop_n(count);
+    let return_type = descriptor.return_type();
+    if *return_type != TypeDescriptor::Void {
+        push(
+            frame,
+            inst,
+            hooks,
+            ValueType::from_descriptor(return_type),
+            &inputs,
+        );
+    }
+    Ok(inputs)
+}
+
+fn constant_type(inst: &Instruction) -> Result<ValueType> {
+    let ty = match &inst.kind {
+        InstructionKind::ConstString(_) => {
+            ValueType::Reference(ReferenceType::class("java/lang/String"))
+        }
+        InstructionKind::Constant(value) => match value {
+            ConstantValue::Int(_) => ValueType::Int,
+            ConstantValue::Float(_) => ValueType::Float,
+            ConstantValue::Long(_) => ValueType::Long,
+            ConstantValue::Double(_) => ValueType::Double,
+            ConstantValue::Class(_) => {
+                ValueType::Reference(ReferenceType::class("java/lang/Class"))
+            }
+            ConstantValue::MethodType(_) => {
+                ValueType::Reference(ReferenceType::class("java/lang/invoke/MethodType"))
+            }
+            ConstantValue::MethodHandle => {
+                ValueType::Reference(ReferenceType::class("java/lang/invoke/MethodHandle"))
+            }
+            ConstantValue::Dynamic(descriptor) => ValueType::from_field_descriptor(descriptor)?,
+        },
+        _ => anyhow::bail!("missing constant operand at offset {}", inst.offset),
+    };
+    Ok(ty)
+}
+
+fn type_operand(inst: &Instruction) -> Result<&str> {
+    match &inst.kind {
+        InstructionKind::Type(name) => Ok(name),
+        _ => anyhow::bail!("missing type operand at offset {}", inst.offset),
+    }
+}
+
+/// Local variable index of a load, store, iinc, or ret instruction.
+fn local_operand(code: &[u8], offset: usize, wide: bool) -> Result<usize> {
+    if wide {
+        Ok(crate::scan::read_u16(code, offset + 2)? as usize)
+    } else {
+        Ok(code.get(offset + 1).copied().context("local index")? as usize)
+    }
+}
+
+/// Result type for arithmetic opcodes laid out as int, long, float, double.
+fn arithmetic_type(relative: u8) -> ValueType {
+    match relative % 4 {
+        0 => ValueType::Int,
+        1 => ValueType::Long,
+        2 => ValueType::Float,
+        _ => ValueType::Double,
+    }
+}
+
+fn primitive_type(descriptor: char) -> ValueType {
+    match descriptor {
+        'J' => ValueType::Long,
+        'F' => ValueType::Float,
+        'D' => ValueType::Double,
+        _ => ValueType::Int,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::dataflow::{self, Analysis};
+    use crate::ir::{
+        CallKind, ControlFlowGraph, DynamicCallSite, FieldRef, MethodAccess, MethodNullness,
+    };
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn method(descriptor: &str, is_static: bool, bytecode: Vec<u8>) -> Method {
+        Method {
+            name: "run".to_string(),
+            descriptor: descriptor.to_string(),
+            access: MethodAccess {
+                is_public: true,
+                is_static,
+                is_abstract: false,
+            },
+            nullness: MethodNullness::unknown(0),
+            bytecode,
+            max_stack: 0,
+            max_locals: 0,
+            line_numbers: Vec::new(),
+            cfg: ControlFlowGraph {
+                blocks: Vec::new(),
+                edges: Vec::new(),
+            },
+            calls: Vec::new(),
+            string_literals: Vec::new(),
+            exception_handlers: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Decode straight-line bytecode, attaching operands by offset.
+    fn instructions(
+        bytecode: &[u8],
+        mut operands: BTreeMap<u32, InstructionKind>,
+    ) -> Vec<Instruction> {
+        let mut result = Vec::new();
+        let mut offset = 0;
+        while offset < bytecode.len() {
+            let opcode = bytecode[offset];
+            result.push(Instruction {
+                offset: offset as u32,
+                opcode,
+                kind: operands
+                    .remove(&(offset as u32))
+                    .unwrap_or(InstructionKind::Other(opcode)),
+            });
+            offset += crate::scan::opcode_length(bytecode, offset).expect("opcode length");
+        }
+        result
+    }
+
+    fn run<F: Fact>(
+        method: &Method,
+        operands: BTreeMap<u32, InstructionKind>,
+        hooks: &impl FactHooks<F>,
+    ) -> Frame<F> {
+        let interpreter = Interpreter::new("com/example/Sample", method);
+        let mut frame = interpreter.entry_frame(hooks).expect("entry frame");
+        for inst in instructions(&method.bytecode, operands) {
+            interpreter
+                .execute(&mut frame, &inst, hooks)
+                .expect("execute instruction");
+        }
+        frame
+    }
+
+    fn types<F>(slots: &[Slot<F>]) -> Vec<ValueType> {
+        slots.iter().map(|slot| slot.ty.clone()).collect()
+    }
+
+    fn reference(name: &str, dimensions: usize) -> ValueType {
+        ValueType::Reference(ReferenceType {
+            base: BaseType::Class(name.to_string()),
+            dimensions,
+        })
+    }
+
+    #[test]
+    fn interpreter_tracks_category2_values() {
+        let bytecode = vec![
+            opcodes::LLOAD_0,
+            opcodes::DLOAD_2,
+            opcodes::DUP2_X2,
+            opcodes::POP2,
+            opcodes::L2D,
+            opcodes::DADD,
+            opcodes::DSTORE,
+            4,
+            opcodes::RETURN,
+        ];
+        let frame = run(&method("(JD)V", true, bytecode), BTreeMap::new(), &NoFacts);
+
+        assert!(frame.stack.is_empty());
+        assert_eq!(
+            vec![
+                ValueType::Long,
+                ValueType::Top,
+                ValueType::Double,
+                ValueType::Top,
+                ValueType::Double,
+                ValueType::Top,
+            ],
+            types::<()>(&frame.locals)
+        );
+    }
+
+    #[test]
+    fn interpreter_models_dup_forms_with_mixed_categories() {
+        let bytecode = vec![
+            opcodes::ILOAD_0,
+            opcodes::LLOAD_1,
+            opcodes::DUP2_X1,
+            opcodes::ILOAD_0,
+            opcodes::DUP_X2,
+            opcodes::RETURN,
+        ];
+        let frame: Frame<()> = run(&method("(IJ)V", true, bytecode), BTreeMap::new(), &NoFacts);
+
+        assert_eq!(
+            vec![
+                ValueType::Long,
+                ValueType::Int,
+                ValueType::Int,
+                ValueType::Long,
+                ValueType::Int,
+            ],
+            types(&frame.stack)
+        );
+    }
+
+    #[test]
+    fn interpreter_resolves_field_array_and_cast_types() {
+        let bytecode = vec![
+            opcodes::ALOAD_0,
+            opcodes::GETFIELD,
+            0,
+            1,
+            opcodes::ICONST_0,
+            opcodes::AALOAD,
+            opcodes::ICONST_0,
+            opcodes::AALOAD,
+            opcodes::DUP,
+            opcodes::CHECKCAST,
+            0,
+            2,
+            opcodes::RETURN,
+        ];
+        let operands = BTreeMap::from([
+            (
+                1,
+                InstructionKind::Field(FieldRef {
+                    owner: "com/example/Sample".to_string(),
+                    name: "items".to_string(),
+                    descriptor: "[[Ljava/lang/String;".to_string(),
+                }),
+            ),
+            (
+                9,
+                InstructionKind::Type("java/lang/CharSequence".to_string()),
+            ),
+        ]);
+        let frame: Frame<()> = run(&method("()V", false, bytecode), operands, &NoFacts);
+
+        assert_eq!(
+            vec![
+                reference("java/lang/String", 0),
+                reference("java/lang/CharSequence", 0),
+            ],
+            types(&frame.stack)
+        );
+    }
+
+    #[test]
+    fn interpreter_initializes_objects_after_constructor() {
+        let bytecode = vec![
+            opcodes::NEW,
+            0,
+            1,
+            opcodes::DUP,
+            opcodes::INVOKESPECIAL,
+            0,
+            2,
+            opcodes::INVOKEDYNAMIC,
+            0,
+            3,
+            0,
+            0,
+            opcodes::RETURN,
+        ];
+        let operands = BTreeMap::from([
+            (
+                0,
+                InstructionKind::Type("java/lang/StringBuilder".to_string()),
+            ),
+            (
+                4,
+                InstructionKind::Invoke(CallSite {
+                    owner: "java/lang/StringBuilder".to_string(),
+                    name: "<init>".to_string(),
+                    descriptor: "()V".to_string(),
+                    kind: CallKind::Special,
+                    offset: 4,
+                }),
+            ),
+            (
+                7,
+                InstructionKind::InvokeDynamic(DynamicCallSite {
+                    name: "makeConcatWithConstants".to_string(),
+                    descriptor: "(Ljava/lang/Object;)Ljava/lang/String;".to_string(),
+                }),
+            ),
+        ]);
+        let frame: Frame<()> = run(&method("()V", true, bytecode), operands, &NoFacts);
+
+        assert_eq!(vec![reference("java/lang/String", 0)], types(&frame.stack));
+    }
+
+    #[test]
+    fn interpreter_handles_wide_locals() {
+        let bytecode = vec![
+            opcodes::ICONST_1,
+            opcodes::WIDE,
+            opcodes::ISTORE,
+            0x01,
+            0x2c,
+            opcodes::WIDE,
+            opcodes::IINC,
+            0x01,
+            0x2c,
+            0x00,
+            0x05,
+            opcodes::WIDE,
+            opcodes::ILOAD,
+            0x01,
+            0x2c,
+            opcodes::NEWARRAY,
+            11,
+            opcodes::RETURN,
+        ];
+        let frame: Frame<()> = run(&method("()V", true, bytecode), BTreeMap::new(), &NoFacts);
+
+        assert_eq!(301, frame.locals.len());
+        assert_eq!(ValueType::Int, frame.locals[300].ty);
+        assert_eq!(
+            vec![ValueType::Reference(ReferenceType {
+                base: BaseType::Primitive('J'),
+                dimensions: 1,
+            })],
+            types(&frame.stack)
+        );
+    }
+
+    /// Offsets of the instructions that may have produced a value.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Origins(BTreeSet<u32>);
+
+    impl Fact for Origins {
+        fn join(&self, other: &Self) -> Self {
+            Origins(self.0.union(&other.0).copied().collect())
+        }
+    }
+
+    struct OriginHooks;
+
+    impl FactHooks<Origins> for OriginHooks {
+        fn produce(
+            &self,
+            inst: &Instruction,
+            _ty: &ValueType,
+            _inputs: &[Slot<Origins>],
+        ) -> Origins {
+            Origins(BTreeSet::from([inst.offset]))
+        }
+    }
+
+    #[test]
+    fn interpreter_attaches_rule_facts_to_slots() {
+        let bytecode = vec![
+            opcodes::ICONST_1,
+            opcodes::ISTORE_0,
+            opcodes::ILOAD_0,
+            opcodes::DUP,
+            opcodes::ICONST_2,
+            opcodes::IADD,
+            opcodes::RETURN,
+        ];
+        let frame = run(
+            &method("()V", true, bytecode),
+            BTreeMap::new(),
+            &OriginHooks,
+        );
+
+        assert_eq!(Origins(BTreeSet::from([0])), frame.locals[0].fact);
+        assert_eq!(Origins(BTreeSet::from([0])), frame.stack[0].fact);
+        assert_eq!(Origins(BTreeSet::from([5])), frame.stack[1].fact);
+
+        let mut other = frame.clone();
+        other.stack[0].fact = Origins(BTreeSet::from([7]));
+        let joined = frame.join(&other);
+        assert_eq!(Origins(BTreeSet::from([0, 7])), joined.stack[0].fact);
+    }
+
+    #[test]
+    fn value_type_join_merges_references() {
+        let strings = reference("java/lang/String", 1);
+        let integers = reference("java/lang/Integer", 1);
+        assert_eq!(strings, ValueType::Null.join(&strings));
+        assert_eq!(reference("java/lang/Object", 1), strings.join(&integers));
+        assert_eq!(
+            reference("java/lang/Object", 0),
+            strings.join(&reference("java/lang/String", 0))
+        );
+        assert_eq!(ValueType::Top, ValueType::Int.join(&ValueType::Float));
+    }
+
+    /// Type-only analysis used to sweep compiled code.
+    struct TypeAnalysis<'a> {
+        interpreter: Interpreter<'a>,
+    }
+
+    impl Analysis for TypeAnalysis<'_> {
+        type Domain = Frame<()>;
+
+        fn boundary_state(&self) -> Result<Frame<()>> {
+            self.interpreter.entry_frame(&NoFacts)
+        }
+
+        fn join(&self, left: &Frame<()>, right: &Frame<()>) -> Frame<()> {
+            left.join(right)
+        }
+
+        fn transfer(&self, frame: &mut Frame<()>, inst: &Instruction) -> Result<()> {
+            self.interpreter.execute(frame, inst, &NoFacts)
+        }
+
+        fn exception_state(&self, frame: &Frame<()>) -> Frame<()> {
+            frame.exception_frame(())
+        }
+    }
+
+    #[test]
+    fn interpreter_types_every_return_in_compiled_code() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: r#"
+package com.example;
+import java.util.function.Supplier;
+public class Sample {
+    private long total;
+    private double[][] grid = new double[2][3];
+    private final Object lock = new Object();
+
+    public long mix(int a, long b, double c, float d) {
+        long result = a + b;
+        result <<= 2;
+        result ^= (long) (c * d);
+        total += result;
+        return result > 0 ? result : -result;
+    }
+
+    public double cell(int row, int column) {
+        grid[row][column] += 1.5;
+        return grid[row][column];
+    }
+
+    public String describe(Object value, int kind) {
+        switch (kind) {
+            case 1: return "one";
+            case 100: return "hundred";
+            default: break;
+        }
+        if (value instanceof CharSequence) {
+            return ((CharSequence) value).toString() + kind;
+        }
+        try {
+            return String.valueOf(value.hashCode());
+        } catch (RuntimeException e) {
+            return e.getMessage();
+        } finally {
+            total++;
+        }
+    }
+
+    public int guarded() {
+        synchronized (lock) {
+            int[] values = {1, 2, 3};
+            int sum = 0;
+            for (int value : values) {
+                sum += value;
+            }
+            return sum;
+        }
+    }
+
+    public Supplier<String> supplier(String prefix) {
+        return () -> prefix + total;
+    }
+
+    public char letter(char[] letters, byte index) {
+        return letters[index];
+    }
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let scan = scan_inputs(output.classes_dir(), &[]).expect("scan classes");
+
+        let mut returns = 0;
+        for class in &scan.classes {
+            for method in &class.methods {
+                let analysis = TypeAnalysis {
+                    interpreter: Interpreter::new(&class.name, method),
+                };
+                let flow = dataflow::solve(method, &analysis).expect("interpret method");
+                assert!(flow.converged);
+                flow.replay(method, &analysis, |_, inst, frame| {
+                    let top = frame.peek(0).map(|slot| slot.ty.clone());
+                    let expected = match inst.opcode {
+                        opcodes::IRETURN => Some(ValueType::Int),
+                        opcodes::LRETURN => Some(ValueType::Long),
+                        opcodes::DRETURN => Some(ValueType::Double),
+                        opcodes::ARETURN => {
+                            assert!(top.as_ref().is_some_and(ValueType::is_reference));
+                            returns += 1;
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(expected) = expected {
+                        assert_eq!(Some(expected), top);
+                        returns += 1;
+                    }
+                    Ok(())
+                })
+                .expect("replay method");
+            }
+        }
+        assert!(returns >= 10);
+    }
+}
@@ -2,96 +2,102 @@
 
 /// Intermediate representation for parsed JVM classes and methods.
 #[derive(Clone, Debug)]
-pub(crate) struct Class {
-    pub(crate) name: String,
-    pub(crate) super_name: Option<String>,
-    pub(crate) interfaces: Vec<String>,
-    pub(crate) referenced_classes: Vec<String>,
-    pub(crate) fields: Vec<Field>,
-    pub(crate) methods: Vec<Method>,
-    pub(crate) artifact_index: i64,
-    pub(crate) is_record: bool,
+pub struct Class {
+    pub name: String,
+    pub super_name: Option<String>,
+    pub interfaces: Vec<String>,
+    pub referenced_classes: Vec<String>,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Method>,
+    pub artifact_index: i64,
+    pub is_record: bool,
+    /// Internal names of the class annotations, visible and invisible at runtime.
+    pub annotations: Vec<String>,
 }
 
 /// Field definition for a class.
 #[derive(Clone, Debug)]
-pub(crate) struct Field {
-    pub(crate) name: String,
-    pub(crate) descriptor: String,
-    pub(crate) access: FieldAccess,
+pub struct Field {
+    pub name: String,
+    pub descriptor: String,
+    pub access: FieldAccess,
 }
 
 /// Field access flags used for rule filtering.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct FieldAccess {
-    pub(crate) is_static: bool,
+pub struct FieldAccess {
+    pub is_static: bool,
 }
 
 /// Intermediate representation for a method and its bytecode.
 #[derive(Clone, Debug)]
-pub(crate) struct Method {
-    pub(crate) name: String,
-    pub(crate) descriptor: String,
-    pub(crate) access: MethodAccess,
-    pub(crate) nullness: MethodNullness,
-    pub(crate) bytecode: Vec<u8>,
-    pub(crate) line_numbers: Vec<LineNumber>,
-    pub(crate) cfg: ControlFlowGraph,
-    pub(crate) calls: Vec<CallSite>,
-    pub(crate) string_literals: Vec<String>,
-    pub(crate) exception_handlers: Vec<ExceptionHandler>,
+pub struct Method {
+    pub name: String,
+    pub descriptor: String,
+    pub access: MethodAccess,
+    pub nullness: MethodNullness,
+    pub bytecode: Vec<u8>,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub line_numbers: Vec<LineNumber>,
+    pub cfg: ControlFlowGraph,
+    pub calls: Vec<CallSite>,
+    pub string_literals: Vec<String>,
+    pub exception_handlers: Vec<ExceptionHandler>,
+    /// Internal names of the method annotations, visible and invisible at runtime.
+    pub annotations: Vec<String>,
 }
 
 /// Method access flags used for rule filtering.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct MethodAccess {
-    pub(crate) is_public: bool,
-    pub(crate) is_static: bool,
-    pub(crate) is_abstract: bool,
+pub struct MethodAccess {
+    pub is_public: bool,
+    pub is_static: bool,
+    pub is_abstract: bool,
 }
 
 /// Exception handler metadata from the Code attribute.
 #[derive(Clone, Debug)]
-pub(crate) struct ExceptionHandler {
-    pub(crate) start_pc: u32,
-    pub(crate) end_pc: u32,
-    pub(crate) handler_pc: u32,
-    pub(crate) catch_type: Option<String>,
+pub struct ExceptionHandler {
+    pub start_pc: u32,
+    pub end_pc: u32,
+    pub handler_pc: u32,
+    pub catch_type: Option<String>,
 }
 
 /// Line number mapping entry from bytecode offsets to source lines.
 #[derive(Clone, Debug)]
-pub(crate) struct LineNumber {
-    pub(crate) start_pc: u32,
-    pub(crate) line: u32,
+pub struct LineNumber {
+    pub start_pc: u32,
+    pub line: u32,
 }
 
 /// Basic block graph for method bytecode.
 #[derive(Clone, Debug)]
-pub(crate) struct ControlFlowGraph {
-    pub(crate) blocks: Vec<BasicBlock>,
-    pub(crate) edges: Vec<FlowEdge>,
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<FlowEdge>,
 }
 
 /// Basic block covering a range of bytecode offsets.
 #[derive(Clone, Debug)]
-pub(crate) struct BasicBlock {
-    pub(crate) start_offset: u32,
-    pub(crate) end_offset: u32,
-    pub(crate) instructions: Vec<Instruction>,
+pub struct BasicBlock {
+    pub start_offset: u32,
+    pub end_offset: u32,
+    pub instructions: Vec<Instruction>,
 }
 
 /// Edge between basic blocks.
 #[derive(Clone, Debug)]
-pub(crate) struct FlowEdge {
-    pub(crate) from: u32,
-    pub(crate) to: u32,
-    pub(crate) kind: EdgeKind,
+pub struct FlowEdge {
+    pub from: u32,
+    pub to: u32,
+    pub kind: EdgeKind,
 }
 
 /// Edge classification used for CFG inspection.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) enum EdgeKind {
+pub enum EdgeKind {
     FallThrough,
     Branch,
     Exception,
@@ -99,42 +105,87 @@ pub(crate) enum EdgeKind {
 
 /// Bytecode instruction captured for analysis.
 #[derive(Clone, Debug)]
-pub(crate) struct Instruction {
-    pub(crate) offset: u32,
-    pub(crate) opcode: u8,
-    pub(crate) kind: InstructionKind,
+pub struct Instruction {
+    pub offset: u32,
+    pub opcode: u8,
+    pub kind: InstructionKind,
 }
 
-/// Instruction kinds needed for call graph construction.
+/// Instruction kinds with the constant pool operands resolved.
 #[derive(Clone, Debug)]
-pub(crate) enum InstructionKind {
+pub enum InstructionKind {
     Invoke(CallSite),
+    InvokeDynamic(DynamicCallSite),
+    Field(FieldRef),
+    /// Class or array type operand of new, anewarray, checkcast, instanceof, multianewarray.
+    Type(String),
     ConstString(String),
+    Constant(ConstantValue),
     Other(u8),
 }
 
+/// Name and descriptor of an invokedynamic call site.
+#[derive(Clone, Debug)]
+pub struct DynamicCallSite {
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// Field reference used by getfield, putfield, getstatic, and putstatic.
+#[derive(Clone, Debug)]
+pub struct FieldRef {
+    pub owner: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// Non-string constant loaded by ldc, ldc_w, or ldc2_w.
+#[derive(Clone, Debug)]
+pub enum ConstantValue {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(String),
+    MethodType(String),
+    MethodHandle,
+    /// Dynamically computed constant with its field descriptor.
+    Dynamic(String),
+}
+
 /// Call site extracted from bytecode.
 #[derive(Clone, Debug)]
-pub(crate) struct CallSite {
-    pub(crate) owner: String,
-    pub(crate) name: String,
-    pub(crate) descriptor: String,
-    pub(crate) kind: CallKind,
-    pub(crate) offset: u32,
+pub struct CallSite {
+    pub owner: String,
+    pub name: String,
+    pub descriptor: String,
+    pub kind: CallKind,
+    pub offset: u32,
 }
 
 /// Call opcode classification used by CHA.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) enum CallKind {
+pub enum CallKind {
     Virtual,
     Interface,
     Special,
     Static,
 }
 
+impl CallKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Virtual => "virtual",
+            Self::Interface => "interface",
+            Self::Special => "special",
+            Self::Static => "static",
+        }
+    }
+}
+
 /// Nullness classification used by JSpecify checks.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum Nullness {
+pub enum Nullness {
     Unknown,
     NonNull,
     Nullable,
@@ -142,13 +193,13 @@ pub(crate) enum Nullness {
 
 /// Nullness annotations for a method signature.
 #[derive(Clone, Debug)]
-pub(crate) struct MethodNullness {
-    pub(crate) return_nullness: Nullness,
-    pub(crate) parameter_nullness: Vec<Nullness>,
+pub struct MethodNullness {
+    pub return_nullness: Nullness,
+    pub parameter_nullness: Vec<Nullness>,
 }
 
 impl MethodNullness {
-    pub(crate) fn unknown(param_count: usize) -> Self {
+    pub fn unknown(param_count: usize) -> Self {
         Self {
             return_nullness: Nullness::Unknown,
             parameter_nullness: vec![Nullness::Unknown; param_count],
@@ -157,7 +208,7 @@ impl MethodNullness {
 }
 
 impl Method {
-    pub(crate) fn line_for_offset(&self, offset: u32) -> Option<u32> {
+    pub fn line_for_offset(&self, offset: u32) -> Option<u32> {
         let mut candidate = None;
         for entry in &self.line_numbers {
             if entry.start_pc <= offset {
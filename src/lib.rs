@@ -0,0 +1,102 @@
+//! Static analysis for JVM class files and JAR files, with SARIF output.
+//!
+//! [`Analyzer`] scans an input and its classpath, runs the built-in rules and any
+//! rules you register, and returns the findings as SARIF results:
+//!
+//! ```no_run
+//! use std::borrow::Cow;
+//!
+//! use inspequte::ir::Class;
+//! use inspequte::rules::{class_location, result_message};
+//! use inspequte::sarif::ResultLevel;
+//! use inspequte::{
+//!     AnalysisContext, Analyzer, Precision, Rule, RuleMetadata, RuleVisitor, SarifResult,
+//! };
+//!
+//! /// Flags classes in the legacy package.
+//! struct NoLegacyPackage;
+//!
+//! impl Rule for NoLegacyPackage {
+//!     fn metadata(&self) -> RuleMetadata {
+//!         RuleMetadata {
+//!             id: "NO_LEGACY_PACKAGE".into(),
+//!             name: "No legacy package".into(),
+//!             description: "New code should not live in com/example/legacy".into(),
+//!             tags: Cow::Borrowed(&["style"]),
+//!             default_level: ResultLevel::Warning,
+//!             help: "# NO_LEGACY_PACKAGE: No legacy package\n".into(),
+//!             cwe: &[],
+//!             precision: Precision::VeryHigh,
+//!             security_severity: None,
+//!         }
+//!     }
+//!
+//!     fn visitor(&self) -> Option<&dyn RuleVisitor> {
+//!         Some(self)
+//!     }
+//! }
+//!
+//! impl RuleVisitor for NoLegacyPackage {
+//!     fn visit_class(
+//!         &self,
+//!         _context: &AnalysisContext,
+//!         class: &Class,
+//!         results: &mut Vec<SarifResult>,
+//!     ) -> anyhow::Result<()> {
+//!         if class.name.starts_with("com/example/legacy/") {
+//!             results.push(
+//!                 SarifResult::builder()
+//!                     .message(result_message(format!("Legacy class {}", class.name)))
+//!                     .locations(vec![class_location(&class.name)])
+//!                     .build(),
+//!             );
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let analysis = Analyzer::builder()
+//!     .input("build/libs/app.jar")
+//!     .classpath(["build/deps/"])
+//!     .rule(NoLegacyPackage)
+//!     .build()?
+//!     .run()?;
+//! for result in &analysis.results {
+//!     println!("{:?}: {:?}", result.rule_id, result.message.text);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod analyzer;
+pub mod baseline;
+pub mod callgraph;
+pub mod callgraph_export;
+mod cfg;
+pub mod class_filter;
+mod classpath;
+pub mod config;
+pub mod dataflow;
+mod descriptor;
+pub mod engine;
+pub mod facts;
+mod interpreter;
+pub mod ir;
+pub mod metrics;
+mod numeric;
+mod opcodes;
+pub mod pointsto;
+pub mod rules;
+pub mod sarif;
+pub mod scan;
+pub mod ssa;
+mod summary;
+#[cfg(test)]
+mod test_harness;
+pub mod trace;
+
+pub use analyzer::{Analysis, AnalysisStats, Analyzer, AnalyzerBuilder};
+pub use engine::{AnalysisContext, Engine, EngineOptions};
+pub use rules::{Precision, Rule, RuleMetadata, RuleOutput, RuleVisitor};
+pub use serde_sarif::sarif::Result as SarifResult;
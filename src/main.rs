@@ -1,39 +1,34 @@
-mod baseline;
-mod callgraph;
-mod cfg;
-mod classpath;
-mod descriptor;
-mod engine;
-mod ir;
-mod opcodes;
-mod rules;
-mod scan;
-#[cfg(test)]
-mod test_harness;
-
-use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
-use jsonschema::JSONSchema;
 use serde_json::json;
-use serde_sarif::sarif::Result as SarifResult;
-use serde_sarif::sarif::{
-    Artifact, Invocation, PropertyBag, ReportingDescriptor, Run, SCHEMA_URL, Sarif, Tool,
-    ToolComponent,
-};
+use serde_sarif::sarif::{Invocation, PropertyBag, ReportingDescriptor, ResultLevel};
 
-use crate::baseline::{load_baseline, write_baseline};
-use crate::classpath::resolve_classpath;
-use crate::engine::{Engine, build_context_with_timings};
-use crate::scan::scan_inputs;
+use inspequte::baseline::{load_baseline, write_baseline};
+use inspequte::callgraph::CallGraphAlgorithm;
+use inspequte::callgraph_export::{
+    ExportFormat, ExportOptions, Granularity, export_graph, find_methods, write_graph,
+};
+use inspequte::class_filter::ClassFilter;
+use inspequte::config::{Config, load_config};
+use inspequte::engine::RuleFailure;
+use inspequte::facts::{extract_facts, write_facts, write_sql};
+use inspequte::metrics::ClassMetrics;
+use inspequte::rules::dead_code::explain_reachability;
+use inspequte::rules::vulnerable_method::load_vulnerable_methods;
+use inspequte::sarif::{build_sarif, failure_notifications, validate_sarif};
+use inspequte::{AnalysisStats, Analyzer, Engine, EngineOptions, RuleMetadata, trace};
 
 const DEFAULT_BASELINE_PATH: &str = ".inspequte/baseline.json";
 
+/// Exit code when output was written but some rules failed on part of the program.
+const PARTIAL_FAILURE_EXIT_CODE: u8 = 2;
+
 /// CLI arguments for inspequte execution.
 #[derive(Parser, Debug)]
 #[command(
@@ -60,24 +55,144 @@ struct ScanArgs {
     quiet: bool,
     #[arg(long)]
     timing: bool,
-    #[arg(long, value_name = "PATH", default_value = DEFAULT_BASELINE_PATH)]
-    baseline: PathBuf,
+    /// Write a Chrome Trace Event file of the scan, call graph and rule phases to PATH.
+    #[arg(long, value_name = "PATH")]
+    trace: Option<PathBuf>,
+    /// Baseline file; defaults to the configuration's, then `.inspequte/baseline.json`.
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+    /// Write class and method metrics as JSON to PATH.
+    #[arg(long, value_name = "PATH")]
+    metrics: Option<PathBuf>,
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+/// Rule and call graph settings shared by commands that run rules.
+#[derive(Args, Debug, Clone)]
+struct AnalysisArgs {
+    #[command(flatten)]
+    rule_selection: RuleSelectionArgs,
+    /// Override a METHOD_METRICS threshold, e.g. `cyclomatic_complexity=30` or `loop_depth=off`.
+    #[arg(long = "metric-threshold", value_name = "NAME=VALUE")]
+    metric_thresholds: Vec<String>,
+    /// Report every result of a rule at LEVEL (error, warning, note or none), e.g. `DEAD_CODE=none`.
+    #[arg(long = "rule-level", value_name = "RULE=LEVEL")]
+    rule_levels: Vec<String>,
+    /// Report reachable methods listed in PATH, one `Class.method[(descriptor)] [ADVISORY]` per line.
+    #[arg(long, value_name = "PATH")]
+    vulnerable_methods: Option<PathBuf>,
+    /// Call graph construction algorithm used by reachability rules.
+    #[arg(long = "callgraph", value_enum, default_value_t = CallGraphAlgorithm::Cha)]
+    call_graph: CallGraphAlgorithm,
+    /// Milliseconds the points-to analysis may take before falling back to CHA.
+    #[arg(long = "points-to-budget-ms", value_name = "MS")]
+    points_to_budget_ms: Option<u64>,
 }
 
 /// Input configuration shared by all commands.
 #[derive(Args, Debug, Clone)]
 struct InputArgs {
+    /// Class file, JAR or directory to analyze; required unless the configuration sets it.
     #[arg(long, value_name = "PATH")]
-    input: PathBuf,
+    input: Option<PathBuf>,
+    /// Replaces the configuration's classpath when given.
+    #[arg(long, value_name = "PATH")]
+    classpath: Vec<PathBuf>,
+    /// Configuration file; `inspequte.toml` in the working directory is used when present.
     #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+/// Rule selection shared by commands that run rules.
+#[derive(Args, Debug, Clone, Default)]
+struct RuleSelectionArgs {
+    /// Run only these rule IDs or categories (correctness, performance, security, style).
+    #[arg(long = "rules", value_name = "RULE", value_delimiter = ',')]
+    rules: Vec<String>,
+    /// Skip these rule IDs or categories.
+    #[arg(long = "exclude-rules", value_name = "RULE", value_delimiter = ',')]
+    exclude_rules: Vec<String>,
+    /// Report only on classes matching PATTERN, a glob such as `com.acme.**` or `regex:...`.
+    #[arg(long, value_name = "PATTERN")]
+    include: Vec<String>,
+    /// Report nothing on classes matching PATTERN; they are still used for the call graph.
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+}
+
+impl RuleSelectionArgs {
+    /// `--rules` replaces the configuration's enabled rules; `--exclude-rules`,
+    /// `--include` and `--exclude` add to its disabled rules and class patterns.
+    fn apply(&self, options: &mut EngineOptions) -> Result<()> {
+        if !self.rules.is_empty() {
+            options.enabled_rules = Some(self.rules.iter().cloned().collect());
+        }
+        options
+            .disabled_rules
+            .extend(self.exclude_rules.iter().cloned());
+        let class_filter = ClassFilter::parse(&self.include, &self.exclude)?;
+        options.class_filter.include.extend(class_filter.include);
+        options.class_filter.exclude.extend(class_filter.exclude);
+        Ok(())
+    }
+
+    fn apply_to_config(&self, config: &mut Config) {
+        if !self.rules.is_empty() {
+            config.rules.enabled = Some(self.rules.clone());
+        }
+        config
+            .rules
+            .disabled
+            .extend(self.exclude_rules.iter().cloned());
+        config.include.extend(self.include.iter().cloned());
+        config.exclude.extend(self.exclude.iter().cloned());
+    }
+}
+
+/// Inputs after merging the command line into the configuration file.
+struct ResolvedInputs {
+    config: Config,
+    input: PathBuf,
     classpath: Vec<PathBuf>,
 }
 
+impl InputArgs {
+    fn resolve(&self) -> Result<ResolvedInputs> {
+        let config = load_config(self.config.as_deref())?;
+        let input = self
+            .input
+            .clone()
+            .or_else(|| config.input.clone())
+            .context("--input is required unless the configuration file sets input")?;
+        let classpath = if self.classpath.is_empty() {
+            config.classpath.clone()
+        } else {
+            self.classpath.clone()
+        };
+        Ok(ResolvedInputs {
+            config,
+            input,
+            classpath,
+        })
+    }
+}
+
 /// Subcommands supported by the CLI.
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Create a baseline file containing all current findings.
     Baseline(BaselineArgs),
+    /// Export the call graph as DOT, JSON or GraphML.
+    Callgraph(CallgraphArgs),
+    /// Explain why a method is reachable, or why DEAD_CODE considers it unreachable.
+    Why(WhyArgs),
+    /// Export classes, methods, call sites and call graph edges as relations for Datalog or SQL.
+    Facts(FactsArgs),
+    /// List every rule with its default level and tags.
+    Rules(RulesArgs),
+    /// Print the documentation of a rule.
+    Explain(ExplainArgs),
 }
 
 /// Arguments for creating a baseline file.
@@ -85,40 +200,144 @@ enum Command {
 struct BaselineArgs {
     #[command(flatten)]
     input: InputArgs,
-    #[arg(long, value_name = "PATH", default_value = DEFAULT_BASELINE_PATH)]
-    output: PathBuf,
+    /// Baseline file; defaults to the configuration's, then `.inspequte/baseline.json`.
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+}
+
+/// Arguments for exporting the call graph.
+#[derive(Args, Debug, Clone)]
+struct CallgraphArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Write the graph to PATH instead of stdout.
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Dot)]
+    format: ExportFormat,
+    /// Call graph construction algorithm.
+    #[arg(long = "callgraph", value_enum, default_value_t = CallGraphAlgorithm::Cha)]
+    call_graph: CallGraphAlgorithm,
+    /// Keep only calls between classes of the analysis target.
+    #[arg(long)]
+    targets_only: bool,
+    /// Collapse nodes to classes or packages.
+    #[arg(long, value_enum, default_value_t = Granularity::Method)]
+    granularity: Granularity,
+    /// Keep only calls reachable from METHOD, e.g. `com/example/App.main`.
+    #[arg(long = "root", value_name = "METHOD")]
+    roots: Vec<String>,
+    /// Maximum number of calls to follow from the roots.
+    #[arg(long, value_name = "N", requires = "roots")]
+    depth: Option<usize>,
+}
+
+/// Arguments for explaining method reachability.
+#[derive(Args, Debug, Clone)]
+struct WhyArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Method to explain, e.g. `com/example/Foo.bar()V` or `com/example/Foo.bar`.
+    #[arg(long, value_name = "METHOD")]
+    method: String,
+    /// Call graph construction algorithm.
+    #[arg(long = "callgraph", value_enum, default_value_t = CallGraphAlgorithm::Cha)]
+    call_graph: CallGraphAlgorithm,
+}
+
+/// Arguments for exporting analysis facts.
+#[derive(Args, Debug, Clone)]
+struct FactsArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Directory the `.facts` files and `schema.dl` are written to.
+    #[arg(long, value_name = "DIR")]
+    out: PathBuf,
+    /// Also write the facts as SQL statements for SQLite to PATH.
+    #[arg(long, value_name = "PATH")]
+    sql: Option<PathBuf>,
+    /// Call graph construction algorithm.
+    #[arg(long = "callgraph", value_enum, default_value_t = CallGraphAlgorithm::Cha)]
+    call_graph: CallGraphAlgorithm,
 }
 
-fn main() -> std::process::ExitCode {
+/// Arguments for listing rules.
+#[derive(Args, Debug, Clone)]
+struct RulesArgs {
+    /// Configuration file with custom rules; defaults to `inspequte.toml` when present.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+/// Arguments for printing rule documentation.
+#[derive(Args, Debug, Clone)]
+struct ExplainArgs {
+    /// Rule ID, e.g. `NULLNESS`.
+    #[arg(value_name = "RULE_ID")]
+    rule_id: String,
+    /// Configuration file with custom rules; defaults to `inspequte.toml` when present.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
     match run(cli) {
-        Ok(()) => std::process::ExitCode::SUCCESS,
+        Ok(code) => code,
         Err(err) => {
             eprintln!("{err:?}");
-            std::process::ExitCode::from(1)
+            ExitCode::from(1)
         }
     }
 }
 
-fn run(cli: Cli) -> Result<()> {
+fn run(cli: Cli) -> Result<ExitCode> {
     match cli.command {
         Some(Command::Baseline(args)) => run_baseline(args),
+        Some(Command::Callgraph(args)) => run_callgraph(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Why(args)) => run_why(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Facts(args)) => run_facts(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Rules(args)) => run_rules(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Explain(args)) => run_explain(args).map(|()| ExitCode::SUCCESS),
         None => run_scan(cli.scan),
     }
 }
 
-fn run_scan(args: ScanArgs) -> Result<()> {
-    ensure_inputs_exist(&args.input.input, &args.input.classpath)?;
+fn run_scan(args: ScanArgs) -> Result<ExitCode> {
+    let inputs = args.input.resolve()?;
+    let baseline_path = baseline_path(args.baseline.as_deref(), &inputs.config);
+
+    let options = engine_options(&args.analysis, &inputs.config)?;
+    let config = effective_config(&inputs, &baseline_path, &args.analysis);
+    if args.trace.is_some() {
+        trace::enable();
+    }
 
     let started_at = Instant::now();
-    let mut analysis = analyze(&args.input.input, &args.input.classpath)?;
+    let mut analysis = Analyzer::builder()
+        .input(&inputs.input)
+        .classpath(&inputs.classpath)
+        .options(options)
+        .metrics(args.metrics.is_some())
+        .build()?
+        .run()?;
     let baseline_started_at = Instant::now();
-    if let Some(baseline) = load_baseline(&args.baseline)? {
+    let baseline_span = trace::span("baseline", "filter baseline");
+    if let Some(baseline) = load_baseline(&baseline_path)? {
         analysis.results = baseline.filter(analysis.results);
     }
+    drop(baseline_span);
     let baseline_duration_ms = baseline_started_at.elapsed().as_millis();
 
-    let invocation = build_invocation(&analysis.invocation_stats);
+    let exit_code = report_failures(&analysis.failures, args.quiet);
+    let invocation = build_invocation(
+        &analysis.stats,
+        &config,
+        &analysis.failures,
+        &analysis.rules,
+    )?;
     let sarif = build_sarif(
         analysis.artifacts,
         invocation,
@@ -130,106 +349,326 @@ fn run_scan(args: ScanArgs) -> Result<()> {
     }
 
     let write_started_at = Instant::now();
+    let write_span = trace::span("output", "write SARIF");
     let mut writer = output_writer(args.output.as_deref())?;
     serde_json::to_writer_pretty(&mut writer, &sarif)
         .context("failed to serialize SARIF output")?;
     writer
         .write_all(b"\n")
         .context("failed to write SARIF output")?;
+    if let (Some(path), Some(metrics)) = (&args.metrics, &analysis.metrics) {
+        write_metrics(path, metrics)?;
+    }
+    drop(write_span);
     let write_duration_ms = write_started_at.elapsed().as_millis();
 
     if args.timing && !args.quiet {
         eprintln!(
             "timing: total_ms={} scan_ms={} classpath_ms={} analysis_callgraph_ms={} analysis_callgraph_hierarchy_ms={} analysis_callgraph_index_ms={} analysis_callgraph_edges_ms={} analysis_artifact_ms={} analysis_rules_ms={} baseline_ms={} write_ms={} (classes={} artifacts={})",
             started_at.elapsed().as_millis(),
-            analysis.invocation_stats.scan_duration_ms,
-            analysis.invocation_stats.classpath_duration_ms,
-            analysis.invocation_stats.analysis_call_graph_duration_ms,
-            analysis
-                .invocation_stats
-                .analysis_call_graph_hierarchy_duration_ms,
-            analysis
-                .invocation_stats
-                .analysis_call_graph_index_duration_ms,
-            analysis
-                .invocation_stats
-                .analysis_call_graph_edges_duration_ms,
-            analysis.invocation_stats.analysis_artifact_duration_ms,
-            analysis.invocation_stats.analysis_rules_duration_ms,
+            analysis.stats.scan_duration_ms,
+            analysis.stats.classpath_duration_ms,
+            analysis.stats.analysis_call_graph_duration_ms,
+            analysis.stats.analysis_call_graph_hierarchy_duration_ms,
+            analysis.stats.analysis_call_graph_index_duration_ms,
+            analysis.stats.analysis_call_graph_edges_duration_ms,
+            analysis.stats.analysis_artifact_duration_ms,
+            analysis.stats.analysis_rules_duration_ms,
             baseline_duration_ms,
             write_duration_ms,
-            analysis.invocation_stats.class_count,
-            analysis.invocation_stats.artifact_count
+            analysis.stats.class_count,
+            analysis.stats.artifact_count
         );
+        let rule_timings: Vec<String> = analysis
+            .stats
+            .rule_stats
+            .iter()
+            .map(|stats| format!("{}={}", stats.id, stats.duration_ms))
+            .collect();
+        eprintln!("timing: rule_ms {}", rule_timings.join(" "));
+    }
+    if let Some(path) = &args.trace {
+        trace::write(path)?;
     }
 
-    Ok(())
+    Ok(exit_code)
+}
+
+/// Print each rule failure to stderr; the partial-failure exit code when there are any.
+fn report_failures(failures: &[RuleFailure], quiet: bool) -> ExitCode {
+    if failures.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    if !quiet {
+        for failure in failures {
+            let location = match (&failure.class_name, &failure.method) {
+                (Some(class_name), Some(method)) => format!(" in {class_name}.{method}"),
+                (Some(class_name), None) => format!(" in {class_name}"),
+                _ => String::new(),
+            };
+            eprintln!(
+                "warning: rule {} failed{location}: {}",
+                failure.rule_id, failure.message
+            );
+        }
+    }
+    ExitCode::from(PARTIAL_FAILURE_EXIT_CODE)
 }
 
-fn run_baseline(args: BaselineArgs) -> Result<()> {
-    ensure_inputs_exist(&args.input.input, &args.input.classpath)?;
-    let analysis = analyze(&args.input.input, &args.input.classpath)?;
-    write_baseline(&args.output, &analysis.results)?;
+fn run_baseline(args: BaselineArgs) -> Result<ExitCode> {
+    let inputs = args.input.resolve()?;
+    let options = engine_options(&args.analysis, &inputs.config)?;
+    let analysis = Analyzer::builder()
+        .input(&inputs.input)
+        .classpath(&inputs.classpath)
+        .options(options)
+        .build()?
+        .run()?;
+    write_baseline(
+        &baseline_path(args.output.as_deref(), &inputs.config),
+        &analysis.results,
+    )?;
+    Ok(report_failures(&analysis.failures, false))
+}
+
+fn run_callgraph(args: CallgraphArgs) -> Result<()> {
+    let inputs = args.input.resolve()?;
+    let mut options = EngineOptions {
+        call_graph: args.call_graph,
+        ..EngineOptions::default()
+    };
+    inputs.config.apply(&mut options)?;
+    let context = Analyzer::builder()
+        .input(&inputs.input)
+        .classpath(&inputs.classpath)
+        .options(options)
+        .build()?
+        .context()?;
+    let options = ExportOptions {
+        granularity: args.granularity,
+        targets_only: args.targets_only,
+        roots: args.roots,
+        depth: args.depth,
+    };
+    let graph = export_graph(
+        &context.classes,
+        &context.call_graph,
+        |class| context.is_analysis_target_class(class),
+        &options,
+    )?;
+    let mut writer = output_writer(args.output.as_deref())?;
+    write_graph(&graph, args.format, &mut writer)?;
+    writer.flush().context("failed to flush call graph")?;
     Ok(())
 }
 
-fn ensure_inputs_exist(input: &Path, classpath: &[PathBuf]) -> Result<()> {
-    if !input.exists() {
-        anyhow::bail!("input not found: {}", input.display());
+fn run_facts(args: FactsArgs) -> Result<()> {
+    let inputs = args.input.resolve()?;
+    let mut options = EngineOptions {
+        call_graph: args.call_graph,
+        ..EngineOptions::default()
+    };
+    inputs.config.apply(&mut options)?;
+    let context = Analyzer::builder()
+        .input(&inputs.input)
+        .classpath(&inputs.classpath)
+        .options(options)
+        .build()?
+        .context()?;
+    let relations = extract_facts(&context);
+    write_facts(&relations, &args.out)?;
+    if let Some(path) = &args.sql {
+        let mut writer = io::BufWriter::new(output_writer(Some(path))?);
+        write_sql(&relations, &mut writer)?;
+        writer.flush().context("failed to flush SQL dump")?;
     }
-    for entry in classpath {
-        if !entry.exists() {
-            anyhow::bail!("classpath entry not found: {}", entry.display());
+    Ok(())
+}
+
+fn run_why(args: WhyArgs) -> Result<()> {
+    let inputs = args.input.resolve()?;
+    let mut options = EngineOptions {
+        call_graph: args.call_graph,
+        ..EngineOptions::default()
+    };
+    inputs.config.apply(&mut options)?;
+    let entry_points = options.dead_code_entry_points.clone();
+    let context = Analyzer::builder()
+        .input(&inputs.input)
+        .classpath(&inputs.classpath)
+        .options(options)
+        .build()?
+        .context()?;
+    let methods = find_methods(&context.classes, &args.method)?;
+    let mut stdout = io::stdout().lock();
+    for (index, method) in methods.iter().enumerate() {
+        if index > 0 {
+            writeln!(stdout).context("failed to write explanation")?;
         }
+        write!(
+            stdout,
+            "{}",
+            explain_reachability(&context, &entry_points, method)
+        )
+        .context("failed to write explanation")?;
     }
     Ok(())
 }
 
-/// Aggregated analysis output before SARIF serialization.
-struct AnalysisOutput {
-    artifacts: Vec<Artifact>,
-    invocation_stats: InvocationStats,
-    rules: Vec<ReportingDescriptor>,
-    results: Vec<SarifResult>,
-}
-
-fn analyze(input: &Path, classpath: &[PathBuf]) -> Result<AnalysisOutput> {
-    let scan_started_at = Instant::now();
-    let scan = scan_inputs(input, classpath)?;
-    let scan_duration_ms = scan_started_at.elapsed().as_millis();
-    let artifact_count = scan.artifacts.len();
-    let classpath_started_at = Instant::now();
-    let classpath_index = resolve_classpath(&scan.classes)?;
-    let classpath_duration_ms = classpath_started_at.elapsed().as_millis();
-    let classpath_class_count = classpath_index.classes.len();
-    let artifacts = scan.artifacts;
-    let classes = scan.classes;
-    let (context, context_timings) =
-        build_context_with_timings(classes, classpath_index, &artifacts);
-    let analysis_rules_started_at = Instant::now();
-    let engine = Engine::new();
-    let analysis = engine.analyze(context)?;
-    let analysis_rules_duration_ms = analysis_rules_started_at.elapsed().as_millis();
-    let invocation_stats = InvocationStats {
-        scan_duration_ms,
-        classpath_duration_ms,
-        analysis_call_graph_duration_ms: context_timings.call_graph_duration_ms,
-        analysis_artifact_duration_ms: context_timings.artifact_duration_ms,
-        analysis_call_graph_hierarchy_duration_ms: context_timings.call_graph_hierarchy_duration_ms,
-        analysis_call_graph_index_duration_ms: context_timings.call_graph_index_duration_ms,
-        analysis_call_graph_edges_duration_ms: context_timings.call_graph_edges_duration_ms,
-        analysis_rules_duration_ms,
-        class_count: scan.class_count,
-        artifact_count,
-        classpath_class_count,
+fn run_rules(args: RulesArgs) -> Result<()> {
+    let config = load_config(args.config.as_deref())?;
+    let rules = configured_rules(&config)?;
+    let width = rules.iter().map(|rule| rule.id.len()).max().unwrap_or(0);
+    let mut stdout = io::stdout().lock();
+    for rule in &rules {
+        writeln!(
+            stdout,
+            "{:width$}  {:7}  {:11}  {}",
+            rule.id,
+            rule.default_level.to_string(),
+            rule.tags.join(","),
+            rule.name
+        )
+        .context("failed to write rules")?;
+    }
+    Ok(())
+}
+
+fn run_explain(args: ExplainArgs) -> Result<()> {
+    let config = load_config(args.config.as_deref())?;
+    let rules = configured_rules(&config)?;
+    let Some(rule) = rules
+        .iter()
+        .find(|rule| rule.id.eq_ignore_ascii_case(&args.rule_id))
+    else {
+        let known: Vec<&str> = rules.iter().map(|rule| rule.id.as_ref()).collect();
+        anyhow::bail!(
+            "unknown rule {}; known rules: {}",
+            args.rule_id,
+            known.join(", ")
+        );
+    };
+    let built_in = !config
+        .custom_rules
+        .iter()
+        .any(|custom| custom.id == rule.id);
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", explain_rule(rule, built_in))
+        .context("failed to write rule documentation")?;
+    Ok(())
+}
+
+/// Built-in rules and the custom rules declared in `config`, sorted by ID.
+fn configured_rules(config: &Config) -> Result<Vec<RuleMetadata>> {
+    let options = EngineOptions {
+        custom_rules: config.custom_rules.clone(),
+        ..EngineOptions::default()
     };
+    Ok(Engine::with_options(options)?.rule_metadata())
+}
 
-    Ok(AnalysisOutput {
-        artifacts,
-        invocation_stats,
-        rules: analysis.rules,
-        results: analysis.results,
-    })
+/// Rule documentation followed by the metadata emitted in SARIF; only built-in rules
+/// have published documentation.
+fn explain_rule(rule: &RuleMetadata, built_in: bool) -> String {
+    let mut text = rule.help.trim_end().to_string();
+    text.push_str("\n\n");
+    text.push_str(&format!("Default level: {}\n", rule.default_level));
+    text.push_str(&format!("Tags: {}\n", rule.sarif_tags().join(", ")));
+    text.push_str(&format!("Precision: {}\n", rule.precision.as_str()));
+    if let Some(severity) = rule.security_severity {
+        text.push_str(&format!("Security severity: {severity:.1}\n"));
+    }
+    if built_in {
+        text.push_str(&format!("Documentation: {}\n", rule.help_uri()));
+    }
+    text
+}
+
+fn engine_options(args: &AnalysisArgs, config: &Config) -> Result<EngineOptions> {
+    let mut options = EngineOptions {
+        call_graph: args.call_graph,
+        points_to_budget: args.points_to_budget_ms.map(Duration::from_millis),
+        ..EngineOptions::default()
+    };
+    config.apply(&mut options)?;
+    args.rule_selection.apply(&mut options)?;
+    for threshold in &args.metric_thresholds {
+        let (name, value) = threshold
+            .split_once('=')
+            .with_context(|| format!("expected NAME=VALUE, got {}", threshold))?;
+        options.metric_thresholds.set(name.trim(), value.trim())?;
+    }
+    for (rule_id, level) in rule_levels(&args.rule_levels)? {
+        options.rule_levels.insert(rule_id, level);
+    }
+    if let Some(path) = &args.vulnerable_methods {
+        options.vulnerable_methods = load_vulnerable_methods(path)?;
+    }
+    Ok(options)
+}
+
+fn rule_levels(overrides: &[String]) -> Result<Vec<(String, ResultLevel)>> {
+    overrides
+        .iter()
+        .map(|entry| {
+            let (rule_id, level) = entry
+                .split_once('=')
+                .with_context(|| format!("expected RULE=LEVEL, got {}", entry))?;
+            let level = level.trim().parse::<ResultLevel>().map_err(|_| {
+                anyhow::anyhow!("unknown level {level}; expected error, warning, note or none")
+            })?;
+            Ok((rule_id.trim().to_string(), level))
+        })
+        .collect()
+}
+
+/// Baseline path from the command line, else the configuration, else the default.
+fn baseline_path(cli: Option<&Path>, config: &Config) -> PathBuf {
+    cli.map(Path::to_path_buf)
+        .or_else(|| config.baseline.clone())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_BASELINE_PATH))
+}
+
+/// Configuration the scan actually ran with, recorded in the SARIF invocation.
+fn effective_config(inputs: &ResolvedInputs, baseline: &Path, args: &AnalysisArgs) -> Config {
+    let mut config = inputs.config.clone();
+    config.input = Some(inputs.input.clone());
+    config.classpath = inputs.classpath.clone();
+    config.baseline = Some(baseline.to_path_buf());
+    args.rule_selection.apply_to_config(&mut config);
+    for (rule_id, level) in rule_levels(&args.rule_levels).unwrap_or_default() {
+        config.rules.settings.entry(rule_id).or_default().severity = Some(level);
+    }
+    if !args.metric_thresholds.is_empty() {
+        let settings = config
+            .rules
+            .settings
+            .entry("METHOD_METRICS".to_string())
+            .or_default();
+        let thresholds = settings
+            .options
+            .entry("thresholds".to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let Some(table) = thresholds.as_table_mut() {
+            for threshold in &args.metric_thresholds {
+                if let Some((name, value)) = threshold.split_once('=') {
+                    table.insert(
+                        name.trim().to_string(),
+                        toml::Value::String(value.trim().to_string()),
+                    );
+                }
+            }
+        }
+    }
+    config
+}
+
+fn write_metrics(path: &Path, metrics: &[ClassMetrics]) -> Result<()> {
+    let mut writer = output_writer(Some(path))?;
+    serde_json::to_writer_pretty(&mut writer, &json!({ "classes": metrics }))
+        .context("failed to serialize metrics")?;
+    writer.write_all(b"\n").context("failed to write metrics")?;
+    Ok(())
 }
 
 fn output_writer(output: Option<&Path>) -> Result<Box<dyn Write>> {
@@ -244,72 +683,22 @@ fn output_writer(output: Option<&Path>) -> Result<Box<dyn Write>> {
     }
 }
 
-/// Metadata captured for SARIF invocation properties.
-struct InvocationStats {
-    scan_duration_ms: u128,
-    classpath_duration_ms: u128,
-    analysis_call_graph_duration_ms: u128,
-    analysis_artifact_duration_ms: u128,
-    analysis_call_graph_hierarchy_duration_ms: u128,
-    analysis_call_graph_index_duration_ms: u128,
-    analysis_call_graph_edges_duration_ms: u128,
-    analysis_rules_duration_ms: u128,
-    class_count: usize,
-    artifact_count: usize,
-    classpath_class_count: usize,
-}
-
-fn build_invocation(stats: &InvocationStats) -> Invocation {
+fn build_invocation(
+    stats: &AnalysisStats,
+    config: &Config,
+    failures: &[RuleFailure],
+    rules: &[ReportingDescriptor],
+) -> Result<Invocation> {
     let arguments: Vec<String> = std::env::args().collect();
     let command_line = arguments.join(" ");
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "inspequte.scan_ms".to_string(),
-        json!(stats.scan_duration_ms),
-    );
-    properties.insert(
-        "inspequte.classpath_ms".to_string(),
-        json!(stats.classpath_duration_ms),
-    );
-    properties.insert(
-        "inspequte.analysis_callgraph_ms".to_string(),
-        json!(stats.analysis_call_graph_duration_ms),
-    );
-    properties.insert(
-        "inspequte.analysis_callgraph_hierarchy_ms".to_string(),
-        json!(stats.analysis_call_graph_hierarchy_duration_ms),
-    );
-    properties.insert(
-        "inspequte.analysis_callgraph_index_ms".to_string(),
-        json!(stats.analysis_call_graph_index_duration_ms),
-    );
-    properties.insert(
-        "inspequte.analysis_callgraph_edges_ms".to_string(),
-        json!(stats.analysis_call_graph_edges_duration_ms),
-    );
+    let mut properties = stats.properties();
     properties.insert(
-        "inspequte.analysis_artifact_ms".to_string(),
-        json!(stats.analysis_artifact_duration_ms),
-    );
-    properties.insert(
-        "inspequte.analysis_rules_ms".to_string(),
-        json!(stats.analysis_rules_duration_ms),
-    );
-    properties.insert(
-        "inspequte.class_count".to_string(),
-        json!(stats.class_count),
-    );
-    properties.insert(
-        "inspequte.artifact_count".to_string(),
-        json!(stats.artifact_count),
-    );
-    properties.insert(
-        "inspequte.classpath_class_count".to_string(),
-        json!(stats.classpath_class_count),
+        "inspequte.config".to_string(),
+        serde_json::to_value(config).context("failed to serialize configuration")?,
     );
 
-    Invocation::builder()
-        .execution_successful(true)
+    let mut invocation = Invocation::builder()
+        .execution_successful(failures.is_empty())
         .arguments(arguments)
         .command_line(command_line)
         .properties(
@@ -317,7 +706,11 @@ fn build_invocation(stats: &InvocationStats) -> Invocation {
                 .additional_properties(properties)
                 .build(),
         )
-        .build()
+        .build();
+    if !failures.is_empty() {
+        invocation.tool_execution_notifications = Some(failure_notifications(failures, rules));
+    }
+    Ok(invocation)
 }
 
 fn should_validate_sarif() -> bool {
@@ -327,82 +720,64 @@ fn should_validate_sarif() -> bool {
         .unwrap_or(false)
 }
 
-fn validate_sarif(sarif: &Sarif) -> Result<()> {
-    let schema = serde_json::from_str(include_str!("../assets/sarif-2.1.0.json"))
-        .context("load SARIF schema")?;
-    let compiled = JSONSchema::compile(&schema)
-        .map_err(|err| anyhow::anyhow!("compile SARIF schema: {err}"))?;
-    let value = serde_json::to_value(sarif).context("serialize SARIF")?;
-    if let Err(errors) = compiled.validate(&value) {
-        let message = errors
-            .map(|error| error.to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
-        anyhow::bail!("SARIF schema validation failed:\n{message}");
-    }
-    Ok(())
-}
-
-fn build_sarif(
-    artifacts: Vec<Artifact>,
-    invocation: Invocation,
-    rules: Vec<ReportingDescriptor>,
-    results: Vec<SarifResult>,
-) -> Sarif {
-    let driver = if rules.is_empty() {
-        ToolComponent::builder()
-            .name("inspequte")
-            .information_uri("https://github.com/KengoTODA/inspequte")
-            .build()
-    } else {
-        ToolComponent::builder()
-            .name("inspequte")
-            .information_uri("https://github.com/KengoTODA/inspequte")
-            .rules(rules)
-            .build()
-    };
-    let tool = Tool {
-        driver,
-        extensions: None,
-        properties: None,
-    };
-    let run = if artifacts.is_empty() {
-        Run::builder()
-            .tool(tool)
-            .invocations(vec![invocation])
-            .results(results)
-            .build()
-    } else {
-        Run::builder()
-            .tool(tool)
-            .invocations(vec![invocation])
-            .results(results)
-            .artifacts(artifacts)
-            .build()
-    };
-
-    Sarif::builder()
-        .schema(SCHEMA_URL)
-        .runs(vec![run])
-        .version(json!("2.1.0"))
-        .build()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::io::Write;
-    use std::path::PathBuf;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use inspequte::engine::RuleStats;
+    use inspequte::sarif::SCHEMA_URL;
 
-    use crate::classpath::resolve_classpath;
-    use crate::engine::{Engine, build_context};
-    use crate::scan::scan_inputs;
+    #[test]
+    fn cli_parses_subcommands_without_top_level_input() {
+        let cli = Cli::try_parse_from([
+            "inspequte",
+            "callgraph",
+            "--input",
+            "app.jar",
+            "--granularity",
+            "package",
+        ])
+        .expect("parse callgraph subcommand");
+        let Some(Command::Callgraph(args)) = cli.command else {
+            panic!("expected callgraph subcommand");
+        };
+        assert_eq!(Some(Path::new("app.jar")), args.input.input.as_deref());
+        assert_eq!(Granularity::Package, args.granularity);
+
+        let cli = Cli::try_parse_from([
+            "inspequte",
+            "baseline",
+            "--input",
+            "app.jar",
+            "--rule-level",
+            "DEAD_CODE=none",
+            "--metric-threshold",
+            "loop_depth=off",
+        ])
+        .expect("parse baseline subcommand");
+        let Some(Command::Baseline(args)) = cli.command else {
+            panic!("expected baseline subcommand");
+        };
+        let options =
+            engine_options(&args.analysis, &Config::default()).expect("baseline engine options");
+        assert_eq!(
+            Some(&ResultLevel::None),
+            options.rule_levels.get("DEAD_CODE")
+        );
+
+        let cli = Cli::try_parse_from(["inspequte", "explain", "NULLNESS"]).expect("parse explain");
+        let Some(Command::Explain(args)) = cli.command else {
+            panic!("expected explain subcommand");
+        };
+        assert_eq!("NULLNESS", args.rule_id);
+
+        let cli = Cli::try_parse_from(["inspequte", "--output", "out.sarif"])
+            .expect("parse scan without input");
+        assert!(cli.scan.input.resolve().is_err());
+    }
 
     #[test]
     fn sarif_is_minimal_and_valid_shape() {
-        let invocation = build_invocation(&InvocationStats {
+        let stats = AnalysisStats {
             scan_duration_ms: 0,
             classpath_duration_ms: 0,
             analysis_call_graph_duration_ms: 0,
@@ -411,10 +786,19 @@ mod tests {
             analysis_call_graph_index_duration_ms: 0,
             analysis_call_graph_edges_duration_ms: 0,
             analysis_rules_duration_ms: 0,
+            rule_stats: vec![RuleStats {
+                id: "NULLNESS".to_string(),
+                duration_ms: 12,
+                result_count: 3,
+            }],
+            call_graph_algorithm: CallGraphAlgorithm::Cha,
+            call_graph_edge_count: 0,
             class_count: 0,
             artifact_count: 0,
             classpath_class_count: 0,
-        });
+        };
+        let invocation =
+            build_invocation(&stats, &Config::default(), &[], &[]).expect("build invocation");
         let sarif = build_sarif(Vec::new(), invocation, Vec::new(), Vec::new());
         let value = serde_json::to_value(&sarif).expect("serialize SARIF");
 
@@ -435,299 +819,25 @@ mod tests {
             value["runs"][0]["invocations"][0]["executionSuccessful"],
             true
         );
-    }
-
-    #[test]
-    fn sarif_callgraph_snapshot() {
-        let temp_dir = std::env::temp_dir().join(format!(
-            "inspequte-test-{}",
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("time")
-                .as_nanos()
-        ));
-        fs::create_dir_all(&temp_dir).expect("create temp dir");
-
-        let class_a = build_class_a();
-        let class_b = build_class_b();
-        fs::write(temp_dir.join("A.class"), class_a).expect("write A.class");
-        fs::write(temp_dir.join("B.class"), class_b).expect("write B.class");
-
-        let scan = scan_inputs(&temp_dir, &[]).expect("scan classes");
-        let classpath = resolve_classpath(&scan.classes).expect("resolve classpath");
-        let artifacts = scan.artifacts.clone();
-        let context = build_context(scan.classes.clone(), classpath, &artifacts);
-        let engine = Engine::new();
-        let analysis = engine.analyze(context).expect("analysis");
-        let invocation = Invocation::builder()
-            .execution_successful(true)
-            .arguments(Vec::<String>::new())
-            .build();
-        let artifacts = normalize_artifacts(artifacts);
-        let sarif = build_sarif(artifacts, invocation, analysis.rules, analysis.results);
-        let actual = serde_json::to_string_pretty(&sarif).expect("serialize SARIF");
-        let snapshot_path = snapshot_path("callgraph.sarif");
-
-        if std::env::var("INSPEQUTE_UPDATE_SNAPSHOTS").is_ok() {
-            fs::create_dir_all(snapshot_path.parent().expect("snapshot parent"))
-                .expect("create snapshot dir");
-            let mut file = fs::File::create(&snapshot_path).expect("create snapshot");
-            file.write_all(actual.as_bytes()).expect("write snapshot");
-            file.write_all(b"\n").expect("write snapshot newline");
-        }
-
-        let expected = fs::read_to_string(&snapshot_path).expect("read snapshot");
-        assert_eq!(actual.trim_end(), expected.trim_end());
-
-        fs::remove_dir_all(&temp_dir).expect("cleanup temp dir");
-    }
-
-    fn snapshot_path(name: &str) -> PathBuf {
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("tests")
-            .join("snapshots")
-            .join(name)
-    }
-
-    fn build_class_a() -> Vec<u8> {
-        let mut builder = ClassFileBuilder::new("A", "java/lang/Object");
-        let object_init = builder.add_method_ref("java/lang/Object", "<init>", "()V");
-        let b_class = builder.add_class("B");
-        let b_init = builder.add_method_ref("B", "<init>", "()V");
-        let b_bar = builder.add_method_ref("B", "bar", "()V");
-
-        let init_code = vec![0x2a, 0xb7, high(object_init), low(object_init), 0xb1];
-        builder.add_method("<init>", "()V", init_code, 1, 1);
-
-        let foo_code = vec![
-            0xbb,
-            high(b_class),
-            low(b_class),
-            0x59,
-            0xb7,
-            high(b_init),
-            low(b_init),
-            0xb6,
-            high(b_bar),
-            low(b_bar),
-            0xb1,
-        ];
-        builder.add_method("foo", "()V", foo_code, 2, 1);
-
-        builder.finish()
-    }
-
-    fn build_class_b() -> Vec<u8> {
-        let mut builder = ClassFileBuilder::new("B", "java/lang/Object");
-        let object_init = builder.add_method_ref("java/lang/Object", "<init>", "()V");
-        let init_code = vec![0x2a, 0xb7, high(object_init), low(object_init), 0xb1];
-        builder.add_method("<init>", "()V", init_code, 1, 1);
-        let bar_code = vec![0xb1];
-        builder.add_method("bar", "()V", bar_code, 0, 1);
-        builder.finish()
-    }
-
-    /// Minimal class file writer for snapshot testing.
-    struct ClassFileBuilder {
-        cp: Vec<CpEntry>,
-        this_class: u16,
-        super_class: u16,
-        methods: Vec<MethodSpec>,
-        code_index: u16,
-    }
-
-    impl ClassFileBuilder {
-        fn new(class_name: &str, super_name: &str) -> Self {
-            let mut builder = Self {
-                cp: Vec::new(),
-                this_class: 0,
-                super_class: 0,
-                methods: Vec::new(),
-                code_index: 0,
-            };
-            builder.code_index = builder.add_utf8("Code");
-            builder.this_class = builder.add_class(class_name);
-            builder.super_class = builder.add_class(super_name);
-            builder
-        }
-
-        fn add_utf8(&mut self, value: &str) -> u16 {
-            self.cp.push(CpEntry::Utf8(value.to_string()));
-            self.cp.len() as u16
-        }
-
-        fn add_class(&mut self, name: &str) -> u16 {
-            let name_index = self.add_utf8(name);
-            self.cp.push(CpEntry::Class(name_index));
-            self.cp.len() as u16
-        }
-
-        fn add_name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
-            let name_index = self.add_utf8(name);
-            let descriptor_index = self.add_utf8(descriptor);
-            self.cp
-                .push(CpEntry::NameAndType(name_index, descriptor_index));
-            self.cp.len() as u16
-        }
-
-        fn add_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
-            let class_index = self.add_class(class);
-            let name_and_type = self.add_name_and_type(name, descriptor);
-            self.cp.push(CpEntry::MethodRef(class_index, name_and_type));
-            self.cp.len() as u16
-        }
-
-        fn add_method(
-            &mut self,
-            name: &str,
-            descriptor: &str,
-            code: Vec<u8>,
-            max_stack: u16,
-            max_locals: u16,
-        ) {
-            let name_index = self.add_utf8(name);
-            let descriptor_index = self.add_utf8(descriptor);
-            self.methods.push(MethodSpec {
-                name_index,
-                descriptor_index,
-                code,
-                max_stack,
-                max_locals,
-            });
-        }
-
-        fn finish(self) -> Vec<u8> {
-            let mut bytes = Vec::new();
-            write_u32(&mut bytes, 0xCAFEBABE);
-            write_u16(&mut bytes, 0);
-            write_u16(&mut bytes, 52);
-            write_u16(&mut bytes, (self.cp.len() + 1) as u16);
-            for entry in &self.cp {
-                entry.write(&mut bytes);
-            }
-            write_u16(&mut bytes, 0x0021);
-            write_u16(&mut bytes, self.this_class);
-            write_u16(&mut bytes, self.super_class);
-            write_u16(&mut bytes, 0);
-            write_u16(&mut bytes, 0);
-            write_u16(&mut bytes, self.methods.len() as u16);
-            for method in &self.methods {
-                write_u16(&mut bytes, 0x0001);
-                write_u16(&mut bytes, method.name_index);
-                write_u16(&mut bytes, method.descriptor_index);
-                write_u16(&mut bytes, 1);
-                write_u16(&mut bytes, self.code_index);
-                let attr_len = 12 + method.code.len() as u32;
-                write_u32(&mut bytes, attr_len);
-                write_u16(&mut bytes, method.max_stack);
-                write_u16(&mut bytes, method.max_locals);
-                write_u32(&mut bytes, method.code.len() as u32);
-                bytes.extend_from_slice(&method.code);
-                write_u16(&mut bytes, 0);
-                write_u16(&mut bytes, 0);
-            }
-            write_u16(&mut bytes, 0);
-            bytes
-        }
-    }
-
-    /// Method definition for generated class files.
-    struct MethodSpec {
-        name_index: u16,
-        descriptor_index: u16,
-        code: Vec<u8>,
-        max_stack: u16,
-        max_locals: u16,
-    }
-
-    /// Constant pool entries needed by snapshot class files.
-    enum CpEntry {
-        Utf8(String),
-        Class(u16),
-        NameAndType(u16, u16),
-        MethodRef(u16, u16),
-    }
-
-    impl CpEntry {
-        fn write(&self, bytes: &mut Vec<u8>) {
-            match self {
-                CpEntry::Utf8(value) => {
-                    bytes.push(1);
-                    write_u16(bytes, value.len() as u16);
-                    bytes.extend_from_slice(value.as_bytes());
-                }
-                CpEntry::Class(name_index) => {
-                    bytes.push(7);
-                    write_u16(bytes, *name_index);
-                }
-                CpEntry::NameAndType(name_index, descriptor_index) => {
-                    bytes.push(12);
-                    write_u16(bytes, *name_index);
-                    write_u16(bytes, *descriptor_index);
-                }
-                CpEntry::MethodRef(class_index, name_and_type) => {
-                    bytes.push(10);
-                    write_u16(bytes, *class_index);
-                    write_u16(bytes, *name_and_type);
-                }
-            }
-        }
-    }
-
-    fn write_u16(bytes: &mut Vec<u8>, value: u16) {
-        bytes.extend_from_slice(&value.to_be_bytes());
-    }
-
-    fn write_u32(bytes: &mut Vec<u8>, value: u32) {
-        bytes.extend_from_slice(&value.to_be_bytes());
-    }
-
-    fn high(value: u16) -> u8 {
-        (value >> 8) as u8
-    }
-
-    fn low(value: u16) -> u8 {
-        (value & 0xff) as u8
-    }
-
-    fn normalize_artifacts(
-        artifacts: Vec<serde_sarif::sarif::Artifact>,
-    ) -> Vec<serde_sarif::sarif::Artifact> {
-        artifacts
-            .into_iter()
-            .map(|mut artifact| {
-                if let Some(location) = artifact.location.as_mut() {
-                    if let Some(uri) = &location.uri {
-                        if let Some(name) = artifact_basename(uri) {
-                            location.uri = Some(name);
-                        }
-                    }
-                }
-                artifact
-            })
-            .collect()
-    }
+        assert!(value["runs"][0]["invocations"][0]["properties"]["inspequte.config"].is_object());
+        assert_eq!(
+            json!({ "NULLNESS": { "ms": 12, "results": 3 } }),
+            value["runs"][0]["invocations"][0]["properties"]["inspequte.rules"]
+        );
 
-    fn artifact_basename(uri: &str) -> Option<String> {
-        if let Some(rest) = uri.strip_prefix("jar:") {
-            let entry = rest.split("!/").nth(1)?;
-            return Some(
-                PathBuf::from(entry)
-                    .file_name()?
-                    .to_string_lossy()
-                    .to_string(),
-            );
-        }
-        if let Some(rest) = uri.strip_prefix("file://") {
-            return Some(
-                PathBuf::from(rest)
-                    .file_name()?
-                    .to_string_lossy()
-                    .to_string(),
-            );
-        }
-        PathBuf::from(uri)
-            .file_name()
-            .map(|name| name.to_string_lossy().to_string())
+        let failure = RuleFailure {
+            rule_id: "NULLNESS".to_string(),
+            class_name: None,
+            method: None,
+            message: "stack underflow".to_string(),
+            panicked: true,
+        };
+        let failed = build_invocation(&stats, &Config::default(), &[failure], &[])
+            .expect("build invocation");
+        assert!(!failed.execution_successful);
+        assert_eq!(
+            Some(1),
+            failed.tool_execution_notifications.map(|n| n.len())
+        );
     }
 }
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::callgraph::CallGraph;
+use crate::ir::{Class, EdgeKind, Method};
+
+/// Size and complexity measurements of a single method.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct MethodMetrics {
+    pub name: String,
+    pub descriptor: String,
+    /// Decision points plus one, counted on the normal (non-exception) control flow.
+    pub cyclomatic_complexity: usize,
+    pub bytecode_size: usize,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub exception_handlers: usize,
+    /// Deepest nesting of natural loops; zero for loop-free methods.
+    pub loop_depth: usize,
+}
+
+/// Size and coupling measurements of a class.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ClassMetrics {
+    pub name: String,
+    pub method_count: usize,
+    /// Weighted methods per class: the sum of method cyclomatic complexities.
+    pub wmc: usize,
+    /// Other classes with a call graph edge into this class.
+    pub fan_in: usize,
+    /// Other classes this class has a call graph edge into.
+    pub fan_out: usize,
+    /// Other classes referenced from the constant pool.
+    pub efferent_coupling: usize,
+    pub methods: Vec<MethodMetrics>,
+}
+
+/// Metrics for every class in `classes`, using `call_graph` for fan-in and fan-out.
+pub fn class_metrics<'a>(
+    classes: impl IntoIterator<Item = &'a Class>,
+    call_graph: &CallGraph,
+) -> Vec<ClassMetrics> {
+    let mut callers: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut callees: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for edge in &call_graph.edges {
+        let caller = edge.caller.class_name.as_str();
+        let callee = edge.callee.class_name.as_str();
+        if caller != callee {
+            callers.entry(callee).or_default().insert(caller);
+            callees.entry(caller).or_default().insert(callee);
+        }
+    }
+    let count = |map: &BTreeMap<&str, BTreeSet<&str>>, name: &str| {
+        map.get(name).map(BTreeSet::len).unwrap_or(0)
+    };
+
+    let mut metrics: Vec<ClassMetrics> = classes
+        .into_iter()
+        .map(|class| {
+            let methods: Vec<MethodMetrics> = class.methods.iter().map(method_metrics).collect();
+            let referenced: BTreeSet<&str> = class
+                .referenced_classes
+                .iter()
+                .map(String::as_str)
+                .filter(|name| *name != class.name)
+                .collect();
+            ClassMetrics {
+                name: class.name.clone(),
+                method_count: methods.len(),
+                wmc: methods
+                    .iter()
+                    .map(|method| method.cyclomatic_complexity)
+                    .sum(),
+                fan_in: count(&callers, &class.name),
+                fan_out: count(&callees, &class.name),
+                efferent_coupling: referenced.len(),
+                methods,
+            }
+        })
+        .collect();
+    metrics.sort_by(|left, right| left.name.cmp(&right.name));
+    metrics
+}
+
+pub fn method_metrics(method: &Method) -> MethodMetrics {
+    let successors = normal_successors(method);
+    let decisions: usize = successors
+        .values()
+        .map(|targets| targets.len().saturating_sub(1))
+        .sum();
+    MethodMetrics {
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
+        cyclomatic_complexity: decisions + 1,
+        bytecode_size: method.bytecode.len(),
+        max_stack: method.max_stack,
+        max_locals: method.max_locals,
+        exception_handlers: method.exception_handlers.len(),
+        loop_depth: loop_depth(&successors),
+    }
+}
+
+/// Distinct non-exception successors of every block, keyed by block start offset.
+fn normal_successors(method: &Method) -> BTreeMap<u32, BTreeSet<u32>> {
+    let mut successors: BTreeMap<u32, BTreeSet<u32>> = method
+        .cfg
+        .blocks
+        .iter()
+        .map(|block| (block.start_offset, BTreeSet::new()))
+        .collect();
+    for edge in &method.cfg.edges {
+        if edge.kind != EdgeKind::Exception
+            && let Some(targets) = successors.get_mut(&edge.from)
+        {
+            targets.insert(edge.to);
+        }
+    }
+    successors
+}
+
+/// Maximum number of natural loops enclosing a single block.
+fn loop_depth(successors: &BTreeMap<u32, BTreeSet<u32>>) -> usize {
+    let dominators = immediate_dominators(successors);
+    let mut predecessors: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for (from, targets) in successors {
+        for to in targets {
+            predecessors.entry(*to).or_default().push(*from);
+        }
+    }
+
+    // Natural loops sharing a header are merged into one loop.
+    let mut loops: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    for (from, targets) in successors {
+        for header in targets {
+            if !dominates(&dominators, *header, *from) {
+                continue;
+            }
+            let body = loops
+                .entry(*header)
+                .or_insert_with(|| BTreeSet::from([*header]));
+            let mut stack = vec![*from];
+            while let Some(block) = stack.pop() {
+                if body.insert(block)
+                    && let Some(sources) = predecessors.get(&block)
+                {
+                    stack.extend(sources.iter().copied());
+                }
+            }
+        }
+    }
+
+    let mut depth: BTreeMap<u32, usize> = BTreeMap::new();
+    for body in loops.values() {
+        for block in body {
+            *depth.entry(*block).or_default() += 1;
+        }
+    }
+    depth.values().copied().max().unwrap_or(0)
+}
+
+/// Immediate dominators of the blocks reachable from offset 0, computed with the
+/// Cooper-Harvey-Kennedy algorithm. The entry block is its own dominator.
+fn immediate_dominators(successors: &BTreeMap<u32, BTreeSet<u32>>) -> BTreeMap<u32, u32> {
+    let mut postorder = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut stack = vec![(0u32, false)];
+    while let Some((block, finished)) = stack.pop() {
+        if finished {
+            postorder.push(block);
+            continue;
+        }
+        if !visited.insert(block) {
+            continue;
+        }
+        stack.push((block, true));
+        for target in successors.get(&block).into_iter().flatten().rev() {
+            if !visited.contains(target) {
+                stack.push((*target, false));
+            }
+        }
+    }
+    let order: BTreeMap<u32, usize> = postorder
+        .iter()
+        .enumerate()
+        .map(|(index, block)| (*block, index))
+        .collect();
+    let mut predecessors: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for (from, targets) in successors {
+        if order.contains_key(from) {
+            for to in targets {
+                predecessors.entry(*to).or_default().push(*from);
+            }
+        }
+    }
+
+    let mut dominators = BTreeMap::from([(0u32, 0u32)]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in postorder.iter().rev().filter(|block| **block != 0) {
+            let mut next: Option<u32> = None;
+            for predecessor in predecessors.get(block).into_iter().flatten() {
+                if !dominators.contains_key(predecessor) {
+                    continue;
+                }
+                next = Some(match next {
+                    None => *predecessor,
+                    Some(current) => {
+                        let (mut left, mut right) = (current, *predecessor);
+                        while left != right {
+                            while order[&left] < order[&right] {
+                                left = dominators[&left];
+                            }
+                            while order[&right] < order[&left] {
+                                right = dominators[&right];
+                            }
+                        }
+                        left
+                    }
+                });
+            }
+            if let Some(next) = next
+                && dominators.get(block) != Some(&next)
+            {
+                dominators.insert(*block, next);
+                changed = true;
+            }
+        }
+    }
+    dominators
+}
+
+fn dominates(dominators: &BTreeMap<u32, u32>, dominator: u32, mut block: u32) -> bool {
+    loop {
+        if block == dominator {
+            return true;
+        }
+        match dominators.get(&block) {
+            Some(parent) if *parent != block => block = *parent,
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::{CallGraphOptions, build_call_graph_with_timings};
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn compile(sources: &[(&str, &str)]) -> Vec<Class> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources: Vec<SourceFile> = sources
+            .iter()
+            .map(|(path, contents)| SourceFile {
+                path: path.to_string(),
+                contents: contents.to_string(),
+            })
+            .collect();
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes
+    }
+
+    fn method<'a>(metrics: &'a ClassMetrics, name: &str) -> &'a MethodMetrics {
+        metrics
+            .methods
+            .iter()
+            .find(|method| method.name == name)
+            .expect("method metrics")
+    }
+
+    const SAMPLE: &str = r#"
+package com.example;
+public class Sample {
+    static int straight(int a) {
+        return a + 1;
+    }
+
+    static int branches(int a, int b) {
+        if (a > 0 && b > 0) {
+            return 1;
+        }
+        switch (a) {
+            case 1: return 2;
+            case 2: return 3;
+            default: return 4;
+        }
+    }
+
+    static int nested(int[][] grid) {
+        int sum = 0;
+        for (int[] row : grid) {
+            for (int cell : row) {
+                while (cell > 10) {
+                    cell /= 2;
+                }
+                sum += cell;
+            }
+        }
+        return sum;
+    }
+
+    static String guarded(Object value) {
+        try {
+            return value.toString();
+        } catch (IllegalStateException | IllegalArgumentException e) {
+            return "";
+        }
+    }
+}
+"#;
+
+    const HELPER: &str = r#"
+package com.example;
+public class Helper {
+    static int twice(int value) {
+        return Sample.straight(value) * 2;
+    }
+}
+"#;
+
+    #[test]
+    fn method_metrics_measure_complexity_and_loops() {
+        let classes = compile(&[("com/example/Sample.java", SAMPLE)]);
+        let (graph, _, _) =
+            build_call_graph_with_timings(&classes, &CallGraphOptions::default(), |_| true);
+        let metrics = class_metrics(&classes, &graph);
+        let sample = &metrics[0];
+
+        let straight = method(sample, "straight");
+        assert_eq!(1, straight.cyclomatic_complexity);
+        assert_eq!(0, straight.loop_depth);
+        assert_eq!(2, straight.max_stack);
+        assert_eq!(1, straight.max_locals);
+        assert_eq!(4, straight.bytecode_size);
+
+        assert_eq!(5, method(sample, "branches").cyclomatic_complexity);
+        assert_eq!(3, method(sample, "nested").loop_depth);
+        assert_eq!(2, method(sample, "guarded").exception_handlers);
+        assert_eq!(1, method(sample, "guarded").cyclomatic_complexity);
+    }
+
+    #[test]
+    fn class_metrics_count_coupling_from_call_graph() {
+        let classes = compile(&[
+            ("com/example/Sample.java", SAMPLE),
+            ("com/example/Helper.java", HELPER),
+        ]);
+        let (graph, _, _) =
+            build_call_graph_with_timings(&classes, &CallGraphOptions::default(), |_| true);
+        let metrics = class_metrics(&classes, &graph);
+
+        let helper = metrics
+            .iter()
+            .find(|class| class.name == "com/example/Helper")
+            .expect("helper metrics");
+        let sample = metrics
+            .iter()
+            .find(|class| class.name == "com/example/Sample")
+            .expect("sample metrics");
+        assert_eq!(2, helper.method_count);
+        assert_eq!(1, helper.fan_out);
+        assert_eq!(0, helper.fan_in);
+        assert_eq!(1, sample.fan_in);
+        assert_eq!(
+            sample
+                .methods
+                .iter()
+                .map(|method| method.cyclomatic_complexity)
+                .sum::<usize>(),
+            sample.wmc
+        );
+        assert!(helper.efferent_coupling >= 2);
+    }
+}
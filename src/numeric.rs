@@ -0,0 +1,782 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use jdescriptor::{MethodDescriptor, TypeDescriptor};
+
+use crate::dataflow::{self, Analysis, EdgeContext};
+use crate::interpreter::{Fact, FactHooks, Frame, Interpreter, Slot, ValueType};
+use crate::ir::{ConstantValue, EdgeKind, Instruction, InstructionKind, Method};
+use crate::opcodes;
+
+/// Closed range of integer values; both bounds are inclusive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Interval {
+    pub(crate) min: i64,
+    pub(crate) max: i64,
+}
+
+impl Interval {
+    pub(crate) const INT: Self = Self::new(i32::MIN as i64, i32::MAX as i64);
+    pub(crate) const LONG: Self = Self::new(i64::MIN, i64::MAX);
+    /// Every possible array length.
+    pub(crate) const LENGTH: Self = Self::new(0, i32::MAX as i64);
+    const BOOLEAN: Self = Self::new(0, 1);
+    const BYTE: Self = Self::new(i8::MIN as i64, i8::MAX as i64);
+    const CHAR: Self = Self::new(0, u16::MAX as i64);
+    const SHORT: Self = Self::new(i16::MIN as i64, i16::MAX as i64);
+
+    pub(crate) const fn new(min: i64, max: i64) -> Self {
+        Self { min, max }
+    }
+
+    pub(crate) const fn constant(value: i64) -> Self {
+        Self::new(value, value)
+    }
+
+    pub(crate) fn as_constant(&self) -> Option<i64> {
+        (self.min == self.max).then_some(self.min)
+    }
+
+    pub(crate) fn join(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Values in both intervals, or `None` when they are disjoint.
+    pub(crate) fn intersect(&self, other: &Self) -> Option<Self> {
+        let min = self.min.max(other.min);
+        let max = self.max.min(other.max);
+        (min <= max).then_some(Self::new(min, max))
+    }
+
+    /// Interval from exact bounds; JVM arithmetic wraps around, so a result
+    /// that does not fit `bounds` may be any value of the type.
+    fn fit(min: i128, max: i128, bounds: Self) -> Self {
+        if min < bounds.min as i128 || max > bounds.max as i128 {
+            bounds
+        } else {
+            Self::new(min as i64, max as i64)
+        }
+    }
+
+    /// Push every bound that grew since `self` to the end of `bounds`.
+    fn widen(&self, next: &Self, bounds: Self) -> Self {
+        Self::new(
+            if next.min < self.min {
+                bounds.min
+            } else {
+                next.min
+            },
+            if next.max > self.max {
+                bounds.max
+            } else {
+                next.max
+            },
+        )
+    }
+
+    fn is_non_negative(&self) -> bool {
+        self.min >= 0
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_constant() {
+            Some(value) => write!(f, "{}", value),
+            None => write!(f, "[{}, {}]", self.min, self.max),
+        }
+    }
+}
+
+/// Numeric facts about an int, long, or array slot.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct NumericFact {
+    /// Possible values of an int or long.
+    pub(crate) range: Interval,
+    /// Possible lengths when the slot holds an array.
+    pub(crate) length: Interval,
+    /// Local the value was loaded from, while that local still holds it.
+    local: Option<usize>,
+}
+
+impl NumericFact {
+    fn of(range: Interval) -> Self {
+        Self {
+            range,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for NumericFact {
+    fn default() -> Self {
+        Self {
+            range: Interval::LONG,
+            length: Interval::LENGTH,
+            local: None,
+        }
+    }
+}
+
+impl Fact for NumericFact {
+    fn join(&self, other: &Self) -> Self {
+        Self {
+            range: self.range.join(&other.range),
+            length: self.length.join(&other.length),
+            local: if self.local == other.local {
+                self.local
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Constant and interval facts for the operand stack of every reachable
+/// instruction in a method.
+///
+/// Locals are not kept; rules observe them once they are loaded. A method on
+/// which the analysis does not converge has no facts at all.
+#[derive(Debug)]
+pub(crate) struct NumericMethod {
+    stacks: BTreeMap<u32, Vec<NumericFact>>,
+}
+
+impl NumericMethod {
+    /// Fact for the stack entry `depth` below the top right before `offset` executes.
+    pub(crate) fn operand(&self, offset: u32, depth: usize) -> Option<&NumericFact> {
+        let stack = self.stacks.get(&offset)?;
+        stack
+            .len()
+            .checked_sub(depth + 1)
+            .and_then(|index| stack.get(index))
+    }
+}
+
+/// Run constant propagation and interval analysis over a method.
+pub(crate) fn analyze_ranges(class_name: &str, method: &Method) -> Result<NumericMethod> {
+    let descriptor =
+        MethodDescriptor::from_str(&method.descriptor).context("parse method descriptor")?;
+    let analysis = NumericAnalysis {
+        method,
+        interpreter: Interpreter::new(class_name, method),
+        parameters: descriptor.parameter_types().to_vec(),
+    };
+    let flow = dataflow::solve(method, &analysis)?;
+    let mut stacks = BTreeMap::new();
+    if flow.converged {
+        flow.replay(method, &analysis, |_, inst, frame| {
+            let stack = frame.stack.iter().map(|slot| slot.fact.clone()).collect();
+            stacks.insert(inst.offset, stack);
+            Ok(())
+        })?;
+    }
+    Ok(NumericMethod { stacks })
+}
+
+/// Forward interval analysis with branch refinement of compared locals.
+struct NumericAnalysis<'a> {
+    method: &'a Method,
+    interpreter: Interpreter<'a>,
+    parameters: Vec<TypeDescriptor>,
+}
+
+impl NumericAnalysis<'_> {
+    fn evaluate(
+        &self,
+        inst: &Instruction,
+        ty: &ValueType,
+        inputs: &[Slot<NumericFact>],
+    ) -> Interval {
+        let bounds = type_bounds(ty);
+        let range = |index: usize| {
+            inputs
+                .get(index)
+                .map(|slot| slot.fact.range)
+                .unwrap_or(bounds)
+        };
+        let code = &self.method.bytecode;
+        let operand = inst.offset as usize + 1;
+        match inst.opcode {
+            opcodes::ICONST_M1..=opcodes::ICONST_5 => {
+                Interval::constant(inst.opcode as i64 - opcodes::ICONST_0 as i64)
+            }
+            opcodes::LCONST_0 | opcodes::LCONST_1 => {
+                Interval::constant((inst.opcode - opcodes::LCONST_0) as i64)
+            }
+            opcodes::BIPUSH => code
+                .get(operand)
+                .map(|byte| Interval::constant(*byte as i8 as i64))
+                .unwrap_or(bounds),
+            opcodes::SIPUSH => match (code.get(operand), code.get(operand + 1)) {
+                (Some(high), Some(low)) => {
+                    Interval::constant(i16::from_be_bytes([*high, *low]) as i64)
+                }
+                _ => bounds,
+            },
+            opcodes::LDC | opcodes::LDC_W | opcodes::LDC2_W => match &inst.kind {
+                InstructionKind::Constant(ConstantValue::Int(value)) => {
+                    Interval::constant(*value as i64)
+                }
+                InstructionKind::Constant(ConstantValue::Long(value)) => Interval::constant(*value),
+                _ => bounds,
+            },
+            opcodes::IADD..=opcodes::DREM if matches!(ty, ValueType::Int | ValueType::Long) => {
+                arithmetic(
+                    (inst.opcode - opcodes::IADD) / 4,
+                    range(0),
+                    range(1),
+                    bounds,
+                )
+            }
+            opcodes::INEG | opcodes::LNEG => {
+                let value = range(0);
+                Interval::fit(-(value.max as i128), -(value.min as i128), bounds)
+            }
+            opcodes::ISHL..=opcodes::LUSHR => shift(
+                (inst.opcode - opcodes::ISHL) / 2,
+                range(0),
+                range(1),
+                *ty == ValueType::Long,
+            ),
+            opcodes::IAND..=opcodes::LXOR => bitwise(
+                (inst.opcode - opcodes::IAND) / 2,
+                range(0),
+                range(1),
+                bounds,
+            ),
+            opcodes::IINC | opcodes::WIDE => {
+                let value = range(0);
+                let increment = self.increment(inst).unwrap_or(0) as i128;
+                Interval::fit(
+                    value.min as i128 + increment,
+                    value.max as i128 + increment,
+                    Interval::INT,
+                )
+            }
+            opcodes::I2L => range(0),
+            opcodes::L2I => narrow(range(0), Interval::INT),
+            opcodes::I2B => narrow(range(0), Interval::BYTE),
+            opcodes::I2C => narrow(range(0), Interval::CHAR),
+            opcodes::I2S => narrow(range(0), Interval::SHORT),
+            opcodes::LCMP => {
+                let (left, right) = (range(0), range(1));
+                if left.max < right.min {
+                    Interval::constant(-1)
+                } else if left.min > right.max {
+                    Interval::constant(1)
+                } else if left.as_constant().is_some() && left == right {
+                    Interval::constant(0)
+                } else {
+                    Interval::new(-1, 1)
+                }
+            }
+            opcodes::FCMPL | opcodes::FCMPG | opcodes::DCMPL | opcodes::DCMPG => {
+                Interval::new(-1, 1)
+            }
+            opcodes::ARRAYLENGTH => inputs
+                .first()
+                .map(|slot| slot.fact.length)
+                .unwrap_or(Interval::LENGTH),
+            opcodes::INSTANCEOF => Interval::BOOLEAN,
+            opcodes::BALOAD => Interval::BYTE,
+            opcodes::CALOAD => Interval::CHAR,
+            opcodes::SALOAD => Interval::SHORT,
+            opcodes::GETFIELD | opcodes::GETSTATIC => match &inst.kind {
+                InstructionKind::Field(field) => TypeDescriptor::from_str(&field.descriptor)
+                    .map(|descriptor| descriptor_bounds(&descriptor))
+                    .unwrap_or(bounds),
+                _ => bounds,
+            },
+            opcodes::INVOKEVIRTUAL
+            | opcodes::INVOKESPECIAL
+            | opcodes::INVOKESTATIC
+            | opcodes::INVOKEINTERFACE => self
+                .interpreter
+                .call_site(inst)
+                .and_then(|call| MethodDescriptor::from_str(&call.descriptor).ok())
+                .map(|descriptor| descriptor_bounds(descriptor.return_type()))
+                .unwrap_or(bounds),
+            _ => bounds,
+        }
+    }
+
+    /// Constant added by `iinc` or `wide iinc`.
+    fn increment(&self, inst: &Instruction) -> Option<i64> {
+        let code = &self.method.bytecode;
+        let at = inst.offset as usize;
+        if inst.opcode == opcodes::WIDE {
+            let high = *code.get(at + 4)?;
+            let low = *code.get(at + 5)?;
+            Some(i16::from_be_bytes([high, low]) as i64)
+        } else {
+            code.get(at + 2).map(|byte| *byte as i8 as i64)
+        }
+    }
+}
+
+impl FactHooks<NumericFact> for NumericAnalysis<'_> {
+    fn parameter(&self, index: Option<usize>, _ty: &ValueType) -> NumericFact {
+        let range = index
+            .and_then(|index| self.parameters.get(index))
+            .map(descriptor_bounds)
+            .unwrap_or(Interval::LONG);
+        NumericFact::of(range)
+    }
+
+    fn produce(
+        &self,
+        inst: &Instruction,
+        ty: &ValueType,
+        inputs: &[Slot<NumericFact>],
+    ) -> NumericFact {
+        let length = match inst.opcode {
+            opcodes::NEWARRAY | opcodes::ANEWARRAY | opcodes::MULTIANEWARRAY => inputs
+                .first()
+                .and_then(|slot| slot.fact.range.intersect(&Interval::LENGTH))
+                .unwrap_or(Interval::LENGTH),
+            _ => Interval::LENGTH,
+        };
+        NumericFact {
+            range: self.evaluate(inst, ty, inputs),
+            length,
+            local: None,
+        }
+    }
+
+    fn load(&self, index: usize, slot: &Slot<NumericFact>) -> NumericFact {
+        NumericFact {
+            local: Some(index),
+            ..slot.fact.clone()
+        }
+    }
+
+    fn store(&self, _index: usize, slot: &Slot<NumericFact>) -> NumericFact {
+        NumericFact {
+            local: None,
+            ..slot.fact.clone()
+        }
+    }
+}
+
+impl Analysis for NumericAnalysis<'_> {
+    type Domain = Frame<NumericFact>;
+
+    fn boundary_state(&self) -> Result<Frame<NumericFact>> {
+        self.interpreter.entry_frame(self)
+    }
+
+    fn join(&self, left: &Frame<NumericFact>, right: &Frame<NumericFact>) -> Frame<NumericFact> {
+        left.join(right)
+    }
+
+    fn widen(
+        &self,
+        previous: &Frame<NumericFact>,
+        next: &Frame<NumericFact>,
+    ) -> Frame<NumericFact> {
+        let locals = next
+            .locals
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| widen_slot(&previous.local(index), slot))
+            .collect();
+        let stack = if previous.stack.len() == next.stack.len() {
+            previous
+                .stack
+                .iter()
+                .zip(next.stack.iter())
+                .map(|(previous, next)| widen_slot(previous, next))
+                .collect()
+        } else {
+            next.stack.clone()
+        };
+        Frame { locals, stack }
+    }
+
+    fn transfer(&self, frame: &mut Frame<NumericFact>, inst: &Instruction) -> Result<()> {
+        self.interpreter.execute(frame, inst, self)?;
+        // Values loaded earlier no longer describe a local that was just overwritten.
+        if let Some(written) = written_local(&self.method.bytecode, inst) {
+            for slot in &mut frame.stack {
+                if slot
+                    .fact
+                    .local
+                    .is_some_and(|local| local.abs_diff(written) <= 1)
+                {
+                    slot.fact.local = None;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn exception_state(&self, frame: &Frame<NumericFact>) -> Frame<NumericFact> {
+        frame.exception_frame(NumericFact::default())
+    }
+
+    fn refine_edge(
+        &self,
+        edge: &EdgeContext<'_, Frame<NumericFact>>,
+        mut frame: Frame<NumericFact>,
+    ) -> Option<Frame<NumericFact>> {
+        let (Some(terminator), Some(before)) = (edge.terminator, edge.before_terminator) else {
+            return Some(frame);
+        };
+        let zero = Slot {
+            ty: ValueType::Int,
+            fact: NumericFact::of(Interval::constant(0)),
+        };
+        let (condition, left, right) = match terminator.opcode {
+            opcodes::IFEQ..=opcodes::IFLE => match before.peek(0) {
+                Some(value) => (terminator.opcode - opcodes::IFEQ, value, &zero),
+                None => return Some(frame),
+            },
+            opcodes::IF_ICMPEQ..=opcodes::IF_ICMPLE => match (before.peek(1), before.peek(0)) {
+                (Some(left), Some(right)) => (terminator.opcode - opcodes::IF_ICMPEQ, left, right),
+                _ => return Some(frame),
+            },
+            _ => return Some(frame),
+        };
+        let condition = if edge.edge.kind == EdgeKind::Branch {
+            condition
+        } else {
+            negate(condition)
+        };
+        // An empty range on either side means the edge is never taken.
+        let left_range = refine(condition, left.fact.range, right.fact.range)?;
+        let right_range = refine(mirror(condition), right.fact.range, left.fact.range)?;
+        for (slot, range) in [(left, left_range), (right, right_range)] {
+            if let Some(local) = slot.fact.local
+                && let Some(target) = frame.locals.get_mut(local)
+            {
+                target.fact.range = range;
+            }
+        }
+        Some(frame)
+    }
+}
+
+fn widen_slot(previous: &Slot<NumericFact>, next: &Slot<NumericFact>) -> Slot<NumericFact> {
+    let mut slot = next.clone();
+    slot.fact.range = previous
+        .fact
+        .range
+        .widen(&next.fact.range, type_bounds(&next.ty));
+    slot.fact.length = previous
+        .fact
+        .length
+        .widen(&next.fact.length, Interval::LENGTH);
+    slot
+}
+
+fn type_bounds(ty: &ValueType) -> Interval {
+    match ty {
+        ValueType::Int => Interval::INT,
+        _ => Interval::LONG,
+    }
+}
+
+fn descriptor_bounds(descriptor: &TypeDescriptor) -> Interval {
+    match descriptor {
+        TypeDescriptor::Boolean => Interval::BOOLEAN,
+        TypeDescriptor::Byte => Interval::BYTE,
+        TypeDescriptor::Char => Interval::CHAR,
+        TypeDescriptor::Short => Interval::SHORT,
+        TypeDescriptor::Integer => Interval::INT,
+        _ => Interval::LONG,
+    }
+}
+
+/// Range after a narrowing conversion that keeps values fitting the target type.
+fn narrow(value: Interval, target: Interval) -> Interval {
+    match value.intersect(&target) {
+        Some(narrowed) if narrowed == value => value,
+        _ => target,
+    }
+}
+
+/// `operation` counts add, sub, mul, div, rem.
+fn arithmetic(operation: u8, left: Interval, right: Interval, bounds: Interval) -> Interval {
+    let (left_min, left_max) = (left.min as i128, left.max as i128);
+    let (right_min, right_max) = (right.min as i128, right.max as i128);
+    match operation {
+        0 => Interval::fit(left_min + right_min, left_max + right_max, bounds),
+        1 => Interval::fit(left_min - right_max, left_max - right_min, bounds),
+        2 => {
+            let products = [
+                left_min * right_min,
+                left_min * right_max,
+                left_max * right_min,
+                left_max * right_max,
+            ];
+            let min = products.iter().copied().min().unwrap_or(0);
+            let max = products.iter().copied().max().unwrap_or(0);
+            Interval::fit(min, max, bounds)
+        }
+        3 => match right.as_constant() {
+            // Truncating division is monotone in the dividend for a fixed divisor.
+            Some(divisor) if divisor != 0 => {
+                let divisor = divisor as i128;
+                let (first, second) = (left_min / divisor, left_max / divisor);
+                Interval::fit(first.min(second), first.max(second), bounds)
+            }
+            _ => bounds,
+        },
+        _ => match right.as_constant() {
+            Some(divisor) if divisor != 0 => {
+                let divisor = divisor as i128;
+                if let Some(value) = left.as_constant() {
+                    return Interval::constant((value as i128 % divisor) as i64);
+                }
+                let limit = (divisor.abs() - 1) as i64;
+                if left.is_non_negative() {
+                    Interval::new(0, left.max.min(limit))
+                } else if left.max <= 0 {
+                    Interval::new(left.min.max(-limit), 0)
+                } else {
+                    Interval::new(-limit, limit)
+                }
+            }
+            _ => bounds,
+        },
+    }
+}
+
+/// `operation` counts shl, shr, ushr.
+fn shift(operation: u8, value: Interval, amount: Interval, long: bool) -> Interval {
+    let bounds = if long { Interval::LONG } else { Interval::INT };
+    let Some(distance) = amount.as_constant() else {
+        // Right shifts move non-negative values towards zero.
+        if operation != 0 && value.is_non_negative() {
+            return Interval::new(0, value.max);
+        }
+        return bounds;
+    };
+    let distance = (distance & if long { 63 } else { 31 }) as u32;
+    match operation {
+        0 => Interval::fit(
+            (value.min as i128) << distance,
+            (value.max as i128) << distance,
+            bounds,
+        ),
+        1 => Interval::new(value.min >> distance, value.max >> distance),
+        _ if value.is_non_negative() => Interval::new(value.min >> distance, value.max >> distance),
+        _ => match value.as_constant() {
+            Some(constant) if long => Interval::constant(((constant as u64) >> distance) as i64),
+            Some(constant) => Interval::constant(((constant as i32 as u32) >> distance) as i64),
+            None => bounds,
+        },
+    }
+}
+
+/// `operation` counts and, or, xor.
+fn bitwise(operation: u8, left: Interval, right: Interval, bounds: Interval) -> Interval {
+    if let (Some(left), Some(right)) = (left.as_constant(), right.as_constant()) {
+        return Interval::constant(match operation {
+            0 => left & right,
+            1 => left | right,
+            _ => left ^ right,
+        });
+    }
+    match operation {
+        // Masking with a non-negative value bounds the result by the mask.
+        0 if left.is_non_negative() && right.is_non_negative() => {
+            Interval::new(0, left.max.min(right.max))
+        }
+        0 if left.is_non_negative() => Interval::new(0, left.max),
+        0 if right.is_non_negative() => Interval::new(0, right.max),
+        0 => bounds,
+        _ if left.is_non_negative() && right.is_non_negative() => {
+            let highest = left.max.max(right.max) as u64;
+            Interval::new(
+                0,
+                u64::MAX.checked_shr(highest.leading_zeros()).unwrap_or(0) as i64,
+            )
+        }
+        _ => bounds,
+    }
+}
+
+/// Conditions count eq, ne, lt, ge, gt, le like the `if<cond>` opcodes.
+fn negate(condition: u8) -> u8 {
+    condition ^ 1
+}
+
+/// Condition with the operands swapped.
+fn mirror(condition: u8) -> u8 {
+    match condition {
+        2 => 4,
+        3 => 5,
+        4 => 2,
+        5 => 3,
+        same => same,
+    }
+}
+
+/// Values of `value` for which `value <condition> other` can hold.
+fn refine(condition: u8, value: Interval, other: Interval) -> Option<Interval> {
+    match condition {
+        0 => value.intersect(&other),
+        1 => match other.as_constant() {
+            Some(constant) if value.as_constant() == Some(constant) => None,
+            Some(constant) if value.min == constant => Some(Interval::new(constant + 1, value.max)),
+            Some(constant) if value.max == constant => Some(Interval::new(value.min, constant - 1)),
+            _ => Some(value),
+        },
+        2 => value.intersect(&Interval::new(i64::MIN, other.max.checked_sub(1)?)),
+        3 => value.intersect(&Interval::new(other.min, i64::MAX)),
+        4 => value.intersect(&Interval::new(other.min.checked_add(1)?, i64::MAX)),
+        _ => value.intersect(&Interval::new(i64::MIN, other.max)),
+    }
+}
+
+/// Local overwritten by a store or `iinc`.
+fn written_local(code: &[u8], inst: &Instruction) -> Option<usize> {
+    let at = inst.offset as usize;
+    match inst.opcode {
+        opcodes::ISTORE..=opcodes::ASTORE | opcodes::IINC => {
+            code.get(at + 1).map(|index| *index as usize)
+        }
+        opcodes::ISTORE_0..=opcodes::ASTORE_3 => {
+            Some(((inst.opcode - opcodes::ISTORE_0) % 4) as usize)
+        }
+        opcodes::WIDE => match code.get(at + 1).copied()? {
+            opcodes::ISTORE..=opcodes::ASTORE | opcodes::IINC => {
+                crate::scan::read_u16(code, at + 2)
+                    .ok()
+                    .map(|index| index as usize)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::ir::Class;
+    use crate::test_harness::compile_sample;
+
+    /// Ranges of the values returned by `name`, in bytecode order.
+    fn returned_ranges(classes: &[Class], name: &str) -> Vec<Interval> {
+        let class = classes
+            .iter()
+            .find(|class| class.name == "com/example/Sample")
+            .expect("sample class");
+        let method = class
+            .methods
+            .iter()
+            .find(|method| method.name == name)
+            .expect("method");
+        let numeric = analyze_ranges(&class.name, method).expect("analyze ranges");
+        method
+            .cfg
+            .blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .filter(|inst| matches!(inst.opcode, opcodes::IRETURN | opcodes::LRETURN))
+            .filter_map(|inst| numeric.operand(inst.offset, 0))
+            .map(|fact| fact.range)
+            .collect()
+    }
+
+    const SOURCE: &str = r#"
+package com.example;
+public class Sample {
+    static int fold() {
+        int x = 3;
+        long y = (x * 4 + 1) << 2;
+        return (int) (y % 10);
+    }
+
+    static int clamp(int n) {
+        if (n > 10) {
+            return n;
+        }
+        if (n < 0) {
+            return 0;
+        }
+        return n;
+    }
+
+    static int length(boolean wide) {
+        int[] values = new int[wide ? 8 : 2];
+        return values.length;
+    }
+
+    static int count() {
+        int count = 0;
+        while (count < 100) {
+            count++;
+        }
+        return count;
+    }
+
+    static int low(byte[] data) {
+        return data[0] & 0x0f;
+    }
+}
+"#;
+
+    #[test]
+    fn numeric_folds_constant_arithmetic() {
+        let classes = compile_sample(SOURCE);
+
+        assert_eq!(
+            vec![Interval::constant(2)],
+            returned_ranges(&classes, "fold")
+        );
+    }
+
+    #[test]
+    fn numeric_refines_locals_on_branches() {
+        let classes = compile_sample(SOURCE);
+
+        assert_eq!(
+            vec![
+                Interval::new(11, i32::MAX as i64),
+                Interval::constant(0),
+                Interval::new(0, 10),
+            ],
+            returned_ranges(&classes, "clamp")
+        );
+    }
+
+    #[test]
+    fn numeric_tracks_array_lengths_and_loop_exits() {
+        let classes = compile_sample(SOURCE);
+
+        assert_eq!(
+            vec![Interval::new(2, 8)],
+            returned_ranges(&classes, "length")
+        );
+        assert_eq!(
+            vec![Interval::new(100, i32::MAX as i64)],
+            returned_ranges(&classes, "count")
+        );
+        assert_eq!(vec![Interval::new(0, 15)], returned_ranges(&classes, "low"));
+    }
+
+    #[test]
+    fn numeric_is_cached_in_context() {
+        let classes = compile_sample(SOURCE);
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+        let class = context
+            .classes
+            .iter()
+            .find(|class| class.name == "com/example/Sample")
+            .expect("sample class");
+        let method = &class.methods[0];
+
+        let first = context.numeric(class, method).expect("analyze");
+        let second = context.numeric(class, method).expect("analyze");
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+    }
+}
@@ -0,0 +1,1171 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::callgraph::{
+    CallEdge, ClassHierarchy, MethodId, MethodIndex, MethodPattern, Resolver, build_hierarchy,
+    implicit_calls, index_methods, is_root_method, lookup_method,
+};
+use crate::interpreter::{BaseType, ReferenceType, ValueType};
+use crate::ir::{CallKind, CallSite, Class, Instruction, InstructionKind, Method};
+use crate::opcodes;
+use crate::ssa::{SsaMethod, ValueDef, ValueId, lift};
+
+/// Field id used for the summary of all elements of an array.
+const ELEMENT: u32 = u32::MAX;
+
+/// Abstract heap object standing for every object created at one place.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum HeapObject {
+    /// Created by `new`, an array creation, or a string constant at `offset` in
+    /// `method`. Multi-dimensional arrays get one object per dimension.
+    Site {
+        method: Arc<MethodId>,
+        offset: u32,
+        type_name: String,
+    },
+    /// Created by code the analysis does not see, such as the caller of an entry
+    /// point or a JDK method; stands for any instance of `type_name` or a subtype.
+    External { type_name: String },
+}
+
+impl HeapObject {
+    /// Internal class name, or descriptor for arrays.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::Site { type_name, .. } | Self::External { type_name } => type_name,
+        }
+    }
+}
+
+/// Pointer variable of the constraint graph.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Node {
+    /// Reference-typed SSA value of a method.
+    Value(u32, ValueId),
+    /// Values returned by a method.
+    Return(u32),
+    /// Field of an abstract object, or its array elements for `ELEMENT`.
+    Field(u32, u32),
+    /// Static field, by interned `owner.name` of its declaring class.
+    Static(u32),
+}
+
+/// Result of the Andersen-style points-to analysis: the objects each reference
+/// may point to, and the call graph built on the fly from them.
+///
+/// The analysis is context-insensitive and field-sensitive. Fields are told apart
+/// by name only, and all elements of an array share one summary.
+#[derive(Debug)]
+pub struct PointsTo {
+    objects: Vec<HeapObject>,
+    object_ids: HashMap<HeapObject, u32>,
+    methods: HashMap<Arc<MethodId>, u32>,
+    names: HashMap<String, u32>,
+    nodes: HashMap<Node, u32>,
+    sets: Vec<BTreeSet<u32>>,
+    pub(crate) edges: Vec<CallEdge>,
+}
+
+impl PointsTo {
+    /// Objects an SSA value of `method` may point to.
+    pub fn value(&self, method: &MethodId, value: ValueId) -> Vec<&HeapObject> {
+        let node = self
+            .methods
+            .get(method)
+            .map(|method| Node::Value(*method, value));
+        self.objects_of(node)
+    }
+
+    /// Objects `method` may return.
+    pub fn returned(&self, method: &MethodId) -> Vec<&HeapObject> {
+        let node = self.methods.get(method).map(|method| Node::Return(*method));
+        self.objects_of(node)
+    }
+
+    /// Objects stored in the field `name` of `object`.
+    pub fn field(&self, object: &HeapObject, name: &str) -> Vec<&HeapObject> {
+        let node = self
+            .object_ids
+            .get(object)
+            .zip(self.names.get(name))
+            .map(|(object, field)| Node::Field(*object, *field));
+        self.objects_of(node)
+    }
+
+    /// Objects stored in any element of the array `object`.
+    pub fn array_elements(&self, object: &HeapObject) -> Vec<&HeapObject> {
+        let node = self
+            .object_ids
+            .get(object)
+            .map(|object| Node::Field(*object, ELEMENT));
+        self.objects_of(node)
+    }
+
+    /// Objects stored in the static field `name` declared by `owner`.
+    pub fn static_field(&self, owner: &str, name: &str) -> Vec<&HeapObject> {
+        let node = self
+            .names
+            .get(&format!("{owner}.{name}"))
+            .map(|field| Node::Static(*field));
+        self.objects_of(node)
+    }
+
+    /// Whether two SSA values of `method` may point to a common object.
+    pub fn may_alias(&self, method: &MethodId, left: ValueId, right: ValueId) -> bool {
+        let Some(method) = self.methods.get(method) else {
+            return false;
+        };
+        let set = |value| {
+            self.nodes
+                .get(&Node::Value(*method, value))
+                .map(|node| &self.sets[*node as usize])
+        };
+        match (set(left), set(right)) {
+            (Some(left), Some(right)) => !left.is_disjoint(right),
+            _ => false,
+        }
+    }
+
+    fn objects_of(&self, node: Option<Node>) -> Vec<&HeapObject> {
+        node.and_then(|node| self.nodes.get(&node))
+            .map(|node| {
+                self.sets[*node as usize]
+                    .iter()
+                    .map(|object| &self.objects[*object as usize])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Run the points-to analysis over `classes`; `None` when it exceeds `budget`.
+pub(crate) fn analyze_points_to(
+    classes: &[Class],
+    service_providers: &[String],
+    entry_points: &[MethodPattern],
+    is_entry_class: impl Fn(&Class) -> bool,
+    budget: Duration,
+) -> Option<PointsTo> {
+    let hierarchy = build_hierarchy(classes);
+    let methods = index_methods(classes);
+    solve_points_to(
+        classes,
+        &hierarchy,
+        &methods,
+        service_providers,
+        entry_points,
+        is_entry_class,
+        budget,
+    )
+}
+
+/// Solve the constraints of the methods reachable from the public methods, static
+/// initializers and configured entry points of entry classes and from service
+/// providers. Objects
+/// flowing in from unseen code are `HeapObject::External`, and calls on them
+/// dispatch like CHA.
+pub(crate) fn solve_points_to(
+    classes: &[Class],
+    hierarchy: &ClassHierarchy<'_>,
+    methods: &MethodIndex,
+    service_providers: &[String],
+    entry_points: &[MethodPattern],
+    is_entry_class: impl Fn(&Class) -> bool,
+    budget: Duration,
+) -> Option<PointsTo> {
+    let started_at = Instant::now();
+    let bodies: HashMap<Arc<MethodId>, (&Class, &Method)> = classes
+        .iter()
+        .flat_map(|class| {
+            class.methods.iter().filter_map(move |method| {
+                lookup_method(methods, &class.name, &method.name, &method.descriptor)
+                    .map(|id| (id, (class, method)))
+            })
+        })
+        .collect();
+    let mut solver = Solver::new(hierarchy, methods, bodies);
+    for class in classes.iter().filter(|class| is_entry_class(class)) {
+        for method in &class.methods {
+            if !is_root_method(class, method, entry_points) {
+                continue;
+            }
+            if let Some(id) = lookup_method(methods, &class.name, &method.name, &method.descriptor)
+            {
+                let method = solver.method_id(id);
+                solver.reach(method);
+                solver.external_arguments(method);
+            }
+        }
+    }
+    for provider in service_providers {
+        for name in ["<init>", "<clinit>"] {
+            if let Some(id) = lookup_method(methods, provider, name, "()V") {
+                let method = solver.method_id(id);
+                solver.reach(method);
+                solver.external_arguments(method);
+            }
+        }
+    }
+
+    let mut steps: u64 = 0;
+    loop {
+        if steps.is_multiple_of(256) && started_at.elapsed() >= budget {
+            return None;
+        }
+        steps += 1;
+        if let Some(method) = solver.worklist.pop() {
+            solver.process(method);
+        } else if let Some(node) = solver.pending.pop() {
+            solver.propagate(node);
+        } else {
+            break;
+        }
+    }
+    Some(solver.finish())
+}
+
+/// Load from a field or array element of the objects a node points to.
+#[derive(Clone, Copy)]
+struct Load {
+    field: u32,
+    target: u32,
+    /// Object loaded from external objects, typed by the loaded value.
+    external: Option<u32>,
+}
+
+/// Store into a field or array element of the objects a node points to.
+#[derive(Clone, Copy)]
+struct Store {
+    field: u32,
+    source: u32,
+}
+
+/// Virtual or interface call, dispatched for each object its receiver points to.
+struct VirtualSite {
+    caller: u32,
+    owner: String,
+    name: String,
+    descriptor: String,
+    /// Interned owner type, which receivers are filtered by.
+    owner_type: u32,
+    /// Interned name and descriptor, which dispatch results are cached by.
+    signature: u32,
+    kind: CallKind,
+    offset: u32,
+    arguments: Vec<Option<u32>>,
+    result: Option<u32>,
+    /// Object returned when a receiver selects no analyzed method.
+    external_result: Option<u32>,
+}
+
+/// Nodes a caller binds its arguments and result to.
+#[derive(Clone)]
+struct Signature {
+    this: Option<u32>,
+    parameters: Vec<Option<u32>>,
+    returned: u32,
+}
+
+/// State of the points-to fixpoint.
+struct Solver<'s, 'a> {
+    hierarchy: &'s ClassHierarchy<'a>,
+    resolver: Resolver<'s, 'a>,
+    bodies: HashMap<Arc<MethodId>, (&'a Class, &'a Method)>,
+    objects: Vec<HeapObject>,
+    object_ids: HashMap<HeapObject, u32>,
+    object_types: Vec<u32>,
+    types: HashMap<String, u32>,
+    type_names: Vec<String>,
+    /// Whether objects of a type, external or not, may be stored in a variable of
+    /// another type.
+    compatible: HashMap<(u32, u32, bool), bool>,
+    /// Targets by site owner type, signature, receiver type and externality.
+    targets: HashMap<(u32, u32, u32, bool), Vec<Arc<MethodId>>>,
+    methods: Vec<Arc<MethodId>>,
+    method_ids: HashMap<Arc<MethodId>, u32>,
+    names: HashMap<String, u32>,
+    nodes: HashMap<Node, u32>,
+    sets: Vec<BTreeSet<u32>>,
+    /// Declared type of each value node, which incompatible objects are dropped by.
+    filters: Vec<Option<u32>>,
+    /// Objects added to each node since it was last propagated.
+    delta: Vec<Vec<u32>>,
+    queued: Vec<bool>,
+    pending: Vec<u32>,
+    successors: Vec<Vec<u32>>,
+    copies: HashSet<(u32, u32)>,
+    loads: Vec<Vec<Load>>,
+    stores: Vec<Vec<Store>>,
+    /// Virtual call sites by receiver node.
+    receivers: Vec<Vec<usize>>,
+    sites: Vec<VirtualSite>,
+    /// Call sites whose arguments and result are bound to a callee.
+    bound: HashSet<(usize, u32)>,
+    /// Sites that already returned an external object.
+    unresolved: HashSet<usize>,
+    ssa: HashMap<u32, Option<Arc<SsaMethod>>>,
+    signatures: HashMap<u32, Option<Signature>>,
+    reachable: HashSet<u32>,
+    worklist: Vec<u32>,
+    linked: HashSet<(u32, u32, u32)>,
+    edges: Vec<CallEdge>,
+}
+
+impl<'s, 'a> Solver<'s, 'a> {
+    fn new(
+        hierarchy: &'s ClassHierarchy<'a>,
+        methods: &'s MethodIndex,
+        bodies: HashMap<Arc<MethodId>, (&'a Class, &'a Method)>,
+    ) -> Self {
+        Self {
+            hierarchy,
+            resolver: Resolver::new(hierarchy, methods),
+            bodies,
+            objects: Vec::new(),
+            object_ids: HashMap::new(),
+            object_types: Vec::new(),
+            types: HashMap::new(),
+            type_names: Vec::new(),
+            compatible: HashMap::new(),
+            targets: HashMap::new(),
+            methods: Vec::new(),
+            method_ids: HashMap::new(),
+            names: HashMap::new(),
+            nodes: HashMap::new(),
+            sets: Vec::new(),
+            filters: Vec::new(),
+            delta: Vec::new(),
+            queued: Vec::new(),
+            pending: Vec::new(),
+            successors: Vec::new(),
+            copies: HashSet::new(),
+            loads: Vec::new(),
+            stores: Vec::new(),
+            receivers: Vec::new(),
+            sites: Vec::new(),
+            bound: HashSet::new(),
+            unresolved: HashSet::new(),
+            ssa: HashMap::new(),
+            signatures: HashMap::new(),
+            reachable: HashSet::new(),
+            worklist: Vec::new(),
+            linked: HashSet::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> PointsTo {
+        let mut edges = self.edges;
+        edges.sort();
+        PointsTo {
+            objects: self.objects,
+            object_ids: self.object_ids,
+            methods: self.method_ids,
+            names: self.names,
+            nodes: self.nodes,
+            sets: self.sets,
+            edges,
+        }
+    }
+
+    fn method_id(&mut self, method: Arc<MethodId>) -> u32 {
+        if let Some(id) = self.method_ids.get(&method) {
+            return *id;
+        }
+        let id = self.methods.len() as u32;
+        self.methods.push(method.clone());
+        self.method_ids.insert(method, id);
+        id
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.names.get(name) {
+            return *id;
+        }
+        let id = self.names.len() as u32;
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    fn object(&mut self, object: HeapObject) -> u32 {
+        if let Some(id) = self.object_ids.get(&object) {
+            return *id;
+        }
+        let id = self.objects.len() as u32;
+        let object_type = self.type_id(object.type_name());
+        self.objects.push(object.clone());
+        self.object_ids.insert(object, id);
+        self.object_types.push(object_type);
+        id
+    }
+
+    fn type_id(&mut self, type_name: &str) -> u32 {
+        if let Some(id) = self.types.get(type_name) {
+            return *id;
+        }
+        let id = self.type_names.len() as u32;
+        self.types.insert(type_name.to_string(), id);
+        self.type_names.push(type_name.to_string());
+        id
+    }
+
+    /// Whether `object` may be stored in a variable of type `filter`. Unparsed
+    /// classes are assumed compatible, and external objects are compatible with
+    /// subtypes of their type too.
+    fn is_compatible(&mut self, object: u32, filter: u32) -> bool {
+        let object_type = self.object_types[object as usize];
+        let is_external = matches!(self.objects[object as usize], HeapObject::External { .. });
+        let key = (object_type, filter, is_external);
+        if let Some(compatible) = self.compatible.get(&key) {
+            return *compatible;
+        }
+        let object_name = self.type_names[object_type as usize].as_str();
+        let filter_name = self.type_names[filter as usize].as_str();
+        let compatible = match (object_name.starts_with('['), filter_name.starts_with('[')) {
+            _ if filter_name == "java/lang/Object" => true,
+            (true, true) => true,
+            (true, false) => matches!(filter_name, "java/lang/Cloneable" | "java/io/Serializable"),
+            (false, true) => is_external && object_name == "java/lang/Object",
+            (false, false) => {
+                self.is_subtype(object_name, filter_name)
+                    || (is_external && self.is_subtype(filter_name, object_name))
+            }
+        };
+        self.compatible.insert(key, compatible);
+        compatible
+    }
+
+    /// Whether `class_name` extends or implements `supertype`; true when the class
+    /// is not parsed.
+    fn is_subtype(&self, class_name: &str, supertype: &str) -> bool {
+        self.hierarchy.class(class_name).is_none_or(|class| {
+            self.hierarchy
+                .is_subtype_of(class.name.as_str(), &[supertype])
+        })
+    }
+
+    fn site(&mut self, method: u32, offset: u32, type_name: String) -> u32 {
+        let method = self.methods[method as usize].clone();
+        self.object(HeapObject::Site {
+            method,
+            offset,
+            type_name,
+        })
+    }
+
+    fn external(&mut self, type_name: &str) -> u32 {
+        self.object(HeapObject::External {
+            type_name: type_name.to_string(),
+        })
+    }
+
+    fn node(&mut self, node: Node) -> u32 {
+        if let Some(id) = self.nodes.get(&node) {
+            return *id;
+        }
+        let id = self.sets.len() as u32;
+        self.nodes.insert(node, id);
+        self.sets.push(BTreeSet::new());
+        self.filters.push(None);
+        self.delta.push(Vec::new());
+        self.queued.push(false);
+        self.successors.push(Vec::new());
+        self.loads.push(Vec::new());
+        self.stores.push(Vec::new());
+        self.receivers.push(Vec::new());
+        id
+    }
+
+    /// Node of a reference-typed SSA value; `None` for primitives and `null`.
+    fn value_node(&mut self, method: u32, ssa: &SsaMethod, value: ValueId) -> Option<u32> {
+        let type_name = type_name(&ssa.value(value).ty)?;
+        let node = Node::Value(method, value);
+        if let Some(id) = self.nodes.get(&node) {
+            return Some(*id);
+        }
+        let id = self.node(node);
+        if type_name != "java/lang/Object" {
+            self.filters[id as usize] = Some(self.type_id(&type_name));
+        }
+        Some(id)
+    }
+
+    fn add_objects(&mut self, node: u32, objects: &[u32]) {
+        let index = node as usize;
+        let filter = self.filters[index];
+        for object in objects {
+            if filter.is_some_and(|filter| !self.is_compatible(*object, filter)) {
+                continue;
+            }
+            if self.sets[index].insert(*object) {
+                self.delta[index].push(*object);
+            }
+        }
+        if !self.delta[index].is_empty() && !self.queued[index] {
+            self.queued[index] = true;
+            self.pending.push(node);
+        }
+    }
+
+    fn add_edge(&mut self, from: u32, to: u32) {
+        if from == to || !self.copies.insert((from, to)) {
+            return;
+        }
+        self.successors[from as usize].push(to);
+        let objects: Vec<u32> = self.sets[from as usize].iter().copied().collect();
+        self.add_objects(to, &objects);
+    }
+
+    fn add_load(&mut self, node: u32, load: Load) {
+        self.loads[node as usize].push(load);
+        let objects: Vec<u32> = self.sets[node as usize].iter().copied().collect();
+        for object in objects {
+            self.apply_load(object, load);
+        }
+    }
+
+    fn add_store(&mut self, node: u32, store: Store) {
+        self.stores[node as usize].push(store);
+        let objects: Vec<u32> = self.sets[node as usize].iter().copied().collect();
+        for object in objects {
+            self.apply_store(object, store);
+        }
+    }
+
+    fn apply_load(&mut self, object: u32, load: Load) {
+        if let (HeapObject::External { .. }, Some(external)) =
+            (&self.objects[object as usize], load.external)
+        {
+            self.add_objects(load.target, &[external]);
+        }
+        let field = self.node(Node::Field(object, load.field));
+        self.add_edge(field, load.target);
+    }
+
+    fn apply_store(&mut self, object: u32, store: Store) {
+        let field = self.node(Node::Field(object, store.field));
+        self.add_edge(store.source, field);
+    }
+
+    fn propagate(&mut self, node: u32) {
+        let index = node as usize;
+        self.queued[index] = false;
+        let delta = std::mem::take(&mut self.delta[index]);
+        if delta.is_empty() {
+            return;
+        }
+        for position in 0..self.successors[index].len() {
+            let successor = self.successors[index][position];
+            self.add_objects(successor, &delta);
+        }
+        for position in 0..self.loads[index].len() {
+            let load = self.loads[index][position];
+            for object in &delta {
+                self.apply_load(*object, load);
+            }
+        }
+        for position in 0..self.stores[index].len() {
+            let store = self.stores[index][position];
+            for object in &delta {
+                self.apply_store(*object, store);
+            }
+        }
+        for position in 0..self.receivers[index].len() {
+            let site = self.receivers[index][position];
+            for object in &delta {
+                self.dispatch(site, *object);
+            }
+        }
+    }
+
+    fn reach(&mut self, method: u32) {
+        if self.reachable.insert(method) {
+            self.worklist.push(method);
+        }
+    }
+
+    fn link(&mut self, caller: u32, callee: u32, kind: CallKind, offset: u32) {
+        if !self.linked.insert((caller, offset, callee)) {
+            return;
+        }
+        self.edges.push(CallEdge {
+            caller: self.methods[caller as usize].clone(),
+            callee: self.methods[callee as usize].clone(),
+            kind,
+            offset,
+        });
+        self.reach(callee);
+    }
+
+    /// SSA form of a method with a body; `None` when it cannot be lifted.
+    fn lifted(&mut self, method: u32) -> Option<Arc<SsaMethod>> {
+        if let Some(ssa) = self.ssa.get(&method) {
+            return ssa.clone();
+        }
+        let ssa = self
+            .bodies
+            .get(&self.methods[method as usize])
+            .filter(|(_, body)| !body.bytecode.is_empty())
+            .and_then(|(class, body)| lift(&class.name, body).ok())
+            .map(Arc::new);
+        self.ssa.insert(method, ssa.clone());
+        ssa
+    }
+
+    /// Nodes of the receiver, parameters and return value of an analyzed method.
+    fn signature(&mut self, method: u32) -> Option<Signature> {
+        if let Some(signature) = self.signatures.get(&method) {
+            return signature.clone();
+        }
+        let signature = self.lifted(method).map(|ssa| {
+            let mut signature = Signature {
+                this: None,
+                parameters: Vec::new(),
+                returned: self.node(Node::Return(method)),
+            };
+            for (value, info) in ssa.values() {
+                match info.def {
+                    ValueDef::This => signature.this = self.value_node(method, &ssa, value),
+                    ValueDef::Parameter(index) => {
+                        if signature.parameters.len() <= index {
+                            signature.parameters.resize(index + 1, None);
+                        }
+                        signature.parameters[index] = self.value_node(method, &ssa, value);
+                    }
+                    _ => {}
+                }
+            }
+            signature
+        });
+        self.signatures.insert(method, signature.clone());
+        signature
+    }
+
+    /// Let the receiver and parameters of a method called by unseen code point to
+    /// external objects of their declared types.
+    fn external_arguments(&mut self, method: u32) {
+        let Some(ssa) = self.lifted(method) else {
+            return;
+        };
+        for (value, info) in ssa.values() {
+            if !matches!(info.def, ValueDef::This | ValueDef::Parameter(_)) {
+                continue;
+            }
+            if let (Some(node), Some(type_name)) =
+                (self.value_node(method, &ssa, value), type_name(&info.ty))
+            {
+                let object = self.external(&type_name);
+                self.add_objects(node, &[object]);
+            }
+        }
+    }
+
+    /// Add the constraints of a newly reachable method.
+    fn process(&mut self, method: u32) {
+        let Some(&(class, body)) = self.bodies.get(&self.methods[method as usize]) else {
+            return;
+        };
+        let Some(ssa) = self.lifted(method) else {
+            self.process_unlifted(method, body);
+            return;
+        };
+        for (value, info) in ssa.values() {
+            match &info.def {
+                ValueDef::Phi { operands, .. } => {
+                    let Some(phi) = self.value_node(method, &ssa, value) else {
+                        continue;
+                    };
+                    for operand in operands {
+                        if let Some(source) = self.value_node(method, &ssa, operand.value) {
+                            self.add_edge(source, phi);
+                        }
+                    }
+                }
+                ValueDef::CaughtException(_) => {
+                    if let (Some(node), Some(type_name)) =
+                        (self.value_node(method, &ssa, value), type_name(&info.ty))
+                    {
+                        let object = self.external(&type_name);
+                        self.add_objects(node, &[object]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let instructions: HashMap<u32, &Instruction> = body
+            .cfg
+            .blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .map(|inst| (inst.offset, inst))
+            .collect();
+        for ssa_inst in ssa.instructions() {
+            let Some(inst) = instructions.get(&ssa_inst.offset) else {
+                continue;
+            };
+            let offset = ssa_inst.offset;
+            let result = ssa_inst
+                .result
+                .and_then(|value| self.value_node(method, &ssa, value));
+            let result_type = ssa_inst
+                .result
+                .and_then(|value| type_name(&ssa.value(value).ty));
+            let operands: Vec<Option<u32>> = ssa_inst
+                .operands
+                .iter()
+                .map(|value| self.value_node(method, &ssa, *value))
+                .collect();
+            let operand = |index: usize| operands.get(index).copied().flatten();
+            match (inst.opcode, &inst.kind) {
+                (opcodes::NEW | opcodes::NEWARRAY | opcodes::ANEWARRAY, _) => {
+                    if let (Some(node), Some(type_name)) = (result, result_type) {
+                        let object = self.site(method, offset, type_name);
+                        self.add_objects(node, &[object]);
+                    }
+                }
+                (opcodes::MULTIANEWARRAY, _) => {
+                    let (Some(node), Some(mut type_name)) = (result, result_type) else {
+                        continue;
+                    };
+                    let mut object = self.site(method, offset, type_name.clone());
+                    self.add_objects(node, &[object]);
+                    for _ in 1..ssa_inst.operands.len() {
+                        type_name.remove(0);
+                        if !type_name.starts_with('[') {
+                            break;
+                        }
+                        let inner = self.site(method, offset, type_name.clone());
+                        let elements = self.node(Node::Field(object, ELEMENT));
+                        self.add_objects(elements, &[inner]);
+                        object = inner;
+                    }
+                }
+                (opcodes::LDC | opcodes::LDC_W, InstructionKind::ConstString(_)) => {
+                    if let Some(node) = result {
+                        let object = self.site(method, offset, "java/lang/String".to_string());
+                        self.add_objects(node, &[object]);
+                    }
+                }
+                (opcodes::GETFIELD, InstructionKind::Field(field)) => {
+                    if let (Some(target), Some(object)) = (result, operand(0)) {
+                        let load = Load {
+                            field: self.name(&field.name),
+                            target,
+                            external: result_type.map(|type_name| self.external(&type_name)),
+                        };
+                        self.add_load(object, load);
+                    }
+                }
+                (opcodes::PUTFIELD, InstructionKind::Field(field)) => {
+                    if let (Some(object), Some(source)) = (operand(0), operand(1)) {
+                        let store = Store {
+                            field: self.name(&field.name),
+                            source,
+                        };
+                        self.add_store(object, store);
+                    }
+                }
+                (opcodes::GETSTATIC, InstructionKind::Field(field)) => {
+                    let Some(target) = result else {
+                        continue;
+                    };
+                    let (node, is_parsed) = self.static_node(&field.owner, &field.name);
+                    self.add_edge(node, target);
+                    if !is_parsed && let Some(type_name) = result_type {
+                        let object = self.external(&type_name);
+                        self.add_objects(target, &[object]);
+                    }
+                }
+                (opcodes::PUTSTATIC, InstructionKind::Field(field)) => {
+                    if let Some(source) = operand(0) {
+                        let (node, _) = self.static_node(&field.owner, &field.name);
+                        self.add_edge(source, node);
+                    }
+                }
+                (opcodes::AALOAD, _) => {
+                    if let (Some(target), Some(array)) = (result, operand(0)) {
+                        let load = Load {
+                            field: ELEMENT,
+                            target,
+                            external: result_type.map(|type_name| self.external(&type_name)),
+                        };
+                        self.add_load(array, load);
+                    }
+                }
+                (opcodes::AASTORE, _) => {
+                    if let (Some(array), Some(source)) = (operand(0), operand(2)) {
+                        self.add_store(
+                            array,
+                            Store {
+                                field: ELEMENT,
+                                source,
+                            },
+                        );
+                    }
+                }
+                (opcodes::ARETURN, _) => {
+                    if let Some(source) = operand(0) {
+                        let returned = self.node(Node::Return(method));
+                        self.add_edge(source, returned);
+                    }
+                }
+                (_, InstructionKind::Invoke(call)) => {
+                    let external = result_type.map(|type_name| self.external(&type_name));
+                    self.call(method, call, &operands, result, external);
+                }
+                (opcodes::INVOKEDYNAMIC, _) => {
+                    if let (Some(node), Some(type_name)) = (result, result_type) {
+                        let object = self.external(&type_name);
+                        self.add_objects(node, &[object]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Reflection and JDK callbacks: reflectively created receivers are external,
+        // and callbacks dispatch on an external object of the callback type.
+        let implicit = implicit_calls(class, body, self.hierarchy);
+        for call in &implicit.calls {
+            match call.kind {
+                CallKind::Static | CallKind::Special => {
+                    if let Some(target) =
+                        self.resolver
+                            .resolve(&call.owner, &call.name, &call.descriptor)
+                    {
+                        let callee = self.method_id(target);
+                        self.link(method, callee, call.kind, call.offset);
+                        self.external_arguments(callee);
+                    }
+                }
+                CallKind::Virtual | CallKind::Interface => {
+                    let receiver = self.external(&call.owner);
+                    let site = self.virtual_site(method, call, Vec::new(), None, None);
+                    self.dispatch(site, receiver);
+                }
+            }
+        }
+    }
+
+    /// Link the calls of a method that cannot be lifted to SSA as CHA would, and
+    /// let their results be external.
+    fn process_unlifted(&mut self, method: u32, body: &Method) {
+        for call in &body.calls {
+            let targets = match call.kind {
+                CallKind::Static | CallKind::Special => self
+                    .resolver
+                    .resolve(&call.owner, &call.name, &call.descriptor)
+                    .into_iter()
+                    .collect(),
+                CallKind::Virtual | CallKind::Interface => {
+                    self.resolver
+                        .dispatch(&call.owner, &call.name, &call.descriptor)
+                }
+            };
+            for target in targets {
+                let callee = self.method_id(target);
+                self.link(method, callee, call.kind, call.offset);
+                self.external_arguments(callee);
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        caller: u32,
+        call: &CallSite,
+        operands: &[Option<u32>],
+        result: Option<u32>,
+        external: Option<u32>,
+    ) {
+        let (receiver, arguments) = match call.kind {
+            CallKind::Static => (None, operands),
+            _ => (
+                operands.first().copied().flatten(),
+                operands.get(1..).unwrap_or_default(),
+            ),
+        };
+        match call.kind {
+            CallKind::Static | CallKind::Special => {
+                let callee = self
+                    .resolver
+                    .resolve(&call.owner, &call.name, &call.descriptor)
+                    .map(|target| self.method_id(target));
+                let signature = callee.and_then(|callee| {
+                    self.link(caller, callee, call.kind, call.offset);
+                    self.signature(callee)
+                });
+                let Some(signature) = signature else {
+                    if let (Some(result), Some(external)) = (result, external) {
+                        self.add_objects(result, &[external]);
+                    }
+                    return;
+                };
+                if let (Some(receiver), Some(this)) = (receiver, signature.this) {
+                    self.add_edge(receiver, this);
+                }
+                self.bind(&signature, arguments, result);
+            }
+            CallKind::Virtual | CallKind::Interface => {
+                let Some(receiver) = receiver else {
+                    return;
+                };
+                let site = self.virtual_site(caller, call, arguments.to_vec(), result, external);
+                self.receivers[receiver as usize].push(site);
+                let objects: Vec<u32> = self.sets[receiver as usize].iter().copied().collect();
+                for object in objects {
+                    self.dispatch(site, object);
+                }
+            }
+        }
+    }
+
+    fn virtual_site(
+        &mut self,
+        caller: u32,
+        call: &CallSite,
+        arguments: Vec<Option<u32>>,
+        result: Option<u32>,
+        external_result: Option<u32>,
+    ) -> usize {
+        let owner_type = self.type_id(&call.owner);
+        let signature = self.name(&format!("{}{}", call.name, call.descriptor));
+        self.sites.push(VirtualSite {
+            caller,
+            owner_type,
+            signature,
+            owner: call.owner.clone(),
+            name: call.name.clone(),
+            descriptor: call.descriptor.clone(),
+            kind: call.kind,
+            offset: call.offset,
+            arguments,
+            result,
+            external_result,
+        });
+        self.sites.len() - 1
+    }
+
+    /// Connect call arguments to parameters and the return value to the result.
+    fn bind(&mut self, signature: &Signature, arguments: &[Option<u32>], result: Option<u32>) {
+        for (argument, parameter) in arguments.iter().zip(&signature.parameters) {
+            if let (Some(argument), Some(parameter)) = (argument, parameter) {
+                self.add_edge(*argument, *parameter);
+            }
+        }
+        if let Some(result) = result {
+            self.add_edge(signature.returned, result);
+        }
+    }
+
+    /// Call the method `object` selects at a virtual site. External objects select
+    /// every override in their type, or in the site's owner when that is narrower.
+    fn dispatch(&mut self, site: usize, object: u32) {
+        let owner_type = self.sites[site].owner_type;
+        if !self.is_compatible(object, owner_type) {
+            return;
+        }
+        let is_external = matches!(self.objects[object as usize], HeapObject::External { .. });
+        let key = (
+            owner_type,
+            self.sites[site].signature,
+            self.object_types[object as usize],
+            is_external,
+        );
+        let targets = match self.targets.get(&key) {
+            Some(targets) => targets.clone(),
+            None => {
+                let targets = self.targets_of(site, object);
+                self.targets.insert(key, targets.clone());
+                targets
+            }
+        };
+        let mut analyzed = false;
+        for target in targets {
+            let callee = self.method_id(target);
+            let (caller, kind, offset) = {
+                let site = &self.sites[site];
+                (site.caller, site.kind, site.offset)
+            };
+            self.link(caller, callee, kind, offset);
+            let Some(signature) = self.signature(callee) else {
+                continue;
+            };
+            analyzed = true;
+            if let Some(this) = signature.this {
+                self.add_objects(this, &[object]);
+            }
+            if self.bound.insert((site, callee)) {
+                let arguments = self.sites[site].arguments.clone();
+                let result = self.sites[site].result;
+                self.bind(&signature, &arguments, result);
+            }
+        }
+        if !analyzed && self.unresolved.insert(site) {
+            let site = &self.sites[site];
+            if let (Some(result), Some(external)) = (site.result, site.external_result) {
+                self.add_objects(result, &[external]);
+            }
+        }
+    }
+
+    fn targets_of(&mut self, site: usize, object: u32) -> Vec<Arc<MethodId>> {
+        let site = &self.sites[site];
+        match &self.objects[object as usize] {
+            HeapObject::Site { type_name, .. } => self
+                .resolver
+                .select(type_name, &site.name, &site.descriptor)
+                .into_iter()
+                .collect(),
+            HeapObject::External { type_name } => {
+                let narrower = self
+                    .hierarchy
+                    .class(type_name)
+                    .map(|class| class.name.as_str())
+                    .filter(|type_name| self.hierarchy.is_subtype_of(type_name, &[&site.owner]));
+                self.resolver.dispatch(
+                    narrower.unwrap_or(&site.owner),
+                    &site.name,
+                    &site.descriptor,
+                )
+            }
+        }
+    }
+
+    /// Node of a static field, keyed by its declaring class, and whether that class
+    /// was parsed; fields of other classes may hold objects the analysis never sees.
+    fn static_node(&mut self, owner: &str, name: &str) -> (u32, bool) {
+        let declaring = self.hierarchy.class(owner).and_then(|class| {
+            let owner = class.name.as_str();
+            self.hierarchy
+                .superclass_chain(owner)
+                .into_iter()
+                .chain(self.hierarchy.superinterfaces(owner))
+                .find(|candidate| {
+                    self.hierarchy
+                        .class(candidate)
+                        .is_some_and(|class| class.fields.iter().any(|field| field.name == name))
+                })
+        });
+        let field = self.name(&format!("{}.{}", declaring.unwrap_or(owner), name));
+        (self.node(Node::Static(field)), declaring.is_some())
+    }
+}
+
+/// Internal class name, or descriptor for arrays, of a reference type.
+fn type_name(ty: &ValueType) -> Option<String> {
+    let ValueType::Reference(ReferenceType { base, dimensions }) = ty else {
+        return None;
+    };
+    let dimensions = "[".repeat(*dimensions);
+    Some(match base {
+        BaseType::Class(name) if dimensions.is_empty() => name.clone(),
+        BaseType::Class(name) => format!("{dimensions}L{name};"),
+        BaseType::Primitive(descriptor) => format!("{dimensions}{descriptor}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::{CallGraphAlgorithm, CallGraphOptions, build_call_graph_with_timings};
+    use crate::test_harness::compile_sample;
+
+    const SAMPLE: &str = r#"
+package com.example;
+class Box {
+    Object value;
+}
+public class Sample {
+    static Object cached;
+
+    public static Object field() {
+        Box first = new Box();
+        Box second = new Box();
+        first.value = "a";
+        second.value = new StringBuilder();
+        return first.value;
+    }
+
+    public static Object array() {
+        Object[] items = new Object[2];
+        items[0] = new StringBuilder();
+        return items[1];
+    }
+
+    private static Object identity(Object value) {
+        return value;
+    }
+
+    public static Object passThrough() {
+        return identity(new java.util.ArrayList<String>());
+    }
+
+    public static Object viaStatic() {
+        cached = new java.util.HashMap<String, String>();
+        return cached;
+    }
+}
+"#;
+
+    fn returned_types(points_to: &PointsTo, name: &str) -> Vec<String> {
+        let method = MethodId {
+            class_name: "com/example/Sample".to_string(),
+            name: name.to_string(),
+            descriptor: "()Ljava/lang/Object;".to_string(),
+        };
+        points_to
+            .returned(&method)
+            .into_iter()
+            .map(|object| object.type_name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn points_to_tracks_fields_arrays_calls_and_static_fields() {
+        let classes = compile_sample(SAMPLE);
+        let points_to = analyze_points_to(&classes, &[], &[], |_| true, Duration::from_secs(60))
+            .expect("analysis within budget");
+
+        assert_eq!(
+            vec!["java/lang/String"],
+            returned_types(&points_to, "field")
+        );
+        assert_eq!(
+            vec!["java/lang/StringBuilder"],
+            returned_types(&points_to, "array")
+        );
+        assert_eq!(
+            vec!["java/util/ArrayList"],
+            returned_types(&points_to, "passThrough")
+        );
+        assert_eq!(
+            vec!["java/util/HashMap"],
+            returned_types(&points_to, "viaStatic")
+        );
+        let cached: Vec<&str> = points_to
+            .static_field("com/example/Sample", "cached")
+            .into_iter()
+            .map(HeapObject::type_name)
+            .collect();
+        assert_eq!(vec!["java/util/HashMap"], cached);
+    }
+
+    #[test]
+    fn points_to_gives_up_when_budget_is_exhausted() {
+        let classes = compile_sample(SAMPLE);
+        assert!(analyze_points_to(&classes, &[], &[], |_| true, Duration::ZERO).is_none());
+
+        let options = CallGraphOptions {
+            algorithm: CallGraphAlgorithm::Pta,
+            points_to_budget: Duration::ZERO,
+            ..CallGraphOptions::default()
+        };
+        let (graph, points_to, _) = build_call_graph_with_timings(&classes, &options, |_| true);
+        assert_eq!(CallGraphAlgorithm::Cha, graph.algorithm);
+        assert!(points_to.is_none());
+        assert!(!graph.edges.is_empty());
+    }
+}
@@ -1,15 +1,16 @@
-use std::collections::BTreeMap;
-use std::str::FromStr;
+use std::borrow::Cow;
 
-use anyhow::{Context, Result};
-use jdescriptor::{MethodDescriptor, TypeDescriptor};
-use serde_sarif::sarif::Result as SarifResult;
+use anyhow::Result;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
-use crate::descriptor::method_param_count;
+use crate::dataflow::{self, Analysis};
 use crate::engine::AnalysisContext;
-use crate::ir::Method;
+use crate::interpreter::{Frame, Interpreter, NoFacts};
+use crate::ir::{Class, Instruction, Method};
 use crate::opcodes;
-use crate::rules::{Rule, RuleMetadata, method_location_with_line, result_message};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
 
 /// Rule that flags array comparisons using == or equals().
 pub(crate) struct ArrayEqualsRule;
@@ -17,39 +18,47 @@ pub(crate) struct ArrayEqualsRule;
 impl Rule for ArrayEqualsRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "ARRAY_EQUALS",
-            name: "Array equals",
-            description: "Array comparisons using == or equals()",
+            id: "ARRAY_EQUALS".into(),
+            name: "Array equals".into(),
+            description: "Array comparisons using == or equals()".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/ARRAY_EQUALS.md").into(),
+            cwe: &[595],
+            precision: Precision::High,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut results = Vec::new();
-        for class in &context.classes {
-            if !context.is_analysis_target_class(class) {
-                continue;
-            }
-            for method in &class.methods {
-                if method.bytecode.is_empty() {
-                    continue;
-                }
-                let artifact_uri = context.class_artifact_uri(class);
-                results.extend(analyze_method(
-                    &class.name,
-                    method,
-                    artifact_uri.as_deref(),
-                )?);
-            }
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for ArrayEqualsRule {
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        if method.bytecode.is_empty() {
+            return Ok(());
         }
-        Ok(results)
+        let artifact_uri = context.class_artifact_uri(class);
+        results.extend(analyze_method(
+            &class.name,
+            method,
+            artifact_uri.as_deref(),
+        )?);
+        Ok(())
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum ValueKind {
-    Unknown,
-    Array,
-    NonArray,
+/// Forward analysis tracking the declared types of values.
+struct ArrayTypeAnalysis<'a> {
+    interpreter: Interpreter<'a>,
 }
 
 fn analyze_method(
@@ -57,188 +66,81 @@ fn analyze_method(
     method: &Method,
     artifact_uri: Option<&str>,
 ) -> Result<Vec<SarifResult>> {
-    let mut results = Vec::new();
-    let mut callsites = BTreeMap::new();
-    for call in &method.calls {
-        callsites.insert(call.offset, call);
-    }
+    let analysis = ArrayTypeAnalysis {
+        interpreter: Interpreter::new(class_name, method),
+    };
+    let flow = dataflow::solve(method, &analysis)?;
 
-    let mut locals = initial_locals(method)?;
-    let mut stack: Vec<ValueKind> = Vec::new();
-    let mut offset = 0usize;
-    while offset < method.bytecode.len() {
-        let opcode = method.bytecode[offset];
-        match opcode {
-            opcodes::ACONST_NULL => stack.push(ValueKind::Unknown),
-            opcodes::ALOAD => {
-                let index = method.bytecode.get(offset + 1).copied().unwrap_or(0) as usize;
-                ensure_local(&mut locals, index);
-                stack.push(locals[index]);
-            }
-            opcodes::ALOAD_0 | opcodes::ALOAD_1 | opcodes::ALOAD_2 | opcodes::ALOAD_3 => {
-                let index = (opcode - opcodes::ALOAD_0) as usize;
-                ensure_local(&mut locals, index);
-                stack.push(locals[index]);
-            }
-            opcodes::ASTORE => {
-                let index = method.bytecode.get(offset + 1).copied().unwrap_or(0) as usize;
-                ensure_local(&mut locals, index);
-                let value = stack.pop().unwrap_or(ValueKind::Unknown);
-                locals[index] = value;
-            }
-            opcodes::ASTORE_0 | opcodes::ASTORE_1 | opcodes::ASTORE_2 | opcodes::ASTORE_3 => {
-                let index = (opcode - opcodes::ASTORE_0) as usize;
-                ensure_local(&mut locals, index);
-                let value = stack.pop().unwrap_or(ValueKind::Unknown);
-                locals[index] = value;
-            }
-            opcodes::NEWARRAY | opcodes::ANEWARRAY => {
-                stack.pop();
-                stack.push(ValueKind::Array);
-            }
-            opcodes::MULTIANEWARRAY => {
-                let dims = method.bytecode.get(offset + 3).copied().unwrap_or(0);
-                for _ in 0..dims {
-                    stack.pop();
-                }
-                stack.push(ValueKind::Array);
-            }
-            opcodes::NEW => {
-                stack.push(ValueKind::NonArray);
-            }
-            opcodes::LDC | opcodes::LDC_W | opcodes::LDC2_W => {
-                stack.push(ValueKind::NonArray);
-            }
-            opcodes::DUP => {
-                if let Some(value) = stack.last().copied() {
-                    stack.push(value);
-                }
-            }
-            opcodes::POP => {
-                stack.pop();
-            }
+    let mut results = Vec::new();
+    flow.replay(method, &analysis, |_, inst, frame| {
+        let is_array = |depth: usize| {
+            frame
+                .peek(depth)
+                .is_some_and(|slot| slot.ty.array_dimensions() > 0)
+        };
+        let text = match inst.opcode {
             opcodes::IF_ACMPEQ | opcodes::IF_ACMPNE => {
-                let right = stack.pop().unwrap_or(ValueKind::Unknown);
-                let left = stack.pop().unwrap_or(ValueKind::Unknown);
-                if left == ValueKind::Array && right == ValueKind::Array {
-                    let message = result_message(format!(
-                        "Array comparison uses reference equality: {}.{}{}",
-                        class_name, method.name, method.descriptor
-                    ));
-                    let line = method.line_for_offset(offset as u32);
-                    let location = method_location_with_line(
-                        class_name,
-                        &method.name,
-                        &method.descriptor,
-                        artifact_uri,
-                        line,
-                    );
-                    results.push(
-                        SarifResult::builder()
-                            .message(message)
-                            .locations(vec![location])
-                            .build(),
-                    );
+                if !is_array(0) || !is_array(1) {
+                    return Ok(());
                 }
+                "Array comparison uses reference equality"
             }
             opcodes::INVOKEVIRTUAL | opcodes::INVOKESPECIAL | opcodes::INVOKEINTERFACE => {
-                if let Some(call) = callsites.get(&(offset as u32)) {
-                    let arg_count = method_param_count(&call.descriptor)?;
-                    for _ in 0..arg_count {
-                        stack.pop();
-                    }
-                    let receiver = stack.pop().unwrap_or(ValueKind::Unknown);
-                    if call.name == "equals"
-                        && call.descriptor == "(Ljava/lang/Object;)Z"
-                        && receiver == ValueKind::Array
-                    {
-                        let message = result_message(format!(
-                            "Array comparison uses equals(): {}.{}{}",
-                            class_name, method.name, method.descriptor
-                        ));
-                        let line = method.line_for_offset(offset as u32);
-                        let location = method_location_with_line(
-                            class_name,
-                            &method.name,
-                            &method.descriptor,
-                            artifact_uri,
-                            line,
-                        );
-                        results.push(
-                            SarifResult::builder()
-                                .message(message)
-                                .locations(vec![location])
-                                .build(),
-                        );
-                    }
-                    if is_reference_return(&call.descriptor)? {
-                        stack.push(return_kind(&call.descriptor)?);
-                    }
+                let Some(call) = analysis.interpreter.call_site(inst) else {
+                    return Ok(());
+                };
+                if call.name != "equals" || call.descriptor != "(Ljava/lang/Object;)Z" {
+                    return Ok(());
                 }
-            }
-            opcodes::INVOKESTATIC => {
-                if let Some(call) = callsites.get(&(offset as u32)) {
-                    let arg_count = method_param_count(&call.descriptor)?;
-                    for _ in 0..arg_count {
-                        stack.pop();
-                    }
-                    if is_reference_return(&call.descriptor)? {
-                        stack.push(return_kind(&call.descriptor)?);
-                    }
+                if !is_array(1) {
+                    return Ok(());
                 }
+                "Array comparison uses equals()"
             }
-            _ => {}
-        }
-        let length = crate::scan::opcode_length(&method.bytecode, offset)?;
-        offset += length;
-    }
+            _ => return Ok(()),
+        };
+        let message = result_message(format!(
+            "{}: {}.{}{}",
+            text, class_name, method.name, method.descriptor
+        ));
+        let line = method.line_for_offset(inst.offset);
+        let location = method_location_with_line(
+            class_name,
+            &method.name,
+            &method.descriptor,
+            artifact_uri,
+            line,
+        );
+        results.push(
+            SarifResult::builder()
+                .message(message)
+                .locations(vec![location])
+                .build(),
+        );
+        Ok(())
+    })?;
 
     Ok(results)
 }
 
-fn initial_locals(method: &Method) -> Result<Vec<ValueKind>> {
-    let mut locals = Vec::new();
-    if !method.access.is_static {
-        locals.push(ValueKind::NonArray);
-    }
-    let descriptor =
-        MethodDescriptor::from_str(&method.descriptor).context("parse method descriptor")?;
-    for param in descriptor.parameter_types() {
-        let is_array = matches!(param, TypeDescriptor::Array(_, _));
-        locals.push(if is_array {
-            ValueKind::Array
-        } else {
-            ValueKind::NonArray
-        });
-        if matches!(param, TypeDescriptor::Long | TypeDescriptor::Double) {
-            locals.push(ValueKind::NonArray);
-        }
+impl Analysis for ArrayTypeAnalysis<'_> {
+    type Domain = Frame<()>;
+
+    fn boundary_state(&self) -> Result<Frame<()>> {
+        self.interpreter.entry_frame(&NoFacts)
     }
-    Ok(locals)
-}
 
-fn ensure_local(locals: &mut Vec<ValueKind>, index: usize) {
-    if index >= locals.len() {
-        locals.resize(index + 1, ValueKind::Unknown);
+    fn join(&self, left: &Frame<()>, right: &Frame<()>) -> Frame<()> {
+        left.join(right)
     }
-}
 
-fn is_reference_return(descriptor: &str) -> Result<bool> {
-    let descriptor = MethodDescriptor::from_str(descriptor).context("parse call descriptor")?;
-    Ok(matches!(
-        descriptor.return_type(),
-        TypeDescriptor::Object(_) | TypeDescriptor::Array(_, _)
-    ))
-}
+    fn transfer(&self, frame: &mut Frame<()>, inst: &Instruction) -> Result<()> {
+        self.interpreter.execute(frame, inst, &NoFacts)
+    }
 
-fn return_kind(descriptor: &str) -> Result<ValueKind> {
-    let descriptor = MethodDescriptor::from_str(descriptor).context("parse call descriptor")?;
-    let kind = match descriptor.return_type() {
-        TypeDescriptor::Array(_, _) => ValueKind::Array,
-        TypeDescriptor::Object(_) => ValueKind::NonArray,
-        _ => ValueKind::Unknown,
-    };
-    Ok(kind)
+    fn exception_state(&self, frame: &Frame<()>) -> Frame<()> {
+        frame.exception_frame(())
+    }
 }
 
 #[cfg(test)]
@@ -335,6 +237,25 @@ public class Sample {
         assert!(messages.is_empty());
     }
 
+    #[test]
+    fn array_equals_ignores_value_merged_from_branches() {
+        let sources = vec![SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: r#"
+package com.example;
+public class Sample {
+    public boolean same(String[] left, Object[] right, boolean flag) {
+        Object value = flag ? "text" : left;
+        return value == right;
+    }
+}
+"#
+            .to_string(),
+        }];
+        let messages = analyze_sources(sources);
+        assert!(messages.is_empty());
+    }
+
     #[test]
     fn array_equals_ignores_null_comparison() {
         let sources = vec![SourceFile {
@@ -0,0 +1,505 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
+
+use crate::callgraph::MethodPattern;
+use crate::class_filter::ClassPattern;
+use crate::engine::AnalysisContext;
+use crate::ir::{Class, InstructionKind, Method};
+use crate::rules::{
+    Precision, RULE_CATEGORIES, Rule, RuleMetadata, RuleVisitor, class_location,
+    method_location_with_line, result_message,
+};
+
+/// Rule declared in a `[[custom-rules]]` table of the configuration file.
+///
+/// Class names are `ClassPattern`s: globs with `/` or `.` separators, or regular
+/// expressions. A class is checked when it meets every condition given (`classes`,
+/// `extends`, `annotated-with`, `not-annotated-with`); each match of a matcher in it
+/// is a result. Without matchers, every class meeting the conditions is a result.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CustomRuleConfig {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Result message, followed by what matched and where.
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Default level of the rule's results.
+    #[serde(default = "default_severity")]
+    pub severity: ResultLevel,
+    /// Categories from `RULE_CATEGORIES`; `style` when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Names of the classes to check.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<String>,
+    /// Supertypes, direct or indirect, of the classes to check.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+    /// Annotations the classes to check carry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotated_with: Vec<String>,
+    /// Annotations the classes to check lack.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_annotated_with: Vec<String>,
+    /// Called methods as `Class.method` or `Class.method(descriptor)`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<String>,
+    /// Classes referenced from the constant pool.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Fields read or written, as `Class.field`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    /// Regular expressions searched for in string literals.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strings: Vec<String>,
+    /// Annotations on methods.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub method_annotations: Vec<String>,
+}
+
+fn default_severity() -> ResultLevel {
+    ResultLevel::Warning
+}
+
+/// Rule compiled from a `CustomRuleConfig`.
+pub(crate) struct CustomRule {
+    metadata: RuleMetadata,
+    message: String,
+    classes: Vec<ClassPattern>,
+    extends: Vec<ClassPattern>,
+    annotated_with: Vec<ClassPattern>,
+    not_annotated_with: Vec<ClassPattern>,
+    calls: Vec<MethodPattern>,
+    references: Vec<ClassPattern>,
+    fields: Vec<(ClassPattern, ClassPattern)>,
+    strings: Vec<Regex>,
+    method_annotations: Vec<ClassPattern>,
+}
+
+impl CustomRule {
+    pub(crate) fn new(config: &CustomRuleConfig) -> Result<Self> {
+        Self::compile(config).with_context(|| format!("custom rule {}", config.id))
+    }
+
+    fn compile(config: &CustomRuleConfig) -> Result<Self> {
+        let id = &config.id;
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        {
+            anyhow::bail!("rule IDs use upper-case letters, digits and underscores");
+        }
+        let tags = if config.tags.is_empty() {
+            vec!["style"]
+        } else {
+            config
+                .tags
+                .iter()
+                .map(|tag| {
+                    RULE_CATEGORIES
+                        .iter()
+                        .copied()
+                        .find(|category| category == tag)
+                        .with_context(|| {
+                            format!(
+                                "unknown category {tag}; categories: {}",
+                                RULE_CATEGORIES.join(", ")
+                            )
+                        })
+                })
+                .collect::<Result<_>>()?
+        };
+        let calls = config
+            .calls
+            .iter()
+            .map(|spec| MethodPattern::parse(spec))
+            .collect::<Result<_>>()?;
+        let fields = config
+            .fields
+            .iter()
+            .map(|spec| {
+                let (owner, name) = spec
+                    .rsplit_once('.')
+                    .filter(|(owner, name)| !owner.is_empty() && !name.is_empty())
+                    .with_context(|| format!("expected Class.field, got {spec}"))?;
+                Ok((ClassPattern::parse(owner)?, ClassPattern::parse(name)?))
+            })
+            .collect::<Result<_>>()?;
+        let strings = config
+            .strings
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("invalid regular expression {pattern}"))
+            })
+            .collect::<Result<_>>()?;
+        let rule = Self {
+            metadata: metadata(config, tags),
+            message: config.message.clone(),
+            classes: class_patterns(&config.classes)?,
+            extends: class_patterns(&config.extends)?,
+            annotated_with: class_patterns(&config.annotated_with)?,
+            not_annotated_with: class_patterns(&config.not_annotated_with)?,
+            calls,
+            references: class_patterns(&config.references)?,
+            fields,
+            strings,
+            method_annotations: class_patterns(&config.method_annotations)?,
+        };
+        if rule.has_no_conditions() && !rule.has_matchers() {
+            anyhow::bail!("a custom rule needs a class condition or a matcher");
+        }
+        Ok(rule)
+    }
+
+    fn has_no_conditions(&self) -> bool {
+        self.classes.is_empty()
+            && self.extends.is_empty()
+            && self.annotated_with.is_empty()
+            && self.not_annotated_with.is_empty()
+    }
+
+    fn has_matchers(&self) -> bool {
+        !self.calls.is_empty()
+            || !self.references.is_empty()
+            || !self.fields.is_empty()
+            || !self.strings.is_empty()
+            || !self.method_annotations.is_empty()
+    }
+
+    fn is_checked(&self, context: &AnalysisContext, class: &Class) -> bool {
+        let annotated = |patterns: &[ClassPattern]| {
+            class
+                .annotations
+                .iter()
+                .any(|annotation| matches_any(patterns, annotation))
+        };
+        (self.classes.is_empty() || matches_any(&self.classes, &class.name))
+            && (self.extends.is_empty() || extends_any(context, class, &self.extends))
+            && (self.annotated_with.is_empty() || annotated(&self.annotated_with))
+            && !annotated(&self.not_annotated_with)
+    }
+
+    fn result(&self, detail: String, location: serde_sarif::sarif::Location) -> SarifResult {
+        SarifResult::builder()
+            .message(result_message(format!("{}: {detail}", self.message)))
+            .locations(vec![location])
+            .build()
+    }
+
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) {
+        let artifact_uri = context.class_artifact_uri(class);
+        let location = |offset: Option<u32>| {
+            method_location_with_line(
+                &class.name,
+                &method.name,
+                &method.descriptor,
+                artifact_uri.as_deref(),
+                offset.and_then(|offset| method.line_for_offset(offset)),
+            )
+        };
+        for annotation in &method.annotations {
+            if matches_any(&self.method_annotations, annotation) {
+                results.push(self.result(format!("annotated with {annotation}"), location(None)));
+            }
+        }
+        for block in &method.cfg.blocks {
+            for instruction in &block.instructions {
+                let detail = match &instruction.kind {
+                    InstructionKind::Invoke(call)
+                        if self.calls.iter().any(|pattern| {
+                            pattern.matches(&call.owner, &call.name, &call.descriptor)
+                        }) =>
+                    {
+                        format!("calls {}.{}{}", call.owner, call.name, call.descriptor)
+                    }
+                    InstructionKind::Field(field)
+                        if self.fields.iter().any(|(owner, name)| {
+                            owner.is_match(&field.owner) && name.is_match(&field.name)
+                        }) =>
+                    {
+                        format!("accesses {}.{}", field.owner, field.name)
+                    }
+                    InstructionKind::ConstString(value)
+                        if self.strings.iter().any(|regex| regex.is_match(value)) =>
+                    {
+                        format!("uses string {value:?}")
+                    }
+                    _ => continue,
+                };
+                results.push(self.result(detail, location(Some(instruction.offset))));
+            }
+        }
+    }
+}
+
+impl Rule for CustomRule {
+    fn metadata(&self) -> RuleMetadata {
+        self.metadata.clone()
+    }
+
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for CustomRule {
+    fn visit_class(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        if !self.is_checked(context, class) {
+            return Ok(());
+        }
+        if !self.has_matchers() {
+            results.push(self.result(format!("class {}", class.name), class_location(&class.name)));
+            return Ok(());
+        }
+        for reference in &class.referenced_classes {
+            if matches_any(&self.references, reference) {
+                results.push(self.result(
+                    format!("references {reference}"),
+                    class_location(&class.name),
+                ));
+            }
+        }
+        for method in &class.methods {
+            self.visit_method(context, class, method, results);
+        }
+        Ok(())
+    }
+}
+
+fn metadata(config: &CustomRuleConfig, tags: Vec<&'static str>) -> RuleMetadata {
+    let name = config.name.clone().unwrap_or_else(|| config.id.clone());
+    let description = config
+        .description
+        .clone()
+        .unwrap_or_else(|| config.message.clone());
+    let help = format!("# {}: {name}\n\n{description}\n", config.id);
+    RuleMetadata {
+        id: config.id.clone().into(),
+        name: name.into(),
+        description: description.into(),
+        tags: tags.into(),
+        default_level: config.severity,
+        help: help.into(),
+        cwe: &[],
+        precision: Precision::High,
+        security_severity: None,
+    }
+}
+
+fn class_patterns(specs: &[String]) -> Result<Vec<ClassPattern>> {
+    specs.iter().map(|spec| ClassPattern::parse(spec)).collect()
+}
+
+fn matches_any(patterns: &[ClassPattern], name: &str) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(name))
+}
+
+/// Whether a superclass or interface of `class`, direct or inherited, matches `patterns`.
+/// Supertypes outside the scanned classes match by name but are not followed.
+fn extends_any(context: &AnalysisContext, class: &Class, patterns: &[ClassPattern]) -> bool {
+    let mut pending: Vec<&str> = class
+        .super_name
+        .iter()
+        .chain(&class.interfaces)
+        .map(String::as_str)
+        .collect();
+    let mut seen = std::collections::BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if !seen.insert(name) {
+            continue;
+        }
+        if matches_any(patterns, name) {
+            return true;
+        }
+        if let Some(supertype) = context.class(name) {
+            pending.extend(
+                supertype
+                    .super_name
+                    .iter()
+                    .chain(&supertype.interfaces)
+                    .map(String::as_str),
+            );
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::{Engine, EngineOptions, build_context};
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn rule(toml: &str) -> Result<CustomRule> {
+        CustomRule::new(&toml::from_str(toml).expect("parse custom rule"))
+    }
+
+    fn messages(rule: &CustomRule, sources: &[SourceFile]) -> Vec<String> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let output = harness
+            .compile(Language::Java, sources, &[])
+            .expect("compile sources");
+        let scan = scan_inputs(output.classes_dir(), &[]).expect("scan classes");
+        let classpath = resolve_classpath(&scan.classes).expect("classpath build");
+        let context = build_context(scan.classes, classpath, &scan.artifacts);
+        rule.run(&context)
+            .expect("run custom rule")
+            .results
+            .into_iter()
+            .filter_map(|result| result.message.text)
+            .collect()
+    }
+
+    fn sources() -> Vec<SourceFile> {
+        let source = |path: &str, contents: &str| SourceFile {
+            path: path.to_string(),
+            contents: contents.to_string(),
+        };
+        vec![
+            source(
+                "example/Internal.java",
+                "package example;\n@interface Internal {}\n",
+            ),
+            source(
+                "example/Repository.java",
+                "package example;\npublic abstract class Repository {}\n",
+            ),
+            source(
+                "example/UserRepository.java",
+                r#"
+package example;
+
+@Internal
+public class UserRepository extends Repository {
+    @Deprecated
+    public long connect() {
+        System.out.println("jdbc:mysql://localhost/users");
+        return System.currentTimeMillis();
+    }
+}
+"#,
+            ),
+            source(
+                "example/Service.java",
+                r#"
+package example;
+
+public class Service {
+    public long now() {
+        return System.currentTimeMillis();
+    }
+}
+"#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn custom_rule_reports_matches_in_checked_classes() {
+        let rule = rule(
+            r#"
+id = "REPOSITORY_API"
+message = "Repositories must not use this API"
+extends = ["example.Repository"]
+annotated-with = ["example.Internal"]
+calls = ["java.lang.System.currentTimeMillis"]
+fields = ["java.lang.System.out"]
+strings = ["^jdbc:"]
+method-annotations = ["java.lang.Deprecated"]
+"#,
+        )
+        .expect("compile rule");
+
+        let mut messages = messages(&rule, &sources());
+        messages.sort();
+
+        assert_eq!(
+            vec![
+                "Repositories must not use this API: accesses java/lang/System.out",
+                "Repositories must not use this API: annotated with java/lang/Deprecated",
+                "Repositories must not use this API: calls java/lang/System.currentTimeMillis()J",
+                "Repositories must not use this API: uses string \"jdbc:mysql://localhost/users\"",
+            ],
+            messages
+        );
+    }
+
+    #[test]
+    fn custom_rule_without_matchers_reports_classes() {
+        let rule = rule(
+            r#"
+id = "UNMARKED_CLASS"
+message = "Classes in example must be marked @Internal"
+classes = ["example.*"]
+not-annotated-with = ["example.Internal"]
+"#,
+        )
+        .expect("compile rule");
+
+        let mut messages = messages(&rule, &sources());
+        messages.sort();
+
+        assert_eq!(
+            vec![
+                "Classes in example must be marked @Internal: class example/Internal",
+                "Classes in example must be marked @Internal: class example/Repository",
+                "Classes in example must be marked @Internal: class example/Service",
+            ],
+            messages
+        );
+    }
+
+    #[test]
+    fn custom_rules_are_validated_and_registered_with_the_engine() {
+        let error = |toml: &str| format!("{:#}", rule(toml).err().expect("invalid rule"));
+        assert!(error("id = \"lower\"\nmessage = \"m\"\nclasses = [\"*\"]").contains("upper-case"));
+        assert!(error("id = \"EMPTY\"\nmessage = \"m\"").contains("condition or a matcher"));
+        assert!(error("id = \"BAD\"\nmessage = \"m\"\nstrings = [\"(\"]").contains("regular"));
+        assert!(
+            error("id = \"BAD\"\nmessage = \"m\"\nclasses = [\"*\"]\ntags = [\"x\"]")
+                .contains("unknown category x")
+        );
+
+        let options = EngineOptions {
+            custom_rules: vec![
+                toml::from_str(
+                    "id = \"NO_DATE\"\nmessage = \"m\"\nreferences = [\"java.util.Date\"]",
+                )
+                .expect("parse custom rule"),
+            ],
+            ..EngineOptions::default()
+        };
+        let metadata = Engine::with_options(options.clone())
+            .expect("engine with custom rule")
+            .rule_metadata();
+        let rule = metadata
+            .iter()
+            .find(|rule| rule.id == "NO_DATE")
+            .expect("custom rule registered");
+        assert_eq!(["style"], *rule.tags);
+        assert_eq!(ResultLevel::Warning, rule.default_level);
+        let mut duplicate = options;
+        duplicate.custom_rules[0].id = "NULLNESS".to_string();
+        assert!(Engine::with_options(duplicate).is_err());
+    }
+}
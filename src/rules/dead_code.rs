@@ -1,121 +1,360 @@
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write as _;
 use std::sync::Arc;
 
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
-use crate::callgraph::MethodId;
+use crate::callgraph::{CallEdge, MethodId, MethodPattern};
 use crate::engine::AnalysisContext;
-use crate::ir::Method;
-use crate::rules::{Rule, RuleMetadata, method_location_with_line, result_message};
+use crate::ir::{Class, Method};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleOutput, method_location_with_line, result_message,
+};
+
+/// Reachable methods attached to each finding as places a call might be missing.
+const RELATED_CANDIDATES: usize = 3;
 
 /// Rule that detects unreachable methods.
-pub(crate) struct DeadCodeRule;
+#[derive(Default)]
+pub(crate) struct DeadCodeRule {
+    /// Methods treated as entry points besides the built-in criteria.
+    pub(crate) entry_points: Vec<MethodPattern>,
+}
 
 impl Rule for DeadCodeRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "DEAD_CODE",
-            name: "Dead code",
-            description: "Unreachable methods detected by call graph",
+            id: "DEAD_CODE".into(),
+            name: "Dead code".into(),
+            description: "Unreachable methods detected by call graph".into(),
+            tags: Cow::Borrowed(&["style"]),
+            default_level: ResultLevel::Note,
+            help: include_str!("../../docs/rules/DEAD_CODE.md").into(),
+            cwe: &[561],
+            precision: Precision::Medium,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut method_map = BTreeMap::new();
-        let mut entry_points = Vec::new();
+    fn run(&self, context: &AnalysisContext) -> Result<RuleOutput> {
+        let reachability = Reachability::new(context, &self.entry_points);
+        if reachability.entry_points.is_empty() {
+            return Ok(RuleOutput::default());
+        }
+        let reachable = reachability.reachable();
 
+        let mut results = Vec::new();
         for class in &context.classes {
             if !context.is_analysis_target_class(class) {
                 continue;
             }
+            let artifact_uri = context.class_artifact_uri(class);
+            let mut unreachable = Vec::new();
+            let mut reachable_in_class = Vec::new();
             for method in &class.methods {
-                let id = Arc::new(MethodId {
-                    class_name: class.name.clone(),
-                    name: method.name.clone(),
-                    descriptor: method.descriptor.clone(),
-                });
-                let artifact_uri = context.class_artifact_uri(class);
-                method_map.insert(id.clone(), (class.name.clone(), method, artifact_uri));
-                if is_entry_method(method) {
-                    entry_points.push(id);
+                if reachable.contains(&method_id(class, method)) {
+                    reachable_in_class.push(method);
+                } else if method_has_body(method) {
+                    unreachable.push(method);
+                }
+            }
+            for method in unreachable {
+                let message = result_message(format!(
+                    "Unreachable method: {}.{}{}",
+                    class.name, method.name, method.descriptor
+                ));
+                let line = method.line_for_offset(0);
+                let location = method_location_with_line(
+                    &class.name,
+                    &method.name,
+                    &method.descriptor,
+                    artifact_uri.as_deref(),
+                    line,
+                );
+                let mut result = SarifResult::builder()
+                    .message(message)
+                    .locations(vec![location])
+                    .build();
+                let related: Vec<_> = nearest_methods(&reachable_in_class, line)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| {
+                        let mut location = method_location_with_line(
+                            &class.name,
+                            &candidate.name,
+                            &candidate.descriptor,
+                            artifact_uri.as_deref(),
+                            candidate.line_for_offset(0),
+                        );
+                        location.id = Some(index as i64);
+                        location.message = Some(result_message(format!(
+                            "Reachable method in the same class: {}.{}{}",
+                            class.name, candidate.name, candidate.descriptor
+                        )));
+                        location
+                    })
+                    .collect();
+                if !related.is_empty() {
+                    result.related_locations = Some(related);
                 }
+                results.push(result);
             }
         }
 
-        if entry_points.is_empty() {
-            return Ok(Vec::new());
-        }
+        Ok(results.into())
+    }
+}
 
-        let adjacency = build_adjacency(&context.call_graph.edges);
-        let reachable = walk_graph(&entry_points, &adjacency);
+/// Reachable methods closest to `line`, the likeliest places for a missing call.
+fn nearest_methods<'a>(candidates: &[&'a Method], line: Option<u32>) -> Vec<&'a Method> {
+    let mut candidates: Vec<&Method> = candidates
+        .iter()
+        .copied()
+        .filter(|method| method_has_body(method))
+        .collect();
+    candidates.sort_by_key(|method| match (line, method.line_for_offset(0)) {
+        (Some(line), Some(other)) => line.abs_diff(other),
+        _ => u32::MAX,
+    });
+    candidates.truncate(RELATED_CANDIDATES);
+    candidates
+}
 
-        let mut results = Vec::new();
-        for (id, (class_name, method, artifact_uri)) in method_map {
-            if reachable.contains(&id) {
+/// How DeadCodeRule chooses the methods reachability starts from.
+pub(crate) const ENTRY_POINT_CRITERIA: &[&str] = &[
+    "public methods of analysis-target classes",
+    "constructors and static initializers of service providers listed in META-INF/services",
+];
+
+/// Entry points and call edges DeadCodeRule decides reachability from.
+pub(crate) struct Reachability<'a> {
+    pub(crate) entry_points: BTreeSet<Arc<MethodId>>,
+    outgoing: BTreeMap<&'a MethodId, Vec<&'a CallEdge>>,
+    incoming: BTreeMap<&'a MethodId, Vec<&'a CallEdge>>,
+}
+
+/// Step of a call path: the method and the call that led to it.
+pub(crate) struct PathStep<'a> {
+    pub(crate) method: &'a MethodId,
+    pub(crate) via: Option<&'a CallEdge>,
+}
+
+/// Breadth-first tree over the call graph, rooted at the entry points.
+pub(crate) struct ShortestPaths<'a> {
+    parents: BTreeMap<&'a MethodId, (Option<&'a CallEdge>, usize)>,
+}
+
+impl<'a> ShortestPaths<'a> {
+    /// Methods reached from an entry point, with their distance in calls.
+    pub(crate) fn reached(&self) -> impl Iterator<Item = (&'a MethodId, usize)> + '_ {
+        self.parents
+            .iter()
+            .map(|(method, (_, depth))| (*method, *depth))
+    }
+
+    /// Shortest call path to `target`, entry point first.
+    pub(crate) fn path_to(&self, target: &MethodId) -> Option<Vec<PathStep<'a>>> {
+        let mut current: &'a MethodId = self.parents.get_key_value(target)?.0;
+        let mut path = Vec::new();
+        while let Some(via) = self.parents[current].0 {
+            path.push(PathStep {
+                method: current,
+                via: Some(via),
+            });
+            current = via.caller.as_ref();
+        }
+        path.push(PathStep {
+            method: current,
+            via: None,
+        });
+        path.reverse();
+        Some(path)
+    }
+}
+
+impl<'a> Reachability<'a> {
+    /// Reachability from the built-in entry points and methods of input classes
+    /// matching `patterns`, including classes excluded from reporting.
+    pub(crate) fn new(context: &'a AnalysisContext, patterns: &[MethodPattern]) -> Self {
+        let mut entry_points = BTreeSet::new();
+        for class in &context.classes {
+            if !context.is_input_class(class) {
                 continue;
             }
-            if !method_has_body(method) {
-                continue;
+            for method in &class.methods {
+                let configured = patterns
+                    .iter()
+                    .any(|pattern| pattern.matches(&class.name, &method.name, &method.descriptor));
+                if is_entry_method(method) || configured {
+                    entry_points.insert(Arc::new(method_id(class, method)));
+                }
             }
-            let message = result_message(format!(
-                "Unreachable method: {}.{}{}",
-                class_name, method.name, method.descriptor
-            ));
-            let line = method.line_for_offset(0);
-            let location = method_location_with_line(
-                &class_name,
-                &method.name,
-                &method.descriptor,
-                artifact_uri.as_deref(),
-                line,
-            );
-            results.push(
-                SarifResult::builder()
-                    .message(message)
-                    .locations(vec![location])
-                    .build(),
-            );
         }
-
-        Ok(results)
+        if !entry_points.is_empty() {
+            entry_points.extend(context.call_graph.entry_points.iter().cloned());
+        }
+        let mut outgoing: BTreeMap<&MethodId, Vec<&CallEdge>> = BTreeMap::new();
+        let mut incoming: BTreeMap<&MethodId, Vec<&CallEdge>> = BTreeMap::new();
+        for edge in &context.call_graph.edges {
+            outgoing.entry(edge.caller.as_ref()).or_default().push(edge);
+            incoming.entry(edge.callee.as_ref()).or_default().push(edge);
+        }
+        Self {
+            entry_points,
+            outgoing,
+            incoming,
+        }
     }
-}
 
-fn build_adjacency(
-    edges: &[crate::callgraph::CallEdge],
-) -> BTreeMap<Arc<MethodId>, Vec<Arc<MethodId>>> {
-    let mut adjacency = BTreeMap::new();
-    for edge in edges {
-        adjacency
-            .entry(edge.caller.clone())
-            .or_insert_with(Vec::new)
-            .push(edge.callee.clone());
-    }
-    adjacency
-}
+    pub(crate) fn reachable(&self) -> BTreeSet<MethodId> {
+        let mut visited: BTreeSet<MethodId> = BTreeSet::new();
+        let mut queue: VecDeque<&MethodId> = VecDeque::new();
+        for entry in &self.entry_points {
+            if visited.insert(entry.as_ref().clone()) {
+                queue.push_back(entry);
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            for edge in self.outgoing.get(node).into_iter().flatten() {
+                if visited.insert(edge.callee.as_ref().clone()) {
+                    queue.push_back(&edge.callee);
+                }
+            }
+        }
+        visited
+    }
 
-fn walk_graph(
-    entries: &[Arc<MethodId>],
-    adjacency: &BTreeMap<Arc<MethodId>, Vec<Arc<MethodId>>>,
-) -> BTreeSet<Arc<MethodId>> {
-    let mut visited = BTreeSet::new();
-    let mut queue = VecDeque::new();
-    for entry in entries {
-        if visited.insert(entry.clone()) {
-            queue.push_back(entry.clone());
+    /// Breadth-first search from every entry point at once.
+    pub(crate) fn shortest_paths(&self) -> ShortestPaths<'_> {
+        let mut parents: BTreeMap<&MethodId, (Option<&CallEdge>, usize)> = BTreeMap::new();
+        let mut queue: VecDeque<&MethodId> = VecDeque::new();
+        for entry in &self.entry_points {
+            if parents.insert(entry, (None, 0)).is_none() {
+                queue.push_back(entry);
+            }
         }
+        while let Some(node) = queue.pop_front() {
+            let depth = parents[node].1;
+            for edge in self.outgoing.get(node).into_iter().flatten() {
+                if !parents.contains_key(edge.callee.as_ref()) {
+                    parents.insert(&edge.callee, (Some(edge), depth + 1));
+                    queue.push_back(&edge.callee);
+                }
+            }
+        }
+        ShortestPaths { parents }
+    }
+
+    /// Shortest call path from an entry point to `target`, entry point first.
+    pub(crate) fn shortest_path(&self, target: &MethodId) -> Option<Vec<PathStep<'_>>> {
+        self.shortest_paths().path_to(target)
     }
-    while let Some(node) = queue.pop_front() {
-        if let Some(neighbors) = adjacency.get(&node) {
-            for neighbor in neighbors {
-                if visited.insert(neighbor.clone()) {
-                    queue.push_back(neighbor.clone());
+
+    /// Call graph edges into `target`.
+    pub(crate) fn callers(&self, target: &MethodId) -> &[&'a CallEdge] {
+        self.incoming.get(target).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Human-readable explanation of why `target` is reachable or not.
+pub fn explain_reachability(
+    context: &AnalysisContext,
+    patterns: &[MethodPattern],
+    target: &MethodId,
+) -> String {
+    let reachability = Reachability::new(context, patterns);
+    let line_of = |edge: &CallEdge| {
+        context
+            .classes
+            .iter()
+            .find(|class| class.name == edge.caller.class_name)
+            .and_then(|class| {
+                class.methods.iter().find(|method| {
+                    method.name == edge.caller.name && method.descriptor == edge.caller.descriptor
+                })
+            })
+            .and_then(|method| method.line_for_offset(edge.offset))
+    };
+    let call_site = |edge: &CallEdge| match line_of(edge) {
+        Some(line) => format!("{} call at line {}", edge.kind.as_str(), line),
+        None => format!("{} call at offset {}", edge.kind.as_str(), edge.offset),
+    };
+
+    let mut out = String::new();
+    let label = method_label(target);
+    if let Some(path) = reachability.shortest_path(target) {
+        let _ = writeln!(out, "{} is reachable:", label);
+        for step in path {
+            match step.via {
+                None => {
+                    let _ = writeln!(out, "  {} (entry point)", method_label(step.method));
+                }
+                Some(edge) => {
+                    let _ = writeln!(
+                        out,
+                        "  -> {} ({})",
+                        method_label(step.method),
+                        call_site(edge)
+                    );
                 }
             }
         }
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{} is unreachable: no call path from an entry point.",
+        label
+    );
+    if reachability.entry_points.is_empty() {
+        let _ = writeln!(
+            out,
+            "No entry point was found, so DEAD_CODE reports nothing for this input."
+        );
+    }
+    let _ = writeln!(out, "Entry points are:");
+    for criterion in ENTRY_POINT_CRITERIA {
+        let _ = writeln!(out, "  - {}", criterion);
+    }
+    for pattern in patterns {
+        let _ = writeln!(
+            out,
+            "  - configured entry point {}.{}{}",
+            pattern.class_name,
+            pattern.name,
+            pattern.descriptor.as_deref().unwrap_or("")
+        );
+    }
+    let callers = reachability.callers(target);
+    if callers.is_empty() {
+        let _ = writeln!(out, "No method in the call graph calls it.");
+    } else {
+        let _ = writeln!(out, "Callers in the call graph, none of them reachable:");
+        for edge in callers {
+            let _ = writeln!(
+                out,
+                "  - {} ({})",
+                method_label(&edge.caller),
+                call_site(edge)
+            );
+        }
+    }
+    out
+}
+
+fn method_label(id: &MethodId) -> String {
+    format!("{}.{}{}", id.class_name, id.name, id.descriptor)
+}
+
+fn method_id(class: &Class, method: &Method) -> MethodId {
+    MethodId {
+        class_name: class.name.clone(),
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
     }
-    visited
 }
 
 fn is_entry_method(method: &Method) -> bool {
@@ -129,10 +368,13 @@ fn method_has_body(method: &Method) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::callgraph::{CallGraphAlgorithm, CallGraphOptions};
+    use crate::class_filter::ClassFilter;
     use crate::classpath::resolve_classpath;
     use crate::descriptor::method_param_count;
-    use crate::engine::build_context;
+    use crate::engine::{build_context, build_context_with_timings};
     use crate::ir::{CallKind, CallSite, Class, ControlFlowGraph, MethodAccess, MethodNullness};
+    use crate::scan::scan_inputs;
     use crate::test_harness::{JvmTestHarness, Language, SourceFile};
 
     fn empty_cfg() -> ControlFlowGraph {
@@ -155,11 +397,14 @@ mod tests {
             access,
             nullness: MethodNullness::unknown(method_param_count(descriptor).expect("param count")),
             bytecode,
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: empty_cfg(),
             calls,
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         }
     }
 
@@ -173,6 +418,7 @@ mod tests {
             methods,
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }
     }
 
@@ -228,13 +474,54 @@ mod tests {
         )];
         let context = context_for(classes);
 
-        let results = DeadCodeRule.run(&context).expect("dead code rule run");
+        let results = DeadCodeRule::default()
+            .run(&context)
+            .expect("dead code rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
         assert!(message.contains("Unreachable method: com/example/App.unreachable()V"));
     }
 
+    #[test]
+    fn dead_code_rule_starts_from_configured_entry_points() {
+        let handler = method_with(
+            "onEvent",
+            "()V",
+            MethodAccess {
+                is_public: false,
+                is_static: false,
+                is_abstract: false,
+            },
+            vec![0],
+            Vec::new(),
+        );
+        let main_method = method_with(
+            "main",
+            "([Ljava/lang/String;)V",
+            MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            vec![0],
+            Vec::new(),
+        );
+        let classes = vec![class_with_methods(
+            "com/example/App",
+            vec![main_method, handler],
+        )];
+        let context = context_for(classes);
+        let rule = DeadCodeRule {
+            entry_points: vec![MethodPattern::parse("com.example.*.on*").expect("pattern")],
+        };
+
+        let results = rule.run(&context).expect("dead code rule run").results;
+
+        assert!(results.is_empty());
+    }
+
     #[test]
     fn dead_code_rule_skips_when_no_entrypoints() {
         let helper = method_with(
@@ -251,7 +538,10 @@ mod tests {
         let classes = vec![class_with_methods("com/example/Util", vec![helper])];
         let context = context_for(classes);
 
-        let results = DeadCodeRule.run(&context).expect("dead code rule run");
+        let results = DeadCodeRule::default()
+            .run(&context)
+            .expect("dead code rule run")
+            .results;
 
         assert!(results.is_empty());
     }
@@ -286,7 +576,10 @@ mod tests {
         )];
         let context = context_for(classes);
 
-        let results = DeadCodeRule.run(&context).expect("dead code rule run");
+        let results = DeadCodeRule::default()
+            .run(&context)
+            .expect("dead code rule run")
+            .results;
 
         assert!(results.is_empty());
     }
@@ -324,4 +617,335 @@ public class App {
 
         assert!(messages.iter().any(|msg| msg.contains("unused()V")));
     }
+
+    #[test]
+    fn dead_code_rule_follows_interface_and_inherited_calls() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/App.java".to_string(),
+            contents: r#"
+package com.example;
+interface Handler {
+    void handle();
+}
+abstract class Base {
+    void inherited() {}
+}
+class Sub extends Base {}
+class Printer implements Handler {
+    public void handle() {
+        print();
+    }
+
+    private void print() {}
+}
+public class App {
+    public void entry(Handler handler, Sub sub) {
+        handler.handle();
+        sub.inherited();
+    }
+}
+"#
+            .to_string(),
+        }];
+
+        let output = harness
+            .compile_and_analyze(Language::Java, &sources, &[])
+            .expect("run harness analysis");
+
+        let messages: Vec<String> = output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("DEAD_CODE"))
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+
+        assert!(
+            !messages
+                .iter()
+                .any(|msg| msg.contains("inherited()V") || msg.contains("print()V")),
+            "{messages:?}"
+        );
+    }
+
+    #[test]
+    fn dead_code_rule_uses_rta_call_graph_when_selected() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/App.java".to_string(),
+            contents: r#"
+package com.example;
+class Worker {
+    void work() {}
+}
+class SpecialWorker extends Worker {
+    void work() {}
+}
+public class App {
+    public void entry() {
+        Worker worker = new Worker();
+        worker.work();
+    }
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let unreachable = |algorithm: CallGraphAlgorithm| {
+            let classpath = resolve_classpath(&classes).expect("classpath build");
+            let (context, _) = build_context_with_timings(
+                classes.clone(),
+                classpath,
+                &[],
+                &CallGraphOptions {
+                    algorithm,
+                    ..CallGraphOptions::default()
+                },
+                ClassFilter::default(),
+            );
+            DeadCodeRule::default()
+                .run(&context)
+                .expect("dead code rule run")
+                .results
+                .iter()
+                .filter_map(|result| result.message.text.clone())
+                .any(|msg| msg.contains("SpecialWorker.work()V"))
+        };
+
+        assert!(!unreachable(CallGraphAlgorithm::Cha));
+        assert!(unreachable(CallGraphAlgorithm::Rta));
+    }
+
+    #[test]
+    fn dead_code_rule_treats_service_providers_as_instantiated() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/App.java".to_string(),
+            contents: r#"
+package com.example;
+class Provider {
+    Provider() {
+        setup();
+    }
+
+    private void setup() {}
+}
+public class App {
+    public void entry() {}
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let unreachable = |service_providers: Vec<String>| {
+            let classpath = resolve_classpath(&classes).expect("classpath build");
+            let (context, _) = build_context_with_timings(
+                classes.clone(),
+                classpath,
+                &[],
+                &CallGraphOptions {
+                    service_providers,
+                    ..CallGraphOptions::default()
+                },
+                ClassFilter::default(),
+            );
+            DeadCodeRule::default()
+                .run(&context)
+                .expect("dead code rule run")
+                .results
+                .iter()
+                .filter_map(|result| result.message.text.clone())
+                .any(|msg| msg.contains("Provider.setup()V"))
+        };
+
+        assert!(unreachable(Vec::new()));
+        assert!(!unreachable(vec!["com/example/Provider".to_string()]));
+    }
+
+    #[test]
+    fn dead_code_rule_roots_every_call_graph_at_configured_entry_points() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/Ctl.java".to_string(),
+            contents: r#"
+package com.example;
+public class Ctl {
+    void handle() {
+        helper();
+    }
+
+    private void helper() {}
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let entry_points = vec![MethodPattern::parse("com.example.Ctl.handle").expect("pattern")];
+        for algorithm in [
+            CallGraphAlgorithm::Cha,
+            CallGraphAlgorithm::Rta,
+            CallGraphAlgorithm::Pta,
+        ] {
+            let classpath = resolve_classpath(&classes).expect("classpath build");
+            let (context, _) = build_context_with_timings(
+                classes.clone(),
+                classpath,
+                &[],
+                &CallGraphOptions {
+                    algorithm,
+                    entry_points: entry_points.clone(),
+                    ..CallGraphOptions::default()
+                },
+                ClassFilter::default(),
+            );
+            let messages: Vec<String> = DeadCodeRule {
+                entry_points: entry_points.clone(),
+            }
+            .run(&context)
+            .expect("dead code rule run")
+            .results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+            assert!(
+                !messages.iter().any(|msg| msg.contains("Ctl.helper()V")),
+                "{algorithm:?}: {messages:?}"
+            );
+        }
+    }
+
+    fn app_with_helpers() -> crate::engine::AnalysisContext {
+        let static_access = MethodAccess {
+            is_public: false,
+            is_static: true,
+            is_abstract: false,
+        };
+        let call = |name: &str, offset: u32| CallSite {
+            owner: "com/example/App".to_string(),
+            name: name.to_string(),
+            descriptor: "()V".to_string(),
+            kind: CallKind::Static,
+            offset,
+        };
+        let mut main_method = method_with(
+            "main",
+            "([Ljava/lang/String;)V",
+            MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            vec![0],
+            vec![call("first", 0)],
+        );
+        main_method.line_numbers = vec![crate::ir::LineNumber {
+            start_pc: 0,
+            line: 3,
+        }];
+        let first = method_with(
+            "first",
+            "()V",
+            static_access,
+            vec![0],
+            vec![call("second", 0)],
+        );
+        let second = method_with("second", "()V", static_access, vec![0], Vec::new());
+        let orphan = method_with(
+            "orphan",
+            "()V",
+            static_access,
+            vec![0],
+            vec![call("unused", 0)],
+        );
+        let unused = method_with("unused", "()V", static_access, vec![0], Vec::new());
+        context_for(vec![class_with_methods(
+            "com/example/App",
+            vec![main_method, first, second, orphan, unused],
+        )])
+    }
+
+    fn app_method(name: &str) -> MethodId {
+        MethodId {
+            class_name: "com/example/App".to_string(),
+            name: name.to_string(),
+            descriptor: "()V".to_string(),
+        }
+    }
+
+    #[test]
+    fn reachability_finds_shortest_path_from_entry_point() {
+        let context = app_with_helpers();
+        let reachability = Reachability::new(&context, &[]);
+
+        let path = reachability
+            .shortest_path(&app_method("second"))
+            .expect("path to second");
+
+        let names: Vec<&str> = path.iter().map(|step| step.method.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "first", "second"]);
+        assert!(path[0].via.is_none());
+        assert!(reachability.shortest_path(&app_method("unused")).is_none());
+    }
+
+    #[test]
+    fn explain_reachability_prints_call_path() {
+        let context = app_with_helpers();
+
+        let explanation = explain_reachability(&context, &[], &app_method("second"));
+
+        assert!(explanation.contains("com/example/App.second()V is reachable"));
+        assert!(explanation.contains("com/example/App.main([Ljava/lang/String;)V (entry point)"));
+        assert!(explanation.contains("-> com/example/App.first()V (static call at line 3)"));
+    }
+
+    #[test]
+    fn explain_reachability_lists_criteria_and_callers_without_path() {
+        let context = app_with_helpers();
+
+        let explanation = explain_reachability(&context, &[], &app_method("unused"));
+
+        assert!(explanation.contains("com/example/App.unused()V is unreachable"));
+        for criterion in ENTRY_POINT_CRITERIA {
+            assert!(explanation.contains(criterion));
+        }
+        assert!(explanation.contains("com/example/App.orphan()V (static call at offset 0)"));
+    }
+
+    #[test]
+    fn dead_code_rule_relates_reachable_methods_in_same_class() {
+        let context = app_with_helpers();
+
+        let results = DeadCodeRule::default()
+            .run(&context)
+            .expect("dead code rule run")
+            .results;
+
+        assert_eq!(2, results.len());
+        let related = results[0]
+            .related_locations
+            .as_ref()
+            .expect("related locations");
+        assert_eq!(3, related.len());
+        let message = related[0]
+            .message
+            .as_ref()
+            .and_then(|message| message.text.as_deref())
+            .unwrap_or("");
+        assert!(message.starts_with("Reachable method in the same class: com/example/App."));
+    }
 }
@@ -1,10 +1,14 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
 use crate::engine::AnalysisContext;
-use crate::ir::Instruction;
+use crate::ir::{Class, Instruction, Method};
 use crate::opcodes;
-use crate::rules::{Rule, RuleMetadata, method_location_with_line, result_message};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
 
 /// Rule that detects empty catch blocks.
 pub(crate) struct EmptyCatchRule;
@@ -12,50 +16,63 @@ pub(crate) struct EmptyCatchRule;
 impl Rule for EmptyCatchRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "EMPTY_CATCH",
-            name: "Empty catch block",
-            description: "Catch blocks with no meaningful instructions",
+            id: "EMPTY_CATCH".into(),
+            name: "Empty catch block".into(),
+            description: "Catch blocks with no meaningful instructions".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/EMPTY_CATCH.md").into(),
+            cwe: &[1069],
+            precision: Precision::High,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut results = Vec::new();
-        for class in &context.classes {
-            for method in &class.methods {
-                for handler in &method.exception_handlers {
-                    let Some(block) = method
-                        .cfg
-                        .blocks
-                        .iter()
-                        .find(|block| block.start_offset == handler.handler_pc)
-                    else {
-                        continue;
-                    };
-                    if is_empty_handler(block.instructions.as_slice()) {
-                        let message = result_message(format!(
-                            "Empty catch block in {}.{}{}",
-                            class.name, method.name, method.descriptor
-                        ));
-                        let line = method.line_for_offset(handler.handler_pc);
-                        let artifact_uri = context.class_artifact_uri(class);
-                        let location = method_location_with_line(
-                            &class.name,
-                            &method.name,
-                            &method.descriptor,
-                            artifact_uri.as_deref(),
-                            line,
-                        );
-                        results.push(
-                            SarifResult::builder()
-                                .message(message)
-                                .locations(vec![location])
-                                .build(),
-                        );
-                    }
-                }
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for EmptyCatchRule {
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        for handler in &method.exception_handlers {
+            let Some(block) = method
+                .cfg
+                .blocks
+                .iter()
+                .find(|block| block.start_offset == handler.handler_pc)
+            else {
+                continue;
+            };
+            if is_empty_handler(block.instructions.as_slice()) {
+                let message = result_message(format!(
+                    "Empty catch block in {}.{}{}",
+                    class.name, method.name, method.descriptor
+                ));
+                let line = method.line_for_offset(handler.handler_pc);
+                let artifact_uri = context.class_artifact_uri(class);
+                let location = method_location_with_line(
+                    &class.name,
+                    &method.name,
+                    &method.descriptor,
+                    artifact_uri.as_deref(),
+                    line,
+                );
+                results.push(
+                    SarifResult::builder()
+                        .message(message)
+                        .locations(vec![location])
+                        .build(),
+                );
             }
         }
-        Ok(results)
+        Ok(())
     }
 }
 
@@ -122,11 +139,14 @@ mod tests {
             access: default_access(),
             nullness: MethodNullness::unknown(method_param_count(descriptor).expect("param count")),
             bytecode: vec![0],
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg,
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: handlers,
+            annotations: Vec::new(),
         }
     }
 
@@ -140,6 +160,7 @@ mod tests {
             methods,
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }
     }
 
@@ -212,7 +233,10 @@ public class EmptyCatchSample {
         let classes = vec![class_with_methods("com/example/App", vec![method])];
         let context = context_for(classes);
 
-        let results = EmptyCatchRule.run(&context).expect("empty catch rule run");
+        let results = EmptyCatchRule
+            .run(&context)
+            .expect("empty catch rule run")
+            .results;
 
         assert_eq!(1, results.len());
     }
@@ -242,7 +266,10 @@ public class EmptyCatchSample {
         let classes = vec![class_with_methods("com/example/App", vec![method])];
         let context = context_for(classes);
 
-        let results = EmptyCatchRule.run(&context).expect("empty catch rule run");
+        let results = EmptyCatchRule
+            .run(&context)
+            .expect("empty catch rule run")
+            .results;
 
         assert!(results.is_empty());
     }
@@ -1,8 +1,11 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
 use crate::engine::AnalysisContext;
-use crate::rules::{Rule, RuleMetadata, class_location, result_message};
+use crate::ir::Class;
+use crate::rules::{Precision, Rule, RuleMetadata, RuleVisitor, class_location, result_message};
 
 /// Rule that flags classes overriding equals or hashCode alone.
 pub(crate) struct IneffectiveEqualsRule;
@@ -10,47 +13,61 @@ pub(crate) struct IneffectiveEqualsRule;
 impl Rule for IneffectiveEqualsRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "INEFFECTIVE_EQUALS_HASHCODE",
-            name: "Ineffective equals/hashCode",
-            description: "Classes with equals without hashCode or vice versa",
+            id: "INEFFECTIVE_EQUALS_HASHCODE".into(),
+            name: "Ineffective equals/hashCode".into(),
+            description: "Classes with equals without hashCode or vice versa".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/INEFFECTIVE_EQUALS_HASHCODE.md").into(),
+            cwe: &[581],
+            precision: Precision::VeryHigh,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut results = Vec::new();
-        for class in &context.classes {
-            let mut has_equals = false;
-            let mut has_hashcode = false;
-            for method in &class.methods {
-                if method.name == "equals" && method.descriptor == "(Ljava/lang/Object;)Z" {
-                    has_equals = true;
-                }
-                if method.name == "hashCode" && method.descriptor == "()I" {
-                    has_hashcode = true;
-                }
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for IneffectiveEqualsRule {
+    fn visit_class(
+        &self,
+        _context: &AnalysisContext,
+        class: &Class,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        let mut has_equals = false;
+        let mut has_hashcode = false;
+        for method in &class.methods {
+            if method.name == "equals" && method.descriptor == "(Ljava/lang/Object;)Z" {
+                has_equals = true;
             }
-            if has_equals ^ has_hashcode {
-                let message = if has_equals {
-                    result_message(format!(
-                        "Class {} overrides equals without hashCode",
-                        class.name
-                    ))
-                } else {
-                    result_message(format!(
-                        "Class {} overrides hashCode without equals",
-                        class.name
-                    ))
-                };
-                let location = class_location(&class.name);
-                results.push(
-                    SarifResult::builder()
-                        .message(message)
-                        .locations(vec![location])
-                        .build(),
-                );
+            if method.name == "hashCode" && method.descriptor == "()I" {
+                has_hashcode = true;
             }
         }
-        Ok(results)
+        if has_equals ^ has_hashcode {
+            let message = if has_equals {
+                result_message(format!(
+                    "Class {} overrides equals without hashCode",
+                    class.name
+                ))
+            } else {
+                result_message(format!(
+                    "Class {} overrides hashCode without equals",
+                    class.name
+                ))
+            };
+            let location = class_location(&class.name);
+            results.push(
+                SarifResult::builder()
+                    .message(message)
+                    .locations(vec![location])
+                    .build(),
+            );
+        }
+        Ok(())
     }
 }
 
@@ -81,11 +98,14 @@ mod tests {
             },
             nullness: MethodNullness::unknown(method_param_count(descriptor).expect("param count")),
             bytecode: vec![0],
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: empty_cfg(),
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         }
     }
 
@@ -99,6 +119,7 @@ mod tests {
             methods,
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }
     }
 
@@ -115,7 +136,8 @@ mod tests {
 
         let results = IneffectiveEqualsRule
             .run(&context)
-            .expect("ineffective equals rule run");
+            .expect("ineffective equals rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
@@ -134,7 +156,8 @@ mod tests {
 
         let results = IneffectiveEqualsRule
             .run(&context)
-            .expect("ineffective equals rule run");
+            .expect("ineffective equals rule run")
+            .results;
 
         assert!(results.is_empty());
     }
@@ -147,7 +170,8 @@ mod tests {
 
         let results = IneffectiveEqualsRule
             .run(&context)
-            .expect("ineffective equals rule run");
+            .expect("ineffective equals rule run")
+            .results;
 
         assert!(results.is_empty());
     }
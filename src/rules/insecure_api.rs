@@ -1,64 +1,98 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
+use crate::callgraph::MethodPattern;
 use crate::engine::AnalysisContext;
-use crate::rules::{Rule, RuleMetadata, method_location_with_line, result_message};
+use crate::ir::{Class, Instruction, InstructionKind, Method};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
+
+/// Methods reported unless the configuration replaces the list.
+const DEFAULT_INSECURE_METHODS: &[&str] = &[
+    "java/lang/Runtime.exec",
+    "java/lang/ProcessBuilder.<init>",
+    "java/lang/ProcessBuilder.start",
+    "java/lang/reflect/Method.invoke",
+    "java/lang/reflect/Constructor.newInstance",
+    "java/lang/Class.forName",
+];
 
 /// Rule that detects insecure API usage.
-pub(crate) struct InsecureApiRule;
+pub(crate) struct InsecureApiRule {
+    pub(crate) methods: Vec<MethodPattern>,
+}
+
+impl Default for InsecureApiRule {
+    fn default() -> Self {
+        Self {
+            methods: DEFAULT_INSECURE_METHODS
+                .iter()
+                .map(|spec| MethodPattern::parse(spec).expect("valid built-in method"))
+                .collect(),
+        }
+    }
+}
 
 impl Rule for InsecureApiRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "INSECURE_API",
-            name: "Insecure API usage",
-            description: "Calls to insecure process or reflection APIs",
+            id: "INSECURE_API".into(),
+            name: "Insecure API usage".into(),
+            description: "Calls to insecure process or reflection APIs".into(),
+            tags: Cow::Borrowed(&["security"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/INSECURE_API.md").into(),
+            cwe: &[78, 470],
+            precision: Precision::Low,
+            security_severity: Some(5.3),
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut results = Vec::new();
-        for class in &context.classes {
-            for method in &class.methods {
-                for call in &method.calls {
-                    if is_insecure_call(&call.owner, &call.name) {
-                        let message = result_message(format!(
-                            "Insecure API usage: {}.{}",
-                            call.owner, call.name
-                        ));
-                        let line = method.line_for_offset(call.offset);
-                        let artifact_uri = context.class_artifact_uri(class);
-                        let location = method_location_with_line(
-                            &class.name,
-                            &method.name,
-                            &method.descriptor,
-                            artifact_uri.as_deref(),
-                            line,
-                        );
-                        results.push(
-                            SarifResult::builder()
-                                .message(message)
-                                .locations(vec![location])
-                                .build(),
-                        );
-                    }
-                }
-            }
-        }
-        Ok(results)
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
     }
 }
 
-fn is_insecure_call(owner: &str, name: &str) -> bool {
-    matches!(
-        (owner, name),
-        ("java/lang/Runtime", "exec")
-            | ("java/lang/ProcessBuilder", "<init>")
-            | ("java/lang/ProcessBuilder", "start")
-            | ("java/lang/reflect/Method", "invoke")
-            | ("java/lang/reflect/Constructor", "newInstance")
-            | ("java/lang/Class", "forName")
-    )
+impl RuleVisitor for InsecureApiRule {
+    fn visit_instruction(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        instruction: &Instruction,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        let InstructionKind::Invoke(call) = &instruction.kind else {
+            return Ok(());
+        };
+        let insecure = self
+            .methods
+            .iter()
+            .any(|pattern| pattern.matches(&call.owner, &call.name, &call.descriptor));
+        if insecure {
+            let message =
+                result_message(format!("Insecure API usage: {}.{}", call.owner, call.name));
+            let line = method.line_for_offset(call.offset);
+            let artifact_uri = context.class_artifact_uri(class);
+            let location = method_location_with_line(
+                &class.name,
+                &method.name,
+                &method.descriptor,
+                artifact_uri.as_deref(),
+                line,
+            );
+            results.push(
+                SarifResult::builder()
+                    .message(message)
+                    .locations(vec![location])
+                    .build(),
+            );
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -68,13 +102,27 @@ mod tests {
     use crate::descriptor::method_param_count;
     use crate::engine::build_context;
     use crate::ir::{
-        CallKind, CallSite, Class, ControlFlowGraph, Method, MethodAccess, MethodNullness,
+        BasicBlock, CallKind, CallSite, ControlFlowGraph, MethodAccess, MethodNullness,
     };
+    use crate::opcodes;
     use crate::test_harness::{JvmTestHarness, Language, SourceFile};
 
-    fn empty_cfg() -> ControlFlowGraph {
+    /// A single block invoking each call in turn.
+    fn cfg_with(calls: &[CallSite]) -> ControlFlowGraph {
+        let instructions = calls
+            .iter()
+            .map(|call| Instruction {
+                offset: call.offset,
+                opcode: opcodes::INVOKEVIRTUAL,
+                kind: InstructionKind::Invoke(call.clone()),
+            })
+            .collect();
         ControlFlowGraph {
-            blocks: Vec::new(),
+            blocks: vec![BasicBlock {
+                start_offset: 0,
+                end_offset: 1,
+                instructions,
+            }],
             edges: Vec::new(),
         }
     }
@@ -90,11 +138,14 @@ mod tests {
             },
             nullness: MethodNullness::unknown(method_param_count("()V").expect("param count")),
             bytecode: vec![0],
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
-            cfg: empty_cfg(),
+            cfg: cfg_with(&calls),
             calls,
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         }
     }
 
@@ -108,6 +159,7 @@ mod tests {
             methods,
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }
     }
 
@@ -131,9 +183,10 @@ mod tests {
         let classes = vec![class_with_methods("com/example/App", vec![method])];
         let context = context_for(classes);
 
-        let results = InsecureApiRule
+        let results = InsecureApiRule::default()
             .run(&context)
-            .expect("insecure api rule run");
+            .expect("insecure api rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
@@ -155,13 +208,37 @@ mod tests {
         let classes = vec![class_with_methods("com/example/App", vec![method])];
         let context = context_for(classes);
 
-        let results = InsecureApiRule
+        let results = InsecureApiRule::default()
             .run(&context)
-            .expect("insecure api rule run");
+            .expect("insecure api rule run")
+            .results;
 
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn insecure_api_rule_uses_configured_methods() {
+        let method = method_with(
+            "run",
+            vec![CallSite {
+                owner: "java/lang/String".to_string(),
+                name: "length".to_string(),
+                descriptor: "()I".to_string(),
+                kind: CallKind::Virtual,
+                offset: 0,
+            }],
+        );
+        let classes = vec![class_with_methods("com/example/App", vec![method])];
+        let context = context_for(classes);
+        let rule = InsecureApiRule {
+            methods: vec![MethodPattern::parse("java.lang.String.length").expect("pattern")],
+        };
+
+        let results = rule.run(&context).expect("insecure api rule run").results;
+
+        assert_eq!(1, results.len());
+    }
+
     #[test]
     fn insecure_api_rule_reports_runtime_exec_from_harness() {
         let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_sarif::sarif::{PropertyBag, Result as SarifResult, ResultLevel};
+
+use crate::engine::AnalysisContext;
+use crate::ir::{Class, Method};
+use crate::metrics::{MethodMetrics, method_metrics};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
+
+/// HotSpot does not JIT-compile methods with more bytecode than this unless it runs
+/// with `-XX:-DontCompileHugeMethods`.
+const HUGE_METHOD_LIMIT: usize = 8000;
+
+/// Upper bounds for method metrics; `None` disables the check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetricThresholds {
+    pub cyclomatic_complexity: Option<usize>,
+    pub bytecode_size: Option<usize>,
+    pub max_stack: Option<usize>,
+    pub max_locals: Option<usize>,
+    pub exception_handlers: Option<usize>,
+    pub loop_depth: Option<usize>,
+}
+
+impl Default for MetricThresholds {
+    fn default() -> Self {
+        Self {
+            cyclomatic_complexity: Some(50),
+            bytecode_size: Some(HUGE_METHOD_LIMIT),
+            max_stack: None,
+            max_locals: None,
+            exception_handlers: None,
+            loop_depth: Some(5),
+        }
+    }
+}
+
+impl MetricThresholds {
+    /// Set the threshold for the metric called `name`; `off` disables it.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let value = if value == "off" {
+            None
+        } else {
+            Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("invalid threshold for {}: {}", name, value))?,
+            )
+        };
+        let slot = match name {
+            "cyclomatic_complexity" => &mut self.cyclomatic_complexity,
+            "bytecode_size" => &mut self.bytecode_size,
+            "max_stack" => &mut self.max_stack,
+            "max_locals" => &mut self.max_locals,
+            "exception_handlers" => &mut self.exception_handlers,
+            "loop_depth" => &mut self.loop_depth,
+            _ => anyhow::bail!("unknown metric: {}", name),
+        };
+        *slot = value;
+        Ok(())
+    }
+
+    /// Metrics above their threshold, as (name, value, threshold).
+    fn exceeded(&self, metrics: &MethodMetrics) -> Vec<(&'static str, usize, usize)> {
+        let checks = [
+            (
+                "cyclomatic_complexity",
+                metrics.cyclomatic_complexity,
+                self.cyclomatic_complexity,
+            ),
+            ("bytecode_size", metrics.bytecode_size, self.bytecode_size),
+            ("max_stack", metrics.max_stack as usize, self.max_stack),
+            ("max_locals", metrics.max_locals as usize, self.max_locals),
+            (
+                "exception_handlers",
+                metrics.exception_handlers,
+                self.exception_handlers,
+            ),
+            ("loop_depth", metrics.loop_depth, self.loop_depth),
+        ];
+        checks
+            .into_iter()
+            .filter_map(|(name, value, threshold)| {
+                threshold
+                    .filter(|threshold| value > *threshold)
+                    .map(|threshold| (name, value, threshold))
+            })
+            .collect()
+    }
+}
+
+/// Rule that reports methods whose size or complexity exceed configured thresholds.
+pub(crate) struct MethodMetricsRule {
+    pub(crate) thresholds: MetricThresholds,
+}
+
+impl Rule for MethodMetricsRule {
+    fn metadata(&self) -> RuleMetadata {
+        RuleMetadata {
+            id: "METHOD_METRICS".into(),
+            name: "Method metric thresholds".into(),
+            description: "Methods whose size or complexity exceed configured thresholds".into(),
+            tags: Cow::Borrowed(&["style"]),
+            default_level: ResultLevel::Note,
+            help: include_str!("../../docs/rules/METHOD_METRICS.md").into(),
+            cwe: &[1121],
+            precision: Precision::VeryHigh,
+            security_severity: None,
+        }
+    }
+
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for MethodMetricsRule {
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        let metrics = method_metrics(method);
+        let exceeded = self.thresholds.exceeded(&metrics);
+        if exceeded.is_empty() {
+            return Ok(());
+        }
+        let artifact_uri = context.class_artifact_uri(class);
+        let properties = metric_properties(&metrics);
+        for (name, value, threshold) in exceeded {
+            let text = if name == "bytecode_size" && value > HUGE_METHOD_LIMIT {
+                format!(
+                    "Method bytecode size {} exceeds HotSpot's {}-byte JIT limit",
+                    value, HUGE_METHOD_LIMIT
+                )
+            } else {
+                format!("Method {} {} exceeds threshold {}", name, value, threshold)
+            };
+            let message = result_message(format!(
+                "{}: {}.{}{}",
+                text, class.name, method.name, method.descriptor
+            ));
+            let location = method_location_with_line(
+                &class.name,
+                &method.name,
+                &method.descriptor,
+                artifact_uri.as_deref(),
+                method.line_for_offset(0),
+            );
+            results.push(
+                SarifResult::builder()
+                    .message(message)
+                    .locations(vec![location])
+                    .properties(properties.clone())
+                    .build(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Every metric of the method, attached to each finding.
+fn metric_properties(metrics: &MethodMetrics) -> PropertyBag {
+    let values = [
+        ("cyclomatic_complexity", metrics.cyclomatic_complexity),
+        ("bytecode_size", metrics.bytecode_size),
+        ("max_stack", metrics.max_stack as usize),
+        ("max_locals", metrics.max_locals as usize),
+        ("exception_handlers", metrics.exception_handlers),
+        ("loop_depth", metrics.loop_depth),
+    ];
+    let properties: BTreeMap<String, serde_json::Value> = values
+        .into_iter()
+        .map(|(name, value)| (format!("inspequte.metrics.{}", name), value.into()))
+        .collect();
+    PropertyBag::builder()
+        .additional_properties(properties)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfg::build_cfg;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::ir::{Class, Instruction, InstructionKind, Method, MethodAccess, MethodNullness};
+    use crate::opcodes;
+
+    /// A method of `size` bytes: `nop`s followed by `return`.
+    fn method_of_size(name: &str, size: usize) -> Method {
+        let mut bytecode = vec![opcodes::NOP; size - 1];
+        bytecode.push(opcodes::RETURN);
+        let instructions: Vec<Instruction> = bytecode
+            .iter()
+            .enumerate()
+            .map(|(offset, opcode)| Instruction {
+                offset: offset as u32,
+                opcode: *opcode,
+                kind: InstructionKind::Other(*opcode),
+            })
+            .collect();
+        let cfg = build_cfg(&bytecode, &instructions, &[]).expect("build cfg");
+        Method {
+            name: name.to_string(),
+            descriptor: "()V".to_string(),
+            access: MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            nullness: MethodNullness::unknown(0),
+            bytecode,
+            max_stack: 0,
+            max_locals: 12,
+            line_numbers: Vec::new(),
+            cfg,
+            calls: Vec::new(),
+            string_literals: Vec::new(),
+            exception_handlers: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    fn run_rule(thresholds: MetricThresholds, methods: Vec<Method>) -> Vec<SarifResult> {
+        let class = Class {
+            name: "com/example/App".to_string(),
+            super_name: None,
+            interfaces: Vec::new(),
+            referenced_classes: Vec::new(),
+            fields: Vec::new(),
+            methods,
+            artifact_index: 0,
+            is_record: false,
+            annotations: Vec::new(),
+        };
+        let classes = vec![class];
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+        MethodMetricsRule { thresholds }
+            .run(&context)
+            .expect("method metrics rule run")
+            .results
+    }
+
+    fn messages(results: &[SarifResult]) -> Vec<String> {
+        results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn method_metrics_reports_huge_methods_by_default() {
+        let results = run_rule(
+            MetricThresholds::default(),
+            vec![method_of_size("huge", 8001), method_of_size("fits", 8000)],
+        );
+
+        assert_eq!(
+            vec![
+                "Method bytecode size 8001 exceeds HotSpot's 8000-byte JIT limit: com/example/App.huge()V"
+                    .to_string()
+            ],
+            messages(&results)
+        );
+        let properties = results[0].properties.as_ref().expect("metric properties");
+        assert_eq!(
+            Some(&serde_json::json!(8001)),
+            properties
+                .additional_properties
+                .get("inspequte.metrics.bytecode_size")
+        );
+    }
+
+    #[test]
+    fn method_metrics_applies_configured_thresholds() {
+        let mut thresholds = MetricThresholds::default();
+        thresholds.set("max_locals", "10").expect("set max_locals");
+        thresholds
+            .set("bytecode_size", "off")
+            .expect("disable size");
+
+        let results = run_rule(thresholds, vec![method_of_size("huge", 9000)]);
+
+        assert_eq!(
+            vec!["Method max_locals 12 exceeds threshold 10: com/example/App.huge()V".to_string()],
+            messages(&results)
+        );
+    }
+
+    #[test]
+    fn metric_thresholds_reject_unknown_names_and_values() {
+        let mut thresholds = MetricThresholds::default();
+
+        assert!(thresholds.set("lines", "10").is_err());
+        assert!(thresholds.set("loop_depth", "deep").is_err());
+    }
+}
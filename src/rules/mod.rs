@@ -1,34 +1,184 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
 use serde_sarif::sarif::{
     ArtifactLocation, Location, LogicalLocation, Message, PhysicalLocation, Region,
-    Result as SarifResult,
+    Result as SarifResult, ResultLevel,
 };
 
-use crate::engine::AnalysisContext;
+use crate::engine::{AnalysisContext, RuleFailure, visit_target_classes};
+use crate::ir::{Class, Instruction, Method};
 
 pub(crate) mod array_equals;
-pub(crate) mod dead_code;
+pub mod custom;
+pub mod dead_code;
 pub(crate) mod empty_catch;
 pub(crate) mod ineffective_equals;
 pub(crate) mod insecure_api;
+pub mod method_metrics;
 pub(crate) mod nullness;
+pub(crate) mod numeric_range;
 pub(crate) mod record_array_field;
+pub(crate) mod unreachable_code;
+pub mod vulnerable_method;
+
+/// Categories a rule can be tagged with and selected by on the command line.
+pub const RULE_CATEGORIES: &[&str] = &["correctness", "performance", "security", "style"];
 
 /// Metadata describing an analysis rule.
 #[derive(Clone, Debug)]
-pub(crate) struct RuleMetadata {
-    pub(crate) id: &'static str,
-    pub(crate) name: &'static str,
-    pub(crate) description: &'static str,
+pub struct RuleMetadata {
+    pub id: Cow<'static, str>,
+    pub name: Cow<'static, str>,
+    pub description: Cow<'static, str>,
+    /// Categories from `RULE_CATEGORIES`, emitted as SARIF `properties.tags`.
+    pub tags: Cow<'static, [&'static str]>,
+    /// Level of results that do not set their own, emitted as `defaultConfiguration.level`.
+    pub default_level: ResultLevel,
+    /// Markdown documentation from `docs/rules/<ID>.md`: a heading, a description
+    /// paragraph, then noncompliant and compliant examples.
+    pub help: Cow<'static, str>,
+    /// CWE IDs of the weaknesses the rule finds.
+    pub cwe: &'static [u32],
+    pub precision: Precision,
+    /// CVSS-like score from 0.0 to 10.0 that code scanning uses to rank security results.
+    pub security_severity: Option<f32>,
+}
+
+/// How often a rule's results are true positives, in SARIF `precision` terms.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Precision {
+    VeryHigh,
+    High,
+    Medium,
+    Low,
+}
+
+impl Precision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::VeryHigh => "very-high",
+            Self::High => "high",
+            Self::Medium => "medium",
+            Self::Low => "low",
+        }
+    }
+}
+
+/// Where the rule documentation in `docs/rules` is published.
+const RULE_DOCS_URI: &str = "https://github.com/KengoTODA/inspequte/blob/main/docs/rules";
+
+impl RuleMetadata {
+    /// First paragraph of the help text, joined into one line.
+    pub fn full_description(&self) -> String {
+        self.help
+            .split("\n\n")
+            .map(str::trim)
+            .find(|paragraph| !paragraph.is_empty() && !paragraph.starts_with('#'))
+            .unwrap_or(&self.description)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn help_uri(&self) -> String {
+        format!("{RULE_DOCS_URI}/{}.md", self.id)
+    }
+
+    /// Categories followed by CWE tags in the form code scanning recognizes.
+    pub fn sarif_tags(&self) -> Vec<String> {
+        self.tags
+            .iter()
+            .map(|tag| tag.to_string())
+            .chain(self.cwe.iter().map(|cwe| format!("external/cwe/cwe-{cwe}")))
+            .collect()
+    }
+
+    /// Whether `selector` names this rule by ID or by one of its tags.
+    pub fn is_selected_by(&self, selector: &str) -> bool {
+        self.id == selector || self.tags.contains(&selector)
+    }
+}
+
+/// Results of a rule run, and the classes and methods it failed to analyze.
+#[derive(Default)]
+pub struct RuleOutput {
+    pub results: Vec<SarifResult>,
+    pub failures: Vec<RuleFailure>,
+}
+
+impl From<Vec<SarifResult>> for RuleOutput {
+    fn from(results: Vec<SarifResult>) -> Self {
+        Self {
+            results,
+            failures: Vec::new(),
+        }
+    }
 }
 
 /// Rule interface for analysis execution.
-pub(crate) trait Rule {
+pub trait Rule {
     fn metadata(&self) -> RuleMetadata;
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>>;
+
+    /// Analyze the whole program. Rules with a `visitor` get a default that visits the
+    /// analysis-target classes with it alone, keeping the results of the classes that
+    /// did not fail.
+    fn run(&self, context: &AnalysisContext) -> Result<RuleOutput> {
+        let Some(visitor) = self.visitor() else {
+            return Ok(RuleOutput::default());
+        };
+        let mut outputs = visit_target_classes(context, &[(&self.metadata().id, visitor)]);
+        let output = outputs.pop().unwrap_or_default();
+        Ok(RuleOutput {
+            results: output.results,
+            failures: output.failures,
+        })
+    }
+
+    /// Callbacks the engine calls during its shared traversal of analysis-target
+    /// classes, in place of `run`.
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        None
+    }
 }
 
-pub(crate) fn method_location_with_line(
+/// Per-class callbacks for rules that judge each analysis-target class on its own.
+/// Classes from the classpath are never visited.
+pub trait RuleVisitor: Sync {
+    fn visit_class(
+        &self,
+        _context: &AnalysisContext,
+        _class: &Class,
+        _results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_method(
+        &self,
+        _context: &AnalysisContext,
+        _class: &Class,
+        _method: &Method,
+        _results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for every instruction of the method's control flow graph, after
+    /// `visit_method`.
+    fn visit_instruction(
+        &self,
+        _context: &AnalysisContext,
+        _class: &Class,
+        _method: &Method,
+        _instruction: &Instruction,
+        _results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub fn method_location_with_line(
     class_name: &str,
     method_name: &str,
     descriptor: &str,
@@ -36,24 +186,24 @@ pub(crate) fn method_location_with_line(
     line: Option<u32>,
 ) -> Location {
     let logical = method_logical_location(class_name, method_name, descriptor);
-    if let (Some(uri), Some(line)) = (artifact_uri, line) {
-        if uri.ends_with(".class") {
-            let region = Region::builder().start_line(line as i64).build();
-            let artifact_location = ArtifactLocation::builder().uri(uri.to_string()).build();
-            let physical = PhysicalLocation::builder()
-                .artifact_location(artifact_location)
-                .region(region)
-                .build();
-            return Location::builder()
-                .logical_locations(vec![logical])
-                .physical_location(physical)
-                .build();
-        }
+    if let (Some(uri), Some(line)) = (artifact_uri, line)
+        && uri.ends_with(".class")
+    {
+        let region = Region::builder().start_line(line as i64).build();
+        let artifact_location = ArtifactLocation::builder().uri(uri.to_string()).build();
+        let physical = PhysicalLocation::builder()
+            .artifact_location(artifact_location)
+            .region(region)
+            .build();
+        return Location::builder()
+            .logical_locations(vec![logical])
+            .physical_location(physical)
+            .build();
     }
     Location::builder().logical_locations(vec![logical]).build()
 }
 
-pub(crate) fn method_logical_location(
+pub fn method_logical_location(
     class_name: &str,
     method_name: &str,
     descriptor: &str,
@@ -64,7 +214,7 @@ pub(crate) fn method_logical_location(
         .build()
 }
 
-pub(crate) fn class_location(class_name: &str) -> Location {
+pub fn class_location(class_name: &str) -> Location {
     let logical = LogicalLocation::builder()
         .name(class_name)
         .kind("type")
@@ -72,6 +222,6 @@ pub(crate) fn class_location(class_name: &str) -> Location {
     Location::builder().logical_locations(vec![logical]).build()
 }
 
-pub(crate) fn result_message(text: impl Into<String>) -> Message {
+pub fn result_message(text: impl Into<String>) -> Message {
     Message::builder().text(text.into()).build()
 }
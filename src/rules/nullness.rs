@@ -1,13 +1,20 @@
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
-
-use crate::descriptor::{ReturnKind, method_param_count, method_return_kind};
-use crate::engine::AnalysisContext;
-use crate::ir::{CallKind, Class, Method, Nullness};
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
+
+use crate::callgraph::MethodId;
+use crate::dataflow::{self, Analysis, EdgeContext};
+use crate::descriptor::method_param_count;
+use crate::engine::{AnalysisContext, isolate};
+use crate::interpreter::{Fact, FactHooks, Frame, Interpreter, Slot, ValueType};
+use crate::ir::{BasicBlock, Class, EdgeKind, Instruction, Method, Nullness};
 use crate::opcodes;
-use crate::rules::{Rule, RuleMetadata, method_location_with_line, result_message};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleOutput, method_location_with_line, result_message,
+};
+use crate::summary::{AlwaysThrows, AlwaysThrowsAnalysis, Summaries, SummaryAnalysis};
 
 // TODO: refer Checkerframework stubs or somthing like it to handle nellness of standard APIs
 
@@ -17,18 +24,33 @@ pub(crate) struct NullnessRule;
 impl Rule for NullnessRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "NULLNESS",
-            name: "Nullness checks",
-            description: "Nullness issues guided by JSpecify annotations",
+            id: "NULLNESS".into(),
+            name: "Nullness checks".into(),
+            description: "Nullness issues guided by JSpecify annotations".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/NULLNESS.md").into(),
+            cwe: &[476],
+            precision: Precision::Medium,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
+    /// Analyze every method of the analysis target; a method that fails is recorded
+    /// and skipped.
+    fn run(&self, context: &AnalysisContext) -> Result<RuleOutput> {
+        let rule_id = self.metadata().id;
         let mut results = Vec::new();
+        let mut failures = Vec::new();
         let mut class_map = BTreeMap::new();
         for class in &context.classes {
             class_map.insert(class.name.clone(), class);
         }
+        let throws = context.summaries(&AlwaysThrowsAnalysis);
+        let summaries = context.summaries(&ReturnNullnessAnalysis {
+            class_map: &class_map,
+            throws: &throws,
+        });
 
         for class in &context.classes {
             if !context.is_analysis_target_class(class) {
@@ -40,16 +62,24 @@ impl Rule for NullnessRule {
                     continue;
                 }
                 let artifact_uri = context.class_artifact_uri(class);
-                results.extend(check_method_flow(
-                    class,
-                    method,
-                    &class_map,
-                    artifact_uri.as_deref(),
-                )?);
+                let checked = isolate(&rule_id, Some(class), Some(method), || {
+                    check_method_flow(
+                        class,
+                        method,
+                        &class_map,
+                        &summaries,
+                        &throws,
+                        artifact_uri.as_deref(),
+                    )
+                });
+                match checked {
+                    Ok(method_results) => results.extend(method_results),
+                    Err(failure) => failures.push(failure),
+                }
             }
         }
 
-        Ok(results)
+        Ok(RuleOutput { results, failures })
     }
 }
 
@@ -156,408 +186,394 @@ fn check_method_flow(
     class: &Class,
     method: &Method,
     class_map: &BTreeMap<String, &Class>,
+    summaries: &Summaries<ReturnNullness>,
+    throws: &Summaries<AlwaysThrows>,
     artifact_uri: Option<&str>,
 ) -> Result<Vec<SarifResult>> {
-    let mut results = Vec::new();
-    let mut callsite_by_offset = BTreeMap::new();
-    for call in &method.calls {
-        callsite_by_offset.insert(call.offset, call);
+    let analysis = NullnessAnalysis::new(class, method, class_map, summaries, throws);
+    let flow = dataflow::solve(method, &analysis)?;
+    if !flow.converged {
+        return Ok(Vec::new());
     }
 
-    let local_count = local_count(method)?;
-    let mut initial_locals = vec![Nullness::Unknown; local_count];
-    if !method.access.is_static && !initial_locals.is_empty() {
-        initial_locals[0] = Nullness::NonNull;
-    }
-    let base_index = if method.access.is_static { 0 } else { 1 };
-    for (index, nullness) in method.nullness.parameter_nullness.iter().enumerate() {
-        let local_index = base_index + index;
-        if let Some(local) = initial_locals.get_mut(local_index) {
-            *local = *nullness;
+    let mut results = Vec::new();
+    flow.replay(method, &analysis, |block, inst, frame| {
+        if analysis.is_unreachable(block, inst) {
+            return Ok(());
         }
-    }
-    let entry_state = State {
-        locals: initial_locals,
-        stack: Vec::new(),
-    };
-
-    let mut block_map = BTreeMap::new();
-    for block in &method.cfg.blocks {
-        block_map.insert(block.start_offset, block);
-    }
-    let mut predecessors: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
-    let mut successors: BTreeMap<u32, Vec<(u32, crate::ir::EdgeKind)>> = BTreeMap::new();
-    for edge in &method.cfg.edges {
-        predecessors.entry(edge.to).or_default().push(edge.from);
-        successors
-            .entry(edge.from)
-            .or_default()
-            .push((edge.to, edge.kind));
-    }
-
-    let mut in_states: BTreeMap<u32, State> = BTreeMap::new();
-    let mut out_states: BTreeMap<u32, State> = BTreeMap::new();
-    let mut worklist = VecDeque::new();
-    if block_map.contains_key(&0) {
-        in_states.insert(0, entry_state.clone());
-        worklist.push_back(0);
-    }
-
-    while let Some(block_start) = worklist.pop_front() {
-        let Some(block) = block_map.get(&block_start) else {
-            continue;
-        };
-        let in_state = match predecessors.get(&block_start) {
-            Some(preds) if block_start != 0 => {
-                let mut merged: Option<State> = None;
-                for pred in preds {
-                    if let Some(state) = out_states.get(pred) {
-                        merged = Some(match merged {
-                            Some(existing) => join_states(&existing, state),
-                            None => state.clone(),
-                        });
-                    }
-                }
-                merged.unwrap_or_else(|| in_states[&block_start].clone())
-            }
-            _ => in_states
-                .get(&block_start)
-                .cloned()
-                .unwrap_or_else(|| entry_state.clone()),
-        };
-
-        let transfer = transfer_block(
-            class,
-            method,
-            block,
-            &in_state,
-            &callsite_by_offset,
-            class_map,
-            artifact_uri,
-        )?;
-        let out_state = transfer.out_state.clone();
-        out_states.insert(block_start, out_state.clone());
-
-        if let Some(succs) = successors.get(&block_start) {
-            for (succ, kind) in succs {
-                let mut next_state = out_state.clone();
-                if let Some(refinement) = transfer.branch_refinement.as_ref() {
-                    if matches!(kind, crate::ir::EdgeKind::Branch) {
-                        next_state = refinement.apply_to(&next_state, BranchKind::Branch);
-                    } else if matches!(kind, crate::ir::EdgeKind::FallThrough) {
-                        next_state = refinement.apply_to(&next_state, BranchKind::FallThrough);
-                    }
-                }
-                let updated = match in_states.get(succ) {
-                    Some(existing) => join_states(existing, &next_state),
-                    None => next_state,
+        let (message, level) = match inst.opcode {
+            opcodes::INVOKEVIRTUAL | opcodes::INVOKEINTERFACE | opcodes::INVOKESPECIAL => {
+                let Some(call) = analysis.interpreter.call_site(inst) else {
+                    return Ok(());
                 };
-                let should_push = match in_states.get(succ) {
-                    Some(existing) => &updated != existing,
-                    None => true,
+                let arg_count = method_param_count(&call.descriptor)?;
+                let Some(receiver) = frame.peek(arg_count).map(|slot| &slot.fact) else {
+                    return Ok(());
                 };
-                in_states.insert(*succ, updated);
-                if should_push {
-                    worklist.push_back(*succ);
+                if receiver.nullness != Nullness::Nullable {
+                    return Ok(());
+                }
+                if receiver.is_null {
+                    let message = format!(
+                        "Nullness issue: null receiver in call to {}.{}{}",
+                        call.owner, call.name, call.descriptor
+                    );
+                    (message, ResultLevel::Error)
+                } else {
+                    let message = format!(
+                        "Nullness issue: possible null receiver in call to {}.{}{}",
+                        call.owner, call.name, call.descriptor
+                    );
+                    (message, ResultLevel::Warning)
                 }
             }
-        }
-
-        results.extend(transfer.results);
-    }
+            opcodes::ARETURN => {
+                let returned = frame.peek(0).map(|slot| slot.fact.nullness);
+                if method.nullness.return_nullness != Nullness::NonNull
+                    || returned != Some(Nullness::Nullable)
+                {
+                    return Ok(());
+                }
+                let message = format!(
+                    "Nullness issue: {}.{}{} returns null but is @NonNull",
+                    class.name, method.name, method.descriptor
+                );
+                (message, ResultLevel::Warning)
+            }
+            _ => return Ok(()),
+        };
+        let line = method.line_for_offset(inst.offset);
+        let location = method_location_with_line(
+            &class.name,
+            &method.name,
+            &method.descriptor,
+            artifact_uri,
+            line,
+        );
+        let mut result = SarifResult::builder()
+            .message(result_message(message))
+            .locations(vec![location])
+            .build();
+        result.level = Some(level);
+        results.push(result);
+        Ok(())
+    })?;
 
     Ok(results)
 }
 
-/// Nullness state at a program point.
-#[derive(Clone, Debug, PartialEq)]
-struct State {
-    locals: Vec<Nullness>,
-    stack: Vec<StackValue>,
-}
-
-/// Operand stack entry with optional local aliasing metadata.
+/// Nullness of a value, with the local it was loaded from for branch refinement.
 #[derive(Clone, Debug, PartialEq)]
-struct StackValue {
+struct NullFact {
     nullness: Nullness,
     local: Option<usize>,
+    /// The value is `null` on every path, not merely possibly null.
+    is_null: bool,
 }
 
-/// Transfer output for a basic block, including emitted results.
-#[derive(Clone)]
-struct BlockTransfer {
-    out_state: State,
-    branch_refinement: Option<BranchRefinement>,
-    results: Vec<SarifResult>,
-}
+impl NullFact {
+    fn of(nullness: Nullness) -> Self {
+        Self {
+            nullness,
+            local: None,
+            is_null: false,
+        }
+    }
 
-/// Nullness refinement applied on conditional branch edges.
-#[derive(Clone)]
-struct BranchRefinement {
-    local: usize,
-    branch_nullness: Nullness,
-    fallthrough_nullness: Nullness,
+    fn null() -> Self {
+        Self {
+            is_null: true,
+            ..Self::of(Nullness::Nullable)
+        }
+    }
 }
 
-enum BranchKind {
-    Branch,
-    FallThrough,
+impl Default for NullFact {
+    fn default() -> Self {
+        Self::of(Nullness::Unknown)
+    }
 }
 
-impl BranchRefinement {
-    fn apply_to(&self, state: &State, kind: BranchKind) -> State {
-        let mut next = state.clone();
-        let value = match kind {
-            BranchKind::Branch => self.branch_nullness,
-            BranchKind::FallThrough => self.fallthrough_nullness,
-        };
-        if let Some(local) = next.locals.get_mut(self.local) {
-            *local = value;
+impl Fact for NullFact {
+    fn join(&self, other: &Self) -> Self {
+        Self {
+            nullness: join_nullness(self.nullness, other.nullness),
+            local: if self.local == other.local {
+                self.local
+            } else {
+                None
+            },
+            is_null: self.is_null && other.is_null,
         }
-        next
     }
 }
 
-fn transfer_block(
-    class: &Class,
-    method: &Method,
-    block: &crate::ir::BasicBlock,
-    input: &State,
-    callsite_by_offset: &BTreeMap<u32, &crate::ir::CallSite>,
-    class_map: &BTreeMap<String, &Class>,
-    artifact_uri: Option<&str>,
-) -> Result<BlockTransfer> {
-    let mut state = input.clone();
-    let mut results = Vec::new();
-    let mut branch_refinement = None;
-    for (index, inst) in block.instructions.iter().enumerate() {
-        let is_last = index + 1 == block.instructions.len();
-        match inst.opcode {
-            opcodes::ACONST_NULL => {
-                state.stack.push(StackValue {
-                    nullness: Nullness::Nullable,
-                    local: None,
-                });
-            }
-            opcodes::ALOAD => {
-                let local_index = method
-                    .bytecode
-                    .get(inst.offset as usize + 1)
-                    .copied()
-                    .unwrap_or(0) as usize;
-                let nullness = state
-                    .locals
-                    .get(local_index)
-                    .copied()
-                    .unwrap_or(Nullness::Unknown);
-                state.stack.push(StackValue {
-                    nullness,
-                    local: Some(local_index),
-                });
-            }
-            opcodes::ALOAD_0 | opcodes::ALOAD_1 | opcodes::ALOAD_2 | opcodes::ALOAD_3 => {
-                let local_index = (inst.opcode - opcodes::ALOAD_0) as usize;
-                let nullness = state
-                    .locals
-                    .get(local_index)
-                    .copied()
-                    .unwrap_or(Nullness::Unknown);
-                state.stack.push(StackValue {
-                    nullness,
-                    local: Some(local_index),
-                });
-            }
-            opcodes::ASTORE => {
-                let local_index = method
-                    .bytecode
-                    .get(inst.offset as usize + 1)
-                    .copied()
-                    .unwrap_or(0) as usize;
-                let value = state.stack.pop().unwrap_or(StackValue {
-                    nullness: Nullness::Unknown,
-                    local: None,
-                });
-                if let Some(local) = state.locals.get_mut(local_index) {
-                    *local = value.nullness;
-                }
-            }
-            opcodes::ASTORE_0 | opcodes::ASTORE_1 | opcodes::ASTORE_2 | opcodes::ASTORE_3 => {
-                let local_index = (inst.opcode - opcodes::ASTORE_0) as usize;
-                let value = state.stack.pop().unwrap_or(StackValue {
-                    nullness: Nullness::Unknown,
-                    local: None,
-                });
-                if let Some(local) = state.locals.get_mut(local_index) {
-                    *local = value.nullness;
-                }
-            }
-            opcodes::POP => {
-                state.stack.pop();
+/// Forward nullness analysis over a single method body.
+struct NullnessAnalysis<'a> {
+    method_id: MethodId,
+    method: &'a Method,
+    interpreter: Interpreter<'a>,
+    class_map: &'a BTreeMap<String, &'a Class>,
+    summaries: &'a Summaries<ReturnNullness>,
+    /// Offset of the first call to a method that always throws, by start offset of
+    /// its block; the rest of the block and its successors are never reached.
+    throwing_calls: BTreeMap<u32, u32>,
+    /// Treat unannotated parameters as non-null and null checks of non-null values
+    /// as dead, so helpers returning null only for null input are not nullable.
+    assume_nonnull_parameters: bool,
+}
+
+impl<'a> NullnessAnalysis<'a> {
+    fn new(
+        class: &'a Class,
+        method: &'a Method,
+        class_map: &'a BTreeMap<String, &'a Class>,
+        summaries: &'a Summaries<ReturnNullness>,
+        throws: &Summaries<AlwaysThrows>,
+    ) -> Self {
+        let method_id = MethodId {
+            class_name: class.name.clone(),
+            name: method.name.clone(),
+            descriptor: method.descriptor.clone(),
+        };
+        let mut throwing_calls = BTreeMap::new();
+        for call in &method.calls {
+            if !throws.always_throws(&method_id, call) {
+                continue;
             }
-            opcodes::DUP => {
-                if let Some(top) = state.stack.last().cloned() {
-                    state.stack.push(top);
-                }
+            let block = method.cfg.blocks.iter().find(|block| {
+                block
+                    .instructions
+                    .iter()
+                    .any(|inst| inst.offset == call.offset)
+            });
+            if let Some(block) = block {
+                let first = throwing_calls
+                    .entry(block.start_offset)
+                    .or_insert(call.offset);
+                *first = (*first).min(call.offset);
             }
-            opcodes::NEW => {
-                state.stack.push(StackValue {
-                    nullness: Nullness::NonNull,
-                    local: None,
-                });
-            }
-            opcodes::IFNULL | opcodes::IFNONNULL => {
-                let value = state.stack.pop().unwrap_or(StackValue {
-                    nullness: Nullness::Unknown,
-                    local: None,
-                });
-                if is_last {
-                    if let Some(local) = value.local {
-                        let (branch_nullness, fallthrough_nullness) =
-                            if inst.opcode == opcodes::IFNULL {
-                                (Nullness::Nullable, Nullness::NonNull)
-                            } else {
-                                (Nullness::NonNull, Nullness::Nullable)
-                            };
-                        branch_refinement = Some(BranchRefinement {
-                            local,
-                            branch_nullness,
-                            fallthrough_nullness,
-                        });
-                    }
+        }
+        Self {
+            method_id,
+            method,
+            interpreter: Interpreter::new(&class.name, method),
+            class_map,
+            summaries,
+            throwing_calls,
+            assume_nonnull_parameters: false,
+        }
+    }
+
+    /// Whether `inst` follows a call in `block` that never returns.
+    fn is_unreachable(&self, block: &BasicBlock, inst: &Instruction) -> bool {
+        self.throwing_calls
+            .get(&block.start_offset)
+            .is_some_and(|call| inst.offset > *call)
+    }
+
+    /// Declared return nullness of the callee, or what its body returns when it
+    /// has no annotation. A virtual call is nullable only when every target may
+    /// return null.
+    fn call_nullness(&self, call: &crate::ir::CallSite) -> Nullness {
+        match lookup_return_nullness(self.class_map, call) {
+            Some(Nullness::Unknown) | None => self
+                .summaries
+                .at_call(&self.method_id, call)
+                .into_iter()
+                .map(|summary| summary.0)
+                .reduce(join_nullness)
+                .unwrap_or(Nullness::Unknown),
+            Some(nullness) => nullness,
+        }
+    }
+}
+
+impl FactHooks<NullFact> for NullnessAnalysis<'_> {
+    fn parameter(&self, index: Option<usize>, _ty: &ValueType) -> NullFact {
+        let nullness = match index {
+            None => Nullness::NonNull,
+            Some(index) => match self.method.nullness.parameter_nullness.get(index) {
+                Some(Nullness::Unknown) | None if self.assume_nonnull_parameters => {
+                    Nullness::NonNull
                 }
-            }
+                Some(nullness) => *nullness,
+                None => Nullness::Unknown,
+            },
+        };
+        NullFact::of(nullness)
+    }
+
+    fn produce(&self, inst: &Instruction, ty: &ValueType, _inputs: &[Slot<NullFact>]) -> NullFact {
+        let nullness = match inst.opcode {
+            opcodes::ACONST_NULL => return NullFact::null(),
+            opcodes::NEW
+            | opcodes::NEWARRAY
+            | opcodes::ANEWARRAY
+            | opcodes::MULTIANEWARRAY
+            | opcodes::LDC
+            | opcodes::LDC_W => Nullness::NonNull,
             opcodes::INVOKEVIRTUAL
             | opcodes::INVOKEINTERFACE
             | opcodes::INVOKESPECIAL
-            | opcodes::INVOKESTATIC => {
-                let call = callsite_by_offset.get(&inst.offset).copied();
-                if let Some(call) = call {
-                    let arg_count = method_param_count(&call.descriptor)?;
-                    for _ in 0..arg_count {
-                        state.stack.pop();
-                    }
-                    if call.kind != CallKind::Static {
-                        let receiver = state.stack.pop().unwrap_or(StackValue {
-                            nullness: Nullness::Unknown,
-                            local: None,
-                        });
-                        if receiver.nullness == Nullness::Nullable {
-                            let message = result_message(format!(
-                                "Nullness issue: possible null receiver in call to {}.{}{}",
-                                call.owner, call.name, call.descriptor
-                            ));
-                            let line = method.line_for_offset(inst.offset);
-                            let location = method_location_with_line(
-                                &class.name,
-                                &method.name,
-                                &method.descriptor,
-                                artifact_uri,
-                                line,
-                            );
-                            results.push(
-                                SarifResult::builder()
-                                    .message(message)
-                                    .locations(vec![location])
-                                    .build(),
-                            );
-                        }
-                    }
-                    if method_return_kind(&call.descriptor)? == ReturnKind::Reference {
-                        let return_nullness =
-                            lookup_return_nullness(class_map, call).unwrap_or(Nullness::Unknown);
-                        state.stack.push(StackValue {
-                            nullness: return_nullness,
-                            local: None,
-                        });
-                    }
-                }
-            }
-            opcodes::ARETURN => {
-                let value = state.stack.pop().unwrap_or(StackValue {
-                    nullness: Nullness::Unknown,
-                    local: None,
-                });
-                if method.nullness.return_nullness == Nullness::NonNull
-                    && value.nullness == Nullness::Nullable
-                {
-                    let message = result_message(format!(
-                        "Nullness issue: {}.{}{} returns null but is @NonNull",
-                        class.name, method.name, method.descriptor
-                    ));
-                    let line = method.line_for_offset(inst.offset);
-                    let location = method_location_with_line(
-                        &class.name,
-                        &method.name,
-                        &method.descriptor,
-                        artifact_uri,
-                        line,
-                    );
-                    results.push(
-                        SarifResult::builder()
-                            .message(message)
-                            .locations(vec![location])
-                            .build(),
-                    );
-                }
+            | opcodes::INVOKESTATIC
+                if ty.is_reference() =>
+            {
+                self.interpreter
+                    .call_site(inst)
+                    .map(|call| self.call_nullness(call))
+                    .unwrap_or(Nullness::Unknown)
             }
-            _ => {}
+            _ => Nullness::Unknown,
+        };
+        NullFact::of(nullness)
+    }
+
+    fn load(&self, index: usize, slot: &Slot<NullFact>) -> NullFact {
+        NullFact {
+            local: Some(index),
+            ..slot.fact.clone()
         }
     }
 
-    Ok(BlockTransfer {
-        out_state: state,
-        branch_refinement,
-        results,
-    })
+    fn store(&self, _index: usize, slot: &Slot<NullFact>) -> NullFact {
+        NullFact {
+            local: None,
+            ..slot.fact.clone()
+        }
+    }
 }
 
-fn local_count(method: &Method) -> Result<usize> {
-    let mut max_index = 0usize;
-    let mut offset = 0usize;
-    while offset < method.bytecode.len() {
-        let opcode = method.bytecode[offset];
-        if opcode == opcodes::ALOAD || opcode == opcodes::ASTORE {
-            if let Some(index) = method.bytecode.get(offset + 1) {
-                max_index = max_index.max(*index as usize);
-            }
-        } else if opcode >= opcodes::ALOAD_0 && opcode <= opcodes::ALOAD_3 {
-            max_index = max_index.max((opcode - opcodes::ALOAD_0) as usize);
-        } else if opcode >= opcodes::ASTORE_0 && opcode <= opcodes::ASTORE_3 {
-            max_index = max_index.max((opcode - opcodes::ASTORE_0) as usize);
+impl Analysis for NullnessAnalysis<'_> {
+    type Domain = Frame<NullFact>;
+
+    fn boundary_state(&self) -> Result<Frame<NullFact>> {
+        self.interpreter.entry_frame(self)
+    }
+
+    fn join(&self, left: &Frame<NullFact>, right: &Frame<NullFact>) -> Frame<NullFact> {
+        left.join(right)
+    }
+
+    fn transfer(&self, frame: &mut Frame<NullFact>, inst: &Instruction) -> Result<()> {
+        self.interpreter.execute(frame, inst, self)
+    }
+
+    fn exception_state(&self, frame: &Frame<NullFact>) -> Frame<NullFact> {
+        frame.exception_frame(NullFact::of(Nullness::NonNull))
+    }
+
+    fn refine_edge(
+        &self,
+        edge: &EdgeContext<'_, Frame<NullFact>>,
+        mut frame: Frame<NullFact>,
+    ) -> Option<Frame<NullFact>> {
+        if edge.edge.kind != EdgeKind::Exception
+            && self.throwing_calls.contains_key(&edge.edge.from)
+        {
+            return None;
+        }
+        let (Some(terminator), Some(before)) = (edge.terminator, edge.before_terminator) else {
+            return Some(frame);
+        };
+        if !matches!(terminator.opcode, opcodes::IFNULL | opcodes::IFNONNULL) {
+            return Some(frame);
         }
-        let length = crate::scan::opcode_length(&method.bytecode, offset)?;
-        offset += length;
+        let Some(tested) = before.peek(0) else {
+            return Some(frame);
+        };
+        let taken = edge.edge.kind == EdgeKind::Branch;
+        let is_null = (terminator.opcode == opcodes::IFNULL) == taken;
+        if self.assume_nonnull_parameters && is_null && tested.fact.nullness == Nullness::NonNull {
+            return None;
+        }
+        let Some(local) = tested.fact.local else {
+            return Some(frame);
+        };
+        if let Some(slot) = frame.locals.get_mut(local) {
+            slot.fact = if is_null {
+                NullFact::null()
+            } else {
+                NullFact::of(Nullness::NonNull)
+            };
+        }
+        Some(frame)
     }
-    let param_count = method_param_count(&method.descriptor)?;
-    let base = if method.access.is_static { 0 } else { 1 };
-    Ok(max_index.max(base + param_count).saturating_add(1))
 }
 
-fn join_states(left: &State, right: &State) -> State {
-    let max_locals = left.locals.len().max(right.locals.len());
-    let mut locals = Vec::with_capacity(max_locals);
-    for index in 0..max_locals {
-        let l = left.locals.get(index).copied().unwrap_or(Nullness::Unknown);
-        let r = right
-            .locals
-            .get(index)
-            .copied()
-            .unwrap_or(Nullness::Unknown);
-        locals.push(join_nullness(l, r));
-    }
-    let stack = if left.stack.len() == right.stack.len() {
-        left.stack
-            .iter()
-            .zip(right.stack.iter())
-            .map(|(l, r)| StackValue {
-                nullness: join_nullness(l.nullness, r.nullness),
-                local: if l.local == r.local { l.local } else { None },
-            })
-            .collect()
-    } else {
-        Vec::new()
-    };
-    State { locals, stack }
+/// What a method body may return. Joining return paths keeps the weakest
+/// guarantee: non-null, then unknown, then nullable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ReturnNullness(Nullness);
+
+impl ReturnNullness {
+    const NON_NULL: Self = Self(Nullness::NonNull);
+
+    fn join(self, other: Self) -> Self {
+        let rank = |nullness| match nullness {
+            Nullness::NonNull => 0,
+            Nullness::Unknown => 1,
+            Nullness::Nullable => 2,
+        };
+        if rank(other.0) > rank(self.0) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Summarizes whether a method may return null, such as a lookup returning `null`
+/// when nothing matches, so callers see it without an annotation.
+struct ReturnNullnessAnalysis<'a> {
+    class_map: &'a BTreeMap<String, &'a Class>,
+    throws: &'a Summaries<AlwaysThrows>,
+}
+
+impl SummaryAnalysis for ReturnNullnessAnalysis<'_> {
+    type Summary = ReturnNullness;
+
+    fn unknown(&self) -> ReturnNullness {
+        ReturnNullness(Nullness::Unknown)
+    }
+
+    fn initial(&self) -> ReturnNullness {
+        ReturnNullness::NON_NULL
+    }
+
+    fn summarize(
+        &self,
+        class: &Class,
+        method: &Method,
+        summaries: &Summaries<ReturnNullness>,
+    ) -> Result<ReturnNullness> {
+        let returns_reference = method
+            .descriptor
+            .rsplit(')')
+            .next()
+            .is_some_and(|ret| ret.starts_with('L') || ret.starts_with('['));
+        if !returns_reference {
+            return Ok(self.unknown());
+        }
+        let mut analysis =
+            NullnessAnalysis::new(class, method, self.class_map, summaries, self.throws);
+        analysis.assume_nonnull_parameters = true;
+        let flow = dataflow::solve(method, &analysis)?;
+        if !flow.converged {
+            return Ok(self.unknown());
+        }
+        let mut returned = ReturnNullness::NON_NULL;
+        flow.replay(method, &analysis, |block, inst, frame| {
+            if inst.opcode == opcodes::ARETURN && !analysis.is_unreachable(block, inst) {
+                let nullness = frame
+                    .peek(0)
+                    .map_or(Nullness::Unknown, |slot| slot.fact.nullness);
+                returned = returned.join(ReturnNullness(nullness));
+            }
+            Ok(())
+        })?;
+        Ok(returned)
+    }
 }
 
 fn join_nullness(left: Nullness, right: Nullness) -> Nullness {
@@ -607,6 +623,8 @@ mod tests {
             access,
             nullness,
             bytecode,
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: ControlFlowGraph {
                 blocks: vec![BasicBlock {
@@ -619,6 +637,7 @@ mod tests {
             calls,
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         }
     }
 
@@ -632,6 +651,7 @@ mod tests {
             methods,
             artifact_index: 0,
             is_record: false,
+            annotations: Vec::new(),
         }
     }
 
@@ -737,6 +757,8 @@ public @interface NullnessUnspecified {}
                 parameter_nullness: Vec::new(),
             },
             bytecode: Vec::new(),
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: ControlFlowGraph {
                 blocks: Vec::new(),
@@ -745,6 +767,7 @@ public @interface NullnessUnspecified {}
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         };
         let override_method = Method {
             name: "value".to_string(),
@@ -759,6 +782,8 @@ public @interface NullnessUnspecified {}
                 parameter_nullness: Vec::new(),
             },
             bytecode: Vec::new(),
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: ControlFlowGraph {
                 blocks: Vec::new(),
@@ -767,6 +792,7 @@ public @interface NullnessUnspecified {}
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         };
         let base = class_with_methods("com/example/Base", None, vec![base_method]);
         let derived = class_with_methods(
@@ -776,7 +802,10 @@ public @interface NullnessUnspecified {}
         );
         let context = context_for(vec![base, derived]);
 
-        let results = NullnessRule.run(&context).expect("nullness rule run");
+        let results = NullnessRule
+            .run(&context)
+            .expect("nullness rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
@@ -798,6 +827,8 @@ public @interface NullnessUnspecified {}
                 parameter_nullness: vec![Nullness::Nullable],
             },
             bytecode: Vec::new(),
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: ControlFlowGraph {
                 blocks: Vec::new(),
@@ -806,6 +837,7 @@ public @interface NullnessUnspecified {}
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         };
         let override_method = Method {
             name: "set".to_string(),
@@ -820,6 +852,8 @@ public @interface NullnessUnspecified {}
                 parameter_nullness: vec![Nullness::NonNull],
             },
             bytecode: Vec::new(),
+            max_stack: 0,
+            max_locals: 0,
             line_numbers: Vec::new(),
             cfg: ControlFlowGraph {
                 blocks: Vec::new(),
@@ -828,6 +862,7 @@ public @interface NullnessUnspecified {}
             calls: Vec::new(),
             string_literals: Vec::new(),
             exception_handlers: Vec::new(),
+            annotations: Vec::new(),
         };
         let base = class_with_methods("com/example/Base", None, vec![base_method]);
         let derived = class_with_methods(
@@ -837,7 +872,10 @@ public @interface NullnessUnspecified {}
         );
         let context = context_for(vec![base, derived]);
 
-        let results = NullnessRule.run(&context).expect("nullness rule run");
+        let results = NullnessRule
+            .run(&context)
+            .expect("nullness rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
@@ -876,13 +914,110 @@ public @interface NullnessUnspecified {}
         let class = class_with_methods("com/example/ReturnNull", None, vec![method]);
         let context = context_for(vec![class]);
 
-        let results = NullnessRule.run(&context).expect("nullness rule run");
+        let results = NullnessRule
+            .run(&context)
+            .expect("nullness rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
         assert!(message.contains("returns null but is @NonNull"));
     }
 
+    #[test]
+    fn nullness_rule_keeps_findings_of_other_methods_when_one_fails() {
+        let returns_null = method_with(
+            "value",
+            "()Ljava/lang/String;",
+            MethodAccess {
+                is_public: true,
+                is_static: false,
+                is_abstract: false,
+            },
+            MethodNullness {
+                return_nullness: Nullness::NonNull,
+                parameter_nullness: Vec::new(),
+            },
+            vec![opcodes::ACONST_NULL, opcodes::ARETURN],
+            vec![
+                Instruction {
+                    offset: 0,
+                    opcode: opcodes::ACONST_NULL,
+                    kind: InstructionKind::Other(opcodes::ACONST_NULL),
+                },
+                Instruction {
+                    offset: 1,
+                    opcode: opcodes::ARETURN,
+                    kind: InstructionKind::Other(opcodes::ARETURN),
+                },
+            ],
+            Vec::new(),
+        );
+        let broken = method_with(
+            "invoke",
+            "(Ljava/lang/Object;)V",
+            MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            MethodNullness {
+                return_nullness: Nullness::Unknown,
+                parameter_nullness: vec![Nullness::Nullable],
+            },
+            vec![
+                opcodes::ALOAD_0,
+                opcodes::INVOKEVIRTUAL,
+                0x00,
+                0x01,
+                opcodes::RETURN,
+            ],
+            vec![
+                Instruction {
+                    offset: 0,
+                    opcode: opcodes::ALOAD_0,
+                    kind: InstructionKind::Other(opcodes::ALOAD_0),
+                },
+                Instruction {
+                    offset: 1,
+                    opcode: opcodes::INVOKEVIRTUAL,
+                    kind: InstructionKind::Other(opcodes::INVOKEVIRTUAL),
+                },
+                Instruction {
+                    offset: 4,
+                    opcode: opcodes::RETURN,
+                    kind: InstructionKind::Other(opcodes::RETURN),
+                },
+            ],
+            vec![CallSite {
+                owner: "com/example/Target".to_string(),
+                name: "run".to_string(),
+                descriptor: "malformed".to_string(),
+                kind: CallKind::Virtual,
+                offset: 1,
+            }],
+        );
+        let context = context_for(vec![
+            class_with_methods("com/example/Broken", None, vec![broken]),
+            class_with_methods("com/example/ReturnNull", None, vec![returns_null]),
+        ]);
+
+        let output = NullnessRule.run(&context).expect("nullness rule run");
+
+        assert_eq!(1, output.results.len());
+        let message = output.results[0].message.text.as_deref().unwrap_or("");
+        assert!(message.contains("returns null but is @NonNull"));
+        assert_eq!(1, output.failures.len());
+        let failure = &output.failures[0];
+        assert_eq!("NULLNESS", failure.rule_id);
+        assert_eq!(Some("com/example/Broken"), failure.class_name.as_deref());
+        assert_eq!(
+            Some("invoke(Ljava/lang/Object;)V"),
+            failure.method.as_deref()
+        );
+        assert!(!failure.panicked);
+    }
+
     #[test]
     fn nullness_flow_reports_nullable_receiver() {
         let method = method_with(
@@ -932,7 +1067,10 @@ public @interface NullnessUnspecified {}
         let class = class_with_methods("com/example/Caller", None, vec![method]);
         let context = context_for(vec![class]);
 
-        let results = NullnessRule.run(&context).expect("nullness rule run");
+        let results = NullnessRule
+            .run(&context)
+            .expect("nullness rule run")
+            .results;
 
         assert_eq!(1, results.len());
         let message = results[0].message.text.as_deref().unwrap_or("");
@@ -972,6 +1110,112 @@ public class Sample {
         );
     }
 
+    #[test]
+    fn nullness_rule_reports_receiver_returned_null_by_helper() {
+        let sources = vec![SourceFile {
+            path: "com/example/Users.java".to_string(),
+            contents: r#"
+package com.example;
+import java.util.Map;
+public class Users {
+    private Map<String, String> names;
+
+    private String findUser(String id) {
+        if (id.isEmpty()) {
+            return null;
+        }
+        return lookup(id);
+    }
+
+    private String lookup(String id) {
+        return names.get(id);
+    }
+
+    public int nameLength(String id) {
+        return findUser(id).length();
+    }
+
+    public int lookupLength(String id) {
+        return lookup(id).length();
+    }
+}
+"#
+            .to_string(),
+        }];
+
+        let output = analyze_with_harness(sources);
+        let messages: Vec<String> = output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("NULLNESS"))
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+
+        assert_eq!(
+            vec![
+                "Nullness issue: possible null receiver in call to java/lang/String.length()I"
+                    .to_string()
+            ],
+            messages
+        );
+    }
+
+    #[test]
+    fn nullness_rule_reports_definite_null_receiver_as_error() {
+        let sources = vec![SourceFile {
+            path: "com/example/Names.java".to_string(),
+            contents: r#"
+package com.example;
+public class Names {
+    public int length(String name) {
+        String fallback = null;
+        if (name.isEmpty()) {
+            return fallback.length();
+        }
+        return name.length();
+    }
+
+    public int trimmed(String name) {
+        if (name == null) {
+            return name.trim().length();
+        }
+        return 0;
+    }
+}
+"#
+            .to_string(),
+        }];
+
+        let output = analyze_with_harness(sources);
+        let findings: Vec<(String, Option<ResultLevel>)> = output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("NULLNESS"))
+            .map(|result| {
+                (
+                    result.message.text.clone().unwrap_or_default(),
+                    result.level,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    "Nullness issue: null receiver in call to java/lang/String.length()I"
+                        .to_string(),
+                    Some(ResultLevel::Error)
+                ),
+                (
+                    "Nullness issue: null receiver in call to java/lang/String.trim()Ljava/lang/String;"
+                        .to_string(),
+                    Some(ResultLevel::Error)
+                ),
+            ],
+            findings
+        );
+    }
+
     #[test]
     fn nullness_rule_skips_unmarked_class_returning_null() {
         let mut sources = jspecify_stubs();
@@ -1091,6 +1335,78 @@ public class Sample {
         );
     }
 
+    #[test]
+    fn nullness_rule_allows_receiver_after_null_check() {
+        let mut sources = jspecify_stubs();
+        sources.push(SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: r#"
+package com.example;
+import org.jspecify.annotations.Nullable;
+public class Sample {
+    public static void use(@Nullable String value) {
+        if (value != null) {
+            value.toString();
+        }
+    }
+}
+"#
+            .to_string(),
+        });
+
+        let output = analyze_with_harness(sources);
+        let has_nullness = output
+            .results
+            .iter()
+            .any(|result| result.rule_id.as_deref() == Some("NULLNESS"));
+        assert!(!has_nullness);
+    }
+
+    #[test]
+    fn nullness_rule_skips_code_after_failing_helper() {
+        let mut sources = jspecify_stubs();
+        sources.push(SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: r#"
+package com.example;
+import java.util.HashMap;
+import java.util.Map;
+import org.jspecify.annotations.NullMarked;
+@NullMarked
+public class Sample {
+    private final Map<String, String> values = new HashMap<>();
+
+    public String value(String key) {
+        String value = values.get(key);
+        if (value == null) {
+            fail("missing " + key);
+            return null;
+        }
+        return value;
+    }
+
+    public int length(String key) {
+        return value(key).length();
+    }
+
+    private static void fail(String message) {
+        throw new IllegalArgumentException(message);
+    }
+}
+"#
+            .to_string(),
+        });
+
+        let output = analyze_with_harness(sources);
+        let messages: Vec<String> = output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("NULLNESS"))
+            .filter_map(|result| result.message.text.clone())
+            .collect();
+        assert!(messages.is_empty(), "unexpected results: {messages:?}");
+    }
+
     #[test]
     fn nullness_rule_allows_unspecified_parameter() {
         let mut sources = jspecify_stubs();
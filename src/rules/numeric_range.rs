@@ -0,0 +1,420 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
+
+use crate::engine::AnalysisContext;
+use crate::ir::{CallSite, Class, Method};
+use crate::numeric::NumericMethod;
+use crate::opcodes;
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
+use crate::ssa::{SsaInstruction, SsaMethod, ValueDef, ValueId};
+
+/// Rule that detects arithmetic and array operations that fail or misbehave
+/// for every value their operands can take.
+pub(crate) struct NumericRangeRule;
+
+impl Rule for NumericRangeRule {
+    fn metadata(&self) -> RuleMetadata {
+        RuleMetadata {
+            id: "NUMERIC_RANGE".into(),
+            name: "Numeric range errors".into(),
+            description:
+                "Division by zero, invalid array sizes and indexes, and out-of-range shifts".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Error,
+            help: include_str!("../../docs/rules/NUMERIC_RANGE.md").into(),
+            cwe: &[129, 369],
+            precision: Precision::High,
+            security_severity: None,
+        }
+    }
+
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for NumericRangeRule {
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        if method.bytecode.is_empty() {
+            return Ok(());
+        }
+        let artifact_uri = context.class_artifact_uri(class);
+        let numeric = context.numeric(class, method)?;
+        // Range findings fail on every execution; hash-based indexes only
+        // for the rare hash code that stays negative.
+        let definite = range_findings(method, &numeric)
+            .into_iter()
+            .map(|(offset, text)| (offset, text, ResultLevel::Error));
+        let possible = abs_hash_indexes(context, class, method)?
+            .into_iter()
+            .map(|(offset, text)| (offset, text, ResultLevel::Warning));
+        for (offset, text, level) in definite.chain(possible) {
+            let message = result_message(format!(
+                "{}: {}.{}{}",
+                text, class.name, method.name, method.descriptor
+            ));
+            let line = method.line_for_offset(offset);
+            let location = method_location_with_line(
+                &class.name,
+                &method.name,
+                &method.descriptor,
+                artifact_uri.as_deref(),
+                line,
+            );
+            let mut result = SarifResult::builder()
+                .message(message)
+                .locations(vec![location])
+                .build();
+            result.level = Some(level);
+            results.push(result);
+        }
+        Ok(())
+    }
+}
+
+/// Operations whose operand ranges make them fail or misbehave on every execution.
+fn range_findings(method: &Method, numeric: &NumericMethod) -> Vec<(u32, String)> {
+    let mut findings = Vec::new();
+    for inst in method
+        .cfg
+        .blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+    {
+        let operand = |depth: usize| numeric.operand(inst.offset, depth);
+        let text = match inst.opcode {
+            opcodes::IDIV | opcodes::LDIV | opcodes::IREM | opcodes::LREM => {
+                let Some(divisor) = operand(0) else {
+                    continue;
+                };
+                if divisor.range.as_constant() != Some(0) {
+                    continue;
+                }
+                if matches!(inst.opcode, opcodes::IDIV | opcodes::LDIV) {
+                    "Division by zero".to_string()
+                } else {
+                    "Remainder by zero".to_string()
+                }
+            }
+            opcodes::NEWARRAY | opcodes::ANEWARRAY | opcodes::MULTIANEWARRAY => {
+                let dimensions = if inst.opcode == opcodes::MULTIANEWARRAY {
+                    method
+                        .bytecode
+                        .get(inst.offset as usize + 3)
+                        .copied()
+                        .unwrap_or(1) as usize
+                } else {
+                    1
+                };
+                let Some(size) = (0..dimensions)
+                    .filter_map(&operand)
+                    .find(|size| size.range.max < 0)
+                else {
+                    continue;
+                };
+                format!("Array allocated with negative size {}", size.range)
+            }
+            opcodes::IALOAD..=opcodes::SALOAD | opcodes::IASTORE..=opcodes::SASTORE => {
+                let depth = usize::from(inst.opcode >= opcodes::IASTORE);
+                let (Some(array), Some(index)) = (operand(depth + 1), operand(depth)) else {
+                    continue;
+                };
+                if index.range.max >= 0 && index.range.min < array.length.max {
+                    continue;
+                }
+                format!(
+                    "Array index {} out of bounds for length {}",
+                    index.range, array.length
+                )
+            }
+            opcodes::ISHL..=opcodes::LUSHR => {
+                let Some(distance) = operand(0) else {
+                    continue;
+                };
+                let (type_name, width) =
+                    if matches!(inst.opcode, opcodes::ISHL | opcodes::ISHR | opcodes::IUSHR) {
+                        ("int", 32)
+                    } else {
+                        ("long", 64)
+                    };
+                if distance.range.max >= 0 && distance.range.min < width {
+                    continue;
+                }
+                format!(
+                    "Shift distance {} is outside 0..{} for {}",
+                    distance.range,
+                    width - 1,
+                    type_name
+                )
+            }
+            _ => continue,
+        };
+        findings.push((inst.offset, text));
+    }
+    findings
+}
+
+/// Array accesses indexed by `Math.abs(x.hashCode())`, optionally reduced with `%`;
+/// `Math.abs(Integer.MIN_VALUE)` is still negative.
+fn abs_hash_indexes(
+    context: &AnalysisContext,
+    class: &Class,
+    method: &Method,
+) -> Result<Vec<(u32, String)>> {
+    let calls: BTreeMap<u32, &CallSite> = method
+        .calls
+        .iter()
+        .map(|call| (call.offset, call))
+        .collect();
+    let has_abs = calls
+        .values()
+        .any(|call| call.owner == "java/lang/Math" && call.name == "abs");
+    if !has_abs {
+        return Ok(Vec::new());
+    }
+    let ssa = context.ssa(class, method)?;
+    let mut findings = Vec::new();
+    for inst in ssa.instructions() {
+        let index = match inst.opcode {
+            opcodes::IALOAD..=opcodes::SALOAD | opcodes::IASTORE..=opcodes::SASTORE => {
+                inst.operands.get(1)
+            }
+            _ => None,
+        };
+        let Some(mut index) = index.copied() else {
+            continue;
+        };
+        if let Some(remainder) = defining(&ssa, index)
+            .filter(|definition| definition.opcode == opcodes::IREM)
+            .and_then(|definition| definition.operands.first())
+        {
+            index = *remainder;
+        }
+        let is_abs_of_hash = call_at(&ssa, &calls, index)
+            .filter(|(call, _)| {
+                call.owner == "java/lang/Math" && call.name == "abs" && call.descriptor == "(I)I"
+            })
+            .and_then(|(_, operands)| operands.first())
+            .and_then(|hash| call_at(&ssa, &calls, *hash))
+            .is_some_and(|(call, _)| call.name == "hashCode" && call.descriptor == "()I");
+        if is_abs_of_hash {
+            findings.push((
+                inst.offset,
+                "Math.abs(hashCode()) used as an array index can be negative".to_string(),
+            ));
+        }
+    }
+    Ok(findings)
+}
+
+fn defining(ssa: &SsaMethod, value: ValueId) -> Option<&SsaInstruction> {
+    match ssa.value(value).def {
+        ValueDef::Instruction(offset) => ssa.instruction(offset),
+        _ => None,
+    }
+}
+
+/// Call that produced `value`, with the values passed to it.
+fn call_at<'a>(
+    ssa: &'a SsaMethod,
+    calls: &BTreeMap<u32, &'a CallSite>,
+    value: ValueId,
+) -> Option<(&'a CallSite, &'a [ValueId])> {
+    let definition = defining(ssa, value)?;
+    let call = calls.get(&definition.offset)?;
+    Some((call, definition.operands.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn analyze_sources(contents: &str) -> Vec<String> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: contents.to_string(),
+        }];
+        let output = harness
+            .compile_and_analyze(Language::Java, &sources, &[])
+            .expect("run harness analysis");
+        output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("NUMERIC_RANGE"))
+            .filter_map(|result| result.message.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn numeric_range_reports_division_and_remainder_by_zero() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public int divide(int value) {
+        int divisor = 0;
+        return value / divisor;
+    }
+
+    public long remainder(long value) {
+        return value % 0L;
+    }
+}
+"#,
+        );
+
+        assert_eq!(2, messages.len(), "{messages:?}");
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Division by zero: com/example/Sample.divide"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Remainder by zero: com/example/Sample.remainder"))
+        );
+    }
+
+    #[test]
+    fn numeric_range_reports_array_size_and_index_errors() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public int[] negative() {
+        int size = -2;
+        return new int[size];
+    }
+
+    public int past(boolean wide) {
+        int[] values = wide ? new int[4] : new int[3];
+        return values[4];
+    }
+
+    public void store(String[] unused) {
+        String[] names = new String[2];
+        int index = -1;
+        names[index] = "x";
+    }
+}
+"#,
+        );
+
+        assert_eq!(3, messages.len(), "{messages:?}");
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Array allocated with negative size -2"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Array index 4 out of bounds for length [3, 4]"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Array index -1 out of bounds for length 2"))
+        );
+    }
+
+    #[test]
+    fn numeric_range_reports_wide_shifts_and_abs_hash_indexes() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public int shift(int value) {
+        return value << 32;
+    }
+
+    public long shiftLong(long value) {
+        return value >>> 64;
+    }
+
+    public Object bucket(Object[] buckets, Object key) {
+        return buckets[Math.abs(key.hashCode()) % buckets.length];
+    }
+}
+"#,
+        );
+
+        assert_eq!(3, messages.len(), "{messages:?}");
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Shift distance 32 is outside 0..31 for int"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Shift distance 64 is outside 0..63 for long"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.starts_with("Math.abs(hashCode())"))
+        );
+    }
+
+    #[test]
+    fn numeric_range_ignores_guarded_and_in_range_operations() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public int sum(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.length; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+
+    public int last() {
+        int[] values = new int[3];
+        int index = 0;
+        while (index < 2) {
+            index++;
+        }
+        return values[index];
+    }
+
+    public int safeDivide(int value, int divisor) {
+        if (divisor == 0) {
+            return 0;
+        }
+        return value / divisor;
+    }
+
+    public int sign(int value) {
+        return value >> 31;
+    }
+
+    public Object bucket(Object[] buckets, Object key) {
+        return buckets[Math.floorMod(key.hashCode(), buckets.length)];
+    }
+
+    public int absolute(Object key) {
+        return Math.abs(key.hashCode());
+    }
+}
+"#,
+        );
+
+        assert!(messages.is_empty(), "{messages:?}");
+    }
+}
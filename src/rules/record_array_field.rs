@@ -1,8 +1,11 @@
+use std::borrow::Cow;
+
 use anyhow::Result;
-use serde_sarif::sarif::Result as SarifResult;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
 
 use crate::engine::AnalysisContext;
-use crate::rules::{Rule, RuleMetadata, class_location, result_message};
+use crate::ir::Class;
+use crate::rules::{Precision, Rule, RuleMetadata, RuleVisitor, class_location, result_message};
 
 /// Rule that flags record components that use array types.
 pub(crate) struct RecordArrayFieldRule;
@@ -10,38 +13,52 @@ pub(crate) struct RecordArrayFieldRule;
 impl Rule for RecordArrayFieldRule {
     fn metadata(&self) -> RuleMetadata {
         RuleMetadata {
-            id: "RECORD_ARRAY_FIELD",
-            name: "Record array field",
-            description: "Records should not use array-typed components",
+            id: "RECORD_ARRAY_FIELD".into(),
+            name: "Record array field".into(),
+            description: "Records should not use array-typed components".into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Note,
+            help: include_str!("../../docs/rules/RECORD_ARRAY_FIELD.md").into(),
+            cwe: &[],
+            precision: Precision::VeryHigh,
+            security_severity: None,
         }
     }
 
-    fn run(&self, context: &AnalysisContext) -> Result<Vec<SarifResult>> {
-        let mut results = Vec::new();
-        for class in &context.classes {
-            if !context.is_analysis_target_class(class) || !class.is_record {
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for RecordArrayFieldRule {
+    fn visit_class(
+        &self,
+        _context: &AnalysisContext,
+        class: &Class,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        if !class.is_record {
+            return Ok(());
+        }
+        for field in &class.fields {
+            if field.access.is_static {
                 continue;
             }
-            for field in &class.fields {
-                if field.access.is_static {
-                    continue;
-                }
-                if field.descriptor.starts_with('[') {
-                    let message = result_message(format!(
-                        "Record component uses array type: {}.{} ({})",
-                        class.name, field.name, field.descriptor
-                    ));
-                    let location = class_location(&class.name);
-                    results.push(
-                        SarifResult::builder()
-                            .message(message)
-                            .locations(vec![location])
-                            .build(),
-                    );
-                }
+            if field.descriptor.starts_with('[') {
+                let message = result_message(format!(
+                    "Record component uses array type: {}.{} ({})",
+                    class.name, field.name, field.descriptor
+                ));
+                let location = class_location(&class.name);
+                results.push(
+                    SarifResult::builder()
+                        .message(message)
+                        .locations(vec![location])
+                        .build(),
+                );
             }
         }
-        Ok(results)
+        Ok(())
     }
 }
 
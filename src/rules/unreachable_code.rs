@@ -0,0 +1,589 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use anyhow::Result;
+use serde_sarif::sarif::{Result as SarifResult, ResultLevel};
+
+use crate::dataflow::{self, Analysis, EdgeContext};
+use crate::engine::AnalysisContext;
+use crate::ir::{BasicBlock, Class, ConstantValue, EdgeKind, Instruction, InstructionKind, Method};
+use crate::opcodes;
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleVisitor, method_location_with_line, result_message,
+};
+use crate::ssa::{SsaMethod, ValueDef, ValueId};
+
+/// Rule that detects unreachable bytecode and branches whose outcome is fixed.
+pub(crate) struct UnreachableCodeRule;
+
+impl Rule for UnreachableCodeRule {
+    fn metadata(&self) -> RuleMetadata {
+        RuleMetadata {
+            id: "UNREACHABLE_CODE".into(),
+            name: "Unreachable code".into(),
+            description: "Unreachable bytecode and conditional branches with a fixed outcome"
+                .into(),
+            tags: Cow::Borrowed(&["correctness"]),
+            default_level: ResultLevel::Warning,
+            help: include_str!("../../docs/rules/UNREACHABLE_CODE.md").into(),
+            cwe: &[561, 570, 571],
+            precision: Precision::High,
+            security_severity: None,
+        }
+    }
+
+    fn visitor(&self) -> Option<&dyn RuleVisitor> {
+        Some(self)
+    }
+}
+
+impl RuleVisitor for UnreachableCodeRule {
+    fn visit_method(
+        &self,
+        context: &AnalysisContext,
+        class: &Class,
+        method: &Method,
+        results: &mut Vec<SarifResult>,
+    ) -> Result<()> {
+        // Kotlin's `$default` stubs test argument masks that are often constant.
+        if method.bytecode.is_empty() || method.name.ends_with("$default") {
+            return Ok(());
+        }
+        let artifact_uri = context.class_artifact_uri(class);
+        let mut findings = unreachable_blocks(method);
+        let ssa = context.ssa(class, method)?;
+        findings.extend(fixed_branches(method, &ssa)?);
+        for (offset, text) in findings {
+            let message = result_message(format!(
+                "{}: {}.{}{}",
+                text, class.name, method.name, method.descriptor
+            ));
+            let line = method.line_for_offset(offset);
+            let location = method_location_with_line(
+                &class.name,
+                &method.name,
+                &method.descriptor,
+                artifact_uri.as_deref(),
+                line,
+            );
+            results.push(
+                SarifResult::builder()
+                    .message(message)
+                    .locations(vec![location])
+                    .build(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Start offsets of unreachable code, one finding per run of adjacent dead blocks.
+fn unreachable_blocks(method: &Method) -> Vec<(u32, String)> {
+    let mut successors: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for edge in &method.cfg.edges {
+        successors.entry(edge.from).or_default().push(edge.to);
+    }
+    let mut reached = BTreeSet::new();
+    let mut queue = VecDeque::from([0u32]);
+    while let Some(offset) = queue.pop_front() {
+        if !reached.insert(offset) {
+            continue;
+        }
+        if let Some(next) = successors.get(&offset) {
+            queue.extend(next.iter().copied());
+        }
+    }
+
+    let mut findings = Vec::new();
+    let mut previous_dead = false;
+    let mut previous: Option<&BasicBlock> = None;
+    for block in &method.cfg.blocks {
+        let dead = !reached.contains(&block.start_offset) && !is_compiler_padding(previous, block);
+        previous = Some(block);
+        if dead && !previous_dead {
+            findings.push((
+                block.start_offset,
+                format!("Unreachable code at offset {}", block.start_offset),
+            ));
+        }
+        previous_dead = dead;
+    }
+    findings
+}
+
+/// javac leaves a dead `goto` behind a `return` or `throw` ending a branch.
+fn is_compiler_padding(previous: Option<&BasicBlock>, block: &BasicBlock) -> bool {
+    let [inst] = block.instructions.as_slice() else {
+        return false;
+    };
+    matches!(inst.opcode, opcodes::GOTO | opcodes::GOTO_W)
+        && previous
+            .and_then(|previous| previous.instructions.last())
+            .is_some_and(|last| {
+                matches!(
+                    last.opcode,
+                    opcodes::IRETURN..=opcodes::RETURN | opcodes::ATHROW
+                )
+            })
+}
+
+/// Conditional branches decided by constants or by values already known to be non-null.
+fn fixed_branches(method: &Method, ssa: &SsaMethod) -> Result<Vec<(u32, String)>> {
+    let instructions: BTreeMap<u32, &Instruction> = method
+        .cfg
+        .blocks
+        .iter()
+        .flat_map(|block| block.instructions.iter())
+        .map(|inst| (inst.offset, inst))
+        .collect();
+    let analysis = NonNullAnalysis { ssa };
+    let flow = dataflow::solve(method, &analysis)?;
+    if !flow.converged {
+        return Ok(Vec::new());
+    }
+
+    let mut findings = Vec::new();
+    flow.replay(method, &analysis, |_, inst, non_null| {
+        let Some(operands) = ssa.instruction(inst.offset).map(|inst| &inst.operands) else {
+            return Ok(());
+        };
+        match inst.opcode {
+            opcodes::IFNULL | opcodes::IFNONNULL => {
+                let Some(value) = operands.first() else {
+                    return Ok(());
+                };
+                if non_null.contains(value) && !is_close_guard(ssa, &instructions, inst, *value) {
+                    findings.push((
+                        inst.offset,
+                        "Redundant null check of a value known to be non-null".to_string(),
+                    ));
+                } else if is_null_constant(ssa, &instructions, *value) {
+                    findings.push((
+                        inst.offset,
+                        "Null check of a constant null value".to_string(),
+                    ));
+                }
+            }
+            opcodes::IFEQ..=opcodes::IF_ICMPLE => {
+                let constants: Option<Vec<i32>> = operands
+                    .iter()
+                    .map(|value| int_constant(method, ssa, &instructions, *value))
+                    .collect();
+                let Some(constants) = constants else {
+                    return Ok(());
+                };
+                let taken = match constants.as_slice() {
+                    [value] => compare(inst.opcode - opcodes::IFEQ, *value, 0),
+                    [left, right] => compare(inst.opcode - opcodes::IF_ICMPEQ, *left, *right),
+                    _ => return Ok(()),
+                };
+                let outcome = if taken { "always" } else { "never" };
+                findings.push((
+                    inst.offset,
+                    format!("Branch on constant values is {} taken", outcome),
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(findings)
+}
+
+/// Whether `check` is javac's `if (resource != null) resource.close()` ending a
+/// try-with-resources statement, emitted even when the body dereferenced the resource.
+fn is_close_guard(
+    ssa: &SsaMethod,
+    instructions: &BTreeMap<u32, &Instruction>,
+    check: &Instruction,
+    value: ValueId,
+) -> bool {
+    if check.opcode != opcodes::IFNULL {
+        return false;
+    }
+    // The check falls through to a load of the resource and the call.
+    instructions
+        .range(check.offset + 1..)
+        .nth(1)
+        .is_some_and(|(offset, inst)| {
+            matches!(&inst.kind, InstructionKind::Invoke(call)
+                if call.name == "close" && call.descriptor == "()V")
+                && ssa
+                    .instruction(*offset)
+                    .and_then(|inst| inst.operands.first())
+                    == Some(&value)
+        })
+}
+
+/// Evaluate an `if<cond>`-style comparison; `condition` counts from `eq`.
+fn compare(condition: u8, left: i32, right: i32) -> bool {
+    match condition {
+        0 => left == right,
+        1 => left != right,
+        2 => left < right,
+        3 => left >= right,
+        4 => left > right,
+        _ => left <= right,
+    }
+}
+
+fn defining_instruction<'a>(
+    ssa: &SsaMethod,
+    instructions: &BTreeMap<u32, &'a Instruction>,
+    value: ValueId,
+) -> Option<&'a Instruction> {
+    match ssa.value(value).def {
+        ValueDef::Instruction(offset) => instructions.get(&offset).copied(),
+        _ => None,
+    }
+}
+
+fn is_null_constant(
+    ssa: &SsaMethod,
+    instructions: &BTreeMap<u32, &Instruction>,
+    value: ValueId,
+) -> bool {
+    defining_instruction(ssa, instructions, value)
+        .is_some_and(|inst| inst.opcode == opcodes::ACONST_NULL)
+}
+
+fn int_constant(
+    method: &Method,
+    ssa: &SsaMethod,
+    instructions: &BTreeMap<u32, &Instruction>,
+    value: ValueId,
+) -> Option<i32> {
+    let inst = defining_instruction(ssa, instructions, value)?;
+    let operand = inst.offset as usize + 1;
+    match inst.opcode {
+        opcodes::ICONST_M1..=opcodes::ICONST_5 => {
+            Some(inst.opcode as i32 - opcodes::ICONST_0 as i32)
+        }
+        opcodes::BIPUSH => method.bytecode.get(operand).map(|byte| *byte as i8 as i32),
+        opcodes::SIPUSH => {
+            let high = *method.bytecode.get(operand)?;
+            let low = *method.bytecode.get(operand + 1)?;
+            Some(i16::from_be_bytes([high, low]) as i32)
+        }
+        _ => match &inst.kind {
+            InstructionKind::Constant(ConstantValue::Int(value)) => Some(*value),
+            _ => None,
+        },
+    }
+}
+
+/// Forward analysis of SSA values that must be non-null: they were dereferenced
+/// or passed a non-null check on every path.
+struct NonNullAnalysis<'a> {
+    ssa: &'a SsaMethod,
+}
+
+impl Analysis for NonNullAnalysis<'_> {
+    type Domain = BTreeSet<ValueId>;
+
+    fn boundary_state(&self) -> Result<BTreeSet<ValueId>> {
+        Ok(BTreeSet::new())
+    }
+
+    fn join(&self, left: &BTreeSet<ValueId>, right: &BTreeSet<ValueId>) -> BTreeSet<ValueId> {
+        left.intersection(right).copied().collect()
+    }
+
+    fn transfer(&self, state: &mut BTreeSet<ValueId>, inst: &Instruction) -> Result<()> {
+        let Some(ssa_inst) = self.ssa.instruction(inst.offset) else {
+            return Ok(());
+        };
+        let dereferenced = match inst.opcode {
+            opcodes::INVOKEVIRTUAL | opcodes::INVOKEINTERFACE => ssa_inst.operands.first(),
+            opcodes::GETFIELD
+            | opcodes::PUTFIELD
+            | opcodes::ARRAYLENGTH
+            | opcodes::ATHROW
+            | opcodes::MONITORENTER
+            | opcodes::IALOAD..=opcodes::SALOAD
+            | opcodes::IASTORE..=opcodes::SASTORE => ssa_inst.operands.first(),
+            _ => None,
+        };
+        if let Some(value) = dereferenced {
+            state.insert(*value);
+        }
+        Ok(())
+    }
+
+    fn refine_edge(
+        &self,
+        edge: &EdgeContext<'_, BTreeSet<ValueId>>,
+        mut state: BTreeSet<ValueId>,
+    ) -> Option<BTreeSet<ValueId>> {
+        let Some(terminator) = edge.terminator else {
+            return Some(state);
+        };
+        if !matches!(terminator.opcode, opcodes::IFNULL | opcodes::IFNONNULL) {
+            return Some(state);
+        }
+        let taken = edge.edge.kind == EdgeKind::Branch;
+        let non_null = (terminator.opcode == opcodes::IFNONNULL) == taken;
+        if non_null
+            && let Some(value) = self
+                .ssa
+                .instruction(terminator.offset)
+                .and_then(|inst| inst.operands.first())
+        {
+            state.insert(*value);
+        }
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfg::build_cfg;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::ir::{Class, MethodAccess, MethodNullness};
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    fn analyze_sources(contents: &str) -> Vec<String> {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: contents.to_string(),
+        }];
+        let output = harness
+            .compile_and_analyze(Language::Java, &sources, &[])
+            .expect("run harness analysis");
+        output
+            .results
+            .iter()
+            .filter(|result| result.rule_id.as_deref() == Some("UNREACHABLE_CODE"))
+            .filter_map(|result| result.message.text.clone())
+            .collect()
+    }
+
+    fn method_from_bytecode(name: &str, bytecode: Vec<u8>) -> Method {
+        let mut instructions = Vec::new();
+        let mut offset = 0;
+        while offset < bytecode.len() {
+            let opcode = bytecode[offset];
+            instructions.push(Instruction {
+                offset: offset as u32,
+                opcode,
+                kind: InstructionKind::Other(opcode),
+            });
+            offset += crate::scan::opcode_length(&bytecode, offset).expect("opcode length");
+        }
+        let cfg = build_cfg(&bytecode, &instructions, &[]).expect("build cfg");
+        Method {
+            name: name.to_string(),
+            descriptor: "()I".to_string(),
+            access: MethodAccess {
+                is_public: true,
+                is_static: true,
+                is_abstract: false,
+            },
+            nullness: MethodNullness::unknown(0),
+            bytecode,
+            max_stack: 0,
+            max_locals: 0,
+            line_numbers: Vec::new(),
+            cfg,
+            calls: Vec::new(),
+            string_literals: Vec::new(),
+            exception_handlers: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    fn run_rule(methods: Vec<Method>) -> Vec<String> {
+        let class = Class {
+            name: "com/example/App".to_string(),
+            super_name: None,
+            interfaces: Vec::new(),
+            referenced_classes: Vec::new(),
+            fields: Vec::new(),
+            methods,
+            artifact_index: 0,
+            is_record: false,
+            annotations: Vec::new(),
+        };
+        let classes = vec![class];
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+        UnreachableCodeRule
+            .run(&context)
+            .expect("unreachable code rule run")
+            .results
+            .iter()
+            .filter_map(|result| result.message.text.clone())
+            .collect()
+    }
+
+    /// `iconst_0; ifeq L1; iconst_1; ireturn; L1: iconst_2; ireturn; iconst_3; ireturn`
+    fn constant_branch_bytecode() -> Vec<u8> {
+        vec![
+            opcodes::ICONST_0,
+            opcodes::IFEQ,
+            0x00,
+            0x05,
+            opcodes::ICONST_1,
+            opcodes::IRETURN,
+            opcodes::ICONST_2,
+            opcodes::IRETURN,
+            opcodes::ICONST_3,
+            opcodes::IRETURN,
+        ]
+    }
+
+    #[test]
+    fn unreachable_code_reports_dead_block_and_constant_branch() {
+        let messages = run_rule(vec![method_from_bytecode(
+            "choose",
+            constant_branch_bytecode(),
+        )]);
+
+        assert_eq!(2, messages.len(), "{messages:?}");
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.contains("Unreachable code at offset 8"))
+        );
+        assert!(messages.iter().any(|msg| msg.contains("always taken")));
+
+        // Only a `goto` right after a return is javac's padding.
+        let dead_loop = method_from_bytecode(
+            "skip",
+            vec![
+                opcodes::GOTO,
+                0x00,
+                0x06,
+                opcodes::GOTO,
+                0xff,
+                0xfd,
+                opcodes::ICONST_1,
+                opcodes::IRETURN,
+            ],
+        );
+        let messages = run_rule(vec![dead_loop]);
+        assert_eq!(1, messages.len(), "{messages:?}");
+        assert!(messages[0].contains("Unreachable code at offset 3"));
+    }
+
+    #[test]
+    fn unreachable_code_ignores_dead_goto_and_default_stubs() {
+        let dead_goto = method_from_bytecode(
+            "value",
+            vec![
+                opcodes::ICONST_1,
+                opcodes::IRETURN,
+                opcodes::GOTO,
+                0xff,
+                0xfe,
+            ],
+        );
+        let stub = method_from_bytecode("choose$default", constant_branch_bytecode());
+
+        let messages = run_rule(vec![dead_goto, stub]);
+
+        assert!(messages.is_empty(), "{messages:?}");
+    }
+
+    #[test]
+    fn unreachable_code_reports_null_check_after_dereference() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public int length(String value) {
+        int length = value.length();
+        if (value == null) {
+            return -1;
+        }
+        return length;
+    }
+}
+"#,
+        );
+
+        assert_eq!(1, messages.len(), "{messages:?}");
+        assert!(messages[0].contains("Redundant null check"));
+    }
+
+    #[test]
+    fn unreachable_code_reports_repeated_null_check() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+public class Sample {
+    public String pick(String value, Object other) {
+        Object copy = value;
+        if (value != null) {
+            if (copy != null) {
+                return value;
+            }
+        }
+        return String.valueOf(other);
+    }
+}
+"#,
+        );
+
+        assert_eq!(1, messages.len(), "{messages:?}");
+    }
+
+    #[test]
+    fn unreachable_code_ignores_checks_before_dereference() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+import java.io.FileInputStream;
+import java.io.IOException;
+public class Sample {
+    public int length(String value) {
+        if (value == null) {
+            return -1;
+        }
+        return value.length();
+    }
+
+    public int maybe(String value, boolean flag) {
+        if (flag) {
+            value.length();
+        }
+        return value == null ? 0 : 1;
+    }
+
+    public int read(String path) throws IOException {
+        try (FileInputStream in = new FileInputStream(path)) {
+            return in.read();
+        }
+    }
+}
+"#,
+        );
+
+        assert!(messages.is_empty(), "{messages:?}");
+    }
+
+    #[test]
+    fn unreachable_code_ignores_try_with_resources_close_guard() {
+        let messages = analyze_sources(
+            r#"
+package com.example;
+import java.io.IOException;
+import java.io.InputStream;
+import java.nio.file.Files;
+import java.nio.file.Path;
+public class Sample {
+    public int read(Path p) throws IOException {
+        try (InputStream in = Files.newInputStream(p)) {
+            return in.read();
+        }
+    }
+}
+"#,
+        );
+
+        assert!(messages.is_empty(), "{messages:?}");
+    }
+}
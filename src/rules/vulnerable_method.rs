@@ -0,0 +1,496 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_sarif::sarif::{
+    CodeFlow, Location, Result as SarifResult, ResultLevel, ThreadFlow, ThreadFlowLocation,
+};
+
+use crate::callgraph::{MethodId, MethodPattern};
+use crate::engine::AnalysisContext;
+use crate::ir::{Class, Method};
+use crate::rules::dead_code::{Reachability, ShortestPaths};
+use crate::rules::{
+    Precision, Rule, RuleMetadata, RuleOutput, method_location_with_line, result_message,
+};
+
+/// Library method named by a security advisory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VulnerableMethod {
+    pub pattern: MethodPattern,
+    /// Advisory identifier, such as a CVE, when the list names one.
+    pub advisory: Option<String>,
+}
+
+/// Read vulnerable methods from `path`: one `Class.method` or
+/// `Class.method(descriptor)` per line, optionally followed by an advisory
+/// identifier. `#` starts a comment.
+pub fn load_vulnerable_methods(path: &Path) -> Result<Vec<VulnerableMethod>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read vulnerable methods: {}", path.display()))?;
+    parse_vulnerable_methods(&content)
+        .with_context(|| format!("failed to parse vulnerable methods: {}", path.display()))
+}
+
+fn parse_vulnerable_methods(content: &str) -> Result<Vec<VulnerableMethod>> {
+    let mut methods = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (spec, advisory) = match fields.as_slice() {
+            [] => continue,
+            [spec] => (*spec, None),
+            [spec, advisory] => (*spec, Some(advisory.to_string())),
+            _ => anyhow::bail!("line {}: expected METHOD [ADVISORY]", index + 1),
+        };
+        let pattern = MethodPattern::parse(spec).with_context(|| format!("line {}", index + 1))?;
+        methods.push(VulnerableMethod { pattern, advisory });
+    }
+    Ok(methods)
+}
+
+/// Rule that reports vulnerable library methods reachable from entry points.
+pub(crate) struct VulnerableMethodRule {
+    pub(crate) methods: Vec<VulnerableMethod>,
+}
+
+impl Rule for VulnerableMethodRule {
+    fn metadata(&self) -> RuleMetadata {
+        RuleMetadata {
+            id: "VULNERABLE_METHOD_REACHABLE".into(),
+            name: "Reachable vulnerable method".into(),
+            description: "Known-vulnerable library methods reachable from analysis targets".into(),
+            tags: Cow::Borrowed(&["security"]),
+            default_level: ResultLevel::Error,
+            help: include_str!("../../docs/rules/VULNERABLE_METHOD_REACHABLE.md").into(),
+            cwe: &[1395],
+            precision: Precision::Medium,
+            security_severity: Some(7.5),
+        }
+    }
+
+    fn run(&self, context: &AnalysisContext) -> Result<RuleOutput> {
+        if self.methods.is_empty() {
+            return Ok(RuleOutput::default());
+        }
+        let bodies: BTreeMap<MethodId, (&Class, &Method)> = context
+            .classes
+            .iter()
+            .flat_map(|class| {
+                class.methods.iter().map(move |method| {
+                    let id = MethodId {
+                        class_name: class.name.clone(),
+                        name: method.name.clone(),
+                        descriptor: method.descriptor.clone(),
+                    };
+                    (id, (class, method))
+                })
+            })
+            .collect();
+        let reachability = Reachability::new(context, &[]);
+        let paths = reachability.shortest_paths();
+
+        let mut results = Vec::new();
+        for vulnerable in &self.methods {
+            if let Some(chain) = shortest_chain(&paths, &bodies, &vulnerable.pattern) {
+                results.push(chain_result(context, &bodies, vulnerable, &chain));
+            }
+        }
+        Ok(results.into())
+    }
+}
+
+/// Call on the way from an entry point to a vulnerable method.
+struct Hop {
+    caller: MethodId,
+    callee: MethodId,
+    offset: u32,
+}
+
+/// Shortest call chain from an entry point to a vulnerable method.
+struct Chain {
+    entry: MethodId,
+    hops: Vec<Hop>,
+}
+
+impl Chain {
+    fn target(&self) -> &MethodId {
+        self.hops.last().map_or(&self.entry, |hop| &hop.callee)
+    }
+}
+
+/// Shortest chain reaching a method matching `pattern`, either as a call graph
+/// node or through a call site of a reached method. The call site covers
+/// methods the call graph cannot resolve, such as abstract methods or classes
+/// missing from the classpath.
+fn shortest_chain(
+    paths: &ShortestPaths<'_>,
+    bodies: &BTreeMap<MethodId, (&Class, &Method)>,
+    pattern: &MethodPattern,
+) -> Option<Chain> {
+    let mut best: Option<(usize, &MethodId, Option<Hop>)> = None;
+    for (method, depth) in paths.reached() {
+        if best.as_ref().is_some_and(|(best, _, _)| *best <= depth) {
+            continue;
+        }
+        if pattern.matches_id(method) {
+            best = Some((depth, method, None));
+            continue;
+        }
+        if best.as_ref().is_some_and(|(best, _, _)| *best <= depth + 1) {
+            continue;
+        }
+        let Some((_, body)) = bodies.get(method) else {
+            continue;
+        };
+        if let Some(call) = body
+            .calls
+            .iter()
+            .find(|call| pattern.matches(&call.owner, &call.name, &call.descriptor))
+        {
+            let hop = Hop {
+                caller: method.clone(),
+                callee: MethodId {
+                    class_name: call.owner.clone(),
+                    name: call.name.clone(),
+                    descriptor: call.descriptor.clone(),
+                },
+                offset: call.offset,
+            };
+            best = Some((depth + 1, method, Some(hop)));
+        }
+    }
+
+    let (_, method, last) = best?;
+    let steps = paths.path_to(method)?;
+    let mut hops: Vec<Hop> = steps
+        .iter()
+        .filter_map(|step| step.via)
+        .map(|edge| Hop {
+            caller: edge.caller.as_ref().clone(),
+            callee: edge.callee.as_ref().clone(),
+            offset: edge.offset,
+        })
+        .collect();
+    hops.extend(last);
+    Some(Chain {
+        entry: steps[0].method.clone(),
+        hops,
+    })
+}
+
+fn chain_result(
+    context: &AnalysisContext,
+    bodies: &BTreeMap<MethodId, (&Class, &Method)>,
+    vulnerable: &VulnerableMethod,
+    chain: &Chain,
+) -> SarifResult {
+    let target = chain.target();
+    let advisory = vulnerable
+        .advisory
+        .as_deref()
+        .map(|advisory| format!(" ({advisory})"))
+        .unwrap_or_default();
+    let message = result_message(format!(
+        "Reachable vulnerable method{}: {} from {}",
+        advisory,
+        label(target),
+        label(&chain.entry)
+    ));
+
+    // Report where analysis-target code last calls toward the vulnerable method.
+    let is_target = |id: &MethodId| {
+        bodies
+            .get(id)
+            .is_some_and(|(class, _)| context.is_analysis_target_class(class))
+    };
+    let primary = match chain.hops.iter().rev().find(|hop| is_target(&hop.caller)) {
+        Some(hop) => method_location(context, bodies, &hop.caller, hop.offset),
+        None => method_location(context, bodies, &chain.entry, 0),
+    };
+
+    let mut steps: Vec<Location> = chain
+        .hops
+        .iter()
+        .map(|hop| {
+            let mut step = method_location(context, bodies, &hop.caller, hop.offset);
+            step.message = Some(result_message(format!("Calls {}", label(&hop.callee))));
+            step
+        })
+        .collect();
+    let mut last = method_location(context, bodies, target, 0);
+    last.message = Some(result_message(format!(
+        "Vulnerable method{}: {}",
+        advisory,
+        label(target)
+    )));
+    steps.push(last);
+    let thread_flow_locations: Vec<ThreadFlowLocation> = steps
+        .into_iter()
+        .enumerate()
+        .map(|(index, step)| {
+            ThreadFlowLocation::builder()
+                .location(step)
+                .nesting_level(index as i64)
+                .build()
+        })
+        .collect();
+    let code_flow = CodeFlow::builder()
+        .thread_flows(vec![
+            ThreadFlow::builder()
+                .locations(thread_flow_locations)
+                .build(),
+        ])
+        .build();
+
+    SarifResult::builder()
+        .message(message)
+        .locations(vec![primary])
+        .code_flows(vec![code_flow])
+        .build()
+}
+
+fn method_location(
+    context: &AnalysisContext,
+    bodies: &BTreeMap<MethodId, (&Class, &Method)>,
+    id: &MethodId,
+    offset: u32,
+) -> Location {
+    let (artifact_uri, line) = match bodies.get(id) {
+        Some((class, method)) => (
+            context.class_artifact_uri(class),
+            method.line_for_offset(offset),
+        ),
+        None => (None, None),
+    };
+    method_location_with_line(
+        &id.class_name,
+        &id.name,
+        &id.descriptor,
+        artifact_uri.as_deref(),
+        line,
+    )
+}
+
+fn label(id: &MethodId) -> String {
+    format!("{}.{}{}", id.class_name, id.name, id.descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::class_filter::ClassPattern;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{CompileOutput, JvmTestHarness, Language, SourceFile};
+
+    fn compile(
+        harness: &JvmTestHarness,
+        path: &str,
+        contents: &str,
+        classpath: &[PathBuf],
+    ) -> CompileOutput {
+        let sources = vec![SourceFile {
+            path: path.to_string(),
+            contents: contents.to_string(),
+        }];
+        harness
+            .compile(Language::Java, &sources, classpath)
+            .expect("compile sources")
+    }
+
+    fn compile_app_and_library() -> (CompileOutput, CompileOutput) {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let library = compile(
+            &harness,
+            "com/vendor/Parser.java",
+            r#"
+package com.vendor;
+public class Parser {
+    public Object parse(String text) {
+        return new Constructor(text).build();
+    }
+
+    public void unused() {}
+}
+class Constructor {
+    private final String text;
+
+    Constructor(String text) {
+        this.text = text;
+    }
+
+    Object build() {
+        return text;
+    }
+}
+"#,
+            &[],
+        );
+        let app = compile(
+            &harness,
+            "com/example/App.java",
+            r#"
+package com.example;
+import com.vendor.Parser;
+public class App {
+    public Object load(String text) {
+        return read(text);
+    }
+
+    private Object read(String text) {
+        return new Parser().parse(text);
+    }
+}
+"#,
+            &[library.classes_dir().to_path_buf()],
+        );
+        (app, library)
+    }
+
+    /// Package `output` as a jar, so that its classes become analysis targets.
+    fn jar(output: &CompileOutput) -> PathBuf {
+        let path = output.temp_dir().path().join("app.jar");
+        let file = fs::File::create(&path).expect("create jar");
+        let mut writer = zip::ZipWriter::new(file);
+        let root = output.classes_dir();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).expect("read classes dir") {
+                let path = entry.expect("read entry").path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let name = path.strip_prefix(root).expect("relative path");
+                writer
+                    .start_file(
+                        name.to_string_lossy().replace('\\', "/"),
+                        zip::write::SimpleFileOptions::default(),
+                    )
+                    .expect("start jar entry");
+                writer
+                    .write_all(&fs::read(&path).expect("read class"))
+                    .expect("write jar entry");
+            }
+        }
+        writer.finish().expect("finish jar");
+        path
+    }
+
+    fn analyze(input: &Path, classpath: &[PathBuf], list: &str) -> Vec<SarifResult> {
+        let scan = scan_inputs(input, classpath).expect("scan classes");
+        let classpath_index = resolve_classpath(&scan.classes).expect("classpath build");
+        let context = build_context(scan.classes, classpath_index, &scan.artifacts);
+        let rule = VulnerableMethodRule {
+            methods: parse_vulnerable_methods(list).expect("parse list"),
+        };
+        rule.run(&context)
+            .expect("vulnerable method rule run")
+            .results
+    }
+
+    fn flow_messages(result: &SarifResult) -> Vec<String> {
+        result.code_flows.as_ref().expect("code flows")[0].thread_flows[0]
+            .locations
+            .iter()
+            .filter_map(|step| step.location.as_ref()?.message.as_ref()?.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parse_vulnerable_methods_reads_specs_and_advisories() {
+        let methods = parse_vulnerable_methods(
+            "# advisories\n\
+             org.yaml.snakeyaml.constructor.Constructor.<init> CVE-2022-1471\n\
+             \n\
+             com/vendor/Parser.parse(Ljava/lang/String;)Ljava/lang/Object; # inline\n",
+        )
+        .expect("parse list");
+        let pattern = |spec: &str| ClassPattern::parse(spec).expect("pattern");
+
+        assert_eq!(
+            methods,
+            vec![
+                VulnerableMethod {
+                    pattern: MethodPattern {
+                        class_name: pattern("org.yaml.snakeyaml.constructor.Constructor"),
+                        name: pattern("<init>"),
+                        descriptor: None,
+                    },
+                    advisory: Some("CVE-2022-1471".to_string()),
+                },
+                VulnerableMethod {
+                    pattern: MethodPattern {
+                        class_name: pattern("com/vendor/Parser"),
+                        name: pattern("parse"),
+                        descriptor: Some("(Ljava/lang/String;)Ljava/lang/Object;".to_string()),
+                    },
+                    advisory: None,
+                },
+            ]
+        );
+        assert!(parse_vulnerable_methods("Parser.parse CVE-1 extra").is_err());
+        assert!(parse_vulnerable_methods("parse").is_err());
+    }
+
+    #[test]
+    fn vulnerable_method_rule_reports_call_chain_into_classpath() {
+        let (app, library) = compile_app_and_library();
+
+        let results = analyze(
+            &jar(&app),
+            &[library.classes_dir().to_path_buf()],
+            "com.vendor.Constructor.<init> CVE-2024-0001\ncom.vendor.Parser.unused\n",
+        );
+
+        assert_eq!(1, results.len());
+        let message = results[0].message.text.as_deref().unwrap_or("");
+        assert_eq!(
+            message,
+            "Reachable vulnerable method (CVE-2024-0001): com/vendor/Constructor.<init>(Ljava/lang/String;)V from com/example/App.load(Ljava/lang/String;)Ljava/lang/Object;"
+        );
+        let primary = results[0].locations.as_ref().expect("locations")[0]
+            .logical_locations
+            .as_ref()
+            .expect("logical locations")[0]
+            .name
+            .clone();
+        assert_eq!(
+            primary.as_deref(),
+            Some("com/example/App.read(Ljava/lang/String;)Ljava/lang/Object;")
+        );
+        assert_eq!(
+            flow_messages(&results[0]),
+            vec![
+                "Calls com/example/App.read(Ljava/lang/String;)Ljava/lang/Object;",
+                "Calls com/vendor/Parser.parse(Ljava/lang/String;)Ljava/lang/Object;",
+                "Calls com/vendor/Constructor.<init>(Ljava/lang/String;)V",
+                "Vulnerable method (CVE-2024-0001): com/vendor/Constructor.<init>(Ljava/lang/String;)V",
+            ]
+        );
+    }
+
+    #[test]
+    fn vulnerable_method_rule_follows_calls_into_missing_library() {
+        let (app, _library) = compile_app_and_library();
+
+        let results = analyze(&jar(&app), &[], "com/vendor/Parser.parse\n");
+
+        assert_eq!(1, results.len());
+        assert_eq!(
+            flow_messages(&results[0]),
+            vec![
+                "Calls com/example/App.read(Ljava/lang/String;)Ljava/lang/Object;",
+                "Calls com/vendor/Parser.parse(Ljava/lang/String;)Ljava/lang/Object;",
+                "Vulnerable method: com/vendor/Parser.parse(Ljava/lang/String;)Ljava/lang/Object;",
+            ]
+        );
+    }
+}
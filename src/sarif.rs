@@ -0,0 +1,474 @@
+use anyhow::{Context, Result};
+use jsonschema::JSONSchema;
+use serde_json::json;
+pub use serde_sarif::sarif::{
+    Artifact, Invocation, Location, Message, Notification, PropertyBag, ReportingDescriptor,
+    ResultLevel, SCHEMA_URL, Sarif,
+};
+use serde_sarif::sarif::{
+    Exception, LogicalLocation, ReportingDescriptorReference, Result as SarifResult, Run, Tool,
+    ToolComponent,
+};
+
+use crate::engine::RuleFailure;
+use crate::rules::result_message;
+
+/// Check `sarif` against the bundled SARIF 2.1.0 schema.
+pub fn validate_sarif(sarif: &Sarif) -> Result<()> {
+    let schema = serde_json::from_str(include_str!("../assets/sarif-2.1.0.json"))
+        .context("load SARIF schema")?;
+    let compiled = JSONSchema::compile(&schema)
+        .map_err(|err| anyhow::anyhow!("compile SARIF schema: {err}"))?;
+    let value = serde_json::to_value(sarif).context("serialize SARIF")?;
+    if let Err(errors) = compiled.validate(&value) {
+        let message = errors
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        anyhow::bail!("SARIF schema validation failed:\n{message}");
+    }
+    Ok(())
+}
+
+/// SARIF log with a single run of inspequte.
+pub fn build_sarif(
+    artifacts: Vec<Artifact>,
+    invocation: Invocation,
+    rules: Vec<ReportingDescriptor>,
+    results: Vec<SarifResult>,
+) -> Sarif {
+    let driver = if rules.is_empty() {
+        ToolComponent::builder()
+            .name("inspequte")
+            .information_uri("https://github.com/KengoTODA/inspequte")
+            .build()
+    } else {
+        ToolComponent::builder()
+            .name("inspequte")
+            .information_uri("https://github.com/KengoTODA/inspequte")
+            .rules(rules)
+            .build()
+    };
+    let tool = Tool {
+        driver,
+        extensions: None,
+        properties: None,
+    };
+    let run = if artifacts.is_empty() {
+        Run::builder()
+            .tool(tool)
+            .invocations(vec![invocation])
+            .results(results)
+            .build()
+    } else {
+        Run::builder()
+            .tool(tool)
+            .invocations(vec![invocation])
+            .results(results)
+            .artifacts(artifacts)
+            .build()
+    };
+
+    Sarif::builder()
+        .schema(SCHEMA_URL)
+        .runs(vec![run])
+        .version(json!("2.1.0"))
+        .build()
+}
+
+/// Error-level `toolExecutionNotifications` for rule failures, each associated with
+/// the rule's descriptor in `rules` and located at the class or method it failed on.
+pub fn failure_notifications(
+    failures: &[RuleFailure],
+    rules: &[ReportingDescriptor],
+) -> Vec<Notification> {
+    failures
+        .iter()
+        .map(|failure| {
+            let mut rule = ReportingDescriptorReference::builder()
+                .id(failure.rule_id.as_str())
+                .build();
+            rule.index = rules
+                .iter()
+                .position(|descriptor| descriptor.id == failure.rule_id)
+                .map(|index| index as i64);
+            let verb = if failure.panicked {
+                "panicked"
+            } else {
+                "failed"
+            };
+            let mut notification = Notification::builder()
+                .level(json!("error"))
+                .message(result_message(format!(
+                    "{} {verb}: {}",
+                    failure.rule_id, failure.message
+                )))
+                .associated_rule(rule)
+                .build();
+            notification.locations = failure.class_name.as_ref().map(|class_name| {
+                let logical = match &failure.method {
+                    Some(method) => LogicalLocation::builder()
+                        .name(format!("{class_name}.{method}"))
+                        .kind("function")
+                        .build(),
+                    None => LogicalLocation::builder()
+                        .name(class_name.clone())
+                        .kind("type")
+                        .build(),
+                };
+                vec![Location::builder().logical_locations(vec![logical]).build()]
+            });
+            if failure.panicked {
+                notification.exception = Some(
+                    Exception::builder()
+                        .kind("panic")
+                        .message(failure.message.clone())
+                        .build(),
+                );
+            }
+            notification
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::classpath::resolve_classpath;
+    use crate::engine::{Engine, build_context};
+    use crate::scan::scan_inputs;
+
+    #[test]
+    fn sarif_callgraph_snapshot() {
+        let temp_dir = std::env::temp_dir().join(format!(
+            "inspequte-test-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("time")
+                .as_nanos()
+        ));
+        fs::create_dir_all(&temp_dir).expect("create temp dir");
+
+        let class_a = build_class_a();
+        let class_b = build_class_b();
+        fs::write(temp_dir.join("A.class"), class_a).expect("write A.class");
+        fs::write(temp_dir.join("B.class"), class_b).expect("write B.class");
+
+        let scan = scan_inputs(&temp_dir, &[]).expect("scan classes");
+        let classpath = resolve_classpath(&scan.classes).expect("resolve classpath");
+        let artifacts = scan.artifacts.clone();
+        let context = build_context(scan.classes.clone(), classpath, &artifacts);
+        let engine = Engine::new();
+        let analysis = engine.analyze(context).expect("analysis");
+        let invocation = Invocation::builder()
+            .execution_successful(true)
+            .arguments(Vec::<String>::new())
+            .build();
+        let artifacts = normalize_artifacts(artifacts);
+        let sarif = build_sarif(artifacts, invocation, analysis.rules, analysis.results);
+        let actual = serde_json::to_string_pretty(&sarif).expect("serialize SARIF");
+        let snapshot_path = snapshot_path("callgraph.sarif");
+
+        if std::env::var("INSPEQUTE_UPDATE_SNAPSHOTS").is_ok() {
+            fs::create_dir_all(snapshot_path.parent().expect("snapshot parent"))
+                .expect("create snapshot dir");
+            let mut file = fs::File::create(&snapshot_path).expect("create snapshot");
+            file.write_all(actual.as_bytes()).expect("write snapshot");
+            file.write_all(b"\n").expect("write snapshot newline");
+        }
+
+        let expected = fs::read_to_string(&snapshot_path).expect("read snapshot");
+        assert_eq!(actual.trim_end(), expected.trim_end());
+
+        fs::remove_dir_all(&temp_dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn rule_failures_become_tool_execution_notifications() {
+        let failures = vec![RuleFailure {
+            rule_id: "NULLNESS".to_string(),
+            class_name: Some("com/example/App".to_string()),
+            method: Some("run()V".to_string()),
+            message: "stack underflow".to_string(),
+            panicked: true,
+        }];
+        let rules = vec![
+            ReportingDescriptor::builder().id("DEAD_CODE").build(),
+            ReportingDescriptor::builder().id("NULLNESS").build(),
+        ];
+        let mut invocation = Invocation::builder().execution_successful(false).build();
+        invocation.tool_execution_notifications = Some(failure_notifications(&failures, &rules));
+        let sarif = build_sarif(Vec::new(), invocation, rules, Vec::new());
+
+        validate_sarif(&sarif).expect("valid SARIF");
+        let value = serde_json::to_value(&sarif).expect("serialize SARIF");
+        let notification = &value["runs"][0]["invocations"][0]["toolExecutionNotifications"][0];
+        assert_eq!("error", notification["level"]);
+        assert_eq!(
+            "NULLNESS panicked: stack underflow",
+            notification["message"]["text"]
+        );
+        assert_eq!(
+            json!({ "id": "NULLNESS", "index": 1 }),
+            notification["associatedRule"]
+        );
+        assert_eq!(
+            "com/example/App.run()V",
+            notification["locations"][0]["logicalLocations"][0]["name"]
+        );
+        assert_eq!("panic", notification["exception"]["kind"]);
+    }
+
+    fn snapshot_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(name)
+    }
+
+    fn build_class_a() -> Vec<u8> {
+        let mut builder = ClassFileBuilder::new("A", "java/lang/Object");
+        let object_init = builder.add_method_ref("java/lang/Object", "<init>", "()V");
+        let b_class = builder.add_class("B");
+        let b_init = builder.add_method_ref("B", "<init>", "()V");
+        let b_bar = builder.add_method_ref("B", "bar", "()V");
+
+        let init_code = vec![0x2a, 0xb7, high(object_init), low(object_init), 0xb1];
+        builder.add_method("<init>", "()V", init_code, 1, 1);
+
+        let foo_code = vec![
+            0xbb,
+            high(b_class),
+            low(b_class),
+            0x59,
+            0xb7,
+            high(b_init),
+            low(b_init),
+            0xb6,
+            high(b_bar),
+            low(b_bar),
+            0xb1,
+        ];
+        builder.add_method("foo", "()V", foo_code, 2, 1);
+
+        builder.finish()
+    }
+
+    fn build_class_b() -> Vec<u8> {
+        let mut builder = ClassFileBuilder::new("B", "java/lang/Object");
+        let object_init = builder.add_method_ref("java/lang/Object", "<init>", "()V");
+        let init_code = vec![0x2a, 0xb7, high(object_init), low(object_init), 0xb1];
+        builder.add_method("<init>", "()V", init_code, 1, 1);
+        let bar_code = vec![0xb1];
+        builder.add_method("bar", "()V", bar_code, 0, 1);
+        builder.finish()
+    }
+
+    /// Minimal class file writer for snapshot testing.
+    struct ClassFileBuilder {
+        cp: Vec<CpEntry>,
+        this_class: u16,
+        super_class: u16,
+        methods: Vec<MethodSpec>,
+        code_index: u16,
+    }
+
+    impl ClassFileBuilder {
+        fn new(class_name: &str, super_name: &str) -> Self {
+            let mut builder = Self {
+                cp: Vec::new(),
+                this_class: 0,
+                super_class: 0,
+                methods: Vec::new(),
+                code_index: 0,
+            };
+            builder.code_index = builder.add_utf8("Code");
+            builder.this_class = builder.add_class(class_name);
+            builder.super_class = builder.add_class(super_name);
+            builder
+        }
+
+        fn add_utf8(&mut self, value: &str) -> u16 {
+            self.cp.push(CpEntry::Utf8(value.to_string()));
+            self.cp.len() as u16
+        }
+
+        fn add_class(&mut self, name: &str) -> u16 {
+            let name_index = self.add_utf8(name);
+            self.cp.push(CpEntry::Class(name_index));
+            self.cp.len() as u16
+        }
+
+        fn add_name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+            let name_index = self.add_utf8(name);
+            let descriptor_index = self.add_utf8(descriptor);
+            self.cp
+                .push(CpEntry::NameAndType(name_index, descriptor_index));
+            self.cp.len() as u16
+        }
+
+        fn add_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+            let class_index = self.add_class(class);
+            let name_and_type = self.add_name_and_type(name, descriptor);
+            self.cp.push(CpEntry::MethodRef(class_index, name_and_type));
+            self.cp.len() as u16
+        }
+
+        fn add_method(
+            &mut self,
+            name: &str,
+            descriptor: &str,
+            code: Vec<u8>,
+            max_stack: u16,
+            max_locals: u16,
+        ) {
+            let name_index = self.add_utf8(name);
+            let descriptor_index = self.add_utf8(descriptor);
+            self.methods.push(MethodSpec {
+                name_index,
+                descriptor_index,
+                code,
+                max_stack,
+                max_locals,
+            });
+        }
+
+        fn finish(self) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            write_u32(&mut bytes, 0xCAFEBABE);
+            write_u16(&mut bytes, 0);
+            write_u16(&mut bytes, 52);
+            write_u16(&mut bytes, (self.cp.len() + 1) as u16);
+            for entry in &self.cp {
+                entry.write(&mut bytes);
+            }
+            write_u16(&mut bytes, 0x0021);
+            write_u16(&mut bytes, self.this_class);
+            write_u16(&mut bytes, self.super_class);
+            write_u16(&mut bytes, 0);
+            write_u16(&mut bytes, 0);
+            write_u16(&mut bytes, self.methods.len() as u16);
+            for method in &self.methods {
+                write_u16(&mut bytes, 0x0001);
+                write_u16(&mut bytes, method.name_index);
+                write_u16(&mut bytes, method.descriptor_index);
+                write_u16(&mut bytes, 1);
+                write_u16(&mut bytes, self.code_index);
+                let attr_len = 12 + method.code.len() as u32;
+                write_u32(&mut bytes, attr_len);
+                write_u16(&mut bytes, method.max_stack);
+                write_u16(&mut bytes, method.max_locals);
+                write_u32(&mut bytes, method.code.len() as u32);
+                bytes.extend_from_slice(&method.code);
+                write_u16(&mut bytes, 0);
+                write_u16(&mut bytes, 0);
+            }
+            write_u16(&mut bytes, 0);
+            bytes
+        }
+    }
+
+    /// Method definition for generated class files.
+    struct MethodSpec {
+        name_index: u16,
+        descriptor_index: u16,
+        code: Vec<u8>,
+        max_stack: u16,
+        max_locals: u16,
+    }
+
+    /// Constant pool entries needed by snapshot class files.
+    enum CpEntry {
+        Utf8(String),
+        Class(u16),
+        NameAndType(u16, u16),
+        MethodRef(u16, u16),
+    }
+
+    impl CpEntry {
+        fn write(&self, bytes: &mut Vec<u8>) {
+            match self {
+                CpEntry::Utf8(value) => {
+                    bytes.push(1);
+                    write_u16(bytes, value.len() as u16);
+                    bytes.extend_from_slice(value.as_bytes());
+                }
+                CpEntry::Class(name_index) => {
+                    bytes.push(7);
+                    write_u16(bytes, *name_index);
+                }
+                CpEntry::NameAndType(name_index, descriptor_index) => {
+                    bytes.push(12);
+                    write_u16(bytes, *name_index);
+                    write_u16(bytes, *descriptor_index);
+                }
+                CpEntry::MethodRef(class_index, name_and_type) => {
+                    bytes.push(10);
+                    write_u16(bytes, *class_index);
+                    write_u16(bytes, *name_and_type);
+                }
+            }
+        }
+    }
+
+    fn write_u16(bytes: &mut Vec<u8>, value: u16) {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn high(value: u16) -> u8 {
+        (value >> 8) as u8
+    }
+
+    fn low(value: u16) -> u8 {
+        (value & 0xff) as u8
+    }
+
+    fn normalize_artifacts(
+        artifacts: Vec<serde_sarif::sarif::Artifact>,
+    ) -> Vec<serde_sarif::sarif::Artifact> {
+        artifacts
+            .into_iter()
+            .map(|mut artifact| {
+                if let Some(location) = artifact.location.as_mut()
+                    && let Some(uri) = &location.uri
+                    && let Some(name) = artifact_basename(uri)
+                {
+                    location.uri = Some(name);
+                }
+                artifact
+            })
+            .collect()
+    }
+
+    fn artifact_basename(uri: &str) -> Option<String> {
+        if let Some(rest) = uri.strip_prefix("jar:") {
+            let entry = rest.split("!/").nth(1)?;
+            return Some(
+                PathBuf::from(entry)
+                    .file_name()?
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+        if let Some(rest) = uri.strip_prefix("file://") {
+            return Some(
+                PathBuf::from(rest)
+                    .file_name()?
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+        PathBuf::from(uri)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+}
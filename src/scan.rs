@@ -17,23 +17,29 @@ use zip::ZipArchive;
 use crate::cfg::build_cfg;
 use crate::descriptor::method_param_count;
 use crate::ir::{
-    CallKind, CallSite, Class, ExceptionHandler, Field, FieldAccess, Instruction, InstructionKind,
-    LineNumber, Method, MethodAccess, MethodNullness, Nullness,
+    CallKind, CallSite, Class, ConstantValue, DynamicCallSite, ExceptionHandler, Field,
+    FieldAccess, FieldRef, Instruction, InstructionKind, LineNumber, Method, MethodAccess,
+    MethodNullness, Nullness,
 };
 use crate::opcodes;
+use crate::trace;
 
 /// Snapshot of parsed artifacts, classes, and counts for a scan.
-pub(crate) struct ScanOutput {
-    pub(crate) artifacts: Vec<Artifact>,
-    pub(crate) class_count: usize,
-    pub(crate) classes: Vec<Class>,
+pub struct ScanOutput {
+    pub artifacts: Vec<Artifact>,
+    pub class_count: usize,
+    pub classes: Vec<Class>,
+    /// Provider classes listed in `META-INF/services` files of scanned JARs.
+    pub service_providers: Vec<String>,
 }
 
-pub(crate) fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOutput> {
+pub fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOutput> {
     let mut artifacts = Vec::new();
     let mut class_count = 0;
     let mut classes = Vec::new();
+    let mut service_providers = BTreeSet::new();
 
+    let input_span = trace::span("scan", input.display().to_string());
     scan_path(
         input,
         true,
@@ -41,11 +47,13 @@ pub(crate) fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOut
         &mut artifacts,
         &mut class_count,
         &mut classes,
+        &mut service_providers,
     )?;
+    drop(input_span);
 
     // Keep deterministic ordering by sorting classpath entries and directory listings.
     let mut classpath_entries = classpath.to_vec();
-    classpath_entries.sort_by(|a, b| path_key(a).cmp(&path_key(b)));
+    classpath_entries.sort_by_key(|a| path_key(a));
 
     if is_jar_path(input) {
         classpath_entries.extend(manifest_classpath(input)?);
@@ -56,6 +64,7 @@ pub(crate) fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOut
         if entry == input {
             continue;
         }
+        let _span = trace::span("scan", entry.display().to_string());
         scan_path(
             &entry,
             false,
@@ -63,6 +72,7 @@ pub(crate) fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOut
             &mut artifacts,
             &mut class_count,
             &mut classes,
+            &mut service_providers,
         )?;
     }
 
@@ -70,6 +80,7 @@ pub(crate) fn scan_inputs(input: &Path, classpath: &[PathBuf]) -> Result<ScanOut
         artifacts,
         class_count,
         classes,
+        service_providers: service_providers.into_iter().collect(),
     })
 }
 
@@ -80,9 +91,10 @@ fn scan_path(
     artifacts: &mut Vec<Artifact>,
     class_count: &mut usize,
     classes: &mut Vec<Class>,
+    service_providers: &mut BTreeSet<String>,
 ) -> Result<()> {
     if path.is_dir() {
-        scan_dir(path, artifacts, class_count, classes)?;
+        scan_dir(path, artifacts, class_count, classes, service_providers)?;
         return Ok(());
     }
 
@@ -97,7 +109,14 @@ fn scan_path(
 
     match extension {
         "class" => scan_class_file(path, roles, artifacts, class_count, classes),
-        "jar" => scan_jar_file(path, roles, artifacts, class_count, classes),
+        "jar" => scan_jar_file(
+            path,
+            roles,
+            artifacts,
+            class_count,
+            classes,
+            service_providers,
+        ),
         _ => {
             if strict {
                 anyhow::bail!("unsupported input file: {}", path.display())
@@ -113,6 +132,7 @@ fn scan_dir(
     artifacts: &mut Vec<Artifact>,
     class_count: &mut usize,
     classes: &mut Vec<Class>,
+    service_providers: &mut BTreeSet<String>,
 ) -> Result<()> {
     let mut entries = Vec::new();
     for entry in fs::read_dir(path)
@@ -123,13 +143,21 @@ fn scan_dir(
         entries.push(entry.path());
     }
 
-    entries.sort_by(|a, b| path_key(a).cmp(&path_key(b)));
+    entries.sort_by_key(|a| path_key(a));
 
     for entry in entries {
         if entry.is_dir() {
-            scan_dir(&entry, artifacts, class_count, classes)?;
+            scan_dir(&entry, artifacts, class_count, classes, service_providers)?;
         } else {
-            scan_path(&entry, false, false, artifacts, class_count, classes)?;
+            scan_path(
+                &entry,
+                false,
+                false,
+                artifacts,
+                class_count,
+                classes,
+                service_providers,
+            )?;
         }
     }
 
@@ -162,6 +190,7 @@ fn scan_class_file(
         methods: parsed.methods,
         artifact_index,
         is_record: parsed.is_record,
+        annotations: parsed.annotations,
     });
     Ok(())
 }
@@ -172,6 +201,7 @@ fn scan_jar_file(
     artifacts: &mut Vec<Artifact>,
     class_count: &mut usize,
     classes: &mut Vec<Class>,
+    service_providers: &mut BTreeSet<String>,
 ) -> Result<()> {
     let file =
         fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
@@ -185,13 +215,21 @@ fn scan_jar_file(
 
     let mut entry_names = Vec::new();
     for index in 0..archive.len() {
-        let entry = archive
+        let mut entry = archive
             .by_index(index)
             .with_context(|| format!("failed to read {}", path.display()))?;
         if entry.is_dir() {
             continue;
         }
         let name = entry.name().to_string();
+        if name.starts_with("META-INF/services/") {
+            let mut content = String::new();
+            entry
+                .read_to_string(&mut content)
+                .with_context(|| format!("failed to read {}:{}", path.display(), name))?;
+            service_providers.extend(parse_service_providers(&content));
+            continue;
+        }
         // TODO: Handle multi-release entries under META-INF/versions/ in a future release.
         if name.ends_with(".class")
             && !name.ends_with("module-info.class")
@@ -227,6 +265,7 @@ fn scan_jar_file(
             methods: parsed.methods,
             artifact_index: jar_index,
             is_record: parsed.is_record,
+            annotations: parsed.annotations,
         });
     }
 
@@ -298,7 +337,7 @@ fn path_key(path: &Path) -> String {
 fn expand_classpath(initial: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
     let mut queue = VecDeque::new();
     let mut initial_sorted = initial;
-    initial_sorted.sort_by(|a, b| path_key(a).cmp(&path_key(b)));
+    initial_sorted.sort_by_key(|a| path_key(a));
     for entry in initial_sorted {
         queue.push_back(entry);
     }
@@ -316,7 +355,7 @@ fn expand_classpath(initial: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
         result.push(entry.clone());
         if is_jar_path(&entry) {
             let mut referenced = manifest_classpath(&entry)?;
-            referenced.sort_by(|a, b| path_key(a).cmp(&path_key(b)));
+            referenced.sort_by_key(|a| path_key(a));
             for item in referenced {
                 queue.push_back(item);
             }
@@ -326,6 +365,16 @@ fn expand_classpath(initial: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
     Ok(result)
 }
 
+/// Provider class names in a `META-INF/services` file, as internal names.
+fn parse_service_providers(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.replace('.', "/"))
+        .collect()
+}
+
 fn manifest_classpath(path: &Path) -> Result<Vec<PathBuf>> {
     let file =
         fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
@@ -355,9 +404,9 @@ fn parse_manifest_classpath(jar_path: &Path, content: &str) -> Vec<PathBuf> {
 
     for raw_line in content.lines() {
         let line = raw_line.trim_end_matches('\r');
-        if line.starts_with(' ') {
+        if let Some(continuation) = line.strip_prefix(' ') {
             if current_key.is_some() {
-                current_value.push_str(&line[1..]);
+                current_value.push_str(continuation);
             }
             continue;
         }
@@ -375,10 +424,10 @@ fn parse_manifest_classpath(jar_path: &Path, content: &str) -> Vec<PathBuf> {
         }
     }
 
-    if let Some(key) = current_key.take() {
-        if key == "Class-Path" {
-            class_path = Some(current_value.clone());
-        }
+    if let Some(key) = current_key.take()
+        && key == "Class-Path"
+    {
+        class_path = Some(current_value.clone());
     }
 
     let Some(class_path) = class_path else {
@@ -415,6 +464,7 @@ struct ParsedClass {
     fields: Vec<crate::ir::Field>,
     methods: Vec<Method>,
     is_record: bool,
+    annotations: Vec<String>,
 }
 
 fn parse_class_bytes(data: &[u8]) -> Result<ParsedClass> {
@@ -466,6 +516,8 @@ fn parse_class_bytes(data: &[u8]) -> Result<ParsedClass> {
         .context("parse class nullness")?;
     let methods = parse_methods(constant_pool, class_file.methods(), default_nullness)
         .context("parse method bytecode")?;
+    let annotations =
+        parse_annotations(class_file.attributes(), constant_pool).context("parse annotations")?;
 
     Ok(ParsedClass {
         name: class_name,
@@ -475,6 +527,7 @@ fn parse_class_bytes(data: &[u8]) -> Result<ParsedClass> {
         fields,
         methods,
         is_record,
+        annotations,
     })
 }
 
@@ -561,6 +614,7 @@ fn parse_class_bytes_minimal(data: &[u8]) -> Result<ParsedClass> {
         fields: Vec::new(),
         methods: Vec::new(),
         is_record: false,
+        annotations: Vec::new(),
     })
 }
 
@@ -768,14 +822,15 @@ fn parse_methods(
             .iter()
             .find_map(|attribute| match attribute {
                 jclassfile::attributes::Attribute::Code {
+                    max_stack,
+                    max_locals,
                     code,
                     exception_table,
                     attributes,
-                    ..
-                } => Some((code, exception_table, attributes)),
+                } => Some((*max_stack, *max_locals, code, exception_table, attributes)),
                 _ => None,
             });
-        let Some((code, exception_table, code_attributes)) = code else {
+        let Some((max_stack, max_locals, code, exception_table, code_attributes)) = code else {
             continue;
         };
         let line_numbers =
@@ -784,23 +839,24 @@ fn parse_methods(
             parse_bytecode(code, constant_pool).context("parse bytecode")?;
         let exception_handlers =
             parse_exception_handlers(exception_table, constant_pool).context("parse handlers")?;
-        let handler_offsets = exception_handlers
-            .iter()
-            .map(|handler| handler.handler_pc)
-            .collect::<Vec<_>>();
-        let cfg =
-            build_cfg(code, &instructions, &handler_offsets).context("build control flow graph")?;
+        let cfg = build_cfg(code, &instructions, &exception_handlers)
+            .context("build control flow graph")?;
+        let annotations = parse_annotations(method.attributes(), constant_pool)
+            .context("parse method annotations")?;
         parsed.push(Method {
             name,
             descriptor,
             access,
             nullness,
             bytecode: code.clone(),
+            max_stack,
+            max_locals,
             line_numbers,
             cfg,
             calls,
             string_literals,
             exception_handlers,
+            annotations,
         });
     }
     Ok(parsed)
@@ -864,6 +920,30 @@ fn parse_default_nullness(
     Ok(DefaultNullness::Inherit)
 }
 
+fn parse_annotations(
+    attributes: &[jclassfile::attributes::Attribute],
+    constant_pool: &[ConstantPool],
+) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for attribute in attributes {
+        let annotations = match attribute {
+            jclassfile::attributes::Attribute::RuntimeVisibleAnnotations {
+                annotations, ..
+            }
+            | jclassfile::attributes::Attribute::RuntimeInvisibleAnnotations { annotations } => {
+                annotations
+            }
+            _ => continue,
+        };
+        for annotation in annotations {
+            names.push(annotation_class_name(constant_pool, annotation)?);
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
 fn parse_method_nullness(
     constant_pool: &[ConstantPool],
     attributes: &[jclassfile::attributes::Attribute],
@@ -915,12 +995,11 @@ fn parse_method_nullness(
         let descriptor =
             MethodDescriptor::from_str(descriptor).context("parse method descriptor")?;
         for (index, param) in descriptor.parameter_types().iter().enumerate() {
-            if is_reference_type(param) {
-                if let Some(param_nullness) = nullness.parameter_nullness.get_mut(index) {
-                    if *param_nullness == Nullness::Unknown {
-                        *param_nullness = Nullness::NonNull;
-                    }
-                }
+            if is_reference_type(param)
+                && let Some(param_nullness) = nullness.parameter_nullness.get_mut(index)
+                && *param_nullness == Nullness::Unknown
+            {
+                *param_nullness = Nullness::NonNull;
             }
         }
         if is_reference_type(descriptor.return_type())
@@ -1013,20 +1092,40 @@ fn parse_bytecode(
                 calls.push(call.clone());
                 InstructionKind::Invoke(call)
             }
-            opcodes::LDC => {
-                let index = code.get(offset + 1).copied().context("ldc index")? as u16;
-                if let Some(value) = resolve_string_literal(constant_pool, index)? {
-                    string_literals.push(value.clone());
-                    InstructionKind::ConstString(value)
-                } else {
-                    InstructionKind::Other(opcode)
-                }
+            opcodes::INVOKEDYNAMIC => {
+                let index = read_u16(code, offset + 1)?;
+                InstructionKind::InvokeDynamic(
+                    resolve_dynamic_call_site(constant_pool, index)
+                        .context("resolve invokedynamic")?,
+                )
             }
-            opcodes::LDC_W | opcodes::LDC2_W => {
+            opcodes::GETSTATIC | opcodes::PUTSTATIC | opcodes::GETFIELD | opcodes::PUTFIELD => {
                 let index = read_u16(code, offset + 1)?;
+                InstructionKind::Field(
+                    resolve_field_ref(constant_pool, index).context("resolve field ref")?,
+                )
+            }
+            opcodes::NEW
+            | opcodes::ANEWARRAY
+            | opcodes::CHECKCAST
+            | opcodes::INSTANCEOF
+            | opcodes::MULTIANEWARRAY => {
+                let index = read_u16(code, offset + 1)?;
+                InstructionKind::Type(
+                    resolve_class_name(constant_pool, index).context("resolve type operand")?,
+                )
+            }
+            opcodes::LDC | opcodes::LDC_W | opcodes::LDC2_W => {
+                let index = if opcode == opcodes::LDC {
+                    code.get(offset + 1).copied().context("ldc index")? as u16
+                } else {
+                    read_u16(code, offset + 1)?
+                };
                 if let Some(value) = resolve_string_literal(constant_pool, index)? {
                     string_literals.push(value.clone());
                     InstructionKind::ConstString(value)
+                } else if let Some(value) = resolve_constant(constant_pool, index)? {
+                    InstructionKind::Constant(value)
                 } else {
                     InstructionKind::Other(opcode)
                 }
@@ -1078,6 +1177,80 @@ fn resolve_method_ref(constant_pool: &[ConstantPool], index: u16) -> Result<Meth
     })
 }
 
+fn resolve_field_ref(constant_pool: &[ConstantPool], index: u16) -> Result<FieldRef> {
+    let entry = constant_pool
+        .get(index as usize)
+        .context("missing field ref entry")?;
+    let ConstantPool::Fieldref {
+        class_index,
+        name_and_type_index,
+    } = entry
+    else {
+        anyhow::bail!("unexpected field ref entry");
+    };
+    let owner = resolve_class_name(constant_pool, *class_index).context("resolve owner")?;
+    let (name_index, descriptor_index) =
+        resolve_name_and_type(constant_pool, *name_and_type_index)?;
+    let name = resolve_utf8(constant_pool, name_index).context("resolve field name")?;
+    let descriptor =
+        resolve_utf8(constant_pool, descriptor_index).context("resolve field descriptor")?;
+    Ok(FieldRef {
+        owner,
+        name,
+        descriptor,
+    })
+}
+
+fn resolve_dynamic_call_site(
+    constant_pool: &[ConstantPool],
+    index: u16,
+) -> Result<DynamicCallSite> {
+    let entry = constant_pool
+        .get(index as usize)
+        .context("missing invokedynamic entry")?;
+    let ConstantPool::InvokeDynamic {
+        name_and_type_index,
+        ..
+    } = entry
+    else {
+        anyhow::bail!("unexpected invokedynamic entry");
+    };
+    let (name_index, descriptor_index) =
+        resolve_name_and_type(constant_pool, *name_and_type_index)?;
+    let name = resolve_utf8(constant_pool, name_index).context("resolve call site name")?;
+    let descriptor =
+        resolve_utf8(constant_pool, descriptor_index).context("resolve call site descriptor")?;
+    Ok(DynamicCallSite { name, descriptor })
+}
+
+fn resolve_constant(constant_pool: &[ConstantPool], index: u16) -> Result<Option<ConstantValue>> {
+    let entry = constant_pool
+        .get(index as usize)
+        .context("missing constant pool entry")?;
+    let value = match entry {
+        ConstantPool::Integer { value } => ConstantValue::Int(*value),
+        ConstantPool::Float { value } => ConstantValue::Float(*value),
+        ConstantPool::Long { value } => ConstantValue::Long(*value),
+        ConstantPool::Double { value } => ConstantValue::Double(*value),
+        ConstantPool::Class { name_index } => {
+            ConstantValue::Class(resolve_utf8(constant_pool, *name_index)?)
+        }
+        ConstantPool::MethodType { descriptor_index } => {
+            ConstantValue::MethodType(resolve_utf8(constant_pool, *descriptor_index)?)
+        }
+        ConstantPool::MethodHandle { .. } => ConstantValue::MethodHandle,
+        ConstantPool::Dynamic {
+            name_and_type_index,
+            ..
+        } => {
+            let (_, descriptor_index) = resolve_name_and_type(constant_pool, *name_and_type_index)?;
+            ConstantValue::Dynamic(resolve_utf8(constant_pool, descriptor_index)?)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
 fn resolve_name_and_type(constant_pool: &[ConstantPool], index: u16) -> Result<(u16, u16)> {
     let entry = constant_pool
         .get(index as usize)
@@ -1319,6 +1492,44 @@ mod tests {
         fs::remove_dir_all(&temp_dir).expect("cleanup temp dir");
     }
 
+    #[test]
+    fn scan_inputs_collects_service_providers_from_jars() {
+        let temp_dir = std::env::temp_dir().join(format!(
+            "inspequte-test-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("time")
+                .as_nanos()
+        ));
+        fs::create_dir_all(&temp_dir).expect("create temp dir");
+        let jar_path = temp_dir.join("providers.jar");
+        let file = fs::File::create(&jar_path).expect("create jar");
+        let mut writer = zip::ZipWriter::new(file);
+        writer
+            .start_file(
+                "META-INF/services/com.example.Codec",
+                SimpleFileOptions::default(),
+            )
+            .expect("start services entry");
+        writer
+            .write_all(
+                b"# providers\ncom.example.JsonCodec\n\ncom.example.Outer$XmlCodec # nested\n",
+            )
+            .expect("write services entry");
+        writer.finish().expect("finish jar");
+
+        let result = scan_inputs(&jar_path, &[]).expect("scan jar");
+
+        assert_eq!(
+            vec![
+                "com/example/JsonCodec".to_string(),
+                "com/example/Outer$XmlCodec".to_string()
+            ],
+            result.service_providers
+        );
+        fs::remove_dir_all(&temp_dir).expect("cleanup temp dir");
+    }
+
     #[test]
     fn scan_inputs_errors_on_missing_manifest_classpath_entry() {
         let temp_dir = std::env::temp_dir().join(format!(
@@ -1365,7 +1576,8 @@ mod tests {
         };
 
         let marked_default =
-            parse_default_nullness(&[marked_attr.clone()], &constant_pool).expect("marked default");
+            parse_default_nullness(std::slice::from_ref(&marked_attr), &constant_pool)
+                .expect("marked default");
         let unmarked_default =
             parse_default_nullness(&[unmarked_attr], &constant_pool).expect("unmarked default");
         let empty_default = parse_default_nullness(&[], &constant_pool).expect("empty default");
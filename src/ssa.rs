@@ -0,0 +1,816 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+
+use crate::dataflow::{self, Analysis};
+pub use crate::interpreter::{BaseType, ReferenceType, ValueType};
+use crate::interpreter::{Fact, FactHooks, Frame, Interpreter, NoFacts, Slot};
+use crate::ir::{BasicBlock, EdgeKind, Instruction, Method};
+use crate::opcodes;
+
+/// Identifier of an SSA value, unique within one method.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ValueId(pub u32);
+
+/// Where an SSA value is defined.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValueDef {
+    This,
+    Parameter(usize),
+    /// Result of the instruction at this offset.
+    Instruction(u32),
+    /// Merge of values at the entry of the block starting at `block`.
+    Phi {
+        block: u32,
+        operands: Vec<PhiOperand>,
+    },
+    /// Exception caught by the handler starting at this offset.
+    CaughtException(u32),
+}
+
+/// Value flowing into a phi from one predecessor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PhiOperand {
+    /// Predecessor block start offset; `None` for the method entry.
+    pub predecessor: Option<u32>,
+    pub value: ValueId,
+}
+
+/// Typed SSA value.
+#[derive(Clone, Debug)]
+pub struct SsaValue {
+    pub ty: ValueType,
+    pub def: ValueDef,
+}
+
+/// Instruction with its stack and local operands replaced by SSA values.
+#[derive(Clone, Debug)]
+pub struct SsaInstruction {
+    pub offset: u32,
+    pub opcode: u8,
+    /// Values consumed, bottom-most stack operand first.
+    pub operands: Vec<ValueId>,
+    pub result: Option<ValueId>,
+}
+
+/// Place where a value is used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum User {
+    Instruction(u32),
+    Phi(ValueId),
+}
+
+/// Register-based SSA form of a method, lifted from its stack bytecode.
+///
+/// Loads, stores, and stack shuffles are copies and disappear; every other
+/// instruction refers to the values it consumes and produces. Only reachable
+/// blocks are lifted.
+#[derive(Debug)]
+pub struct SsaMethod {
+    values: Vec<SsaValue>,
+    instructions: BTreeMap<u32, SsaInstruction>,
+    phis: BTreeMap<u32, Vec<ValueId>>,
+    users: BTreeMap<ValueId, Vec<User>>,
+}
+
+impl SsaMethod {
+    pub fn value(&self, id: ValueId) -> &SsaValue {
+        &self.values[id.0 as usize]
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = (ValueId, &SsaValue)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (ValueId(index as u32), value))
+    }
+
+    pub fn instruction(&self, offset: u32) -> Option<&SsaInstruction> {
+        self.instructions.get(&offset)
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &SsaInstruction> {
+        self.instructions.values()
+    }
+
+    /// Phi values at the entry of the block starting at `block`.
+    pub fn phis(&self, block: u32) -> &[ValueId] {
+        self.phis.get(&block).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Instructions and phis consuming `id`.
+    pub fn users(&self, id: ValueId) -> &[User] {
+        self.users.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Lift a method into SSA form.
+pub(crate) fn lift(class_name: &str, method: &Method) -> Result<SsaMethod> {
+    let interpreter = Interpreter::new(class_name, method);
+    let types = dataflow::solve(
+        method,
+        &TypeAnalysis {
+            interpreter: Interpreter::new(class_name, method),
+        },
+    )?;
+    if !types.converged {
+        anyhow::bail!("type inference did not converge for {}", method.name);
+    }
+    let mut lifter = Lifter::new(method);
+    lifter.lift(&interpreter, &types.entry_states)?;
+    Ok(lifter.finish())
+}
+
+/// Position of a slot in a frame, used to key phis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum SlotPosition {
+    Local(usize),
+    Stack(usize),
+}
+
+/// SSA value carried by an interpreter slot; `None` for undefined slots.
+impl Fact for Option<ValueId> {
+    fn join(&self, other: &Self) -> Self {
+        if self == other { *self } else { None }
+    }
+}
+
+/// Interpreter hooks that allocate a fresh value for every produced result.
+struct ValueHooks<'a> {
+    values: &'a RefCell<Vec<SsaValue>>,
+    /// Result and operands of the instruction being executed.
+    produced: Cell<Option<ValueId>>,
+    inputs: RefCell<Vec<ValueId>>,
+}
+
+impl ValueHooks<'_> {
+    fn allocate(&self, ty: &ValueType, def: ValueDef) -> ValueId {
+        let ty = match ty {
+            // Constructors run on the same value, so record the initialized type.
+            ValueType::Uninitialized(_, class_name) | ValueType::UninitializedThis(class_name) => {
+                ValueType::Reference(ReferenceType::class(class_name))
+            }
+            ty => ty.clone(),
+        };
+        let mut values = self.values.borrow_mut();
+        values.push(SsaValue { ty, def });
+        ValueId(values.len() as u32 - 1)
+    }
+}
+
+impl FactHooks<Option<ValueId>> for ValueHooks<'_> {
+    fn parameter(&self, index: Option<usize>, ty: &ValueType) -> Option<ValueId> {
+        let def = match index {
+            None => ValueDef::This,
+            Some(index) => ValueDef::Parameter(index),
+        };
+        Some(self.allocate(ty, def))
+    }
+
+    fn produce(
+        &self,
+        inst: &Instruction,
+        ty: &ValueType,
+        inputs: &[Slot<Option<ValueId>>],
+    ) -> Option<ValueId> {
+        let id = self.allocate(ty, ValueDef::Instruction(inst.offset));
+        self.produced.set(Some(id));
+        *self.inputs.borrow_mut() = inputs.iter().filter_map(|slot| slot.fact).collect();
+        Some(id)
+    }
+}
+
+/// State of an in-progress lifting.
+struct Lifter<'a> {
+    method: &'a Method,
+    values: RefCell<Vec<SsaValue>>,
+    instructions: BTreeMap<u32, SsaInstruction>,
+    /// Phi placed for each slot at each join block.
+    phis: BTreeMap<u32, BTreeMap<SlotPosition, ValueId>>,
+    entry_frames: BTreeMap<u32, Frame<Option<ValueId>>>,
+    exit_frames: BTreeMap<u32, Frame<Option<ValueId>>>,
+    /// Frames before each instruction, kept for blocks that can throw into a handler.
+    throwing_frames: BTreeMap<u32, Vec<Frame<Option<ValueId>>>>,
+    /// Phi operands gathered per phi before trivial phis are removed.
+    operands: BTreeMap<ValueId, Vec<PhiOperand>>,
+}
+
+impl<'a> Lifter<'a> {
+    fn new(method: &'a Method) -> Self {
+        Self {
+            method,
+            values: RefCell::new(Vec::new()),
+            instructions: BTreeMap::new(),
+            phis: BTreeMap::new(),
+            entry_frames: BTreeMap::new(),
+            exit_frames: BTreeMap::new(),
+            throwing_frames: BTreeMap::new(),
+            operands: BTreeMap::new(),
+        }
+    }
+
+    fn hooks(&self) -> ValueHooks<'_> {
+        ValueHooks {
+            values: &self.values,
+            produced: Cell::new(None),
+            inputs: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn lift(
+        &mut self,
+        interpreter: &Interpreter<'_>,
+        types: &BTreeMap<u32, Frame<()>>,
+    ) -> Result<()> {
+        let cfg = &self.method.cfg;
+        let mut predecessors: BTreeMap<u32, Vec<(u32, EdgeKind)>> = BTreeMap::new();
+        for edge in &cfg.edges {
+            predecessors
+                .entry(edge.to)
+                .or_default()
+                .push((edge.from, edge.kind));
+        }
+        let handlers: BTreeSet<u32> = cfg
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Exception)
+            .map(|edge| edge.to)
+            .collect();
+        let throwing: BTreeSet<u32> = cfg
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Exception)
+            .map(|edge| edge.from)
+            .collect();
+
+        // Join blocks get a phi per defined slot up front, so blocks can be
+        // lifted before all of their predecessors.
+        let entry = {
+            let hooks = self.hooks();
+            interpreter.entry_frame(&hooks)?
+        };
+        for block in &cfg.blocks {
+            let start = block.start_offset;
+            let Some(shape) = types.get(&start) else {
+                continue;
+            };
+            let preds = predecessors.get(&start).map(Vec::len).unwrap_or(0);
+            let is_join = handlers.contains(&start) || preds > 1 || (start == 0 && preds > 0);
+            if start == 0 && !is_join {
+                self.entry_frames.insert(start, entry.clone());
+            } else if is_join {
+                let frame = self.phi_frame(start, shape, handlers.contains(&start));
+                self.entry_frames.insert(start, frame);
+            }
+        }
+
+        // Lift blocks once their entry frame is known; single-predecessor blocks
+        // inherit their predecessor's exit frame.
+        let reachable: Vec<&BasicBlock> = cfg
+            .blocks
+            .iter()
+            .filter(|block| types.contains_key(&block.start_offset))
+            .collect();
+        let mut pending: Vec<&BasicBlock> = reachable.clone();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut deferred = Vec::new();
+            for block in pending {
+                let start = block.start_offset;
+                let frame = match self.entry_frames.get(&start) {
+                    Some(frame) => frame.clone(),
+                    None => {
+                        let pred = predecessors
+                            .get(&start)
+                            .and_then(|preds| preds.first())
+                            .map(|(from, _)| *from);
+                        match pred.and_then(|pred| self.exit_frames.get(&pred)) {
+                            Some(frame) => {
+                                let frame = frame.clone();
+                                self.entry_frames.insert(start, frame.clone());
+                                frame
+                            }
+                            None => {
+                                deferred.push(block);
+                                continue;
+                            }
+                        }
+                    }
+                };
+                self.lift_block(interpreter, block, frame, throwing.contains(&start))?;
+            }
+            if deferred.len() == before {
+                anyhow::bail!(
+                    "cannot order blocks of {} for SSA lifting",
+                    self.method.name
+                );
+            }
+            pending = deferred;
+        }
+
+        self.connect_phis(&entry, &predecessors);
+        Ok(())
+    }
+
+    fn phi_frame(
+        &mut self,
+        block: u32,
+        shape: &Frame<()>,
+        is_handler: bool,
+    ) -> Frame<Option<ValueId>> {
+        let exception_type = caught_type(self.method, block);
+        let hooks = self.hooks();
+        let mut placed = BTreeMap::new();
+        let mut phi = |position: SlotPosition, ty: &ValueType| {
+            if *ty == ValueType::Top {
+                return None;
+            }
+            let id = hooks.allocate(
+                ty,
+                ValueDef::Phi {
+                    block,
+                    operands: Vec::new(),
+                },
+            );
+            placed.insert(position, id);
+            Some(id)
+        };
+        let locals = shape
+            .locals
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| Slot {
+                ty: slot.ty.clone(),
+                fact: phi(SlotPosition::Local(index), &slot.ty),
+            })
+            .collect();
+        let stack = if is_handler {
+            let ty = exception_type;
+            vec![Slot {
+                fact: Some(hooks.allocate(&ty, ValueDef::CaughtException(block))),
+                ty,
+            }]
+        } else {
+            shape
+                .stack
+                .iter()
+                .enumerate()
+                .map(|(index, slot)| Slot {
+                    ty: slot.ty.clone(),
+                    fact: phi(SlotPosition::Stack(index), &slot.ty),
+                })
+                .collect()
+        };
+        self.phis.insert(block, placed);
+        Frame { locals, stack }
+    }
+
+    fn lift_block(
+        &mut self,
+        interpreter: &Interpreter<'_>,
+        block: &BasicBlock,
+        mut frame: Frame<Option<ValueId>>,
+        can_throw: bool,
+    ) -> Result<()> {
+        let mut throwing_frames = Vec::new();
+        for inst in &block.instructions {
+            if can_throw {
+                throwing_frames.push(frame.clone());
+            }
+            let before = frame.clone();
+            let hooks = self.hooks();
+            interpreter.execute(&mut frame, inst, &hooks)?;
+            let result = hooks.produced.get();
+            let operands = match result {
+                Some(_) => hooks.inputs.take(),
+                None => consumed(inst, &before, &frame),
+            };
+            self.instructions.insert(
+                inst.offset,
+                SsaInstruction {
+                    offset: inst.offset,
+                    opcode: inst.opcode,
+                    operands,
+                    result,
+                },
+            );
+        }
+        if can_throw {
+            self.throwing_frames
+                .insert(block.start_offset, throwing_frames);
+        }
+        self.exit_frames.insert(block.start_offset, frame);
+        Ok(())
+    }
+
+    fn connect_phis(
+        &mut self,
+        entry: &Frame<Option<ValueId>>,
+        predecessors: &BTreeMap<u32, Vec<(u32, EdgeKind)>>,
+    ) {
+        for (block, placed) in &self.phis {
+            let preds = predecessors.get(block).map(Vec::as_slice).unwrap_or(&[]);
+            for (position, phi) in placed {
+                let mut operands = Vec::new();
+                if *block == 0 {
+                    operands.extend(slot_value(entry, *position).map(|value| PhiOperand {
+                        predecessor: None,
+                        value,
+                    }));
+                }
+                for (from, kind) in preds {
+                    let frames: Vec<&Frame<Option<ValueId>>> = if *kind == EdgeKind::Exception {
+                        self.throwing_frames
+                            .get(from)
+                            .map(|frames| frames.iter().collect())
+                            .unwrap_or_default()
+                    } else {
+                        self.exit_frames.get(from).into_iter().collect()
+                    };
+                    for frame in frames {
+                        if let Some(value) = slot_value(frame, *position) {
+                            let operand = PhiOperand {
+                                predecessor: Some(*from),
+                                value,
+                            };
+                            if !operands.contains(&operand) {
+                                operands.push(operand);
+                            }
+                        }
+                    }
+                }
+                self.operands.insert(*phi, operands);
+            }
+        }
+    }
+
+    /// Remove trivial phis, renumber the remaining values, and index users.
+    fn finish(self) -> SsaMethod {
+        let mut values = self.values.into_inner();
+        let mut operands = self.operands;
+
+        // A phi whose operands are all one other value (or itself) is that value.
+        let mut replaced: BTreeMap<ValueId, ValueId> = BTreeMap::new();
+        loop {
+            let mut changed = false;
+            for (phi, phi_operands) in &operands {
+                if replaced.contains_key(phi) {
+                    continue;
+                }
+                let distinct: BTreeSet<ValueId> = phi_operands
+                    .iter()
+                    .map(|operand| resolve(&replaced, operand.value))
+                    .filter(|value| value != phi)
+                    .collect();
+                if distinct.len() <= 1 {
+                    let target = distinct.into_iter().next();
+                    if let Some(target) = target {
+                        replaced.insert(*phi, target);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut renumbered = BTreeMap::new();
+        let mut kept = Vec::new();
+        let mut original_ids = Vec::new();
+        for (index, value) in values.drain(..).enumerate() {
+            let id = ValueId(index as u32);
+            if replaced.contains_key(&id) {
+                continue;
+            }
+            renumbered.insert(id, ValueId(kept.len() as u32));
+            kept.push(value);
+            original_ids.push(id);
+        }
+        let map = |id: ValueId| renumbered[&resolve(&replaced, id)];
+
+        let mut phis: BTreeMap<u32, Vec<ValueId>> = BTreeMap::new();
+        for (index, value) in kept.iter_mut().enumerate() {
+            let ValueDef::Phi {
+                block,
+                operands: phi_operands,
+            } = &mut value.def
+            else {
+                continue;
+            };
+            if let Some(gathered) = operands.remove(&original_ids[index]) {
+                *phi_operands = gathered
+                    .into_iter()
+                    .map(|operand| PhiOperand {
+                        predecessor: operand.predecessor,
+                        value: map(operand.value),
+                    })
+                    .collect();
+            }
+            phis.entry(*block).or_default().push(ValueId(index as u32));
+        }
+
+        let mut instructions = self.instructions;
+        for inst in instructions.values_mut() {
+            for operand in &mut inst.operands {
+                *operand = map(*operand);
+            }
+            inst.result = inst.result.map(map);
+        }
+
+        let mut users: BTreeMap<ValueId, Vec<User>> = BTreeMap::new();
+        for inst in instructions.values() {
+            for operand in &inst.operands {
+                users
+                    .entry(*operand)
+                    .or_default()
+                    .push(User::Instruction(inst.offset));
+            }
+        }
+        for ids in phis.values() {
+            for phi in ids {
+                if let ValueDef::Phi { operands, .. } = &kept[phi.0 as usize].def {
+                    for operand in operands {
+                        let entry = users.entry(operand.value).or_default();
+                        if !entry.contains(&User::Phi(*phi)) {
+                            entry.push(User::Phi(*phi));
+                        }
+                    }
+                }
+            }
+        }
+
+        SsaMethod {
+            values: kept,
+            instructions,
+            phis,
+            users,
+        }
+    }
+}
+
+/// Declared type of the exception caught at `handler_pc`.
+fn caught_type(method: &Method, handler_pc: u32) -> ValueType {
+    let catch_types: BTreeSet<Option<&str>> = method
+        .exception_handlers
+        .iter()
+        .filter(|handler| handler.handler_pc == handler_pc)
+        .map(|handler| handler.catch_type.as_deref())
+        .collect();
+    let class_name = match catch_types.into_iter().collect::<Vec<_>>().as_slice() {
+        [Some(class_name)] => class_name,
+        _ => "java/lang/Throwable",
+    };
+    ValueType::Reference(ReferenceType::class(class_name))
+}
+
+fn resolve(replaced: &BTreeMap<ValueId, ValueId>, mut id: ValueId) -> ValueId {
+    while let Some(next) = replaced.get(&id) {
+        id = *next;
+    }
+    id
+}
+
+fn slot_value(frame: &Frame<Option<ValueId>>, position: SlotPosition) -> Option<ValueId> {
+    match position {
+        SlotPosition::Local(index) => frame.locals.get(index).and_then(|slot| slot.fact),
+        SlotPosition::Stack(index) => frame.stack.get(index).and_then(|slot| slot.fact),
+    }
+}
+
+/// Operands consumed by an instruction that produced no value, found by
+/// comparing the stack before and after it executed.
+fn consumed(
+    inst: &Instruction,
+    before: &Frame<Option<ValueId>>,
+    after: &Frame<Option<ValueId>>,
+) -> Vec<ValueId> {
+    match inst.opcode {
+        // Copies between locals and the stack are not uses.
+        opcodes::ILOAD..=opcodes::ALOAD_3
+        | opcodes::ISTORE..=opcodes::ASTORE_3
+        | opcodes::POP
+        | opcodes::POP2
+        | opcodes::DUP..=opcodes::SWAP
+        | opcodes::WIDE => Vec::new(),
+        // A cast checks its operand without producing a new value.
+        opcodes::CHECKCAST => before
+            .peek(0)
+            .and_then(|slot| slot.fact)
+            .into_iter()
+            .collect(),
+        _ => {
+            let common = before
+                .stack
+                .iter()
+                .zip(after.stack.iter())
+                .take_while(|(left, right)| left.fact == right.fact)
+                .count();
+            before.stack[common..]
+                .iter()
+                .filter_map(|slot| slot.fact)
+                .collect()
+        }
+    }
+}
+
+/// Type-only analysis giving the frame shape at each block entry.
+struct TypeAnalysis<'a> {
+    interpreter: Interpreter<'a>,
+}
+
+impl Analysis for TypeAnalysis<'_> {
+    type Domain = Frame<()>;
+
+    fn boundary_state(&self) -> Result<Frame<()>> {
+        self.interpreter.entry_frame(&NoFacts)
+    }
+
+    fn join(&self, left: &Frame<()>, right: &Frame<()>) -> Frame<()> {
+        left.join(right)
+    }
+
+    fn transfer(&self, frame: &mut Frame<()>, inst: &Instruction) -> Result<()> {
+        self.interpreter.execute(frame, inst, &NoFacts)
+    }
+
+    fn exception_state(&self, frame: &Frame<()>) -> Frame<()> {
+        frame.exception_frame(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::build_context;
+    use crate::ir::Class;
+    use crate::test_harness::compile_sample;
+
+    fn lift_method(classes: &[Class], name: &str) -> SsaMethod {
+        let class = classes
+            .iter()
+            .find(|class| class.name == "com/example/Sample")
+            .expect("sample class");
+        let method = class
+            .methods
+            .iter()
+            .find(|method| method.name == name)
+            .expect("method");
+        lift(&class.name, method).expect("lift method")
+    }
+
+    fn operands_of(ssa: &SsaMethod, opcode: u8) -> Vec<ValueId> {
+        ssa.instructions()
+            .find(|inst| inst.opcode == opcode)
+            .map(|inst| inst.operands.clone())
+            .expect("instruction")
+    }
+
+    const SOURCE: &str = r#"
+package com.example;
+import java.io.FileInputStream;
+import java.io.IOException;
+public class Sample {
+    static boolean same(int x) {
+        int y = x;
+        return y == x;
+    }
+
+    static int pick(boolean flag, int a, int b) {
+        int result;
+        if (flag) {
+            result = a;
+        } else {
+            result = b;
+        }
+        return result;
+    }
+
+    static int loop(int n) {
+        int sum = 0;
+        for (int i = 0; i < n; i++) {
+            sum += i;
+        }
+        return sum;
+    }
+
+    void read(String path) throws IOException {
+        FileInputStream in = new FileInputStream(path);
+        in.close();
+    }
+
+    static String recover(Runnable action) {
+        try {
+            action.run();
+            return "ok";
+        } catch (IllegalStateException e) {
+            return e.getMessage();
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn ssa_tracks_value_identity_through_locals() {
+        let classes = compile_sample(SOURCE);
+        let ssa = lift_method(&classes, "same");
+
+        let operands = operands_of(&ssa, opcodes::IF_ICMPNE);
+        assert_eq!(2, operands.len());
+        assert_eq!(operands[0], operands[1]);
+        assert_eq!(ValueDef::Parameter(0), ssa.value(operands[0]).def);
+        assert_eq!(ValueType::Int, ssa.value(operands[0]).ty);
+    }
+
+    #[test]
+    fn ssa_places_phi_at_join() {
+        let classes = compile_sample(SOURCE);
+        let ssa = lift_method(&classes, "pick");
+
+        let returned = operands_of(&ssa, opcodes::IRETURN)[0];
+        let ValueDef::Phi { block, operands } = &ssa.value(returned).def else {
+            panic!("expected phi");
+        };
+        assert_eq!(&[returned], ssa.phis(*block));
+        let defs: Vec<&ValueDef> = operands
+            .iter()
+            .map(|operand| &ssa.value(operand.value).def)
+            .collect();
+        assert_eq!(vec![&ValueDef::Parameter(1), &ValueDef::Parameter(2)], defs);
+        assert!(ssa.users(operands[0].value).contains(&User::Phi(returned)));
+    }
+
+    #[test]
+    fn ssa_removes_trivial_loop_phis() {
+        let classes = compile_sample(SOURCE);
+        let ssa = lift_method(&classes, "loop");
+
+        let compared = operands_of(&ssa, opcodes::IF_ICMPGE);
+        assert_eq!(ValueDef::Parameter(0), ssa.value(compared[1]).def);
+        let header = match &ssa.value(compared[0]).def {
+            ValueDef::Phi { block, .. } => *block,
+            def => panic!("expected loop counter phi, got {:?}", def),
+        };
+        assert_eq!(2, ssa.phis(header).len());
+    }
+
+    #[test]
+    fn ssa_links_allocation_to_later_calls() {
+        let classes = compile_sample(SOURCE);
+        let ssa = lift_method(&classes, "read");
+
+        let close = ssa
+            .instructions()
+            .filter(|inst| inst.opcode == opcodes::INVOKEVIRTUAL)
+            .last()
+            .expect("close call");
+        let receiver = ssa.value(close.operands[0]);
+        let ValueDef::Instruction(offset) = receiver.def else {
+            panic!("expected instruction result");
+        };
+        assert_eq!(
+            opcodes::NEW,
+            ssa.instruction(offset).expect("new instruction").opcode
+        );
+        assert_eq!(
+            ValueType::Reference(ReferenceType::class("java/io/FileInputStream")),
+            receiver.ty
+        );
+        assert_eq!(2, ssa.users(close.operands[0]).len());
+    }
+
+    #[test]
+    fn ssa_types_caught_exceptions() {
+        let classes = compile_sample(SOURCE);
+        let ssa = lift_method(&classes, "recover");
+
+        let receiver = operands_of(&ssa, opcodes::INVOKEVIRTUAL)[0];
+        assert!(matches!(
+            ssa.value(receiver).def,
+            ValueDef::CaughtException(_)
+        ));
+        assert_eq!(
+            ValueType::Reference(ReferenceType::class("java/lang/IllegalStateException")),
+            ssa.value(receiver).ty
+        );
+    }
+
+    #[test]
+    fn ssa_is_cached_in_context() {
+        let classes = compile_sample(SOURCE);
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let context = build_context(classes, classpath, &[]);
+        let class = context
+            .classes
+            .iter()
+            .find(|class| class.name == "com/example/Sample")
+            .expect("sample class");
+        let method = &class.methods[0];
+
+        let first = context.ssa(class, method).expect("lift");
+        let second = context.ssa(class, method).expect("lift");
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+    }
+}
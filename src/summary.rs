@@ -0,0 +1,446 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::callgraph::{CallGraph, MethodId};
+use crate::ir::{BasicBlock, CallKind, CallSite, Class, EdgeKind, Method};
+use crate::opcodes;
+
+/// Rounds over a call cycle before its summaries give up and become unknown.
+const MAX_CYCLE_ROUNDS: usize = 10;
+
+/// Interprocedural fact computed once per method from its body and the summaries
+/// of the methods it calls, such as "may return null" or "always throws".
+pub(crate) trait SummaryAnalysis {
+    /// Per-method fact. Its type identifies the summaries shared between rules.
+    type Summary: Clone + PartialEq + Send + Sync + 'static;
+
+    /// Summary of callees without a body or outside the parsed classes.
+    fn unknown(&self) -> Self::Summary;
+
+    /// Optimistic starting summary for methods in a call cycle, refined until the
+    /// cycle's summaries stop changing.
+    fn initial(&self) -> Self::Summary;
+
+    /// Summarize `method`; summaries of its callees are already available.
+    fn summarize(
+        &self,
+        class: &Class,
+        method: &Method,
+        summaries: &Summaries<Self::Summary>,
+    ) -> Result<Self::Summary>;
+}
+
+/// Summaries of every parsed method with a body, queried by method or call site.
+pub(crate) struct Summaries<S> {
+    summaries: BTreeMap<Arc<MethodId>, S>,
+    /// Call graph targets by caller and call site offset.
+    calls: BTreeMap<Arc<MethodId>, BTreeMap<u32, Vec<Arc<MethodId>>>>,
+    parsed_classes: BTreeSet<String>,
+    unknown: S,
+}
+
+impl<S> Summaries<S> {
+    #[cfg(test)]
+    pub(crate) fn get(&self, method: &MethodId) -> Option<&S> {
+        self.summaries.get(method)
+    }
+
+    /// Summaries of every method `call` in `caller` may invoke, with the unknown
+    /// summary standing for targets that were not summarized. Never empty.
+    pub(crate) fn at_call(&self, caller: &MethodId, call: &CallSite) -> Vec<&S> {
+        let mut summaries: Vec<&S> = self
+            .calls
+            .get(caller)
+            .and_then(|sites| sites.get(&call.offset))
+            .into_iter()
+            .flatten()
+            .filter(|callee| callee.name == call.name && callee.descriptor == call.descriptor)
+            .map(|callee| self.summaries.get(callee).unwrap_or(&self.unknown))
+            .collect();
+        let is_open = matches!(call.kind, CallKind::Virtual | CallKind::Interface)
+            && !self.parsed_classes.contains(&call.owner);
+        if summaries.is_empty() || is_open {
+            summaries.push(&self.unknown);
+        }
+        summaries
+    }
+}
+
+/// Whether a method never returns normally, such as an assertion helper that
+/// always throws.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct AlwaysThrows(pub(crate) bool);
+
+impl Summaries<AlwaysThrows> {
+    /// Whether `call` in `caller` throws whichever method it invokes, so code
+    /// after it is not reached.
+    pub(crate) fn always_throws(&self, caller: &MethodId, call: &CallSite) -> bool {
+        self.at_call(caller, call)
+            .into_iter()
+            .all(|summary| summary.0)
+    }
+}
+
+/// Summarizes whether a method reaches a return instruction. Calls to methods
+/// that always throw end their path, and exception handlers count as reachable.
+pub(crate) struct AlwaysThrowsAnalysis;
+
+impl SummaryAnalysis for AlwaysThrowsAnalysis {
+    type Summary = AlwaysThrows;
+
+    fn unknown(&self) -> AlwaysThrows {
+        AlwaysThrows(false)
+    }
+
+    fn initial(&self) -> AlwaysThrows {
+        AlwaysThrows(true)
+    }
+
+    fn summarize(
+        &self,
+        class: &Class,
+        method: &Method,
+        summaries: &Summaries<AlwaysThrows>,
+    ) -> Result<AlwaysThrows> {
+        let caller = MethodId {
+            class_name: class.name.clone(),
+            name: method.name.clone(),
+            descriptor: method.descriptor.clone(),
+        };
+        let calls: BTreeMap<u32, &CallSite> = method
+            .calls
+            .iter()
+            .map(|call| (call.offset, call))
+            .collect();
+        let blocks: BTreeMap<u32, &BasicBlock> = method
+            .cfg
+            .blocks
+            .iter()
+            .map(|block| (block.start_offset, block))
+            .collect();
+        let mut reached = BTreeSet::new();
+        let mut pending = vec![0];
+        while let Some(start) = pending.pop() {
+            if !reached.insert(start) {
+                continue;
+            }
+            let Some(block) = blocks.get(&start) else {
+                continue;
+            };
+            let mut completes = true;
+            for inst in &block.instructions {
+                if (opcodes::IRETURN..=opcodes::RETURN).contains(&inst.opcode) {
+                    return Ok(AlwaysThrows(false));
+                }
+                if calls
+                    .get(&inst.offset)
+                    .is_some_and(|call| summaries.always_throws(&caller, call))
+                {
+                    completes = false;
+                    break;
+                }
+            }
+            pending.extend(
+                method
+                    .cfg
+                    .edges
+                    .iter()
+                    .filter(|edge| {
+                        edge.from == start && (completes || edge.kind == EdgeKind::Exception)
+                    })
+                    .map(|edge| edge.to),
+            );
+        }
+        Ok(AlwaysThrows(true))
+    }
+}
+
+/// Summarize every method with a body bottom-up: strongly connected components of
+/// the call graph are visited callees first, and the methods of a cycle are
+/// summarized repeatedly until they agree.
+pub(crate) fn compute_summaries<A: SummaryAnalysis>(
+    classes: &[Class],
+    call_graph: &CallGraph,
+    analysis: &A,
+) -> Summaries<A::Summary> {
+    let methods: Vec<(&Class, &Method, Arc<MethodId>)> = classes
+        .iter()
+        .flat_map(|class| {
+            class
+                .methods
+                .iter()
+                .filter(|method| !method.bytecode.is_empty())
+                .map(move |method| {
+                    let id = MethodId {
+                        class_name: class.name.clone(),
+                        name: method.name.clone(),
+                        descriptor: method.descriptor.clone(),
+                    };
+                    (class, method, Arc::new(id))
+                })
+        })
+        .collect();
+    let indices: BTreeMap<&MethodId, usize> = methods
+        .iter()
+        .enumerate()
+        .map(|(index, (_, _, id))| (id.as_ref(), index))
+        .collect();
+    let mut successors = vec![BTreeSet::new(); methods.len()];
+    let mut calls: BTreeMap<Arc<MethodId>, BTreeMap<u32, Vec<Arc<MethodId>>>> = BTreeMap::new();
+    for edge in &call_graph.edges {
+        calls
+            .entry(edge.caller.clone())
+            .or_default()
+            .entry(edge.offset)
+            .or_default()
+            .push(edge.callee.clone());
+        if let (Some(caller), Some(callee)) = (
+            indices.get(edge.caller.as_ref()),
+            indices.get(edge.callee.as_ref()),
+        ) {
+            successors[*caller].insert(*callee);
+        }
+    }
+    let successors: Vec<Vec<usize>> = successors
+        .into_iter()
+        .map(|callees| callees.into_iter().collect())
+        .collect();
+
+    let mut summaries = Summaries {
+        summaries: BTreeMap::new(),
+        calls,
+        parsed_classes: classes.iter().map(|class| class.name.clone()).collect(),
+        unknown: analysis.unknown(),
+    };
+    let summarize = |index: usize, summaries: &Summaries<A::Summary>| {
+        let (class, method, _) = &methods[index];
+        analysis
+            .summarize(class, method, summaries)
+            .unwrap_or_else(|_| analysis.unknown())
+    };
+    for component in strongly_connected_components(&successors) {
+        let is_cycle = component.len() > 1 || successors[component[0]].contains(&component[0]);
+        if !is_cycle {
+            let summary = summarize(component[0], &summaries);
+            summaries
+                .summaries
+                .insert(methods[component[0]].2.clone(), summary);
+            continue;
+        }
+        for index in &component {
+            summaries
+                .summaries
+                .insert(methods[*index].2.clone(), analysis.initial());
+        }
+        let mut converged = false;
+        for _ in 0..MAX_CYCLE_ROUNDS {
+            let mut changed = false;
+            for index in &component {
+                let summary = summarize(*index, &summaries);
+                let id = &methods[*index].2;
+                if summaries.summaries.get(id) != Some(&summary) {
+                    summaries.summaries.insert(id.clone(), summary);
+                    changed = true;
+                }
+            }
+            if !changed {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            for index in &component {
+                summaries
+                    .summaries
+                    .insert(methods[*index].2.clone(), analysis.unknown());
+            }
+        }
+    }
+    summaries
+}
+
+/// Tarjan's algorithm without recursion; components come out callees first.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut order = vec![usize::MAX; successors.len()];
+    let mut low = vec![0; successors.len()];
+    let mut on_stack = vec![false; successors.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next = 0;
+    for root in 0..successors.len() {
+        if order[root] != usize::MAX {
+            continue;
+        }
+        order[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        let mut frames = vec![(root, 0)];
+        while let Some(frame) = frames.last_mut() {
+            let node = frame.0;
+            if let Some(successor) = successors[node].get(frame.1).copied() {
+                frame.1 += 1;
+                if order[successor] == usize::MAX {
+                    order[successor] = next;
+                    low[successor] = next;
+                    next += 1;
+                    stack.push(successor);
+                    on_stack[successor] = true;
+                    frames.push((successor, 0));
+                } else if on_stack[successor] {
+                    low[node] = low[node].min(order[successor]);
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some((parent, _)) = frames.last() {
+                low[*parent] = low[*parent].min(low[node]);
+            }
+            if low[node] == order[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().expect("component member on stack");
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::{CallGraphOptions, build_call_graph_with_timings};
+    use crate::opcodes;
+    use crate::scan::scan_inputs;
+    use crate::test_harness::{JvmTestHarness, Language, SourceFile};
+
+    /// Whether a method may throw explicitly, itself or through its callees.
+    struct MayThrow;
+
+    impl SummaryAnalysis for MayThrow {
+        type Summary = bool;
+
+        fn unknown(&self) -> bool {
+            false
+        }
+
+        fn initial(&self) -> bool {
+            false
+        }
+
+        fn summarize(
+            &self,
+            class: &Class,
+            method: &Method,
+            summaries: &Summaries<bool>,
+        ) -> Result<bool> {
+            let caller = MethodId {
+                class_name: class.name.clone(),
+                name: method.name.clone(),
+                descriptor: method.descriptor.clone(),
+            };
+            let throws = method
+                .cfg
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+                .any(|inst| inst.opcode == opcodes::ATHROW);
+            Ok(throws
+                || method.calls.iter().any(|call| {
+                    summaries
+                        .at_call(&caller, call)
+                        .into_iter()
+                        .any(|summary| *summary)
+                }))
+        }
+    }
+
+    #[test]
+    fn summaries_propagate_through_call_cycles() {
+        let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+        let sources = vec![SourceFile {
+            path: "com/example/Sample.java".to_string(),
+            contents: r#"
+package com.example;
+public class Sample {
+    static void ping(int n) {
+        if (n > 0) {
+            pong(n - 1);
+        }
+    }
+
+    static void pong(int n) {
+        ping(n);
+        fail();
+    }
+
+    static void fail() {
+        throw new IllegalStateException();
+    }
+
+    static void even(int n) {
+        if (n > 0) {
+            odd(n - 1);
+        }
+    }
+
+    static void odd(int n) {
+        even(n);
+    }
+}
+"#
+            .to_string(),
+        }];
+        let output = harness
+            .compile(Language::Java, &sources, &[])
+            .expect("compile sources");
+        let classes = scan_inputs(output.classes_dir(), &[])
+            .expect("scan classes")
+            .classes;
+        let (call_graph, _, _) =
+            build_call_graph_with_timings(&classes, &CallGraphOptions::default(), |_| true);
+
+        let summaries = compute_summaries(&classes, &call_graph, &MayThrow);
+
+        let may_throw = |name: &str| {
+            summaries
+                .get(&MethodId {
+                    class_name: "com/example/Sample".to_string(),
+                    name: name.to_string(),
+                    descriptor: if name == "fail" { "()V" } else { "(I)V" }.to_string(),
+                })
+                .copied()
+        };
+        assert_eq!(Some(true), may_throw("fail"));
+        assert_eq!(Some(true), may_throw("pong"));
+        assert_eq!(Some(true), may_throw("ping"));
+        assert_eq!(Some(false), may_throw("even"));
+        assert_eq!(Some(false), may_throw("odd"));
+
+        let throws = compute_summaries(&classes, &call_graph, &AlwaysThrowsAnalysis);
+        let always_throws = |name: &str| {
+            throws
+                .get(&MethodId {
+                    class_name: "com/example/Sample".to_string(),
+                    name: name.to_string(),
+                    descriptor: if name == "fail" { "()V" } else { "(I)V" }.to_string(),
+                })
+                .map(|summary| summary.0)
+        };
+        assert_eq!(Some(true), always_throws("fail"));
+        assert_eq!(Some(true), always_throws("pong"));
+        assert_eq!(Some(false), always_throws("ping"));
+        assert_eq!(Some(false), always_throws("even"));
+    }
+}
@@ -7,6 +7,7 @@ use tempfile::TempDir;
 
 use crate::classpath::resolve_classpath;
 use crate::engine::{Engine, EngineOutput, build_context};
+use crate::ir::Class;
 use crate::scan::scan_inputs;
 
 /// Supported JVM source languages for the harness.
@@ -129,6 +130,21 @@ impl JvmTestHarness {
     }
 }
 
+/// Compiles `source` as `com/example/Sample.java` and scans the classes it declares.
+pub(crate) fn compile_sample(source: &str) -> Vec<Class> {
+    let harness = JvmTestHarness::new().expect("JAVA_HOME must be set for harness tests");
+    let sources = vec![SourceFile {
+        path: "com/example/Sample.java".to_string(),
+        contents: source.to_string(),
+    }];
+    let output = harness
+        .compile(Language::Java, &sources, &[])
+        .expect("compile sources");
+    scan_inputs(output.classes_dir(), &[])
+        .expect("scan classes")
+        .classes
+}
+
 fn javac_path() -> Result<PathBuf> {
     let java_home = std::env::var("JAVA_HOME").context("JAVA_HOME not set")?;
     let mut path = PathBuf::from(java_home);
@@ -0,0 +1,178 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Spans recorded since `enable`, written as a Chrome Trace Event file by `write`.
+static TRACE: OnceLock<Trace> = OnceLock::new();
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+struct Trace {
+    origin: Instant,
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+/// Complete ("X") event of the Chrome Trace Event format, timed in microseconds.
+#[derive(Debug, Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    ts: u128,
+    dur: u128,
+    pid: u32,
+    tid: u64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    args: BTreeMap<String, Value>,
+}
+
+/// Start recording spans. Until this is called, spans are dropped unrecorded.
+pub fn enable() {
+    TRACE.get_or_init(|| Trace {
+        origin: Instant::now(),
+        events: Mutex::new(Vec::new()),
+    });
+    thread_id();
+}
+
+/// Time from now until the returned span is dropped, shown as `name` in `category`.
+pub fn span(category: &'static str, name: impl Into<String>) -> Span {
+    Span {
+        category,
+        name: name.into(),
+        started_at: Instant::now(),
+        args: BTreeMap::new(),
+    }
+}
+
+/// Running span; recorded when dropped.
+pub struct Span {
+    category: &'static str,
+    name: String,
+    started_at: Instant,
+    args: BTreeMap<String, Value>,
+}
+
+impl Span {
+    /// Attach a value shown with the span, e.g. its result count.
+    pub fn arg(&mut self, key: &str, value: impl Into<Value>) {
+        if TRACE.get().is_some() {
+            self.args.insert(key.to_string(), value.into());
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(trace) = TRACE.get() else {
+            return;
+        };
+        let event = TraceEvent {
+            name: std::mem::take(&mut self.name),
+            cat: self.category,
+            ph: "X",
+            ts: self
+                .started_at
+                .saturating_duration_since(trace.origin)
+                .as_micros(),
+            dur: self.started_at.elapsed().as_micros(),
+            pid: 1,
+            tid: thread_id(),
+            args: std::mem::take(&mut self.args),
+        };
+        trace
+            .events
+            .lock()
+            .expect("trace events lock poisoned")
+            .push(event);
+    }
+}
+
+/// Small per-thread number, so the main thread is 1 and workers follow.
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+/// Write the recorded spans to `path`, for chrome://tracing or Perfetto.
+pub fn write(path: &Path) -> Result<()> {
+    let Some(trace) = TRACE.get() else {
+        return Ok(());
+    };
+    let mut events = std::mem::take(&mut *trace.events.lock().expect("trace events lock poisoned"));
+    events.sort_by_key(|event| (event.ts, event.tid));
+    let file = File::create(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(
+        &mut writer,
+        &json!({ "traceEvents": events, "displayTimeUnit": "ms" }),
+    )
+    .context("failed to serialize trace")?;
+    writer.write_all(b"\n").context("failed to write trace")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classpath::resolve_classpath;
+    use crate::engine::{Engine, build_context};
+    use crate::test_harness::compile_sample;
+
+    #[test]
+    fn trace_writes_spans_as_complete_events() {
+        enable();
+        {
+            let mut span = span("rule", "TEST_RULE");
+            span.arg("results", 2);
+        }
+        let classes = compile_sample("package com.example; public class Sample {}");
+        let classpath = resolve_classpath(&classes).expect("classpath build");
+        let engine = Engine::new();
+        engine
+            .analyze(build_context(classes, classpath, &[]))
+            .expect("engine run");
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("trace.json");
+
+        write(&path).expect("write trace");
+
+        let trace: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read trace"))
+                .expect("parse trace");
+        let events = trace["traceEvents"].as_array().expect("events");
+        let event = events
+            .iter()
+            .find(|event| event["name"] == "TEST_RULE")
+            .expect("rule span");
+        assert_eq!("X", event["ph"]);
+        assert_eq!("rule", event["cat"]);
+        assert_eq!(2, event["args"]["results"]);
+        for rule in engine.rule_metadata() {
+            assert!(
+                events.iter().any(|event| event["cat"] == "rule"
+                    && event["name"] == *rule.id
+                    && event["args"]["results"].is_u64()),
+                "no span for {}",
+                rule.id
+            );
+        }
+    }
+}
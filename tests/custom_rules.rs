@@ -0,0 +1,47 @@
+use std::process::Command;
+
+#[test]
+fn rules_and_explain_include_custom_rules_from_config() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let config = dir.path().join("inspequte.toml");
+    std::fs::write(
+        &config,
+        r#"
+[[custom-rules]]
+id = "NO_DATE"
+name = "No java.util.Date"
+message = "Use java.time instead of java.util.Date"
+references = ["java.util.Date"]
+"#,
+    )
+    .expect("write config");
+    let inspequte = env!("CARGO_BIN_EXE_inspequte");
+
+    let rules = Command::new(inspequte)
+        .arg("rules")
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .expect("run inspequte rules");
+    assert!(rules.status.success(), "{rules:?}");
+    let listed = String::from_utf8_lossy(&rules.stdout);
+    assert!(
+        listed
+            .lines()
+            .any(|line| line.starts_with("NO_DATE") && line.ends_with("No java.util.Date")),
+        "{listed}"
+    );
+    assert!(listed.lines().any(|line| line.starts_with("NULLNESS")));
+
+    let explained = Command::new(inspequte)
+        .arg("explain")
+        .arg("no_date")
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .expect("run inspequte explain");
+    assert!(explained.status.success(), "{explained:?}");
+    let text = String::from_utf8_lossy(&explained.stdout);
+    assert!(text.starts_with("# NO_DATE: No java.util.Date"), "{text}");
+    assert!(!text.contains("Documentation:"), "{text}");
+}